@@ -0,0 +1,233 @@
+//! Signed attestations over the results of a test run.
+//!
+//! An attestation is an [in-toto statement] whose subject is the results file
+//! and whose predicate records what was tested: the commit of the
+//! specification and the engine and its version. The statement is signed with
+//! `ssh-keygen -Y sign`, so anyone with the signer's public key can verify a
+//! published conformance claim:
+//!
+//! ```bash
+//! ssh-keygen -Y verify -f allowed_signers -I ci@example.org -n spectool \
+//!     -s results.json.intoto.json.sig < results.json.intoto.json
+//! sha256sum results.json  # compare with `subject[0].digest.sha256`
+//! ```
+//!
+//! [in-toto statement]: https://github.com/in-toto/attestation/blob/main/spec/v1/statement.md
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::bail;
+use bon::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest as _;
+use sha2::Sha256;
+
+/// The type of an in-toto statement.
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// The type of the predicate of a conformance attestation.
+pub const PREDICATE_TYPE: &str = "https://github.com/openwdl/spectool/conformance/v1";
+
+/// The namespace signatures are made in, which must be given to
+/// `ssh-keygen -Y verify`.
+pub const NAMESPACE: &str = "spectool";
+
+/// What a conformance run tested.
+#[derive(Builder, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Predicate {
+    /// The URL of the specification repository.
+    #[builder(into)]
+    repository_url: String,
+
+    /// The branch of the specification repository.
+    #[builder(into)]
+    branch: String,
+
+    /// The commit of the specification repository, if it could be
+    /// determined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+
+    /// The engine preset the run used, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    engine: Option<String>,
+
+    /// The version of the engine, if provided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    engine_version: Option<String>,
+
+    /// The identifier of the run.
+    #[builder(into)]
+    run_id: String,
+}
+
+/// An artifact an attestation is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subject {
+    /// The file name of the artifact.
+    name: String,
+
+    /// The digests of the artifact by algorithm.
+    digest: BTreeMap<String, String>,
+}
+
+/// An in-toto statement attesting to the results of a conformance run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    /// The type of the statement.
+    #[serde(rename = "_type")]
+    type_: String,
+
+    /// The results file.
+    subject: Vec<Subject>,
+
+    /// The type of the predicate.
+    predicate_type: String,
+
+    /// What the run tested.
+    predicate: Predicate,
+}
+
+impl Statement {
+    /// Creates a statement about the results file at `path`.
+    pub fn new(path: &Path, predicate: Predicate) -> Result<Self> {
+        let contents = std::fs::read(path)
+            .with_context(|| format!("reading results file `{}`", path.display()))?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Ok(Self {
+            type_: String::from(STATEMENT_TYPE),
+            subject: vec![Subject {
+                name,
+                digest: BTreeMap::from([(
+                    String::from("sha256"),
+                    format!("{:x}", Sha256::digest(&contents)),
+                )]),
+            }],
+            predicate_type: String::from(PREDICATE_TYPE),
+            predicate,
+        })
+    }
+
+    /// Gets the digests of the results file by algorithm.
+    pub fn digest(&self) -> &BTreeMap<String, String> {
+        &self.subject[0].digest
+    }
+
+    /// Gets what the run tested.
+    pub fn predicate(&self) -> &Predicate {
+        &self.predicate
+    }
+
+    /// Writes the statement to `path` and signs it with the SSH private key
+    /// at `key`, returning the path of the detached signature.
+    pub fn sign(&self, path: &Path, key: &Path) -> Result<PathBuf> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("writing attestation `{}`", path.display()))?;
+
+        let signature = signature_path(path);
+        // `ssh-keygen` refuses to overwrite an existing signature.
+        if signature.exists() {
+            std::fs::remove_file(&signature)
+                .with_context(|| format!("removing `{}`", signature.display()))?;
+        }
+
+        let output = Command::new("ssh-keygen")
+            .args(["-Y", "sign", "-n", NAMESPACE, "-f"])
+            .arg(key)
+            .arg(path)
+            .output()
+            .context("running `ssh-keygen` to sign the attestation")?;
+
+        if !output.status.success() {
+            bail!(
+                "signing the attestation with `{}` failed: {}",
+                key.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(signature)
+    }
+}
+
+impl Predicate {
+    /// Gets the commit of the specification repository.
+    pub fn commit(&self) -> Option<&str> {
+        self.commit.as_deref()
+    }
+
+    /// Gets the version of the engine.
+    pub fn engine_version(&self) -> Option<&str> {
+        self.engine_version.as_deref()
+    }
+}
+
+/// Gets the path an attestation of the results file at `path` is written to.
+pub fn statement_path(path: &Path) -> PathBuf {
+    append_extension(path, "intoto.json")
+}
+
+/// Gets the path of the detached signature of the file at `path`.
+pub fn signature_path(path: &Path) -> PathBuf {
+    append_extension(path, "sig")
+}
+
+/// Appends an extension to a path, keeping any it already has.
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statement() {
+        let dir = tempfile::tempdir().unwrap();
+        let results = dir.path().join("results.json");
+        std::fs::write(&results, "{}").unwrap();
+
+        let predicate = Predicate::builder()
+            .repository_url("https://github.com/openwdl/wdl.git")
+            .branch("wdl-1.2")
+            .commit(String::from("abc123"))
+            .engine_version(String::from("1.0.0"))
+            .run_id("run")
+            .build();
+        let statement = Statement::new(&results, predicate).unwrap();
+
+        assert_eq!(
+            statement.digest()["sha256"],
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+        assert_eq!(statement.predicate().commit(), Some("abc123"));
+
+        let json = serde_json::to_value(&statement).unwrap();
+        assert_eq!(json["_type"], STATEMENT_TYPE);
+        assert_eq!(json["subject"][0]["name"], "results.json");
+        assert_eq!(json["predicateType"], PREDICATE_TYPE);
+        assert_eq!(json["predicate"]["engineVersion"], "1.0.0");
+        assert!(json["predicate"].get("engine").is_none());
+
+        assert_eq!(
+            signature_path(&statement_path(&results)),
+            dir.path().join("results.json.intoto.json.sig")
+        );
+    }
+}
@@ -1,9 +1,32 @@
 //! Badge generation for test results.
 
+use std::path::Path;
+use std::sync::LazyLock;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use regex::Regex;
 use serde::Serialize;
 
+use crate::diagnostic::Code;
+use crate::diagnostic::Diagnostic;
+
+/// The regex for a placeholder within a badge template.
+static PLACEHOLDER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
+/// The placeholders supported within badge templates.
+const PLACEHOLDERS: &[&str] = &[
+    "passed",
+    "failed",
+    "total",
+    "percent",
+    "branch",
+    "engine_version",
+    "tier",
+];
+
 /// A shields.io endpoint badge.
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Badge {
     /// Schema version (always `1`).
@@ -39,11 +62,72 @@ impl Badge {
         }
     }
 
+    /// Replaces the label and message of the badge by rendering templates
+    /// with the given values.
+    ///
+    /// When no message template is given, the default message is kept.
+    pub fn with_templates(
+        mut self,
+        label: &str,
+        message: Option<&str>,
+        values: &TemplateValues,
+    ) -> Self {
+        self.label = values.render(label);
+        if let Some(message) = message {
+            self.message = values.render(message);
+        }
+        self
+    }
+
     /// Outputs the badge as JSON to stdout.
     pub fn output(&self) {
         let json = serde_json::to_string_pretty(self).expect("badge serialization to succeed");
         println!("{}", json);
     }
+
+    /// Writes the badge as JSON to a file.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("badge serialization to succeed");
+        std::fs::write(path, json).with_context(|| format!("writing badge to `{}`", path.display()))
+    }
+
+    /// Gets the URL of a static shields.io image of the badge, for embedding
+    /// in Markdown.
+    pub fn image_url(&self) -> String {
+        format!(
+            "https://img.shields.io/badge/{}-{}-{}",
+            escape_static(&self.label),
+            escape_static(&self.message),
+            escape_static(&self.color)
+        )
+    }
+
+    /// Gets the label of the badge.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// Escapes text for a part of a static shields.io badge URL.
+///
+/// Dashes and underscores separate the parts of the URL, so they are doubled;
+/// everything else that is not URL-safe is percent-encoded.
+fn escape_static(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '-' => escaped.push_str("--"),
+            '_' => escaped.push_str("__"),
+            c if c.is_ascii_alphanumeric() || c == '.' || c == '~' => escaped.push(c),
+            c => {
+                let mut buffer = [0; 4];
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    escaped.push_str(&format!("%{byte:02X}"));
+                }
+            }
+        }
+    }
+    escaped
 }
 
 /// Determines badge color based on pass rate.
@@ -62,3 +146,101 @@ fn determine_color(passed: usize, total: usize) -> String {
         String::from("red")
     }
 }
+
+/// The values substituted into badge templates.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateValues {
+    /// The number of tests that passed.
+    pub passed: usize,
+    /// The number of tests that failed.
+    pub failed: usize,
+    /// The branch of the specification that was tested.
+    pub branch: String,
+    /// The version of the engine, if known.
+    pub engine_version: Option<String>,
+    /// The tier of tests the badge summarizes, if known.
+    pub tier: Option<String>,
+}
+
+impl TemplateValues {
+    /// Renders a template by replacing each `{placeholder}`.
+    ///
+    /// Placeholders without a value are left unchanged.
+    pub fn render(&self, template: &str) -> String {
+        let total = self.passed + self.failed;
+
+        PLACEHOLDER_REGEX
+            .replace_all(template, |captures: &regex::Captures<'_>| {
+                match &captures[1] {
+                    "passed" => self.passed.to_string(),
+                    "failed" => self.failed.to_string(),
+                    "total" => total.to_string(),
+                    "percent" if total == 0 => String::from("0"),
+                    "percent" => {
+                        format!("{:.1}", (self.passed as f64) / (total as f64) * 100.0)
+                    }
+                    "branch" => self.branch.clone(),
+                    "engine_version" if self.engine_version.is_some() => {
+                        self.engine_version.clone().unwrap()
+                    }
+                    "tier" if self.tier.is_some() => self.tier.clone().unwrap(),
+                    _ => captures[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+}
+
+/// Validates that a badge template only uses supported placeholders.
+pub fn validate_template(template: &str) -> Result<(), Diagnostic> {
+    for captures in PLACEHOLDER_REGEX.captures_iter(template) {
+        let name = &captures[1];
+
+        if !PLACEHOLDERS.contains(&name) {
+            let supported = PLACEHOLDERS
+                .iter()
+                .map(|p| format!("`{{{p}}}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            return Err(Diagnostic::new(
+                Code::UnknownPlaceholder,
+                format!("unknown placeholder `{{{name}}}` in badge template `{template}`"),
+            )
+            .with_hint(format!("supported placeholders are {supported}")));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates() {
+        let values = TemplateValues {
+            passed: 157,
+            failed: 12,
+            branch: String::from("wdl-1.2"),
+            engine_version: Some(String::from("0.9.0")),
+            tier: Some(String::from("optional")),
+        };
+
+        assert_eq!(
+            values.render("{passed}/{total} ({percent}%)"),
+            "157/169 (92.9%)"
+        );
+        assert_eq!(
+            values.render("Sprocket {engine_version} ({branch})"),
+            "Sprocket 0.9.0 (wdl-1.2)"
+        );
+        assert_eq!(values.render("{tier}: {percent}%"), "optional: 92.9%");
+        assert!(validate_template("{passed} {failed}").is_ok());
+        assert_eq!(
+            validate_template("{pased}").unwrap_err().code(),
+            Code::UnknownPlaceholder
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Baselines of tests that are known to fail.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::conformance::TestResult;
+
+/// How the result of a test relates to the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The test is not in the baseline.
+    Unlisted,
+    /// The test is in the baseline and failed as expected.
+    ExpectedFailure,
+    /// The test is in the baseline but passed.
+    UnexpectedPass,
+}
+
+/// A list of tests that are known to fail.
+///
+/// Engines can adopt conformance testing gradually by listing the tests they
+/// do not pass yet; those failures are then reported without failing the run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    /// The names of the tests that are known to fail, with or without the
+    /// `.wdl` extension.
+    failing: BTreeSet<String>,
+}
+
+impl Baseline {
+    /// Reads a baseline from a JSON file, or from a TOML file if the path has a
+    /// `.toml` extension.
+    ///
+    /// Either way, the file contains a `failing` list of test names.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading baseline `{}`", path.display()))?;
+
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&contents)
+                .with_context(|| format!("parsing baseline `{}`", path.display()))
+        } else {
+            serde_json::from_str(&contents)
+                .with_context(|| format!("parsing baseline `{}`", path.display()))
+        }
+    }
+
+    /// Writes the baseline to a JSON file, or to a TOML file if the path has a
+    /// `.toml` extension.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::to_string(self)?
+        } else {
+            serde_json::to_string_pretty(self)? + "\n"
+        };
+
+        std::fs::write(path, contents)
+            .with_context(|| format!("writing baseline `{}`", path.display()))
+    }
+
+    /// Creates a baseline listing the tests that failed in a run.
+    ///
+    /// Tests in this baseline that did not run, or were skipped, are kept so
+    /// that a filtered run does not drop them.
+    pub fn updated<'a>(
+        &self,
+        results: impl IntoIterator<Item = (&'a str, &'a TestResult)>,
+    ) -> Self {
+        let mut failing = BTreeSet::new();
+        let mut ran = Vec::new();
+
+        for (name, result) in results {
+            match result {
+                TestResult::Failed(_) => {
+                    failing.insert(normalize(name).to_string());
+                }
+                TestResult::Passed => {}
+                TestResult::Skipped(_) => continue,
+            }
+
+            ran.push(name);
+        }
+
+        for name in self.unknown(&ran) {
+            failing.insert(name.to_string());
+        }
+
+        Self { failing }
+    }
+
+    /// Returns whether a test is in the baseline.
+    pub fn contains(&self, name: &str) -> bool {
+        self.failing
+            .iter()
+            .any(|failing| normalize(failing) == normalize(name))
+    }
+
+    /// Gets how the result of a test relates to the baseline.
+    pub fn status(&self, name: &str, result: &TestResult) -> Status {
+        if !self.contains(name) {
+            return Status::Unlisted;
+        }
+
+        match result {
+            TestResult::Failed(_) => Status::ExpectedFailure,
+            TestResult::Passed => Status::UnexpectedPass,
+            TestResult::Skipped(_) => Status::Unlisted,
+        }
+    }
+
+    /// Gets the names in the baseline that are not among the given test
+    /// names.
+    pub fn unknown<'a>(&'a self, names: &[&str]) -> Vec<&'a str> {
+        self.failing
+            .iter()
+            .map(String::as_str)
+            .filter(|failing| {
+                !names
+                    .iter()
+                    .any(|name| normalize(name) == normalize(failing))
+            })
+            .collect()
+    }
+}
+
+/// Strips the `.wdl` extension from a test name.
+fn normalize(name: &str) -> &str {
+    name.trim_end_matches(".wdl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::SkipReason;
+
+    #[test]
+    fn statuses() {
+        let baseline: Baseline =
+            serde_json::from_str(r#"{"failing": ["gpu_task", "map.wdl"]}"#).unwrap();
+        let failed = TestResult::Failed(FailureReason::NoOutput);
+
+        assert_eq!(
+            baseline.status("gpu_task.wdl", &failed),
+            Status::ExpectedFailure
+        );
+        assert_eq!(
+            baseline.status("map.wdl", &TestResult::Passed),
+            Status::UnexpectedPass
+        );
+        assert_eq!(baseline.status("array.wdl", &failed), Status::Unlisted);
+        assert_eq!(
+            baseline.status("gpu_task.wdl", &TestResult::Skipped(SkipReason::Ignored)),
+            Status::Unlisted
+        );
+        assert_eq!(baseline.unknown(&["map.wdl"]), ["gpu_task"]);
+    }
+
+    #[test]
+    fn update() {
+        let baseline: Baseline =
+            serde_json::from_str(r#"{"failing": ["gpu_task", "map.wdl", "array"]}"#).unwrap();
+        let failed = TestResult::Failed(FailureReason::NoOutput);
+
+        let updated = baseline.updated([
+            ("map.wdl", &TestResult::Passed),
+            ("array.wdl", &TestResult::Skipped(SkipReason::Ignored)),
+            ("struct.wdl", &failed),
+        ]);
+
+        assert_eq!(
+            updated.failing.iter().collect::<Vec<_>>(),
+            ["array", "gpu_task", "struct"]
+        );
+    }
+}
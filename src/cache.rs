@@ -0,0 +1,428 @@
+//! Directories that `spectool` keeps within the system's temporary directory.
+//!
+//! Checkouts of the specification repository and compiled test suites are
+//! kept after a run so that they can be inspected. Each is created with a
+//! recognizable prefix so that stale directories can be cleaned up later.
+//! While a directory is in use, a lock is held on a file next to it so that
+//! other runs do not remove it.
+
+use std::fs::DirEntry;
+use std::fs::File;
+use std::fs::TryLockError;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use std::str::FromStr;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::info;
+use tracing::warn;
+
+/// The prefix of a directory containing a checkout of the specification
+/// repository.
+pub const REPOSITORY_PREFIX: &str = "spectool-repo-";
+
+/// The prefix of a directory containing a compiled test suite.
+pub const SUITE_PREFIX: &str = "spectool-suite-";
+
+/// The prefix of the working directory of a single test.
+pub const WORKDIR_PREFIX: &str = "spectool-work-";
+
+/// The prefixes of every directory kept by `spectool`.
+const PREFIXES: &[&str] = &[REPOSITORY_PREFIX, SUITE_PREFIX, WORKDIR_PREFIX];
+
+/// The directories created by [`keep_dir()`], which stay in use until
+/// `spectool` exits.
+static KEPT: Mutex<Vec<InUse>> = Mutex::new(Vec::new());
+
+/// Creates a directory with the given prefix within the system's temporary
+/// directory that is kept after `spectool` exits.
+///
+/// The directory is marked as in use until `spectool` exits.
+pub fn keep_dir(prefix: &str) -> std::io::Result<PathBuf> {
+    let dir = tempfile::Builder::new().prefix(prefix).tempdir()?.keep();
+    let in_use = InUse::acquire(&dir)?;
+    KEPT.lock().expect("lock to not be poisoned").push(in_use);
+    Ok(dir)
+}
+
+/// Gets the path to the lock file of a directory.
+fn lock_path(dir: &Path) -> PathBuf {
+    let mut path = dir.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// Marks a directory as in use so that it is not removed by other runs.
+///
+/// The mark is a lock held on a file next to the directory, which is released
+/// and removed when this is dropped (or released when `spectool` exits).
+#[derive(Debug)]
+pub struct InUse {
+    /// The path to the lock file.
+    path: PathBuf,
+
+    /// The locked file.
+    file: File,
+}
+
+impl InUse {
+    /// Marks a directory as in use.
+    pub fn acquire(dir: &Path) -> std::io::Result<Self> {
+        let path = lock_path(dir);
+        let file = File::create(&path)?;
+        file.lock_shared()?;
+        Ok(Self { path, file })
+    }
+
+    /// Checks whether a directory is marked as in use by any process.
+    pub fn check(dir: &Path) -> bool {
+        let Ok(file) = File::open(lock_path(dir)) else {
+            return false;
+        };
+
+        matches!(file.try_lock(), Err(TryLockError::WouldBlock))
+    }
+}
+
+impl Drop for InUse {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+        let _ = self.file.unlock();
+    }
+}
+
+/// A size in bytes, written with an optional `K`, `M`, or `G` suffix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Size(u64);
+
+impl Size {
+    /// Creates a size from a number of bytes.
+    pub fn from_bytes(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    /// Gets the number of bytes.
+    pub fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "invalid size `{s}`: expected a number optionally followed by `K`, `M`, or `G` \
+                 (e.g., `500M`)"
+            )
+        };
+
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number = number.parse::<u64>().map_err(|_| error())?;
+
+        let multiplier = match unit.to_ascii_uppercase().trim_end_matches('B') {
+            "" => 1,
+            "K" => 1 << 10,
+            "M" => 1 << 20,
+            "G" => 1 << 30,
+            _ => return Err(error()),
+        };
+
+        number.checked_mul(multiplier).map(Self).ok_or_else(error)
+    }
+}
+
+impl TryFrom<String> for Size {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl std::fmt::Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const UNITS: &[(u64, &str)] = &[(1 << 30, "G"), (1 << 20, "M"), (1 << 10, "K")];
+
+        match UNITS.iter().find(|(size, _)| self.0 >= *size) {
+            Some((size, unit)) => write!(f, "{:.1}{unit}", self.0 as f64 / *size as f64),
+            None => write!(f, "{}B", self.0),
+        }
+    }
+}
+
+/// A directory kept by `spectool`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The path to the directory.
+    path: PathBuf,
+
+    /// When the directory was last modified.
+    modified: SystemTime,
+
+    /// The total size of the directory's contents in bytes.
+    size: u64,
+}
+
+impl Entry {
+    /// Creates a new entry.
+    pub fn new(path: PathBuf, modified: SystemTime, size: u64) -> Self {
+        Self {
+            path,
+            modified,
+            size,
+        }
+    }
+
+    /// Gets the path to the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets when the directory was last modified.
+    pub fn modified(&self) -> SystemTime {
+        self.modified
+    }
+
+    /// Gets the total size of the directory's contents in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Finds every directory kept by `spectool` within a directory that is not
+/// in use.
+///
+/// Directories that cannot be read are skipped with a warning.
+pub fn entries(dir: &Path) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading `{}`", dir.display()))? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("skipping an entry of `{}`: {e}", dir.display());
+                continue;
+            }
+        };
+
+        match read_entry(&entry) {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(e) => {
+                let path = entry.path();
+                warn!("skipping `{}`: {e:#}", path.display());
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Reads a directory entry, returning `None` if it is not a directory kept
+/// by `spectool` or it is in use.
+fn read_entry(entry: &DirEntry) -> Result<Option<Entry>> {
+    let name = entry.file_name();
+    let name = name.to_string_lossy();
+
+    if !PREFIXES.iter().any(|prefix| name.starts_with(prefix)) || !entry.file_type()?.is_dir() {
+        return Ok(None);
+    }
+
+    let path = entry.path();
+    if InUse::check(&path) {
+        return Ok(None);
+    }
+
+    let modified = entry.metadata()?.modified()?;
+    let size = fs_extra::dir::get_size(&path)
+        .with_context(|| format!("measuring the size of `{}`", path.display()))?;
+    Ok(Some(Entry::new(path, modified, size)))
+}
+
+/// Selects the entries to remove.
+///
+/// Entries last modified more than `older_than` before `now` are removed.
+/// Then, the oldest remaining entries are removed until the total size is at
+/// most `max_size`. With neither limit, every entry is removed.
+pub fn select(
+    mut entries: Vec<Entry>,
+    now: SystemTime,
+    older_than: Option<Duration>,
+    max_size: Option<u64>,
+) -> Vec<Entry> {
+    if older_than.is_none() && max_size.is_none() {
+        return entries;
+    }
+
+    // Newest first, so that the oldest entries are popped from the end.
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.modified));
+
+    let (mut kept, mut removed) = entries.into_iter().partition::<Vec<_>, _>(|entry| {
+        older_than
+            .is_none_or(|limit| now.duration_since(entry.modified).unwrap_or_default() <= limit)
+    });
+
+    if let Some(max_size) = max_size {
+        while kept.iter().map(Entry::size).sum::<u64>() > max_size {
+            removed.extend(kept.pop());
+        }
+    }
+
+    removed
+}
+
+/// Removes the selected entries, returning the number of bytes freed.
+pub fn remove(entries: &[Entry]) -> Result<u64> {
+    let mut freed = 0;
+
+    for entry in entries {
+        std::fs::remove_dir_all(entry.path())
+            .with_context(|| format!("removing `{}`", entry.path().display()))?;
+        let _ = std::fs::remove_file(lock_path(entry.path()));
+        freed += entry.size();
+    }
+
+    Ok(freed)
+}
+
+/// Evicts the oldest directories kept by `spectool` within the given
+/// directories until their total size is at most `max_size`.
+///
+/// Directories that do not exist are skipped, as are kept directories that
+/// are in use.
+pub fn evict(dirs: &[PathBuf], max_size: Size) -> Result<()> {
+    let mut scanned = Vec::new();
+    let mut found = Vec::new();
+
+    for dir in dirs {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+        if !dir.is_dir() || scanned.contains(&dir) {
+            continue;
+        }
+
+        found.extend(entries(&dir)?);
+        scanned.push(dir);
+    }
+
+    let evicted = select(found, SystemTime::now(), None, Some(max_size.bytes()));
+
+    if !evicted.is_empty() {
+        let freed = remove(&evicted)?;
+        info!(
+            "evicted {} cached director{} ({})",
+            evicted.len(),
+            if evicted.len() == 1 { "y" } else { "ies" },
+            Size(freed)
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 86400);
+        let entry = |name: &str, days_ago: u64, size: u64| {
+            Entry::new(
+                PathBuf::from(name),
+                now - Duration::from_secs(days_ago * 86400),
+                size,
+            )
+        };
+        let entries = vec![entry("a", 40, 10), entry("b", 10, 20), entry("c", 1, 30)];
+        let names = |entries: Vec<Entry>| {
+            entries
+                .iter()
+                .map(|entry| entry.path().display().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(select(entries.clone(), now, None, None)),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            names(select(
+                entries.clone(),
+                now,
+                Some(Duration::from_secs(30 * 86400)),
+                None
+            )),
+            ["a"]
+        );
+        assert_eq!(
+            names(select(entries.clone(), now, None, Some(30))),
+            ["a", "b"]
+        );
+        assert!(select(entries, now, None, Some(60)).is_empty());
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!("500".parse::<Size>().unwrap().bytes(), 500);
+        assert_eq!("2K".parse::<Size>().unwrap().bytes(), 2048);
+        assert_eq!("1gb".parse::<Size>().unwrap().bytes(), 1 << 30);
+        assert_eq!("1536M".parse::<Size>().unwrap().to_string(), "1.5G");
+        assert!("1T".parse::<Size>().is_err());
+    }
+
+    #[test]
+    fn in_use() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join(format!("{WORKDIR_PREFIX}test"));
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("file"), "contents").unwrap();
+
+        let in_use = InUse::acquire(&dir).unwrap();
+        assert!(InUse::check(&dir));
+        assert!(entries(root.path()).unwrap().is_empty());
+
+        drop(in_use);
+        assert!(!InUse::check(&dir));
+        assert!(!lock_path(&dir).exists());
+
+        let found = entries(root.path()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path(), dir);
+        assert_eq!(found[0].size(), 8);
+    }
+
+    #[test]
+    fn eviction() {
+        let temp = tempfile::tempdir().unwrap();
+        let work_root = tempfile::tempdir().unwrap();
+        let old = work_root.path().join(format!("{WORKDIR_PREFIX}old"));
+        let held = temp.path().join(format!("{SUITE_PREFIX}held"));
+
+        for dir in [&old, &held] {
+            std::fs::create_dir(dir).unwrap();
+            std::fs::write(dir.join("file"), "contents").unwrap();
+        }
+
+        let _in_use = InUse::acquire(&held).unwrap();
+        let dirs = [
+            temp.path().to_path_buf(),
+            work_root.path().to_path_buf(),
+            work_root.path().to_path_buf(),
+            temp.path().join("missing"),
+        ];
+        evict(&dirs, Size::from_bytes(0)).unwrap();
+
+        assert!(!old.exists());
+        assert!(held.exists());
+    }
+}
@@ -1,3 +1,59 @@
 //! Subcommands supported by the CLI.
 
+pub mod cache;
+pub mod diff;
+pub mod doctor;
+pub mod explain;
+pub mod history;
+pub mod list;
+pub mod merge_results;
+pub mod open;
+pub mod replay;
+pub mod selector_test;
+pub mod stats;
 pub mod test;
+pub mod validate_output;
+pub mod validate_spec;
+
+use std::time::Duration;
+
+/// Parses a duration, such as an age or a time limit, written as a number
+/// followed by `d`, `h`, `m`, or `s`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let error = || {
+        format!(
+            "invalid duration `{s}`: expected a number followed by `d`, `h`, `m`, or `s` (e.g., \
+             `30d`)"
+        )
+    };
+
+    let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(error)?;
+    let (number, unit) = s.split_at(split);
+    let number = number.parse::<u64>().map_err(|_| error())?;
+
+    let secs = match unit {
+        "d" => number.checked_mul(86400),
+        "h" => number.checked_mul(3600),
+        "m" => number.checked_mul(60),
+        "s" => Some(number),
+        _ => None,
+    };
+
+    secs.map(Duration::from_secs).ok_or_else(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_parsing() {
+        assert_eq!(
+            parse_duration("30d").unwrap(),
+            Duration::from_secs(30 * 86400)
+        );
+        assert_eq!(parse_duration("90m").unwrap(), Duration::from_secs(90 * 60));
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("1w").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! A subcommand to manage the directories that `spectool` keeps between runs.
+
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use clap::Parser;
+use clap::Subcommand;
+
+use crate::cache;
+use crate::cache::Size;
+
+/// Manages the repository checkouts, compiled suites, and working directories
+/// that `spectool` keeps in the temporary directory.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The action to perform.
+    #[command(subcommand)]
+    action: Action,
+}
+
+/// An action on the kept directories.
+#[derive(Subcommand, Debug)]
+pub enum Action {
+    /// Removes kept directories.
+    ///
+    /// Without any options, every kept directory is removed.
+    Clean {
+        /// Remove directories last modified longer ago than this (e.g., `30d`,
+        /// `12h`).
+        #[arg(long, value_name = "AGE", value_parser = super::parse_duration)]
+        older_than: Option<Duration>,
+
+        /// Then remove the oldest remaining directories until their total size
+        /// is at most this (e.g., `5G`).
+        #[arg(long, value_name = "SIZE")]
+        max_size: Option<Size>,
+
+        /// Print the directories that would be removed without removing them.
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    match args.action {
+        Action::Clean {
+            older_than,
+            max_size,
+            dry_run,
+        } => {
+            let selected = cache::select(
+                cache::entries(&std::env::temp_dir())?,
+                SystemTime::now(),
+                older_than,
+                max_size.map(|size| size.bytes()),
+            );
+
+            for entry in &selected {
+                println!("{}", entry.path().display());
+            }
+
+            let freed = if dry_run {
+                selected.iter().map(cache::Entry::size).sum()
+            } else {
+                cache::remove(&selected)?
+            };
+
+            eprintln!(
+                "{} {} director{} ({})",
+                if dry_run { "would remove" } else { "removed" },
+                selected.len(),
+                if selected.len() == 1 { "y" } else { "ies" },
+                Size::from_bytes(freed)
+            );
+        }
+    }
+
+    Ok(())
+}
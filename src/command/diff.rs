@@ -0,0 +1,232 @@
+//! A subcommand to compare the results of two test runs.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::report::Outcome;
+use crate::report::Report;
+
+/// Compares two results files (from `--results-file`), such as from two
+/// engines, and reports the tests that pass in one but not the other.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The first results file.
+    a: PathBuf,
+
+    /// The second results file.
+    b: PathBuf,
+
+    /// The name of the first results in the output (defaults to the file
+    /// name).
+    #[arg(long)]
+    label_a: Option<String>,
+
+    /// The name of the second results in the output (defaults to the file
+    /// name).
+    #[arg(long)]
+    label_b: Option<String>,
+}
+
+/// The state of a test within a results file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum State {
+    /// The test passed.
+    Passed,
+    /// The test failed.
+    Failed,
+    /// The test was skipped.
+    Skipped,
+    /// The test is not in the results file.
+    Missing,
+}
+
+impl State {
+    /// Every state, in the order they are shown.
+    const ALL: [State; 4] = [State::Passed, State::Failed, State::Skipped, State::Missing];
+}
+
+impl From<Option<&Outcome>> for State {
+    fn from(outcome: Option<&Outcome>) -> Self {
+        match outcome {
+            Some(Outcome::Passed) => State::Passed,
+            Some(Outcome::Failed { .. }) => State::Failed,
+            Some(Outcome::Skipped { .. }) => State::Skipped,
+            None => State::Missing,
+        }
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            State::Passed => "passed",
+            State::Failed => "failed",
+            State::Skipped => "skipped",
+            State::Missing => "missing",
+        })
+    }
+}
+
+/// The comparison of two results files.
+#[derive(Debug, Default)]
+struct Comparison {
+    /// The number of tests for each pair of states.
+    matrix: BTreeMap<(State, State), usize>,
+
+    /// The tests that passed only in the first results, with how they ended
+    /// in the second.
+    only_a: Vec<(String, String)>,
+
+    /// The tests that passed only in the second results, with how they ended
+    /// in the first.
+    only_b: Vec<(String, String)>,
+}
+
+impl Comparison {
+    /// Compares two reports.
+    fn new(a: &Report, b: &Report) -> Self {
+        let outcomes = |report: &Report| {
+            report
+                .tests()
+                .iter()
+                .map(|entry| (entry.name().to_string(), entry.result().clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let a = outcomes(a);
+        let b = outcomes(b);
+
+        let mut names = a.keys().chain(b.keys()).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        let mut comparison = Self::default();
+        for name in names {
+            let (outcome_a, outcome_b) = (a.get(name), b.get(name));
+            let states = (State::from(outcome_a), State::from(outcome_b));
+            *comparison.matrix.entry(states).or_default() += 1;
+
+            match states {
+                (State::Passed, State::Passed) => {}
+                (State::Passed, _) => comparison.only_a.push((name.clone(), describe(outcome_b))),
+                (_, State::Passed) => comparison.only_b.push((name.clone(), describe(outcome_a))),
+                _ => {}
+            }
+        }
+
+        comparison
+    }
+}
+
+/// Describes how a test ended for the list of differences.
+fn describe(outcome: Option<&Outcome>) -> String {
+    match outcome {
+        Some(Outcome::Failed { code, .. }) => format!("failed: {code}"),
+        outcome => State::from(outcome).to_string(),
+    }
+}
+
+/// Gets the label for a results file.
+fn label(label: Option<String>, path: &Path) -> String {
+    label.unwrap_or_else(|| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string())
+    })
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let a = Report::from_path(&args.a)?;
+    let b = Report::from_path(&args.b)?;
+    let label_a = label(args.label_a, &args.a);
+    let label_b = label(args.label_b, &args.b);
+
+    let comparison = Comparison::new(&a, &b);
+
+    for (label, other, tests) in [
+        (&label_a, &label_b, &comparison.only_a),
+        (&label_b, &label_a, &comparison.only_b),
+    ] {
+        println!("passed only in {label} ({}):", tests.len());
+        for (name, outcome) in tests {
+            println!("  {name} ({other}: {outcome})");
+        }
+        println!();
+    }
+
+    // The matrix of states, with the first results as rows
+    let corner = format!("{label_a} \\ {label_b}");
+    let width = corner.len().max(7);
+    print!("{corner:width$}");
+    for state in State::ALL {
+        print!("  {state:>7}");
+    }
+    println!();
+
+    for row in State::ALL {
+        print!("{row:width$}");
+        for column in State::ALL {
+            let count = comparison
+                .matrix
+                .get(&(row, column))
+                .copied()
+                .unwrap_or_default();
+            print!("  {count:>7}");
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::SkipReason;
+    use crate::conformance::TestResult;
+    use crate::report::Entry;
+
+    #[test]
+    fn comparison() {
+        let failed = TestResult::Failed(FailureReason::NoOutput);
+        let a = Report::new(vec![
+            Entry::new("both.wdl", &TestResult::Passed, None),
+            Entry::new("a.wdl", &TestResult::Passed, None),
+            Entry::new("neither.wdl", &failed, None),
+            Entry::new("only_in_a.wdl", &TestResult::Passed, None),
+        ]);
+        let b = Report::new(vec![
+            Entry::new("both.wdl", &TestResult::Passed, None),
+            Entry::new("a.wdl", &failed, None),
+            Entry::new(
+                "neither.wdl",
+                &TestResult::Skipped(SkipReason::Ignored),
+                None,
+            ),
+            Entry::new("b.wdl", &TestResult::Passed, None),
+        ]);
+
+        let comparison = Comparison::new(&a, &b);
+        assert_eq!(
+            comparison.only_a,
+            [
+                (String::from("a.wdl"), String::from("failed: no_output")),
+                (String::from("only_in_a.wdl"), String::from("missing")),
+            ]
+        );
+        assert_eq!(
+            comparison.only_b,
+            [(String::from("b.wdl"), String::from("missing"))]
+        );
+        assert_eq!(comparison.matrix[&(State::Passed, State::Passed)], 1);
+        assert_eq!(comparison.matrix[&(State::Passed, State::Failed)], 1);
+        assert_eq!(comparison.matrix[&(State::Failed, State::Skipped)], 1);
+        assert_eq!(comparison.matrix[&(State::Missing, State::Passed)], 1);
+    }
+}
@@ -0,0 +1,336 @@
+//! A subcommand to diagnose problems with the environment `spectool` runs in.
+
+use std::fmt;
+use std::io::IsTerminal as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Result;
+use anyhow::bail;
+use clap::Parser;
+
+use crate::engine::Engine;
+
+/// Checks that the tools, directories, and network access that test runs
+/// depend on are available, and suggests fixes for any that are not.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The git repository URL to check is reachable.
+    #[arg(long, default_value = "https://github.com/openwdl/wdl.git")]
+    repository_url: String,
+
+    /// Check that the command of this engine's preset can be found.
+    #[arg(long, value_enum, conflicts_with = "command")]
+    engine: Option<Engine>,
+
+    /// Check that the program of this command template can be found.
+    #[arg(long, value_name = "TEMPLATE")]
+    command: Option<String>,
+
+    /// Skip checking that the repository is reachable.
+    #[arg(long, default_value_t = false)]
+    offline: bool,
+}
+
+/// The status of a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    /// The check passed.
+    Ok,
+    /// The check failed, but test runs can proceed in some configurations.
+    Warning,
+    /// The check failed, and test runs are expected to fail.
+    Error,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Status::Ok => "ok",
+            Status::Warning => "warning",
+            Status::Error => "error",
+        })
+    }
+}
+
+/// The result of a single check.
+#[derive(Debug)]
+struct Check {
+    /// What was checked.
+    name: String,
+
+    /// The status of the check.
+    status: Status,
+
+    /// What was found.
+    detail: String,
+
+    /// How to fix a failed check.
+    fix: Option<String>,
+}
+
+impl Check {
+    /// Creates a passed check.
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    /// Creates a failed check.
+    fn failed(
+        name: impl Into<String>,
+        status: Status,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    /// Prints the check.
+    fn print(&self, color: bool) {
+        let code = match self.status {
+            Status::Ok => "\x1b[32m",
+            Status::Warning => "\x1b[33m",
+            Status::Error => "\x1b[31m",
+        };
+        let (code, reset) = if color { (code, "\x1b[0m") } else { ("", "") };
+        let status = format!("{:<8}", self.status);
+
+        println!("{code}{status}{reset} {}: {}", self.name, self.detail);
+        if let Some(fix) = &self.fix {
+            println!("         fix: {fix}");
+        }
+    }
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let mut checks = vec![check_bash(), check_git(), check_docker()];
+    checks.push(check_writable(
+        "temporary directory",
+        &std::env::temp_dir(),
+        Status::Error,
+        "set `TMPDIR` to a writable directory",
+    ));
+    checks.push(check_writable(
+        "current directory",
+        &std::env::current_dir()?,
+        Status::Warning,
+        "run from a writable directory, or write results elsewhere (e.g., with \
+         `--results-file`)",
+    ));
+
+    if !args.offline {
+        checks.push(check_repository(&args.repository_url));
+    }
+
+    match (&args.engine, &args.command) {
+        (Some(engine), _) => checks.push(check_command(
+            &format!("{engine} command"),
+            engine.preset().command(),
+        )),
+        (None, Some(command)) => checks.push(check_command("command", command)),
+        (None, None) => {}
+    }
+
+    let color = crate::diagnostic::use_color(std::io::stdout().is_terminal());
+    for check in &checks {
+        check.print(color);
+    }
+
+    let errors = checks
+        .iter()
+        .filter(|check| check.status == Status::Error)
+        .count();
+    if errors > 0 {
+        bail!(
+            "{errors} check{} failed",
+            if errors == 1 { "" } else { "s" }
+        );
+    }
+
+    Ok(())
+}
+
+/// Runs a program and returns the first line of its output, or a description
+/// of why it could not be run.
+fn probe(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program).args(args).output().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            String::from("not found")
+        } else {
+            e.to_string()
+        }
+    })?;
+
+    let first_line = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    if output.status.success() {
+        Ok(first_line(&output.stdout))
+    } else {
+        Err(first_line(&output.stderr))
+    }
+}
+
+/// Checks that `bash`, which every command is run with, is available.
+fn check_bash() -> Check {
+    match probe("bash", &["--version"]) {
+        Ok(version) => Check::ok("bash", version),
+        Err(e) => Check::failed(
+            "bash",
+            Status::Error,
+            e,
+            "install `bash` and add it to `PATH`; every test command is run with it",
+        ),
+    }
+}
+
+/// Checks that `git` is available.
+///
+/// `spectool` clones the specification without `git`, but it is needed to
+/// clone the specification by hand for `--specification-dir`.
+fn check_git() -> Check {
+    match probe("git", &["--version"]) {
+        Ok(version) => Check::ok("git", version),
+        Err(e) => Check::failed(
+            "git",
+            Status::Warning,
+            e,
+            "install `git` to clone the specification yourself for `--specification-dir`",
+        ),
+    }
+}
+
+/// Checks that Docker is available and that its daemon can be reached.
+fn check_docker() -> Check {
+    match probe("docker", &["info", "--format", "{{.ServerVersion}}"]) {
+        Ok(version) => Check::ok("docker", format!("server version {version}")),
+        Err(e) if e == "not found" => Check::failed(
+            "docker",
+            Status::Warning,
+            e,
+            "install Docker if your engine runs tasks in containers or you use `--pull-images`",
+        ),
+        Err(e) => Check::failed(
+            "docker",
+            Status::Warning,
+            format!("the daemon could not be reached: {e}"),
+            "start the Docker daemon, or add your user to the `docker` group",
+        ),
+    }
+}
+
+/// Checks that a file can be created within a directory.
+fn check_writable(name: &str, dir: &Path, status: Status, fix: &str) -> Check {
+    match tempfile::tempfile_in(dir) {
+        Ok(_) => Check::ok(name, format!("`{}` is writable", dir.display())),
+        Err(e) => Check::failed(
+            name,
+            status,
+            format!("`{}` is not writable: {e}", dir.display()),
+            fix,
+        ),
+    }
+}
+
+/// Checks that the specification repository can be reached without
+/// credentials.
+fn check_repository(url: &str) -> Check {
+    let name = "repository";
+    let result = git2::Remote::create_detached(url)
+        .and_then(|mut remote| remote.connect(git2::Direction::Fetch));
+
+    match result {
+        Ok(()) => Check::ok(name, format!("`{url}` is reachable")),
+        Err(e) if e.code() == git2::ErrorCode::Auth || e.message().contains("401") => {
+            Check::failed(
+                name,
+                Status::Error,
+                format!("`{url}` requires credentials: {}", e.message()),
+                "clone the repository with your own credentials and pass \
+                 `--specification-dir`",
+            )
+        }
+        Err(e) => Check::failed(
+            name,
+            Status::Error,
+            format!("`{url}` could not be reached: {}", e.message()),
+            "check your network connection and proxy settings (e.g., `HTTPS_PROXY`), or \
+             pass `--specification-dir` with a local checkout",
+        ),
+    }
+}
+
+/// Checks that the program a command template runs can be found.
+fn check_command(name: &str, command: &str) -> Check {
+    let Some(program) = command.split_whitespace().next() else {
+        return Check::failed(
+            name,
+            Status::Error,
+            "the command is empty",
+            "provide a command template",
+        );
+    };
+
+    match find_program(program, std::env::var_os("PATH").as_deref()) {
+        Some(path) => Check::ok(name, format!("`{program}` is `{}`", path.display())),
+        None => Check::failed(
+            name,
+            Status::Error,
+            format!("`{program}` was not found"),
+            format!("install `{program}` or add the directory containing it to `PATH`"),
+        ),
+    }
+}
+
+/// Finds a program within the directories of a `PATH`.
+///
+/// Programs containing a path separator are looked up directly.
+fn find_program(program: &str, path: Option<&std::ffi::OsStr>) -> Option<PathBuf> {
+    if program.contains(std::path::MAIN_SEPARATOR) {
+        let path = PathBuf::from(program);
+        return path.is_file().then_some(path);
+    }
+
+    std::env::split_paths(path?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_programs() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("engine");
+        std::fs::write(&program, "").unwrap();
+
+        let path = std::env::join_paths([Path::new("/nonexistent"), dir.path()]).unwrap();
+        assert_eq!(find_program("engine", Some(&path)), Some(program.clone()));
+        assert_eq!(find_program("missing", Some(&path)), None);
+        assert_eq!(find_program("engine", None), None);
+        assert_eq!(
+            find_program(program.to_str().unwrap(), None),
+            Some(program.clone())
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! A subcommand to explain failure codes.
+
+use anyhow::Result;
+use clap::Parser;
+use strum::IntoEnumIterator;
+
+use crate::conformance::FailureCategory;
+use crate::conformance::FailureCode;
+use crate::diagnostic::Code;
+use crate::diagnostic::Diagnostic;
+
+/// Explains a failure code reported by the conformance tests.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The failure code (e.g., `output_mismatch`) or category (e.g.,
+    /// `validation`) to explain.
+    code: String,
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let codes = find_codes(&args.code)?;
+
+    let explanations = codes.into_iter().map(render).collect::<Vec<_>>();
+    println!("{}", explanations.join("\n"));
+
+    Ok(())
+}
+
+/// Finds the failure codes matching a code or category name.
+fn find_codes(name: &str) -> Result<Vec<FailureCode>, Diagnostic> {
+    if let Some(code) = FailureCode::iter().find(|code| code.as_str() == name) {
+        return Ok(vec![code]);
+    }
+
+    if let Some(category) = FailureCategory::iter().find(|category| category.as_str() == name) {
+        return Ok(FailureCode::iter()
+            .filter(|code| code.category() == category)
+            .collect());
+    }
+
+    let codes = FailureCode::iter()
+        .map(|code| format!("`{code}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Err(Diagnostic::new(
+        Code::UnknownFailureCode,
+        format!("unknown failure code `{name}`"),
+    )
+    .with_hint(format!("supported failure codes are {codes}")))
+}
+
+/// Renders the explanation of a failure code.
+fn render(code: FailureCode) -> String {
+    let explanation = code.explain();
+    let mut rendered = format!(
+        "{code} ({category})\n\nWhat spectool checked:\n  {checked}\n",
+        category = code.category(),
+        checked = explanation.checked()
+    );
+
+    rendered.push_str("\nCommon causes:\n");
+    for cause in explanation.causes() {
+        rendered.push_str(&format!("  - {cause}\n"));
+    }
+
+    if !explanation.spec_sections().is_empty() {
+        rendered.push_str("\nRelevant specification sections:\n");
+        for section in explanation.spec_sections() {
+            rendered.push_str(&format!("  - {section}\n"));
+        }
+    }
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_and_categories() {
+        assert_eq!(find_codes("no_output").unwrap(), [FailureCode::NoOutput]);
+        assert_eq!(
+            find_codes("validation").unwrap(),
+            [
+                FailureCode::OutputMismatch,
+                FailureCode::NoOutput,
+                FailureCode::FileMismatch
+            ]
+        );
+        assert_eq!(
+            find_codes("no-output").unwrap_err().code(),
+            Code::UnknownFailureCode
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! A subcommand to show the pass-rate trend of the runs within a history.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::history;
+use crate::history::Record;
+
+/// Shows the pass rate of each run recorded in a history file (from
+/// `--history-file` or a `history` sink), along with the tests each run newly
+/// broke or fixed.
+///
+/// Runs are compared with the previous run of the same engine against the
+/// same branch of the specification.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The history file.
+    path: PathBuf,
+
+    /// Only show runs of this engine (the engine preset, or otherwise the
+    /// label of the badge).
+    #[arg(long)]
+    engine: Option<String>,
+
+    /// Only show runs against this branch of the specification.
+    #[arg(short, long)]
+    branch: Option<String>,
+
+    /// The number of most recent runs to show.
+    #[arg(long, default_value_t = 20)]
+    last: usize,
+}
+
+/// A run with the change from the previous run of its series.
+#[derive(Debug)]
+struct Row<'a> {
+    /// The run.
+    record: &'a Record,
+
+    /// The change in pass rate from the previous run, in percentage points.
+    change: Option<f64>,
+
+    /// The tests the run newly broke.
+    broken: Vec<&'a str>,
+
+    /// The tests the run newly fixed.
+    fixed: Vec<&'a str>,
+}
+
+/// Compares each run with the previous run of its series.
+fn rows(records: &[Record]) -> Vec<Row<'_>> {
+    let mut previous = BTreeMap::<(&str, &str), &Record>::new();
+
+    records
+        .iter()
+        .map(|record| {
+            let row = match previous.get(&record.series()) {
+                Some(earlier) => {
+                    let (broken, fixed) = record.changes_since(earlier);
+                    Row {
+                        record,
+                        change: Some(record.summary().percent() - earlier.summary().percent()),
+                        broken,
+                        fixed,
+                    }
+                }
+                None => Row {
+                    record,
+                    change: None,
+                    broken: Vec::new(),
+                    fixed: Vec::new(),
+                },
+            };
+
+            previous.insert(record.series(), record);
+            row
+        })
+        .collect()
+}
+
+/// Renders a table of runs followed by the tests each run broke or fixed.
+fn render(rows: &[Row<'_>]) -> String {
+    let mut out = String::new();
+    if rows.is_empty() {
+        let _ = writeln!(out, "No runs recorded.");
+        return out;
+    }
+
+    let width = rows
+        .iter()
+        .map(|row| row.record.engine().len())
+        .max()
+        .unwrap_or_default()
+        .max("engine".len());
+    let _ = writeln!(
+        out,
+        "{:<16}  {:<width$}  {:<10}  {:<7}  {:>6}  {:>6}  {:>9}  change",
+        "finished", "engine", "branch", "commit", "passed", "failed", "pass rate"
+    );
+
+    for row in rows {
+        let summary = row.record.summary();
+        let commit = summary.commit().map(|c| &c[..c.len().min(7)]);
+        let change = row
+            .change
+            .map(|change| format!("{change:+.1}"))
+            .unwrap_or_default();
+        let line = format!(
+            "{:<16}  {:<width$}  {:<10}  {:<7}  {:>6}  {:>6}  {:>8.1}%  {change}",
+            history::format_timestamp(row.record.finished_at()),
+            row.record.engine(),
+            summary.branch().unwrap_or("-"),
+            commit.unwrap_or("-"),
+            summary.passed(),
+            summary.failed(),
+            summary.percent(),
+        );
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+
+    let changed = rows
+        .iter()
+        .filter(|row| !row.broken.is_empty() || !row.fixed.is_empty())
+        .collect::<Vec<_>>();
+    if changed.is_empty() {
+        return out;
+    }
+
+    let _ = writeln!(out, "\nChanges:");
+    for row in changed {
+        let _ = writeln!(
+            out,
+            "  {} {}",
+            history::format_timestamp(row.record.finished_at()),
+            row.record.engine()
+        );
+        for (verb, names) in [("broke", &row.broken), ("fixed", &row.fixed)] {
+            if !names.is_empty() {
+                let _ = writeln!(out, "    {verb} {}", names.join(", "));
+            }
+        }
+    }
+
+    out
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let records = history::read(&args.path)?
+        .into_iter()
+        .filter(|record| {
+            let (engine, branch) = record.series();
+            args.engine.as_deref().is_none_or(|e| e == engine)
+                && args.branch.as_deref().is_none_or(|b| b == branch)
+        })
+        .collect::<Vec<_>>();
+
+    // Runs are compared before the window is applied, so the first run shown
+    // still has a change.
+    let rows = rows(&records);
+    let rows = &rows[rows.len().saturating_sub(args.last)..];
+    print!("{}", render(rows));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::TestResult;
+    use crate::report::Entry;
+    use crate::report::Report;
+
+    #[test]
+    fn trend() {
+        let failed = TestResult::Failed(FailureReason::NoOutput);
+        let record = |engine: &str, a: &TestResult, b: &TestResult, finished_at: u64| {
+            let report = Report::new(vec![
+                Entry::new("a.wdl", a, None),
+                Entry::new("b.wdl", b, None),
+            ]);
+            let summary = report
+                .summary()
+                .with_engine(Some(engine.to_string()), None)
+                .with_specification("wdl-1.2", Some(String::from("0123456789")));
+            Record::new("WDL", &summary, &report).with_finished_at(finished_at)
+        };
+        let records = [
+            record("sprocket", &TestResult::Passed, &failed, 0),
+            record("miniwdl", &failed, &failed, 60),
+            record("sprocket", &failed, &TestResult::Passed, 86400),
+        ];
+
+        let rows = rows(&records);
+        assert_eq!(rows[1].change, None);
+        assert_eq!(rows[2].change, Some(0.0));
+        assert_eq!(rows[2].broken, ["a.wdl"]);
+
+        let rendered = render(&rows);
+        assert!(rendered.contains(
+            "1970-01-02 00:00  sprocket  wdl-1.2     0123456       1       1      50.0%  +0.0\n"
+        ));
+        assert!(rendered.contains("\nChanges:\n  1970-01-02 00:00 sprocket\n    broke a.wdl\n"));
+        assert!(rendered.contains("    fixed b.wdl\n"));
+    }
+}
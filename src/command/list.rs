@@ -0,0 +1,109 @@
+//! A subcommand to list the conformance tests without running them.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::conformance::JsonSyntax;
+use crate::conformance::Target;
+use crate::conformance::Test;
+use crate::conformance::test::Runner;
+use crate::conformance::test::runner::CompileOptions;
+
+/// Lists the conformance tests within the specification.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The branch to check out.
+    #[arg(short, long, default_value = "wdl-1.2")]
+    branch: String,
+
+    /// The git repository URL to clone.
+    #[arg(long, default_value = "https://github.com/openwdl/wdl.git")]
+    repository_url: String,
+
+    /// A directory that contains the specification repository.
+    #[arg(short, long)]
+    specification_dir: Option<PathBuf>,
+
+    /// Accept comments, trailing commas, and other JSON5 syntax within the
+    /// input, output, and config blocks of each test.
+    #[arg(long, default_value_t = false)]
+    lenient_json: bool,
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let contents = super::test::read_specification(
+        &args.branch,
+        &args.repository_url,
+        args.specification_dir.clone(),
+    )?;
+
+    // The tests are only written to disk so that they can be inspected the
+    // same way as for a test run; the directory is removed afterwards.
+    let root_dir = tempfile::tempdir()?;
+    let options = CompileOptions::builder()
+        .json_syntax(if args.lenient_json {
+            JsonSyntax::Lenient
+        } else {
+            JsonSyntax::Strict
+        })
+        .build();
+    let runner = Runner::compile(root_dir.path().to_path_buf(), &contents, &options)?;
+
+    let rows = runner.tests().map(row).collect::<Vec<_>>();
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default();
+
+    for (name, details) in &rows {
+        println!("{name:<width$}  {details}");
+    }
+
+    eprintln!("\n{} test(s)", rows.len());
+
+    Ok(())
+}
+
+/// Describes a test as its name and a summary of its target, required
+/// capabilities, tags, and whether it is ignored.
+fn row(test: &Test) -> (String, String) {
+    let mut details = vec![match test.target() {
+        Some(Target::Workflow(name)) => format!("workflow {name}"),
+        Some(Target::Task(name)) => format!("task {name}"),
+        None => String::from("no target"),
+    }];
+
+    let config = test.config();
+
+    if !config.capabilities().is_empty() {
+        let capabilities = config
+            .capabilities()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        details.push(format!("capabilities: {}", capabilities.join(",")));
+    }
+
+    if !config.tags().is_empty() {
+        let tags = config
+            .tags()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        details.push(format!("tags: {}", tags.join(",")));
+    }
+
+    if config.fail() {
+        details.push(String::from("expected to fail"));
+    }
+
+    if config.ignore() {
+        details.push(String::from("ignored"));
+    }
+
+    (test.file_name().to_string(), details.join("; "))
+}
@@ -0,0 +1,98 @@
+//! A subcommand to merge the results of the shards of a test run.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::badge;
+use crate::badge::Badge;
+use crate::badge::TemplateValues;
+use crate::report::Report;
+use crate::report::SummaryFormat;
+
+/// Merges the results files (from `--results-file`) of the shards of a test
+/// run (from `--shard-index` and `--shard-count`) into one, and prints the
+/// badge and summary of the whole run.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The results file of each shard.
+    #[arg(required = true)]
+    results: Vec<PathBuf>,
+
+    /// Write the merged results to a file.
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// The label for the badge, as with `spectool test --label`.
+    #[arg(long, default_value = "Spectool")]
+    label: String,
+
+    /// A template for the message of the badge, as with
+    /// `spectool test --badge-message-template`.
+    #[arg(long, value_name = "TEMPLATE")]
+    badge_message_template: Option<String>,
+
+    /// The branch of the specification the shards tested, available to badge
+    /// templates as `{branch}`.
+    #[arg(long, default_value = "wdl-1.2")]
+    branch: String,
+
+    /// The version of the engine the shards tested, available to badge
+    /// templates as `{engine_version}`.
+    #[arg(long, value_name = "VERSION")]
+    engine_version: Option<String>,
+
+    /// Print a single-line summary of the merged results to stdout before the
+    /// badge.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    summary_format: Option<SummaryFormat>,
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    for template in std::iter::once(&args.label).chain(&args.badge_message_template) {
+        badge::validate_template(template)?;
+    }
+
+    let reports = args
+        .results
+        .iter()
+        .map(Report::from_path)
+        .collect::<Result<Vec<_>>>()?;
+    let report = Report::merge(reports)?;
+
+    if let Some(path) = &args.output {
+        report.write(path)?;
+    }
+
+    let summary = report.summary();
+    eprintln!(
+        "Merged {} results file(s): {} passed, {} failed, {} skipped",
+        args.results.len(),
+        summary.passed(),
+        summary.failed(),
+        summary.skipped()
+    );
+
+    if let Some(format) = args.summary_format {
+        println!("{}", summary.render(format));
+    }
+
+    let values = TemplateValues {
+        passed: summary.passed(),
+        failed: summary.failed(),
+        branch: args.branch,
+        engine_version: args.engine_version,
+        tier: Some(String::from("overall")),
+    };
+    Badge::from_results(
+        &args.label,
+        summary.passed(),
+        summary.passed() + summary.failed(),
+    )
+    .with_templates(&args.label, args.badge_message_template.as_deref(), &values)
+    .output();
+
+    Ok(())
+}
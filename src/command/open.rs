@@ -0,0 +1,266 @@
+//! A subcommand to open the section of the specification that contains a
+//! test.
+
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::bail;
+use clap::Parser;
+
+use crate::conformance::JsonSyntax;
+use crate::conformance::Test;
+use crate::conformance::Tests;
+use crate::diagnostic::Code;
+use crate::diagnostic::Diagnostic;
+use crate::shell::quote;
+
+/// The maximum number of similarly named tests suggested when no test has
+/// the given name.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Opens the section of the specification that contains a test in the
+/// browser, or the local specification at the test's line in an editor.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The name of the test (e.g., `hello.wdl`); the `.wdl` extension may be
+    /// omitted.
+    test: String,
+
+    /// The branch to check out.
+    #[arg(short, long, default_value = "wdl-1.2")]
+    branch: String,
+
+    /// The git repository URL to clone.
+    #[arg(long, default_value = "https://github.com/openwdl/wdl.git")]
+    repository_url: String,
+
+    /// A directory that contains the specification repository.
+    #[arg(short, long)]
+    specification_dir: Option<PathBuf>,
+
+    /// The URL of the specification that sections are linked to.
+    ///
+    /// Defaults to `SPEC.md` at the checked out commit when the repository
+    /// URL is an `https` URL.
+    #[arg(long, value_name = "URL")]
+    specification_url: Option<String>,
+
+    /// Open the local specification in `$VISUAL` (or `$EDITOR`) at the first
+    /// line of the test instead of opening the browser.
+    ///
+    /// The editor is passed `+<line>`, which vi, Emacs, nano, and most other
+    /// terminal editors accept.
+    #[arg(long, default_value_t = false)]
+    editor: bool,
+
+    /// Print the URL (or `<path>:<line>` with `--editor`) instead of opening
+    /// it.
+    #[arg(long, default_value_t = false)]
+    print: bool,
+
+    /// Accept comments, trailing commas, and other JSON5 syntax within the
+    /// input, output, and config blocks of each test.
+    #[arg(long, default_value_t = false)]
+    lenient_json: bool,
+}
+
+/// Where a test is within the specification.
+#[derive(Debug, PartialEq, Eq)]
+enum Location {
+    /// The specification (or the section of it containing the test) online.
+    Url(String),
+
+    /// A line of the local specification file.
+    Line {
+        /// The path of the specification file.
+        path: PathBuf,
+
+        /// The one-based line.
+        line: usize,
+    },
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Url(url) => write!(f, "{url}"),
+            Location::Line { path, line } => write!(f, "{}:{line}", path.display()),
+        }
+    }
+}
+
+/// Finds a test by name, with or without the `.wdl` extension.
+fn find<'a>(tests: &'a Tests, name: &str) -> Result<&'a Test, Diagnostic> {
+    let with_extension = format!("{name}.wdl");
+    if let Some(test) = tests
+        .tests()
+        .find(|test| test.file_name() == name || test.file_name() == with_extension)
+    {
+        return Ok(test);
+    }
+
+    let query = name.strip_suffix(".wdl").unwrap_or(name).to_lowercase();
+    let similar = tests
+        .tests()
+        .map(Test::file_name)
+        .filter(|file_name| file_name.to_lowercase().contains(&query))
+        .take(MAX_SUGGESTIONS)
+        .map(|file_name| format!("`{file_name}`"))
+        .collect::<Vec<_>>();
+
+    let diagnostic = Diagnostic::new(
+        Code::UnknownTest,
+        format!("no test in the specification is named `{name}`"),
+    );
+    Err(if similar.is_empty() {
+        diagnostic.with_hint("run `spectool list` to see the names of the tests")
+    } else {
+        diagnostic.with_hint(format!("similarly named tests are {}", similar.join(", ")))
+    })
+}
+
+/// Gets where a test is within the specification: online, unless `editor`
+/// is set or there is no URL to link to.
+fn locate(
+    test: &Test,
+    specification_url: Option<&str>,
+    path: &Path,
+    editor: bool,
+) -> Result<Location> {
+    if !editor {
+        if let Some(url) = super::test::section_url(specification_url, test)
+            .or_else(|| specification_url.map(str::to_string))
+        {
+            return Ok(Location::Url(url));
+        }
+
+        return Err(Diagnostic::new(
+            Code::MissingOption,
+            "the specification has no URL to open because the repository URL is not an `https` \
+             URL",
+        )
+        .with_hint("pass `--specification-url`, or `--editor` to open the local specification")
+        .into());
+    }
+
+    Ok(Location::Line {
+        path: path.to_path_buf(),
+        line: test.lines().map(|lines| lines.start()).unwrap_or(1),
+    })
+}
+
+/// Opens a location in the browser or an editor.
+fn open(location: &Location) -> Result<()> {
+    let status = match location {
+        Location::Url(url) => {
+            let mut command = if cfg!(target_os = "macos") {
+                Command::new("open")
+            } else if cfg!(windows) {
+                let mut command = Command::new("cmd");
+                command.args(["/C", "start", ""]);
+                command
+            } else {
+                Command::new("xdg-open")
+            };
+            command
+                .arg(url)
+                .status()
+                .with_context(|| format!("opening `{url}` in the browser"))?
+        }
+        Location::Line { path, line } => {
+            let Some(editor) = ["VISUAL", "EDITOR"]
+                .into_iter()
+                .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+            else {
+                bail!("neither `VISUAL` nor `EDITOR` is set");
+            };
+
+            // The editor may include arguments of its own, so it is run
+            // within a shell
+            Command::new("sh")
+                .args([
+                    "-c",
+                    &format!("{editor} +{line} {}", quote(&path.to_string_lossy())),
+                ])
+                .status()
+                .with_context(|| format!("running editor `{editor}`"))?
+        }
+    };
+
+    if !status.success() {
+        bail!("failed to open `{location}` ({status})");
+    }
+
+    Ok(())
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let specification = super::test::checkout_specification(
+        &args.branch,
+        &args.repository_url,
+        args.specification_dir.clone(),
+    )?;
+    let tests = Tests::compile_with_syntax(
+        specification.contents(),
+        if args.lenient_json {
+            JsonSyntax::Lenient
+        } else {
+            JsonSyntax::Strict
+        },
+    )?;
+    let test = find(&tests, &args.test)?;
+
+    let specification_url = args.specification_url.clone().or_else(|| {
+        super::test::specification_url(
+            &args.repository_url,
+            specification.commit().unwrap_or(&args.branch),
+        )
+    });
+    let location = locate(
+        test,
+        specification_url.as_deref(),
+        specification.path(),
+        args.editor,
+    )?;
+
+    if args.print {
+        println!("{location}");
+        return Ok(());
+    }
+
+    open(&location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations() {
+        let spec = "# Types\n\n## Strings\n\n<details>\n<summary>\nExample: hello.wdl\n\n```wdl\n\
+                    version 1.2\n\nworkflow hello {}\n```\n</summary>\n<p>\nExample input:\n\n\
+                    ```json\n{}\n```\n\nExample output:\n\n```json\n{}\n```\n</p>\n</details>\n";
+        let tests = Tests::compile(spec).unwrap();
+
+        let test = find(&tests, "hello").unwrap();
+        assert_eq!(test.file_name(), "hello.wdl");
+        let error = find(&tests, "hell.wdl").unwrap_err();
+        assert_eq!(error.code(), Code::UnknownTest);
+        assert_eq!(error.hint(), Some("similarly named tests are `hello.wdl`"));
+
+        let path = Path::new("wdl/SPEC.md");
+        assert_eq!(
+            locate(test, Some("https://example.com/SPEC.md"), path, false).unwrap(),
+            Location::Url(String::from("https://example.com/SPEC.md#strings"))
+        );
+        assert!(locate(test, None, path, false).is_err());
+
+        let location = locate(test, None, path, true).unwrap();
+        assert_eq!(location.to_string(), "wdl/SPEC.md:5");
+    }
+}
@@ -0,0 +1,302 @@
+//! A subcommand to validate the recorded outputs of a run again.
+
+use std::io::IsTerminal as _;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::bail;
+use clap::Parser;
+use strum::IntoEnumIterator as _;
+
+use super::test::Columns;
+use super::test::Printer;
+use super::test::ResultContext;
+use crate::conformance::FloatTolerance;
+use crate::conformance::JsonSyntax;
+use crate::conformance::Tag;
+use crate::conformance::Test;
+use crate::conformance::TestResult;
+use crate::conformance::test::Runner;
+use crate::conformance::test::runner::CompileOptions;
+use crate::conformance::test::validation::KeyMap;
+use crate::engine::Engine;
+use crate::engine::Profile;
+use crate::report::Entry;
+use crate::report::Report;
+use crate::selector::Library;
+
+mod experiment;
+
+/// Validates the outputs recorded by a previous run (with `spectool test
+/// --artifacts-dir`) again, without executing the engine.
+///
+/// Only the validation of outputs is replayed, which is useful when tuning
+/// tolerances and selectors. Tests that are expected to fail, that have no
+/// expected outputs, or that were checked with `--check-command` are
+/// skipped, as their outcome depends on more than the outputs.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The artifacts of the run, which is the directory named after the run
+    /// identifier within the `--artifacts-dir` of the run.
+    #[arg(long, value_name = "DIR")]
+    artifacts: PathBuf,
+
+    /// The branch to check out.
+    #[arg(short, long, default_value = "wdl-1.2")]
+    branch: String,
+
+    /// The git repository URL to clone.
+    #[arg(long, default_value = "https://github.com/openwdl/wdl.git")]
+    repository_url: String,
+
+    /// A directory that contains the specification repository.
+    #[arg(short, long)]
+    specification_dir: Option<PathBuf>,
+
+    /// Accept comments, trailing commas, and other JSON5 syntax within the
+    /// input, output, and config blocks of each test.
+    #[arg(long, default_value_t = false)]
+    lenient_json: bool,
+
+    /// The engine whose preset output selectors and key remapping are used,
+    /// as with `spectool test --engine`.
+    #[arg(long, value_enum)]
+    engine: Option<Engine>,
+
+    /// A `jq` selector to apply to the outputs before validation, as with
+    /// `spectool test --output-selector`.
+    #[arg(long)]
+    output_selector: Vec<String>,
+
+    /// A `jq` selector to apply to the expected outputs before validation, as
+    /// with `spectool test --expected-selector`.
+    #[arg(long)]
+    expected_selector: Vec<String>,
+
+    /// Path to a JSON file of named selectors, as with
+    /// `spectool test --selectors-file`.
+    #[arg(long, value_name = "PATH")]
+    selectors_file: Option<PathBuf>,
+
+    /// Strip the `<target>.` prefix from output keys before validation.
+    #[arg(long, default_value_t = false)]
+    strip_output_prefix: bool,
+
+    /// The tolerance within which floating point outputs are considered equal,
+    /// as with `spectool test --float-tolerance`.
+    #[arg(long, value_name = "TOLERANCE")]
+    float_tolerance: Option<FloatTolerance>,
+
+    /// Write a JSON report of the replayed results to this path, as with
+    /// `spectool test --results-file`.
+    #[arg(long, value_name = "PATH")]
+    results_file: Option<PathBuf>,
+
+    /// Validate the outputs under several comparison settings at once
+    /// (`configured`, `strict`, `lenient-numbers`, and `unordered-arrays`)
+    /// and report which failures are sensitive to which setting.
+    #[arg(long, default_value_t = false, conflicts_with = "results_file")]
+    compare_settings: bool,
+}
+
+/// A test whose recorded outputs can be validated again.
+struct Replayable<'a> {
+    /// The test.
+    test: &'a Test,
+
+    /// The expected outputs of the test.
+    expected: &'a serde_json::Value,
+
+    /// The path of the recorded outputs.
+    outputs: PathBuf,
+}
+
+impl Args {
+    /// Gets the profile the outputs are validated with.
+    fn profile(&self) -> Result<Profile> {
+        let preset = self.engine.map(|engine| engine.preset());
+        let preset = preset.as_ref().map(|preset| preset.profile());
+
+        let library = self
+            .selectors_file
+            .as_ref()
+            .map(Library::from_path)
+            .transpose()?
+            .unwrap_or_default();
+        let output_selectors = match (&self.output_selector[..], preset) {
+            ([], Some(profile)) => profile.output_selectors().to_vec(),
+            (selectors, _) => library.resolve(selectors)?,
+        };
+        let expected_selectors = match (&self.expected_selector[..], preset) {
+            ([], Some(profile)) => profile.expected_selectors().to_vec(),
+            (selectors, _) => library.resolve(selectors)?,
+        };
+        let key_map = KeyMap::builder()
+            .strip_prefix(self.strip_output_prefix)
+            .build();
+
+        Ok(Profile::builder()
+            .output_key_map(match preset {
+                Some(profile) => profile.output_key_map().merged(&key_map),
+                None => key_map,
+            })
+            .output_selectors(output_selectors)
+            .expected_selectors(expected_selectors)
+            .build())
+    }
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    if !args.artifacts.is_dir() {
+        bail!(
+            "artifacts directory `{}` does not exist",
+            args.artifacts.display()
+        );
+    }
+
+    let profile = args.profile()?;
+    let contents = super::test::read_specification(
+        &args.branch,
+        &args.repository_url,
+        args.specification_dir.clone(),
+    )?;
+
+    // As with `spectool list`, the tests are only written to disk to compile
+    // them; the directory is removed afterwards.
+    let root_dir = tempfile::tempdir()?;
+    let options = CompileOptions::builder()
+        .json_syntax(if args.lenient_json {
+            JsonSyntax::Lenient
+        } else {
+            JsonSyntax::Strict
+        })
+        .build();
+    let runner = Runner::compile(root_dir.path().to_path_buf(), &contents, &options)?;
+
+    let specification_url = super::test::specification_url(&args.repository_url, &args.branch);
+    let printer = Printer::new(
+        crate::diagnostic::use_color(std::io::stderr().is_terminal()),
+        false,
+    );
+    let columns = Columns::new(
+        runner.tests().map(Test::file_name),
+        super::test::terminal_width(),
+    );
+    let mut replayable = Vec::new();
+    let mut skipped = 0;
+
+    for test in runner.tests() {
+        let dir = args
+            .artifacts
+            .join(test.file_name().trim_end_matches(".wdl"));
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let expected_output = test
+            .output()
+            .filter(|_| !test.config().fail() && !test.config().tags().contains(&Tag::Static));
+        let Some(expected_output) = expected_output else {
+            super::test::print_result(
+                test.file_name(),
+                "SKIP",
+                Some("the outcome does not depend only on the outputs"),
+                None,
+                &ResultContext::default(),
+                columns,
+                &printer,
+            );
+            skipped += 1;
+            continue;
+        };
+
+        replayable.push(Replayable {
+            test,
+            expected: expected_output,
+            outputs: dir.join("outputs.json"),
+        });
+    }
+
+    if args.compare_settings {
+        let rows = experiment::run(&replayable, &profile, args.float_tolerance);
+        print!("{}", experiment::render(&rows));
+        eprintln!(
+            "\nCompared {} test(s) under {} settings ({} skipped)",
+            rows.len(),
+            experiment::Setting::iter().count(),
+            skipped
+        );
+        return Ok(());
+    }
+
+    let mut entries = Vec::new();
+
+    for Replayable {
+        test,
+        expected,
+        outputs,
+    } in replayable
+    {
+        let validation = super::test::validation_options(test, &profile, args.float_tolerance);
+        let result = match super::test::evaluate_outputs(expected, &outputs, &profile, &validation)
+        {
+            Ok(()) => {
+                super::test::print_result(
+                    test.file_name(),
+                    "PASS",
+                    None,
+                    None,
+                    &ResultContext::default(),
+                    columns,
+                    &printer,
+                );
+                TestResult::Passed
+            }
+            Err(reason) => {
+                super::test::print_result(
+                    test.file_name(),
+                    "FAIL",
+                    Some(&reason.to_string()),
+                    None,
+                    &ResultContext {
+                        lines: test.lines(),
+                        section_url: super::test::section_url(specification_url.as_deref(), test)
+                            .as_deref(),
+                        invocation: None,
+                        diff: reason.diff(),
+                    },
+                    columns,
+                    &printer,
+                );
+                TestResult::Failed(reason)
+            }
+        };
+
+        entries.push(
+            Entry::new(test.file_name(), &result, None)
+                .with_lines(test.lines())
+                .with_section_url(super::test::section_url(specification_url.as_deref(), test)),
+        );
+    }
+
+    let report = Report::new(entries);
+    let summary = report.summary();
+    eprintln!(
+        "\nReplayed {} test(s): {} passed, {} failed ({} skipped)",
+        summary.passed() + summary.failed(),
+        summary.passed(),
+        summary.failed(),
+        skipped
+    );
+
+    if let Some(path) = &args.results_file {
+        report.write(path)?;
+    }
+
+    if summary.failed() > 0 {
+        bail!("{} replayed test(s) failed", summary.failed());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,227 @@
+//! Validating recorded outputs under several comparison settings at once.
+//!
+//! Each test is validated under every [`Setting`], so that failures can be
+//! attributed to the comparison rules rather than to the engine: a test that
+//! only fails under `strict` relies on a tolerance or unordered arrays, and a
+//! test that only passes under `lenient-numbers` differs by floating point
+//! formatting alone.
+
+use std::fmt;
+use std::fmt::Write as _;
+
+use strum::IntoEnumIterator as _;
+use strum_macros::EnumIter;
+
+use super::Replayable;
+use crate::command::test::evaluate_outputs;
+use crate::command::test::validation_options;
+use crate::conformance::FloatTolerance;
+use crate::conformance::test::validation::Options as ValidationOptions;
+use crate::engine::Profile;
+
+/// The tolerance applied to both absolute and relative differences under
+/// [`Setting::LenientNumbers`].
+const LENIENT_TOLERANCE: f64 = 1e-6;
+
+/// A comparison setting outputs are validated under.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter)]
+pub enum Setting {
+    /// The settings of each test and of the command line, as for a run.
+    Configured,
+    /// Numbers must be equal to within [`f64::EPSILON`] and the elements of
+    /// every array must be in order.
+    Strict,
+    /// The configured settings with numbers equal to within
+    /// [`LENIENT_TOLERANCE`].
+    LenientNumbers,
+    /// The configured settings with the elements of every array in any order.
+    UnorderedArrays,
+}
+
+impl Setting {
+    /// Applies the setting to the options a test is validated with.
+    fn apply(self, options: ValidationOptions) -> ValidationOptions {
+        match self {
+            Setting::Configured => options,
+            Setting::Strict => options
+                .with_float_tolerance(FloatTolerance::default())
+                .with_unordered(Vec::new()),
+            Setting::LenientNumbers => {
+                let tolerance = options.float_tolerance();
+                options.with_float_tolerance(FloatTolerance::new(
+                    tolerance.absolute().max(LENIENT_TOLERANCE),
+                    tolerance.relative().max(LENIENT_TOLERANCE),
+                ))
+            }
+            Setting::UnorderedArrays => options.with_unordered_arrays(true),
+        }
+    }
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Setting::Configured => "configured",
+            Setting::Strict => "strict",
+            Setting::LenientNumbers => "lenient-numbers",
+            Setting::UnorderedArrays => "unordered-arrays",
+        })
+    }
+}
+
+/// Whether a test passed under each setting.
+#[derive(Debug)]
+pub struct Row {
+    /// The name of the test.
+    name: String,
+
+    /// Whether the test passed under each setting, in the order of
+    /// [`Setting::iter`].
+    passed: Vec<bool>,
+}
+
+impl Row {
+    /// Gets whether the test passed under a setting.
+    fn passed(&self, setting: Setting) -> bool {
+        // SAFETY: a row has an outcome for every setting.
+        self.passed[Setting::iter().position(|s| s == setting).unwrap()]
+    }
+}
+
+/// Validates the recorded outputs of each test under every setting.
+pub fn run(
+    replayable: &[Replayable<'_>],
+    profile: &Profile,
+    float_tolerance: Option<FloatTolerance>,
+) -> Vec<Row> {
+    replayable
+        .iter()
+        .map(|r| {
+            let options = validation_options(r.test, profile, float_tolerance);
+            let passed = Setting::iter()
+                .map(|setting| {
+                    evaluate_outputs(
+                        r.expected,
+                        &r.outputs,
+                        profile,
+                        &setting.apply(options.clone()),
+                    )
+                    .is_ok()
+                })
+                .collect();
+
+            Row {
+                name: r.test.file_name().to_string(),
+                passed,
+            }
+        })
+        .collect()
+}
+
+/// Renders the outcome of each test that failed under any setting, the
+/// number of tests that passed under each setting, and the tests whose
+/// outcome differs from the configured settings.
+pub fn render(rows: &[Row]) -> String {
+    let mut out = String::new();
+    let failing = rows
+        .iter()
+        .filter(|row| row.passed.contains(&false))
+        .collect::<Vec<_>>();
+
+    if failing.is_empty() {
+        let _ = writeln!(out, "Every test passed under every setting.");
+        return out;
+    }
+
+    let width = failing
+        .iter()
+        .map(|row| row.name.len())
+        .max()
+        .unwrap_or_default()
+        .max("test".len());
+    let _ = write!(out, "{:<width$}", "test");
+    for setting in Setting::iter() {
+        let _ = write!(out, "  {setting}");
+    }
+    let _ = writeln!(out);
+
+    for row in &failing {
+        let mut line = format!("{:<width$}", row.name);
+        for setting in Setting::iter() {
+            let status = if row.passed(setting) { "pass" } else { "FAIL" };
+            let _ = write!(line, "  {status:<w$}", w = setting.to_string().len());
+        }
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+
+    let _ = writeln!(out, "\nPassed:");
+    for setting in Setting::iter() {
+        let passed = rows.iter().filter(|row| row.passed(setting)).count();
+        let _ = writeln!(out, "  {setting:<16}  {passed}/{}", rows.len());
+    }
+
+    let _ = writeln!(out, "\nSensitive to the setting:");
+    let mut sensitive = false;
+    for setting in Setting::iter().skip(1) {
+        let names = |fixed: bool| {
+            rows.iter()
+                .filter(|row| {
+                    row.passed(setting) == fixed && row.passed(Setting::Configured) != fixed
+                })
+                .map(|row| row.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        for (verb, names) in [("fixes", names(true)), ("breaks", names(false))] {
+            if !names.is_empty() {
+                sensitive = true;
+                let _ = writeln!(out, "  {setting:<16}  {verb} {}", names.join(", "));
+            }
+        }
+    }
+
+    if !sensitive {
+        let _ = writeln!(out, "  none");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_sensitive_tests() {
+        let row = |name: &str, passed: [bool; 4]| Row {
+            name: name.to_string(),
+            passed: passed.to_vec(),
+        };
+        let rows = [
+            row("a.wdl", [true, true, true, true]),
+            row("float.wdl", [false, false, true, false]),
+            row("glob.wdl", [true, false, true, true]),
+        ];
+
+        let rendered = render(&rows);
+        assert!(!rendered.contains("a.wdl"));
+        assert!(rendered.contains("float.wdl  FAIL        FAIL    pass             FAIL\n"));
+        assert!(rendered.contains("  strict            1/3\n"));
+        assert!(rendered.contains("  strict            breaks glob.wdl\n"));
+        assert!(rendered.contains("  lenient-numbers   fixes float.wdl\n"));
+
+        assert_eq!(
+            render(&rows[..1]),
+            "Every test passed under every setting.\n"
+        );
+    }
+
+    #[test]
+    fn lenient_numbers_keeps_larger_tolerance() {
+        let options = ValidationOptions::builder()
+            .float_tolerance(FloatTolerance::new(1e-3, 0.0))
+            .build();
+        let tolerance = Setting::LenientNumbers.apply(options).float_tolerance();
+        assert_eq!(tolerance, FloatTolerance::new(1e-3, LENIENT_TOLERANCE));
+    }
+}
@@ -0,0 +1,92 @@
+//! A subcommand to try out output selectors.
+
+use std::io::BufRead as _;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::anyhow;
+use clap::Parser;
+use serde_json::Value;
+
+use crate::selector;
+use crate::selector::Library;
+
+/// Evaluates an output selector against a sample file.
+///
+/// The selector is evaluated exactly as it would be with `--output-selector`
+/// during `spectool test`.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The `jq` selector to evaluate.
+    ///
+    /// May be repeated to apply several selectors in sequence, and may refer
+    /// to a named selector as `@<name>`. If not provided, selectors are read
+    /// from standard input one per line and each is evaluated in turn.
+    #[arg(long)]
+    selector: Vec<String>,
+
+    /// Path to a JSON file of named selectors.
+    #[arg(long, value_name = "PATH")]
+    selectors_file: Option<PathBuf>,
+
+    /// Path to the sample JSON file (e.g., the outputs reported by an engine).
+    #[arg(long, value_name = "PATH")]
+    input: PathBuf,
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let contents = std::fs::read_to_string(&args.input)
+        .with_context(|| format!("reading `{}`", args.input.display()))?;
+    let input: Value = serde_json::from_str(&contents)
+        .with_context(|| format!("parsing `{}`", args.input.display()))?;
+
+    let library = args
+        .selectors_file
+        .as_ref()
+        .map(Library::from_path)
+        .transpose()?
+        .unwrap_or_default();
+
+    if !args.selector.is_empty() {
+        let selectors = library.resolve(&args.selector)?;
+        let output = selector::apply_all(&selectors, &input).map_err(|e| anyhow!("{e}"))?;
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        eprint!("selector> ");
+        std::io::stderr().flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            eprintln!();
+            break;
+        };
+
+        let selector = line.trim();
+        if selector.is_empty() {
+            continue;
+        }
+
+        let selectors = match library.resolve(&[selector.to_string()]) {
+            Ok(selectors) => selectors,
+            Err(e) => {
+                eprintln!("{e}");
+                continue;
+            }
+        };
+
+        match selector::apply_all(&selectors, &input) {
+            Ok(output) => println!("{}", serde_json::to_string_pretty(&output)?),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,215 @@
+//! A subcommand to aggregate the output mismatches of one or more test runs.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::conformance::test::validation::MismatchKind;
+use crate::report::Outcome;
+use crate::report::Report;
+use crate::report::Summary;
+
+/// Aggregates which output keys most frequently mismatch across the failing
+/// tests of one or more results files (from `--results-file`).
+///
+/// Passing the results of several runs (such as those kept over time) counts
+/// mismatches across the history of an engine, which reveals systematic
+/// issues such as floating point formatting or path handling rather than
+/// problems with single tests.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The results files to aggregate.
+    #[arg(required = true)]
+    results: Vec<PathBuf>,
+
+    /// The number of most frequently mismatched keys to show.
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+/// The output mismatches aggregated across results files.
+#[derive(Debug)]
+struct Stats {
+    /// The summary of every test across the results files.
+    summary: Summary,
+
+    /// The number of failed tests.
+    failed: usize,
+
+    /// The number of failed tests with mismatched outputs.
+    mismatched: usize,
+
+    /// The number of failed tests with a mismatch of each kind.
+    kinds: BTreeMap<MismatchKind, usize>,
+
+    /// The number of failed tests with a mismatch at each (generalized) path,
+    /// along with the kinds of those mismatches.
+    keys: BTreeMap<String, (usize, BTreeSet<MismatchKind>)>,
+}
+
+impl Stats {
+    /// Aggregates the output mismatches of reports.
+    fn new<'a>(reports: impl IntoIterator<Item = &'a Report> + Clone) -> Self {
+        let summaries = reports
+            .clone()
+            .into_iter()
+            .map(Report::summary)
+            .collect::<Vec<_>>();
+        let mut stats = Self {
+            summary: Summary::new(
+                summaries.iter().map(Summary::passed).sum(),
+                summaries.iter().map(Summary::failed).sum(),
+                summaries.iter().map(Summary::skipped).sum(),
+                reports
+                    .clone()
+                    .into_iter()
+                    .filter_map(Report::duration)
+                    .sum(),
+            ),
+            failed: 0,
+            mismatched: 0,
+            kinds: BTreeMap::new(),
+            keys: BTreeMap::new(),
+        };
+
+        for entry in reports.into_iter().flat_map(|report| report.tests()) {
+            let Outcome::Failed { keys, .. } = entry.result() else {
+                continue;
+            };
+
+            stats.failed += 1;
+            if keys.is_empty() {
+                continue;
+            }
+
+            // A test counts once per kind and path, however many elements of
+            // an array mismatched.
+            stats.mismatched += 1;
+            let kinds = keys.iter().map(|key| key.kind()).collect::<BTreeSet<_>>();
+            for kind in kinds {
+                *stats.kinds.entry(kind).or_default() += 1;
+            }
+
+            let mut paths = BTreeMap::<_, BTreeSet<_>>::new();
+            for key in keys {
+                paths
+                    .entry(key.generalized_path())
+                    .or_default()
+                    .insert(key.kind());
+            }
+            for (path, kinds) in paths {
+                let (count, all) = stats.keys.entry(path).or_default();
+                *count += 1;
+                all.extend(kinds);
+            }
+        }
+
+        stats
+    }
+
+    /// Renders the number of tests with a mismatch of each kind and the `top`
+    /// most frequently mismatched keys.
+    fn render(&self, top: usize) -> String {
+        let mut out = self.summary.render_figures();
+        let _ = writeln!(
+            out,
+            "\n{} of {} failed test(s) had mismatched outputs",
+            self.mismatched, self.failed
+        );
+
+        if self.mismatched == 0 {
+            return out;
+        }
+
+        let mut kinds = self.kinds.iter().collect::<Vec<_>>();
+        kinds.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let _ = writeln!(out, "\nBy kind:");
+        for (kind, count) in kinds {
+            let _ = writeln!(out, "  {kind:<14}  {count:>5}");
+        }
+
+        let mut keys = self.keys.iter().collect::<Vec<_>>();
+        keys.sort_by(|a, b| b.1.0.cmp(&a.1.0).then(a.0.cmp(b.0)));
+        let width = keys
+            .iter()
+            .take(top)
+            .map(|(path, _)| path.len())
+            .max()
+            .unwrap_or_default();
+        let _ = writeln!(out, "\nMost frequently mismatched keys:");
+        for (path, (count, kinds)) in keys.into_iter().take(top) {
+            let kinds = kinds.iter().map(|k| k.as_str()).collect::<Vec<_>>();
+            let _ = writeln!(out, "  {path:<width$}  {count:>5}  {}", kinds.join(", "));
+        }
+
+        out
+    }
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let reports = args
+        .results
+        .iter()
+        .map(Report::from_path)
+        .collect::<Result<Vec<_>>>()?;
+
+    print!("{}", Stats::new(&reports).render(args.top));
+    eprintln!("\nAggregated {} results file(s)", reports.len());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::TestResult;
+    use crate::conformance::test::validation::MismatchedKey;
+    use crate::report::Entry;
+
+    #[test]
+    fn aggregates_mismatches() {
+        let mismatch = |keys: Vec<MismatchedKey>| {
+            TestResult::Failed(FailureReason::OutputMismatch {
+                details: String::new(),
+                diff: None,
+                keys,
+            })
+        };
+        let run = Report::new(vec![
+            Entry::new(
+                "a.wdl",
+                &mismatch(vec![
+                    MismatchedKey::new("a.x[0]", MismatchKind::Number),
+                    MismatchedKey::new("a.x[1]", MismatchKind::Number),
+                ]),
+                None,
+            ),
+            Entry::new(
+                "b.wdl",
+                &mismatch(vec![MismatchedKey::new("a.x", MismatchKind::Type)]),
+                None,
+            ),
+            Entry::new("c.wdl", &TestResult::Failed(FailureReason::NoOutput), None),
+        ]);
+
+        let rerun = Report::new(run.tests()[..1].to_vec());
+
+        let stats = Stats::new([&run, &rerun]);
+        assert_eq!(stats.failed, 4);
+        assert_eq!(stats.mismatched, 3);
+        assert_eq!(stats.kinds[&MismatchKind::Number], 2);
+
+        let rendered = stats.render(1);
+        assert!(rendered.starts_with("Passed:    0\nFailed:    4\n"));
+        assert!(rendered.contains("\n\n3 of 4 failed test(s) had mismatched outputs\n"));
+        assert!(rendered.contains("  number              2\n"));
+        assert!(rendered.contains("  a.x[*]      2  number\n"));
+        assert!(!rendered.contains("  a.x  "));
+    }
+}
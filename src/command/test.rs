@@ -1,39 +1,157 @@
 //! A subcommand to run the conformance tests.
 
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io::BufRead as _;
+use std::io::BufReader;
+use std::io::IsTerminal as _;
+use std::io::Read;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
+use std::process::Output;
 use std::process::Stdio;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Context as _;
 use anyhow::Result;
 use anyhow::bail;
 use clap::Parser;
-use statrs::statistics::Data;
-use statrs::statistics::OrderStatistics;
-use statrs::statistics::Statistics;
+use indicatif::ProgressBar;
+use indicatif::ProgressStyle;
+use rayon::prelude::*;
+use serde::Deserialize;
 use strum::IntoEnumIterator;
 use tracing::info;
+use tracing::warn;
 
 use crate::Repository;
+use crate::attestation;
+use crate::attestation::Statement;
+use crate::badge;
 use crate::badge::Badge;
+use crate::badge::TemplateValues;
+use crate::baseline;
+use crate::baseline::Baseline;
 use crate::conformance::Capability;
+use crate::conformance::Dialect;
 use crate::conformance::FailureReason;
+use crate::conformance::FloatTolerance;
+use crate::conformance::JsonSyntax;
+use crate::conformance::Layout;
+use crate::conformance::Lines;
+use crate::conformance::Pattern;
+use crate::conformance::Resources;
 use crate::conformance::ReturnCode;
+use crate::conformance::Shard;
 use crate::conformance::SkipReason;
+use crate::conformance::Tag;
+use crate::conformance::Target;
 use crate::conformance::Test;
+use crate::conformance::TestFilter;
 use crate::conformance::TestResult;
+use crate::conformance::Tier;
 use crate::conformance::test::Runner;
-use crate::conformance::test::validation::validate_outputs;
+use crate::conformance::test::runner::CompileOptions;
+use crate::conformance::test::validation;
+use crate::conformance::test::validation::KeyMap;
+use crate::conformance::test::validation::Options as ValidationOptions;
+use crate::diagnostic::Code;
+use crate::diagnostic::Diagnostic;
+use crate::engine::Engine;
+use crate::engine::OutputSource;
+use crate::engine::Profile;
+use crate::engine::TargetArgs;
+use crate::history;
+use crate::report::DurationStats;
+use crate::report::Entry;
+use crate::report::Invocation;
+use crate::report::Outcome;
+use crate::report::Report;
+use crate::report::Summary;
+use crate::report::SummaryFormat;
+use crate::selector;
+use crate::selector::Library;
+use crate::shell;
+use crate::shell::Substitutions;
+use crate::shell::input_args;
+use crate::shell::quote;
 use crate::shell::substitute;
+use crate::shell::substitute_argv;
+use crate::shell::uses_placeholder;
+use crate::shell::validate_template;
+use crate::sink;
+use crate::wdl::Construct;
+use crate::wdl::container_images;
+use crash_loop::CrashLoop;
+use environment::Environment;
+use events::Event;
+use events::Events;
+
+mod artifacts;
+mod bundle;
+mod config_file;
+mod crash_loop;
+mod environment;
+mod events;
+mod index;
+mod manifest;
+mod matrix;
+mod provenance;
 
 /// The file name of the specification.
 const SPEC_FILE_NAME: &str = "SPEC.md";
 
+/// The number of the latest runs within the history whose failures are run
+/// first with `--failed-first`.
+const FAILED_FIRST_RUNS: usize = 5;
+
+/// The outcome of processing a single test.
+struct Processed {
+    /// The position of the test within the specification.
+    index: usize,
+    /// The file name of the test.
+    name: String,
+    /// The conformance tier of the test.
+    tier: Tier,
+    /// The lines the test occupies within the specification.
+    lines: Option<Lines>,
+    /// The result of the test.
+    result: TestResult,
+    /// The execution time of the test, if it was executed.
+    elapsed: Option<Duration>,
+    /// The portion of the execution time the engine reported as setup.
+    setup: Option<Duration>,
+    /// The working directory of the test, if it was executed.
+    workdir: Option<PathBuf>,
+    /// How the command for the test was invoked, if it was executed.
+    invocation: Option<Invocation>,
+}
+
+impl Processed {
+    /// Creates the outcome of a test that was skipped before execution.
+    fn skipped(test: &Test, reason: SkipReason) -> Self {
+        Self {
+            index: test.index(),
+            name: test.file_name().to_string(),
+            tier: test.tier(),
+            lines: test.lines(),
+            result: TestResult::Skipped(reason),
+            elapsed: None,
+            setup: None,
+            workdir: None,
+            invocation: None,
+        }
+    }
+}
+
 /// Holds the timing data for different test result categories.
 #[derive(Clone)]
 struct TestTimings {
@@ -89,8 +207,125 @@ impl TestTimings {
     }
 }
 
+/// How test inputs are delivered to the engine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum InputMode {
+    /// Inputs are written to an `inputs.json` file available as `~{input}`.
+    #[default]
+    File,
+    /// Inputs are expanded into `key=value` arguments available as
+    /// `~{input_args}`.
+    Args,
+}
+
+/// Which working directories of tests are kept after a run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum KeepWorkdirs {
+    /// Every working directory is kept.
+    All,
+    /// Only the working directories of failed tests are kept.
+    #[default]
+    Failed,
+    /// No working directory is kept.
+    None,
+}
+
+impl KeepWorkdirs {
+    /// Gets whether the working directory of a test with a result is kept.
+    fn keeps(&self, result: &TestResult) -> bool {
+        match self {
+            KeepWorkdirs::All => true,
+            KeepWorkdirs::Failed => matches!(result, TestResult::Failed(_)),
+            KeepWorkdirs::None => false,
+        }
+    }
+}
+
+/// State shared by every test within a run.
+struct Shared {
+    /// The command line arguments.
+    args: Args,
+
+    /// The engine profile.
+    profile: Profile,
+
+    /// The filter that selects which tests are run.
+    filter: TestFilter,
+
+    /// The contents of the engine options template, if provided.
+    engine_options: Option<String>,
+
+    /// The tests that are known to fail.
+    baseline: Baseline,
+
+    /// The root directory of the compiled conformance tests.
+    root_dir: PathBuf,
+
+    /// The environment that commands are run with.
+    environment: Environment,
+
+    /// The identifier of the run.
+    run_id: String,
+
+    /// The commit of the specification that was tested, if it could be
+    /// determined.
+    specification_commit: Option<String>,
+
+    /// The URL of the rendered specification, if known.
+    specification_url: Option<String>,
+
+    /// The number of failures that count towards `--max-failures`.
+    failures: AtomicUsize,
+
+    /// Whether the first tests all failed with the same error.
+    crash_loop: CrashLoop,
+
+    /// When the run reaches its `--run-timeout`, if it has one.
+    deadline: Option<Instant>,
+
+    /// The stream of events, if one was requested.
+    events: Option<Events>,
+
+    /// The layout of the result line printed for each test.
+    columns: Columns,
+}
+
+/// The specification as checked out for a run.
+#[derive(Debug)]
+pub(crate) struct Specification {
+    /// The contents of the specification.
+    contents: String,
+
+    /// The commit of the specification repository that was checked out, if
+    /// it could be determined.
+    commit: Option<String>,
+
+    /// The path of the specification file.
+    path: PathBuf,
+}
+
+impl Specification {
+    /// Gets the contents of the specification.
+    pub(crate) fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    /// Gets the commit of the specification repository that was checked out,
+    /// if it could be determined.
+    pub(crate) fn commit(&self) -> Option<&str> {
+        self.commit.as_deref()
+    }
+
+    /// Gets the path of the specification file.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
 /// Performs conformance tests on the WDL specification.
-#[derive(Parser, Debug)]
+#[derive(Clone, Parser, Debug)]
 pub struct Args {
     /// The branch to check out.
     #[arg(short, long, default_value = "wdl-1.2")]
@@ -108,10 +343,28 @@ pub struct Args {
     #[arg(short, long, default_value_t = false)]
     force: bool,
 
+    /// How the tests are laid out within the conformance tests directory.
+    ///
+    /// `sections` writes each test to `<section-slug>/<file-name>`, so that
+    /// sections may reuse the file names of examples in other sections, and
+    /// records the path of each test in the results file and JUnit report.
+    /// Local imports are resolved relative to the directory of the test.
+    #[arg(long, value_enum, default_value_t = Layout::Flat)]
+    layout: Layout,
+
     /// A directory that contains the specification repository.
     #[arg(short, long)]
     specification_dir: Option<PathBuf>,
 
+    /// The URL of the rendered specification that failures link to, such as
+    /// `https://github.com/openwdl/wdl/blob/wdl-1.2/SPEC.md`.
+    ///
+    /// Each failure links to the anchor of the section containing the test.
+    /// Defaults to `SPEC.md` at the tested commit (or `--branch`) within
+    /// `--repository-url`, when that is an `https` URL.
+    #[arg(long, value_name = "URL")]
+    specification_url: Option<String>,
+
     /// Runtime capabilities available for tests (comma-separated).
     ///
     /// Tests requiring capabilities not in this list will be skipped.
@@ -122,6 +375,47 @@ pub struct Args {
     #[arg(long, conflicts_with = "capabilities")]
     all_capabilities: bool,
 
+    /// Path to a TOML file of settings.
+    ///
+    /// Each key is the long name of an option, such as `include` or
+    /// `output-selector`, and `command` is the command template. Options
+    /// provided on the command line take precedence over the file. Defaults
+    /// to `spectool.toml` in the current directory, if it exists.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Use the known-good settings of a common engine.
+    ///
+    /// The preset supplies the command template, target arguments, output
+    /// selectors, and whether outputs are read from stdout. Any of these
+    /// provided on the command line take precedence over the preset, and the
+    /// command may be omitted.
+    #[arg(long, value_enum)]
+    engine: Option<Engine>,
+
+    /// Path to a manifest the engine publishes (conventionally
+    /// `capabilities.toml`) describing what it supports.
+    ///
+    /// The manifest supplies the command template, engine version, supported
+    /// WDL versions and capabilities, unsupported constructs, and output
+    /// conventions. Settings provided on the command line or in the config
+    /// file take precedence over the manifest, which takes precedence over
+    /// the `--engine` preset.
+    #[arg(long, value_name = "PATH")]
+    engine_manifest: Option<PathBuf>,
+
+    /// Run every test against each of several engines and report a
+    /// conformance matrix.
+    ///
+    /// Each value is either the name of an engine preset (e.g., `sprocket`)
+    /// or `NAME=COMMAND` for a command template. Engines with their own
+    /// target arguments, selectors, or baseline may be defined as `[[matrix]]`
+    /// tables in the config file. The matrix is written to stdout as a
+    /// Markdown table, and each file written by the run (e.g., with
+    /// `--results-file`) gets the name of the engine added to it.
+    #[arg(long, value_name = "ENGINE", conflicts_with_all = ["engine", "command"])]
+    matrix: Vec<matrix::Entry>,
+
     /// Arguments to append when running a workflow.
     ///
     /// Use `~{target}` for the workflow name.
@@ -134,12 +428,103 @@ pub struct Args {
     #[arg(long, default_value = "")]
     task_target_args: String,
 
-    /// Redirect stdout to the outputs file.
+    /// The engine cannot execute a bare task.
+    ///
+    /// Implies `--wrap-tasks`. Tests that target a task that cannot be
+    /// wrapped are skipped.
+    #[arg(long, default_value_t = false)]
+    tasks_unsupported: bool,
+
+    /// WDL constructs the engine does not support (comma-separated).
+    ///
+    /// Tests using any of these constructs, after any `--target-dialect`
+    /// transform or `--preprocess-cmd`, are skipped instead of failing with
+    /// a parse error.
+    #[arg(long, value_delimiter = ',', value_name = "CONSTRUCTS")]
+    unsupported_constructs: Vec<Construct>,
+
+    /// Wrap tests that target a task in a generated workflow.
+    ///
+    /// The wrapper forwards the task's inputs and outputs and becomes the
+    /// target, and input and expected output keys are renamed to match. This
+    /// is useful for engines that can only execute workflows.
+    #[arg(long, default_value_t = false)]
+    wrap_tasks: bool,
+
+    /// Strip the `<target>.` prefix from output keys before validation.
     ///
-    /// If enabled, appends `> ~{output}` at the end of the command.
+    /// Useful for engines that report outputs without the prefix used by the
+    /// specification's expected outputs.
     #[arg(long, default_value_t = false)]
+    strip_output_prefix: bool,
+
+    /// The tolerance within which floating point outputs are considered equal.
+    ///
+    /// Either an absolute tolerance (e.g., `1e-6`) or `abs=<number>` and/or
+    /// `rel=<number>` separated by a comma, where the relative tolerance is
+    /// scaled by the larger magnitude of the two numbers. A `float_tolerance`
+    /// in a test's config takes precedence.
+    #[arg(long, value_name = "TOLERANCE")]
+    float_tolerance: Option<FloatTolerance>,
+
+    /// Redirect stdout to the outputs file.
+    ///
+    /// If enabled, appends `> ~{output}` at the end of the command. This is a
+    /// shorthand for `--outputs-from stdout`.
+    #[arg(long, default_value_t = false, conflicts_with = "outputs_from")]
     redirect_stdout: bool,
 
+    /// Where the outputs of each test are read from.
+    ///
+    /// With `file`, outputs are read from the file written to `~{output}` (or
+    /// from `--output-file`). With `stdout`, they are parsed from the standard
+    /// output of the command. With `auto`, the file is tried first and stdout
+    /// is parsed if the command did not write one; the source that was used
+    /// is recorded with each test in the results file.
+    #[arg(long, value_enum, value_name = "SOURCE")]
+    outputs_from: Option<OutputSource>,
+
+    /// How test inputs are delivered to the engine.
+    ///
+    /// With `file`, inputs are written to an `inputs.json` file referenced by
+    /// `~{input}`. With `args`, inputs are expanded into quoted `key=value`
+    /// arguments referenced by `~{input_args}` and no file is written.
+    #[arg(long, value_enum, default_value_t = InputMode::File)]
+    input_mode: InputMode,
+
+    /// Run the command template (and `--check-command`) directly rather than
+    /// with `bash -c`.
+    ///
+    /// The template is split into arguments as a shell would (with quotes and
+    /// backslash escapes) before placeholders are replaced, so substituted
+    /// paths and inputs are passed as single arguments however they are
+    /// quoted. Pipes, redirections, and variables are not interpreted.
+    #[arg(long, default_value_t = false)]
+    no_shell: bool,
+
+    /// Path to a template for an engine options file.
+    ///
+    /// The template's contents have the same `~{...}` substitutions applied as
+    /// the command and are written to each test's working directory. The
+    /// written file is available to the command as `~{options}`.
+    ///
+    /// For example, a Cromwell workflow options JSON can set
+    /// `"final_workflow_outputs_dir": "~{workdir}/out"`.
+    #[arg(long, value_name = "TEMPLATE")]
+    engine_options_file: Option<PathBuf>,
+
+    /// A validation-only command for tests tagged as `static`.
+    ///
+    /// Static tests only concern whether a document is accepted or rejected
+    /// (e.g., grammar and typing rules), so they are checked with this
+    /// command rather than executed. The test passes if the command exits
+    /// with zero, or with non-zero for tests marked with `fail: true`.
+    ///
+    /// For example, `--check-command "sprocket check ~{path}"`. When not
+    /// provided, static tests are executed like any other test.
+    #[arg(long, value_name = "TEMPLATE")]
+    check_command: Option<String>,
+
     /// Path to read outputs from after the command executes.
     ///
     /// Supports `~{target}` substitution for the workflow or task name. When
@@ -153,26 +538,95 @@ pub struct Args {
 
     /// Only run tests matching these patterns (comma-separated).
     ///
-    /// Patterns are matched as substrings of test names.
+    /// Patterns are globs in which `*` matches any sequence of characters
+    /// and `?` matches a single character; a pattern with a wildcard must
+    /// match the whole test name (e.g., `string_*`), while one without
+    /// matches any test name containing it.
     /// Mutually exclusive with `--exclude`.
-    #[arg(long, value_delimiter = ',', conflicts_with = "exclude")]
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["exclude", "exclude_regex"])]
     include: Vec<String>,
 
     /// Skip tests matching these patterns (comma-separated).
     ///
-    /// Patterns are matched as substrings of test names.
+    /// Patterns are globs, as with `--include`.
     /// Mutually exclusive with `--include`.
-    #[arg(long, value_delimiter = ',', conflicts_with = "include")]
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["include", "include_regex"])]
     exclude: Vec<String>,
 
+    /// Only run tests whose name matches one of these regular expressions.
+    ///
+    /// Regular expressions are searched for within test names (without the
+    /// `.wdl` extension), so use `^` and `$` to match whole names. Tests
+    /// matching either these or the `--include` patterns are run.
+    #[arg(long, value_name = "REGEX", conflicts_with_all = ["exclude", "exclude_regex"])]
+    include_regex: Vec<Pattern>,
+
+    /// Skip tests whose name matches any of these regular expressions.
+    #[arg(long, value_name = "REGEX")]
+    exclude_regex: Vec<Pattern>,
+
+    /// Only run tests with at least one of these tags (comma-separated).
+    ///
+    /// Tags are set with `tags` in the config of each test, e.g.,
+    /// `deprecated`, `static`, or any tag the specification defines.
+    #[arg(long, value_delimiter = ',')]
+    tags: Vec<Tag>,
+
+    /// Skip tests with any of these tags (comma-separated), e.g.,
+    /// `--exclude-tags deprecated`.
+    #[arg(long, value_delimiter = ',', value_name = "TAGS")]
+    exclude_tags: Vec<Tag>,
+
+    /// Only run tests within specification sections whose heading contains
+    /// one of these patterns (comma-separated, case-insensitive).
+    #[arg(long, value_delimiter = ',')]
+    sections: Vec<String>,
+
+    /// Only run tests written for one of these WDL versions
+    /// (comma-separated).
+    ///
+    /// Versions are matched against each test's `version` statement before
+    /// any version is injected.
+    #[arg(long, value_delimiter = ',', value_name = "VERSIONS")]
+    wdl_versions: Vec<String>,
+
+    /// Only run the tests in this shard (zero-based), out of `--shard-count`
+    /// shards.
+    ///
+    /// Tests are assigned to shards by a stable hash of their name.
+    #[arg(long, env = "SPECTOOL_SHARD_INDEX", value_name = "INDEX")]
+    shard_index: Option<usize>,
+
+    /// The total number of shards when running with `--shard-index`.
+    #[arg(long, env = "SPECTOOL_SHARD_COUNT", value_name = "COUNT")]
+    shard_count: Option<usize>,
+
     /// A `jq` selector to apply to `outputs.json` before validation.
     ///
     /// This allows transforming the output JSON before comparing against expected output.
     /// For example, `--output-selector '.outputs'` will extract the `outputs` field from the output.
     ///
-    /// Uses `jq` syntax (e.g., `'.outputs'`, `'.result.data[0]'`, etc.).
+    /// Uses `jq` syntax (e.g., `'.outputs'`, `'.result.data[0]'`, etc.). May
+    /// be repeated to apply several selectors in sequence. A selector of the
+    /// form `@<name>` refers to a named selector from `--selectors-file`.
+    #[arg(long)]
+    output_selector: Vec<String>,
+
+    /// A `jq` selector to apply to the expected outputs before validation.
+    ///
+    /// Useful when a transform of the engine's outputs cannot fully reconcile
+    /// their shape with the expected outputs. May be repeated and may refer to
+    /// named selectors in the same way as `--output-selector`.
     #[arg(long)]
-    output_selector: Option<String>,
+    expected_selector: Vec<String>,
+
+    /// Path to a JSON file of named selectors.
+    ///
+    /// The file maps each name to a selector or to a list of selectors
+    /// applied in sequence, for example `{"unwrap": ".outputs"}`. Named
+    /// selectors are referenced with `--output-selector @<name>`.
+    #[arg(long, value_name = "PATH")]
+    selectors_file: Option<PathBuf>,
 
     /// WDL version to inject into test files.
     ///
@@ -184,286 +638,1913 @@ pub struct Args {
     #[arg(long, value_name = "VERSION")]
     inject_wdl_version: Option<String>,
 
+    /// Transform the WDL 1.2 tests to an older WDL version, on a best-effort
+    /// basis, for engines that do not support WDL 1.2.
+    ///
+    /// `requirements` sections become `runtime` sections and `hints`
+    /// sections are removed. Tests that use features without a counterpart
+    /// in the older version (e.g., `env` declarations or new standard library
+    /// functions) are skipped with the features listed.
+    #[arg(long, value_name = "VERSION", conflicts_with = "inject_wdl_version")]
+    target_dialect: Option<Dialect>,
+
+    /// A command that rewrites the WDL source of each test before it is
+    /// written to disk (e.g., `sed 's/requirements {/runtime {/'`).
+    ///
+    /// The command is run with `bash`, receiving the source (after any
+    /// `--inject-wdl-version`) on stdin and the file name of the test in
+    /// `SPECTOOL_TEST`, and must write the rewritten source to stdout. The
+    /// names of workflows, tasks, inputs, and outputs must not be changed.
+    /// Tests whose source was changed (by this or `--target-dialect`) are
+    /// marked as `preprocessed` in the results file.
+    #[arg(long, value_name = "COMMAND")]
+    preprocess_cmd: Option<String>,
+
+    /// Accept comments, trailing commas, and other JSON5 syntax within the
+    /// input, output, and config blocks of each test.
+    ///
+    /// By default, these blocks must be strict JSON.
+    #[arg(long, default_value_t = false)]
+    lenient_json: bool,
+
     /// Label for JSON badge output to stdout.
     ///
     /// The badge is output in Shields.io endpoint format with test results.
     /// For example, `--label "Cromwell WDL 1.2"` outputs:
     /// `{"schemaVersion": 1, "label": "Sprocket WDL 1.2", "message": "157/169 passed", "color": "yellow"}`
+    ///
+    /// The label may contain the same placeholders as
+    /// `--badge-message-template`.
     #[arg(long, default_value = "Spectool")]
     label: String,
 
-    /// Fail with a non-zero exit code if any tests fail.
-    #[arg(long, default_value_t = false)]
-    strict: bool,
+    /// A template for the message of the JSON badge.
+    ///
+    /// The following placeholders are supported: `{passed}`, `{failed}`,
+    /// `{total}`, `{percent}`, `{branch}`, and `{engine_version}`. For
+    /// example, `--badge-message-template "{passed}/{total} ({percent}%)"`.
+    /// Defaults to `{passed}/{total} passed`.
+    #[arg(long, value_name = "TEMPLATE")]
+    badge_message_template: Option<String>,
+
+    /// The version of the engine being tested, available to badge templates
+    /// as `{engine_version}`.
+    #[arg(long, value_name = "VERSION")]
+    engine_version: Option<String>,
 
-    /// Number of CPU cores to use for parallel test execution.
+    /// Write a badge for all tests and one for each conformance tier to this
+    /// directory.
     ///
-    /// Set to 1 for sequential execution (default).
-    /// Set to 0 to use all available CPU cores.
-    #[arg(short = 'n', long, default_value = "0")]
-    n_cpu: usize,
+    /// The badges are written as `overall.json`, `required.json`, and
+    /// `optional.json`. Tests that require a runtime capability are optional;
+    /// all other tests are required. The tier is available to badge templates
+    /// as `{tier}`, and is appended to the label in parentheses when the label
+    /// does not use it.
+    #[arg(long, value_name = "DIR")]
+    tier_badges: Option<PathBuf>,
+
+    /// Print a single-line summary of the run to stdout after the badge.
+    ///
+    /// The summary includes the number of tests that passed, failed, and were
+    /// skipped, the pass rate, and the wall time, either as `key=value` pairs
+    /// (`oneline`) or as a JSON object (`json`).
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    summary_format: Option<SummaryFormat>,
 
-    /// The command to call for each execution.
+    /// Write a machine-readable JSON report of the results to this path.
     ///
-    #[arg(help = r#"The command to call for each execution.
+    /// Each failed test includes a stable `category` and `code` so that
+    /// downstream tools can filter failures without parsing messages.
+    #[arg(long, value_name = "PATH")]
+    results_file: Option<PathBuf>,
 
-The following substitutions are supported:
+    /// Sign an attestation of the results file with this SSH private key.
+    ///
+    /// The attestation is an in-toto statement recording the digest of the
+    /// results file, the commit of the specification, and the engine and its
+    /// version. It is written next to the results file with an
+    /// `.intoto.json` extension, along with a detached `ssh-keygen -Y sign`
+    /// signature (`.intoto.json.sig`) in the `spectool` namespace. Requires
+    /// `--results-file`.
+    #[arg(long, value_name = "KEY")]
+    sign_results: Option<PathBuf>,
+
+    /// Write a Markdown report of the results to this path.
+    ///
+    /// The report has a pass-rate badge, a table of counts, and the details
+    /// of each failure in a collapsible section, for use as a pull request
+    /// comment or with `--markdown-report "$GITHUB_STEP_SUMMARY"` in GitHub
+    /// Actions. The file is overwritten.
+    #[arg(long, value_name = "PATH")]
+    markdown_report: Option<PathBuf>,
 
-  - `~{path}` is the path to the file.
-  - `~{input}` is the path to the inputs.json file.
-  - `~{output}` is the path to the outputs.json file."#)]
-    command: String,
-}
+    /// Append the summary of the run and the status of each test to this
+    /// history file, keyed by engine, specification branch, and commit.
+    ///
+    /// `spectool history` shows the pass-rate trend of the recorded runs and
+    /// the tests each run newly broke or fixed. With `--matrix`, every engine
+    /// is appended to the same file.
+    #[arg(long, value_name = "PATH")]
+    history_file: Option<PathBuf>,
 
-/// The main method.
-pub fn main(mut args: Args) -> Result<()> {
-    //======================//
-    // Handle capabilities //
-    //======================//
+    /// Post a summary of the run to this Slack incoming webhook.
+    ///
+    /// With a history file (`--history-file` or a `history` sink), the
+    /// message compares the number of passing tests with the previous run of
+    /// the same engine.
+    #[arg(long, value_name = "URL", env = "SPECTOOL_SLACK_WEBHOOK")]
+    slack_webhook: Option<String>,
+
+    /// When `--slack-webhook` posts: after every run, or only when fewer
+    /// tests passed than in the previous run recorded in the history file.
+    #[arg(long, value_enum, default_value_t = sink::Notify::Always)]
+    notify_on: sink::Notify,
+
+    /// Pull every container image referenced by the selected tests before
+    /// running them.
+    ///
+    /// Images are pulled in parallel so that per-test timings reflect
+    /// execution rather than network transfers, and so that timeouts do not
+    /// trigger on first use of an image.
+    #[arg(long, default_value_t = false)]
+    pull_images: bool,
 
-    if args.all_capabilities {
-        args.capabilities = Capability::iter().collect();
-    }
+    /// The command used to pull each container image with `--pull-images`.
+    ///
+    /// `~{image}` is replaced with the name of the image.
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = "docker pull ~{image}",
+        requires = "pull_images"
+    )]
+    pull_command: String,
+
+    /// Copy only the files within the `data` directory that a test refers to
+    /// into its working directory, rather than every resource.
+    ///
+    /// References are the strings starting with `data/` in a test's input or
+    /// WDL source. This verifies that each test declares the data it reads;
+    /// tests that compute paths to data at runtime will fail.
+    #[arg(long, default_value_t = false)]
+    stage_referenced_data: bool,
 
-    //=======================================//
-    // Checkout the specification repository //
-    //=======================================//
+    /// The directory within which each test gets its own working directory.
+    ///
+    /// Defaults to the system's temporary directory.
+    #[arg(long, value_name = "DIR", env = "SPECTOOL_WORK_ROOT")]
+    work_root: Option<PathBuf>,
 
-    let (_, path) = Repository::builder()
-        .branch(args.branch.clone())
-        .url(args.repository_url.clone())
-        .maybe_local_dir(args.specification_dir.clone())
-        .build()
-        .checkout()?;
+    /// Which working directories are kept for inspection after the run.
+    ///
+    /// By default, only those of failed tests are kept; `--keep-workdirs`
+    /// on its own keeps them all, and `--keep-workdirs=none` keeps none. The
+    /// others are removed once the run has been reported.
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = KeepWorkdirs::Failed,
+        default_missing_value = "all"
+    )]
+    keep_workdirs: KeepWorkdirs,
+
+    /// Run commands with a scrubbed environment so that results do not vary
+    /// with the locale or stray environment variables of the machine.
+    ///
+    /// Only `HOME`, `PATH`, `TMPDIR`, `USER`, and the variables passed with
+    /// `--env-allow` are passed through. Empty and relative entries are
+    /// removed from `PATH`, `LANG` and `LC_ALL` are set to the `--locale`
+    /// (`C.UTF-8` by default), and `TZ` is set to `UTC`.
+    #[arg(long, default_value_t = false)]
+    hermetic_env: bool,
 
-    //=================================//
-    // Read the specification contents //
-    //=================================//
+    /// Additional environment variables to pass through with `--hermetic-env`
+    /// (comma-separated).
+    #[arg(long, value_delimiter = ',', value_name = "VARS")]
+    env_allow: Vec<String>,
 
-    let spec = path.join(SPEC_FILE_NAME);
+    /// Environment variables to withhold with `--hermetic-env`, even if they
+    /// would otherwise be passed through (comma-separated).
+    #[arg(long, value_delimiter = ',', value_name = "VARS")]
+    env_deny: Vec<String>,
 
-    if !spec.exists() {
-        bail!(
-            "the specification does not exist at `{}` in the git repository",
-            SPEC_FILE_NAME
-        );
-    }
+    /// The locale to run commands with, set as `LANG` and `LC_ALL`.
+    #[arg(long, value_name = "LOCALE")]
+    locale: Option<String>,
 
-    let contents = std::fs::read_to_string(spec)?;
+    /// Set an environment variable for the engine (repeatable), such as a
+    /// credential or `MINIWDL_CFG`.
+    ///
+    /// Variables are set even with `--hermetic-env`. Their values are
+    /// recorded as `<redacted>` with each test's invocation.
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = Environment::parse_var)]
+    env: Vec<(String, String)>,
 
-    //===============================//
-    // Compile the conformance tests //
-    //===============================//
+    /// Path to a JSON file, written by the engine, reporting the portion of
+    /// each test's execution time that was spent on setup.
+    ///
+    /// Supports the same `~{...}` substitutions as the command. The file
+    /// should contain an object such as `{"setup_secs": 12.5}`. When present,
+    /// setup time (e.g., pulling container images) is excluded from the
+    /// timing statistics and recorded separately in the results file.
+    #[arg(long, value_name = "TEMPLATE")]
+    setup_time_file: Option<String>,
+
+    /// Stream the output of each command to the console as it is produced.
+    ///
+    /// Each line written to stdout or stderr is prefixed with the name of the
+    /// test. The output is still captured for evaluation. This is useful for
+    /// debugging long-running tests, and is best combined with `-n 1`.
+    #[arg(long, default_value_t = false)]
+    stream: bool,
 
-    let root_dir = args
-        .conformance_test_dir
-        .as_ref()
-        .map(|path| std::path::absolute(path).expect("path to be made absolute"))
-        .unwrap_or_else(|| tempfile::tempdir().expect("tempdir to create").keep());
+    /// Print a heartbeat every this many seconds while a test is running.
+    ///
+    /// Useful in CI, where a slow test (e.g., one waiting on a container image
+    /// to be pulled) would otherwise make the log appear to hang.
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    heartbeat: Option<u64>,
 
-    let runner = Runner::compile(
-        root_dir,
-        contents,
-        args.force,
-        args.inject_wdl_version.clone(),
-    )?;
+    /// The width of the console that result lines are laid out for.
+    ///
+    /// Defaults to `COLUMNS`, then to the width of the terminal, then to 100.
+    /// The status column is aligned after the longest selected test name
+    /// that fits, and details that do not fit on the result line are wrapped
+    /// onto indented lines.
+    #[arg(long, env = "COLUMNS", value_name = "COLUMNS")]
+    width: Option<usize>,
+
+    /// Show a progress bar below the result lines with the number of tests
+    /// that passed, failed, and were skipped so far, the most recent test,
+    /// the elapsed time, and an estimate of the time remaining.
+    ///
+    /// The progress bar is only shown when stderr is a terminal; otherwise,
+    /// only the result lines are printed.
+    #[arg(long, default_value_t = false)]
+    progress: bool,
 
-    //=======================================//
-    // Configure parallel execution settings //
-    //=======================================//
+    /// Never color the result lines.
+    ///
+    /// Result lines are only colored when stderr is a terminal and `NO_COLOR`
+    /// is not set.
+    #[arg(long, default_value_t = false)]
+    no_color: bool,
+
+    /// Whether the global `--quiet` was passed, in which case only the result
+    /// lines of failed and unexpectedly passing tests are printed before the
+    /// summary.
+    #[arg(skip)]
+    quiet: bool,
+
+    /// Write an event to this file as a line of JSON when the run starts,
+    /// when each test starts and finishes, with each `--heartbeat`, and when
+    /// the run finishes; `-` writes the events to stdout in place of the
+    /// badge and summary line.
+    ///
+    /// Each event has an `event` type, a `timestamp` in seconds since the
+    /// Unix epoch, and the `run_id`. Finished tests include their status,
+    /// failure code and message, command, and duration, as in the results
+    /// file.
+    #[arg(long, value_name = "PATH")]
+    events: Option<PathBuf>,
 
-    let n_cpu = if args.n_cpu == 0 {
-        num_cpus::get()
-    } else {
-        args.n_cpu
-    };
+    /// Print the exact command used to execute each failed test.
+    ///
+    /// The command is printed with the directory it was executed in so that
+    /// it can be copied and pasted to reproduce the failure. The command is
+    /// always recorded in the results file.
+    #[arg(long, default_value_t = false)]
+    show_commands: bool,
 
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(n_cpu)
-        .build()
-        .expect("thread pool to build");
-    info!("using {n} cores", n = n_cpu);
+    /// Write an archive of every failed test to this path (`.tar.gz`).
+    ///
+    /// For each failed test, the archive contains the WDL document, inputs,
+    /// expected and actual outputs, the command's logs, and the exact command
+    /// that was run, along with a report of the environment. This is intended
+    /// to be attached to engine bug reports.
+    #[arg(long, value_name = "PATH")]
+    bundle_failures: Option<PathBuf>,
 
-    //===============//
-    // Run the tests //
-    //===============//
+    /// Write an HTML page to this path with the metadata and summary of the
+    /// run, linking to every file it wrote and to the artifacts of each
+    /// failed test.
+    ///
+    /// Files within the page's directory are linked relative to it, so
+    /// writing every output to one directory and uploading it as a CI
+    /// artifact gives a bundle that can be browsed.
+    #[arg(long, value_name = "PATH")]
+    html_index: Option<PathBuf>,
 
-    let wall_time_start = std::time::Instant::now();
+    /// Write the artifacts of every test that is run to a subdirectory of this
+    /// directory named after the test, within a directory named after the
+    /// run identifier.
+    ///
+    /// Each subdirectory contains the exact command that was run
+    /// (`command.sh`), its full stdout and stderr (`stdout.log` and
+    /// `stderr.log`), `inputs.json`, and `outputs.json`.
+    #[arg(long, value_name = "DIR")]
+    artifacts_dir: Option<PathBuf>,
 
-    let args = Arc::new(args);
-    let root_dir = Arc::new(runner.root_dir().to_path_buf());
-    let timings = TestTimings::new();
-    let print_lock = Arc::new(Mutex::new(()));
-    let (tx, rx) = mpsc::channel();
+    /// The identifier of the run.
+    ///
+    /// The identifier is included in log lines, the artifacts directory, the
+    /// results file, the summary, and the failure bundle so that the results
+    /// of concurrent or past runs can be told apart. A random UUID is
+    /// generated if none is provided; pass one to correlate a run with, e.g.,
+    /// a CI job.
+    #[arg(long, value_name = "ID", env = "SPECTOOL_RUN_ID")]
+    run_id: Option<String>,
+
+    /// Fail tests that take longer than the `max_duration` in their config.
+    ///
+    /// Without this flag, such tests are reported with a warning but
+    /// otherwise pass. Setup time reported with `--setup-time-file` does not
+    /// count towards the duration.
+    #[arg(long, default_value_t = false)]
+    enforce_durations: bool,
 
-    for test in runner.tests() {
-        let test = test.clone();
-        let root_dir = Arc::clone(&root_dir);
-        let args = Arc::clone(&args);
-        let timings = timings.clone();
-        let print_lock = Arc::clone(&print_lock);
-        let tx = tx.clone();
-        pool.spawn(move || {
-            process_test(test, args, root_dir, timings, print_lock, tx);
-        });
-    }
+    /// Path to a file listing tests that are known to fail.
+    ///
+    /// The file is JSON, or TOML if it has a `.toml` extension, and contains a
+    /// `failing` list of test names. Failures of these tests are reported as
+    /// `XFAIL` and do not count against `--strict`, while passes are reported
+    /// as `XPASS` so that the baseline can be updated.
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<PathBuf>,
 
-    drop(tx);
-    let results: Vec<_> = rx.into_iter().collect();
-    let wall_time_elapsed = wall_time_start.elapsed();
+    /// After the run, write the tests that failed back into the `--baseline`
+    /// file, creating it if it does not exist.
+    ///
+    /// Tests in the baseline that were not run are kept.
+    #[arg(long, default_value_t = false)]
+    update_baseline: bool,
 
-    let (
-        expected_pass_test_pass_times,
-        expected_pass_test_fail_times,
-        expected_fail_test_pass_times,
-        expected_fail_test_fail_times,
-    ) = timings.into_parts();
+    /// Abort the run after the first failure.
+    ///
+    /// Shorthand for `--max-failures 1`.
+    #[arg(long, default_value_t = false, conflicts_with = "max_failures")]
+    fail_fast: bool,
 
-    //===================//
-    // Print summary     //
-    //===================//
+    /// Abort the run after this many tests fail.
+    ///
+    /// Tests that are already running finish, and the tests that have not
+    /// started are skipped. Failures expected by the `--baseline` are not
+    /// counted.
+    #[arg(long, value_name = "N")]
+    max_failures: Option<NonZeroUsize>,
+
+    /// Abort the run if this many of the first tests to finish all fail to
+    /// execute with the same error, as when the engine is not installed or
+    /// cannot start; `0` never aborts.
+    ///
+    /// Tests fail with the same error when the engine fails to start, exits
+    /// with the same unexpected return code, or produces no outputs, and the
+    /// last line of their stderr is the same. Tests that have not started are
+    /// skipped.
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    crash_loop_threshold: usize,
+
+    /// Stop starting tests once the run has taken this long (e.g., `45m`),
+    /// written as a number followed by `d`, `h`, `m`, or `s`.
+    ///
+    /// Tests that are already running finish, and the tests that have not
+    /// started are marked as not run. The summary and reports flag the run as
+    /// truncated, so a CI job with a hard time limit still gets the results
+    /// of the tests that ran.
+    #[arg(long, value_name = "DURATION", value_parser = super::parse_duration)]
+    run_timeout: Option<Duration>,
+
+    /// Only run the tests that failed in the previous run, as recorded in the
+    /// `--state-file`. Requires `--state-file`.
+    ///
+    /// Other filters still apply, so `--rerun-failed --include array` only
+    /// reruns the failed tests matching `array`.
+    #[arg(long, default_value_t = false)]
+    rerun_failed: bool,
 
-    eprintln!("\n{}", "=".repeat(60));
-    eprintln!("Test Summary");
-    eprintln!("{}", "=".repeat(60));
-    eprintln!();
+    /// Run the tests that failed recently before the rest, so that the most
+    /// relevant results arrive first. Requires `--state-file` or a history
+    /// file.
+    ///
+    /// The tests that failed in the previous run recorded in the
+    /// `--state-file` come first, followed by the tests that failed in the
+    /// latest runs of the same engine and branch within the history, most
+    /// recent failures first. Results are still reported in specification
+    /// order.
+    #[arg(long, default_value_t = false)]
+    failed_first: bool,
 
-    let passed = results.iter().filter(|(_, r)| r.is_passed()).count();
-    let failed = results.iter().filter(|(_, r)| r.is_failed()).count();
-    let skipped = results.iter().filter(|(_, r)| r.is_skipped()).count();
+    /// Record the outcome of every test in this file after each run, for
+    /// `--rerun-failed`.
+    ///
+    /// The file is a results file (as with `--results-file`) that is updated
+    /// rather than replaced, so tests that were not run keep their previous
+    /// outcome.
+    #[arg(long, value_name = "PATH")]
+    state_file: Option<PathBuf>,
 
-    eprintln!("Passed:  {}", passed);
-    eprintln!("Failed:  {}", failed);
-    eprintln!("Skipped: {}", skipped);
-    eprintln!("Total:   {}", passed + failed);
-    eprintln!();
-    eprintln!("Wall time:    {:.2}s", wall_time_elapsed.as_secs_f64());
-    eprintln!();
+    /// Fail with a non-zero exit code if any tests fail.
+    #[arg(long, default_value_t = false)]
+    strict: bool,
 
-    // Calculate and display statistics for each category
-    if !expected_pass_test_pass_times.is_empty() {
-        let times_secs: Vec<f64> = expected_pass_test_pass_times
-            .iter()
-            .map(|d| d.as_secs_f64())
-            .collect();
-        let n = times_secs.len();
-        let mean = times_secs.as_slice().mean();
-        let stddev = times_secs.as_slice().std_dev();
-        let median = Data::new(times_secs).median();
-        eprintln!(
-            "Stats (expected pass, test pass, n={}): mean={:.2}s, median={:.2}s, stddev={:.2}s",
-            n, mean, median, stddev
-        );
-    }
+    /// Before running, evict the oldest repository checkouts, compiled
+    /// suites, and working directories that `spectool` kept in the temporary
+    /// directory and the `--work-root` until their total size is at most this
+    /// (e.g., `5G`). Directories in use by another run are never evicted.
+    #[arg(long, value_name = "SIZE")]
+    cache_max_size: Option<crate::cache::Size>,
 
-    if !expected_pass_test_fail_times.is_empty() {
-        let times_secs: Vec<f64> = expected_pass_test_fail_times
-            .iter()
-            .map(|d| d.as_secs_f64())
-            .collect();
-        let n = times_secs.len();
-        let mean = times_secs.as_slice().mean();
-        let stddev = times_secs.as_slice().std_dev();
-        let median = Data::new(times_secs).median();
-        eprintln!(
-            "Stats (expected pass, test fail, n={}): mean={:.2}s, median={:.2}s, stddev={:.2}s",
-            n, mean, median, stddev
-        );
-    }
+    /// Print the effective value of every setting and where it came from,
+    /// then exit without running any tests.
+    ///
+    /// Conflicting settings are still reported as errors.
+    #[arg(long, default_value_t = false)]
+    explain_config: bool,
 
-    if !expected_fail_test_pass_times.is_empty() {
-        let times_secs: Vec<f64> = expected_fail_test_pass_times
-            .iter()
-            .map(|d| d.as_secs_f64())
-            .collect();
-        let n = times_secs.len();
-        let mean = times_secs.as_slice().mean();
-        let stddev = times_secs.as_slice().std_dev();
-        let median = Data::new(times_secs).median();
-        eprintln!(
-            "Stats (expected fail, test pass, n={}): mean={:.2}s, median={:.2}s, stddev={:.2}s",
-            n, mean, median, stddev
-        );
-    }
+    /// The effective settings and where each came from.
+    #[arg(skip)]
+    settings: Vec<provenance::Setting>,
 
-    if !expected_fail_test_fail_times.is_empty() {
-        let times_secs: Vec<f64> = expected_fail_test_fail_times
-            .iter()
-            .map(|d| d.as_secs_f64())
-            .collect();
-        let n = times_secs.len();
-        let mean = times_secs.as_slice().mean();
-        let stddev = times_secs.as_slice().std_dev();
-        let median = Data::new(times_secs).median();
-        eprintln!(
-            "Stats (expected fail, test fail, n={}): mean={:.2}s, median={:.2}s, stddev={:.2}s",
-            n, mean, median, stddev
-        );
-    }
+    /// The result sinks from the config file.
+    #[arg(skip)]
+    sinks: Vec<sink::Config>,
+
+    /// Number of CPU cores to use for parallel test execution.
+    ///
+    /// Each test is executed in its own working directory, so tests do not
+    /// interfere with each other when run in parallel.
+    ///
+    /// Set to 1 for sequential execution.
+    /// Set to 0 to use all available CPU cores (default).
+    #[arg(short = 'n', long, visible_alias = "jobs", default_value = "0")]
+    n_cpu: usize,
+
+    /// The command to call for each execution.
+    ///
+    #[arg(help = r#"The command to call for each execution.
+
+The following substitutions are supported:
+
+  - `~{path}` is the path to the file.
+  - `~{input}` is the path to the inputs.json file.
+  - `~{input_args}` is the inputs as quoted `key=value` arguments.
+  - `~{output}` is the path to the outputs.json file.
+  - `~{target}` is the name of the workflow or task.
+  - `~{workdir}` is the path to the working directory of the test.
+  - `~{options}` is the path to the engine options file.
+
+May be omitted when `--engine` is provided or `command` is set in the config
+file."#)]
+    command: Option<String>,
+}
+
+impl Args {
+    /// Records where each setting came from for `--explain-config`.
+    pub fn with_settings(mut self, matches: &clap::ArgMatches) -> Self {
+        self.settings = provenance::collect(matches);
+        self
+    }
+
+    /// Sets whether `--quiet` was passed, which leaves only the failed tests
+    /// and the summary on the console.
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Gets where the outputs of each test are read from.
+    fn output_source(&self) -> OutputSource {
+        match (self.outputs_from, self.redirect_stdout) {
+            (Some(source), _) => source,
+            (None, true) => OutputSource::Stdout,
+            (None, false) => OutputSource::File,
+        }
+    }
+
+    /// Gets the command template.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no command was provided on the command line, in the config
+    /// file, or by the engine preset, which is checked before any tests run.
+    fn command_template(&self) -> &str {
+        self.command
+            .as_deref()
+            .expect("command to be provided or filled in from the engine preset")
+    }
+
+    /// Gets the number of failures after which the run is aborted, if any.
+    fn max_failures(&self) -> Option<usize> {
+        self.max_failures
+            .map(NonZeroUsize::get)
+            .or(self.fail_fast.then_some(1))
+    }
+
+    /// Gets the directory within which the working directory of each test is
+    /// created.
+    fn work_root(&self) -> PathBuf {
+        self.work_root.clone().unwrap_or_else(std::env::temp_dir)
+    }
+
+    /// Fills in each setting that was not provided on the command line from
+    /// the engine preset, if one was selected.
+    fn apply_preset(&mut self) {
+        let Some(engine) = self.engine else {
+            return;
+        };
+
+        let preset = engine.preset();
+        let profile = preset.profile();
+        let mut record = |name: &str, value: &str| {
+            provenance::record(
+                &mut self.settings,
+                name,
+                value,
+                provenance::Source::Preset(engine),
+            )
+        };
+
+        if self.command.is_none() {
+            record("<COMMAND>", preset.command());
+            self.command = Some(preset.command().to_string());
+        }
+
+        // An explicit `--output-file` replaces the preset's output discovery,
+        // as the engine is known to write its outputs there.
+        if self.outputs_from.is_none() && !self.redirect_stdout && self.output_file.is_none() {
+            record("--outputs-from", &preset.outputs().to_string());
+            self.outputs_from = Some(preset.outputs());
+        }
+
+        if self.workflow_target_args.is_empty() && !profile.target_args().workflow().is_empty() {
+            record("--workflow-target-args", profile.target_args().workflow());
+            self.workflow_target_args = profile.target_args().workflow().to_string();
+        }
+
+        if self.task_target_args.is_empty() && !profile.target_args().task().is_empty() {
+            record("--task-target-args", profile.target_args().task());
+            self.task_target_args = profile.target_args().task().to_string();
+        }
+
+        if profile.tasks_unsupported() && !self.tasks_unsupported {
+            record("--tasks-unsupported", "true");
+            self.tasks_unsupported = true;
+        }
+
+        if self.unsupported_constructs.is_empty() && !profile.unsupported_constructs().is_empty() {
+            let constructs = profile.unsupported_constructs();
+            record(
+                "--unsupported-constructs",
+                &constructs
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+            self.unsupported_constructs = constructs.to_vec();
+        }
+
+        if self.output_selector.is_empty() && !profile.output_selectors().is_empty() {
+            record("--output-selector", &profile.output_selectors().join(","));
+            self.output_selector = profile.output_selectors().to_vec();
+        }
+    }
+}
+
+/// The main method.
+pub fn main(mut args: Args) -> Result<()> {
+    //======================//
+    // Read the config file //
+    //======================//
+
+    if let Some(file) = config_file::ConfigFile::discover(args.config.as_deref())? {
+        file.apply(&mut args);
+    }
+
+    //=====================//
+    // Identify the run    //
+    //=====================//
+
+    let run_id = args
+        .run_id
+        .clone()
+        .unwrap_or_else(crate::report::generate_run_id);
+
+    // The span is at the error level so that the identifier is included in
+    // log lines at every verbosity.
+    let span = tracing::error_span!("run", id = %run_id);
+    let _span = span.enter();
+    info!("starting run `{run_id}`");
+
+    //============================//
+    // Apply the engine manifest //
+    //============================//
+
+    // Each engine of a matrix reads its manifest separately
+    if args.matrix.is_empty()
+        && let Some(path) = args.engine_manifest.clone()
+    {
+        manifest::apply(&path, &mut args)?;
+    }
+
+    //======================//
+    // Handle capabilities //
+    //======================//
+
+    if args.all_capabilities {
+        args.capabilities = Capability::iter().collect();
+    }
+
+    //=====================================//
+    // Run against every engine of a matrix //
+    //=====================================//
+
+    if !args.matrix.is_empty() {
+        return matrix::main(args, run_id);
+    }
+
+    //=========================//
+    // Apply the engine preset //
+    //=========================//
+
+    args.apply_preset();
+
+    //===============================//
+    // Validate the command template //
+    //===============================//
+
+    if args.explain_config {
+        println!("{}", provenance::render(&args.settings));
+        validate_command(&args)?;
+        return Ok(());
+    }
+
+    let mut specification = None;
+    let run = execute(args, run_id, &mut specification)?;
+    // Events written to stdout replace the badge and summary line, so that
+    // stdout can be parsed as events
+    let stdout = run.shared.args.events.as_deref() != Some(Path::new("-"));
+    let unexpected_failures = report(&run, stdout)?;
+
+    if run.shared.args.strict && unexpected_failures > 0 {
+        bail!("{unexpected_failures} test(s) failed");
+    }
+
+    Ok(())
+}
+
+/// The tests run against a single engine.
+struct Run<'a> {
+    /// The state shared by every test within the run.
+    shared: Arc<Shared>,
+
+    /// The compiled conformance tests.
+    runner: Runner<'a>,
+
+    /// The outcome of each test, in specification order.
+    results: Vec<Processed>,
+
+    /// The execution times of the tests.
+    timings: TestTimings,
+
+    /// The wall time of the run.
+    wall_time: Duration,
+}
+
+/// Runs the tests against the engine described by `args`.
+///
+/// The specification is read into `specification` if it has not already been
+/// read, so that it is only read once when running against several engines.
+fn execute(
+    args: Args,
+    run_id: String,
+    specification: &mut Option<Specification>,
+) -> Result<Run<'_>> {
+    validate_command(&args)?;
+
+    let engine_options = args
+        .engine_options_file
+        .as_ref()
+        .map(|path| {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("reading engine options template `{}`", path.display()))
+        })
+        .transpose()?;
+
+    if let Some(template) = &engine_options {
+        validate_template(template)?;
+    }
+
+    for template in std::iter::once(&args.label).chain(&args.badge_message_template) {
+        badge::validate_template(template)?;
+
+        if template.contains("{engine_version}") && args.engine_version.is_none() {
+            return Err(Diagnostic::new(
+                Code::MissingOption,
+                format!("badge template `{template}` uses `{{engine_version}}`, but no engine version was provided"),
+            )
+            .with_hint("pass `--engine-version` with the version of the engine being tested")
+            .into());
+        }
+    }
+
+    let library = args
+        .selectors_file
+        .as_ref()
+        .map(Library::from_path)
+        .transpose()?
+        .unwrap_or_default();
+    let output_selectors = library.resolve(&args.output_selector)?;
+    let expected_selectors = library.resolve(&args.expected_selector)?;
+
+    if args.update_baseline && args.baseline.is_none() {
+        return Err(Diagnostic::new(
+            Code::MissingOption,
+            "`--update-baseline` was passed, but no baseline file was provided",
+        )
+        .with_hint("pass `--baseline` with the path of the baseline file to write")
+        .into());
+    }
+
+    let shard = match (args.shard_index, args.shard_count) {
+        (None, None) => None,
+        (Some(index), Some(count)) => Some(Shard::new(index, count).ok_or_else(|| {
+            Diagnostic::new(
+                Code::ConflictingOptions,
+                format!("shard index {index} is out of range for {count} shard(s)"),
+            )
+            .with_hint("shard indices start at 0 and must be less than the shard count")
+        })?),
+        _ => {
+            return Err(Diagnostic::new(
+                Code::MissingOption,
+                "only one of the shard index and shard count was provided",
+            )
+            .with_hint(
+                "pass both `--shard-index` and `--shard-count`, or set both \
+                 `SPECTOOL_SHARD_INDEX` and `SPECTOOL_SHARD_COUNT`",
+            )
+            .into());
+        }
+    };
+
+    if !args.hermetic_env && (!args.env_allow.is_empty() || !args.env_deny.is_empty()) {
+        return Err(Diagnostic::new(
+            Code::MissingOption,
+            "`--env-allow` and `--env-deny` only apply to a hermetic environment",
+        )
+        .with_hint("pass `--hermetic-env` to run commands with a scrubbed environment")
+        .into());
+    }
+
+    let environment = if args.hermetic_env {
+        Environment::hermetic(
+            std::env::vars(),
+            &args.env_allow,
+            &args.env_deny,
+            args.locale.as_deref(),
+        )
+    } else {
+        Environment::inherited(args.locale.as_deref())
+    }
+    .with_vars(args.env.iter().cloned());
+
+    let baseline = args
+        .baseline
+        .as_ref()
+        .filter(|path| !args.update_baseline || path.exists())
+        .map(Baseline::from_path)
+        .transpose()?
+        .unwrap_or_default();
+
+    if let Some(max_size) = args.cache_max_size {
+        crate::cache::evict(&[std::env::temp_dir(), args.work_root()], max_size)?;
+    }
+
+    //=================================//
+    // Read the specification contents //
+    //=================================//
+
+    // The specification is borrowed rather than copied, so it is only held
+    // in memory once when running against several engines
+    let specification = match specification {
+        Some(specification) => specification,
+        None => specification.insert(checkout_specification(
+            &args.branch,
+            &args.repository_url,
+            args.specification_dir.clone(),
+        )?),
+    };
+    let contents = specification.contents();
+    let commit = specification.commit();
+
+    //===============================//
+    // Compile the conformance tests //
+    //===============================//
+
+    let root_dir = args
+        .conformance_test_dir
+        .as_ref()
+        .map(|path| std::path::absolute(path).expect("path to be made absolute"))
+        .unwrap_or_else(|| {
+            crate::cache::keep_dir(crate::cache::SUITE_PREFIX).expect("tempdir to create")
+        });
+
+    let options = CompileOptions::builder()
+        .force(args.force)
+        .maybe_inject_wdl_version(args.inject_wdl_version.clone())
+        .maybe_preprocess_cmd(args.preprocess_cmd.clone())
+        .maybe_target_dialect(args.target_dialect)
+        .unsupported_constructs(args.unsupported_constructs.clone())
+        .wrap_tasks(args.wrap_tasks || args.tasks_unsupported)
+        .json_syntax(if args.lenient_json {
+            JsonSyntax::Lenient
+        } else {
+            JsonSyntax::Strict
+        })
+        .layout(args.layout)
+        .build();
+
+    let runner = Runner::compile(root_dir, contents, &options)?;
+
+    let names = runner.tests().map(Test::file_name).collect::<Vec<_>>();
+    for name in baseline.unknown(&names) {
+        warn!("baseline lists `{name}`, which is not a test in the specification");
+    }
+
+    //=======================================//
+    // Configure parallel execution settings //
+    //=======================================//
+
+    let n_cpu = if args.n_cpu == 0 {
+        num_cpus::get()
+    } else {
+        args.n_cpu
+    };
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(n_cpu)
+        .build()
+        .expect("thread pool to build");
+    info!("using {n} cores", n = n_cpu);
+
+    let work_root = args.work_root();
+    std::fs::create_dir_all(&work_root)
+        .with_context(|| format!("creating work root `{}`", work_root.display()))?;
+
+    //==================//
+    // Select the tests //
+    //==================//
+
+    let rerun = args
+        .state_file
+        .as_ref()
+        .filter(|_| args.rerun_failed)
+        .map(|path| -> Result<_> {
+            let state = Report::from_path(path)
+                .context("`--rerun-failed` requires the state of a previous run")?;
+            let failed = state.failed().map(str::to_string).collect::<BTreeSet<_>>();
+            if failed.is_empty() {
+                warn!("no tests failed in the previous run");
+            } else {
+                info!(
+                    "rerunning {} test(s) that failed in the previous run",
+                    failed.len()
+                );
+            }
+            Ok(failed)
+        })
+        .transpose()?;
+    let recent_failures = args
+        .failed_first
+        .then(|| recent_failures(&args))
+        .transpose()?;
+
+    let filter = TestFilter::builder()
+        .include(
+            args.include
+                .iter()
+                .map(|glob| Pattern::glob(glob))
+                .chain(args.include_regex.iter().cloned())
+                .collect(),
+        )
+        .exclude(
+            args.exclude
+                .iter()
+                .map(|glob| Pattern::glob(glob))
+                .chain(args.exclude_regex.iter().cloned())
+                .collect(),
+        )
+        .tags(args.tags.clone())
+        .exclude_tags(args.exclude_tags.clone())
+        .sections(args.sections.clone())
+        .versions(args.wdl_versions.clone())
+        .maybe_shard(shard)
+        .maybe_names(rerun)
+        .build();
+
+    let unreferenced = unreferenced_resources(runner.resources(), runner.filter(&filter));
+    if !unreferenced.is_empty() {
+        warn!(
+            "{} resource(s) are not referred to by any selected test: {}",
+            unreferenced.len(),
+            unreferenced.join(", ")
+        );
+    }
+
+    //=======================//
+    // Pull container images //
+    //=======================//
+
+    if args.pull_images {
+        let tests = runner.filter(&filter).collect::<Vec<_>>();
+        pull_images(&args, &tests, &pool, &environment);
+    }
+
+    //===============//
+    // Run the tests //
+    //===============//
+
+    let wall_time_start = Instant::now();
+
+    let profile = Profile::builder()
+        .target_args(
+            TargetArgs::builder()
+                .workflow(args.workflow_target_args.clone())
+                .task(args.task_target_args.clone())
+                .build(),
+        )
+        .tasks_unsupported(args.tasks_unsupported)
+        .unsupported_constructs(args.unsupported_constructs.clone())
+        .output_key_map(
+            KeyMap::builder()
+                .strip_prefix(args.strip_output_prefix)
+                .build(),
+        )
+        .output_selectors(output_selectors)
+        .expected_selectors(expected_selectors)
+        .build();
+
+    let specification_url = args
+        .specification_url
+        .clone()
+        .or_else(|| specification_url(&args.repository_url, commit.unwrap_or(&args.branch)));
+    let crash_loop = CrashLoop::new(args.crash_loop_threshold);
+    let deadline = args.run_timeout.map(|timeout| wall_time_start + timeout);
+    let events = args
+        .events
+        .as_deref()
+        .map(|path| Events::create(path, &run_id))
+        .transpose()?;
+    let columns = Columns::new(
+        runner.filter(&filter).map(Test::file_name),
+        args.width.or_else(terminal_width),
+    );
+    let shared = Arc::new(Shared {
+        args,
+        profile,
+        filter,
+        engine_options,
+        baseline,
+        root_dir: runner.root_dir().to_path_buf(),
+        environment,
+        run_id,
+        specification_url,
+        specification_commit: commit.map(str::to_string),
+        failures: AtomicUsize::new(0),
+        crash_loop,
+        deadline,
+        events,
+        columns,
+    });
+    let timings = TestTimings::new();
+    let mut tests = runner.filter(&shared.filter).collect::<Vec<_>>();
+    if let Some(failures) = &recent_failures {
+        // Sorting is stable, so the other tests keep their order
+        tests.sort_by_key(|test| {
+            failures
+                .get(test.file_name())
+                .copied()
+                .unwrap_or(usize::MAX)
+        });
+        info!(
+            "running {} test(s) that failed recently first",
+            tests
+                .iter()
+                .filter(|test| failures.contains_key(test.file_name()))
+                .count()
+        );
+    }
+    let is_terminal = std::io::stderr().is_terminal();
+    let printer = Printer::new(
+        !shared.args.no_color && crate::diagnostic::use_color(is_terminal),
+        shared.args.quiet,
+    );
+    let printer = Arc::new(if shared.args.progress && is_terminal {
+        printer.with_progress(tests.len())
+    } else {
+        printer
+    });
+    let (tx, rx) = mpsc::channel();
+
+    if let Some(events) = &shared.events {
+        events.emit(&Event::RunStarted { tests: tests.len() });
+    }
+
+    for test in tests {
+        let test = test.clone();
+        let shared = Arc::clone(&shared);
+        let timings = timings.clone();
+        let printer = Arc::clone(&printer);
+        let tx = tx.clone();
+        let span = tracing::Span::current();
+        pool.spawn(move || {
+            span.in_scope(|| process_test(test, shared, timings, printer, tx));
+        });
+    }
+
+    drop(tx);
+    let mut results = Vec::new();
+    for processed in rx {
+        printer.finished(&processed.name, &processed.result);
+        if let Some(events) = &shared.events {
+            events.emit(&Event::TestFinished {
+                test: &processed.name,
+                outcome: Outcome::from(&processed.result),
+                command: processed.invocation.as_ref().map(Invocation::command),
+                duration_secs: processed.elapsed.map(|d| d.as_secs_f64()),
+            });
+        }
+        results.push(processed);
+    }
+    printer.clear();
+
+    if let Some(events) = &shared.events {
+        events.emit(&Event::RunFinished {
+            passed: results.iter().filter(|p| p.result.is_passed()).count(),
+            failed: results.iter().filter(|p| p.result.is_failed()).count(),
+            skipped: results.iter().filter(|p| p.result.is_skipped()).count(),
+            duration_secs: wall_time_start.elapsed().as_secs_f64(),
+        });
+    }
+
+    // Tests finish in any order, so report them in specification order.
+    results.sort_by_key(|p| p.index);
+
+    Ok(Run {
+        shared,
+        runner,
+        results,
+        timings,
+        wall_time: wall_time_start.elapsed(),
+    })
+}
+
+/// Gets the tests that failed recently for `--failed-first`, with the number
+/// of runs since each last failed.
+///
+/// Tests that failed in the previous run recorded in the `--state-file` last
+/// failed `0` runs ago, as do the tests that failed in the latest run within
+/// the history.
+fn recent_failures(args: &Args) -> Result<BTreeMap<String, usize>> {
+    let history = args
+        .history_file
+        .iter()
+        .chain(args.sinks.iter().filter_map(|sink| match sink {
+            sink::Config::History { path } => Some(path),
+            _ => None,
+        }));
+
+    let mut failures = BTreeMap::new();
+    if let Some(path) = history.into_iter().next() {
+        // Runs are recorded with the rendered label of their badge, which
+        // only depends on the counts of the run if they are placeholders
+        let engine = args.engine.map(|engine| engine.to_string());
+        let label = overall_values(args, 0, 0).render(&args.label);
+        let series = history::series(engine.as_deref(), Some(&label), Some(&args.branch));
+        failures = history::recent_failures(path, series, FAILED_FIRST_RUNS)?;
+    }
+
+    if let Some(path) = &args.state_file
+        && path.exists()
+    {
+        let state = Report::from_path(path)?;
+        failures.extend(state.failed().map(|name| (name.to_string(), 0)));
+    }
+
+    Ok(failures)
+}
+
+/// Gets the file names of the resources that none of the tests refer to.
+fn unreferenced_resources<'a>(
+    resources: &'a Resources<'_>,
+    tests: impl IntoIterator<Item = &'a Test>,
+) -> Vec<&'a str> {
+    let references = tests
+        .into_iter()
+        .flat_map(Test::data_references)
+        .collect::<BTreeSet<_>>();
+
+    resources
+        .unreferenced(&references)
+        .map(|resource| resource.filename())
+        .collect()
+}
+
+/// Reports the results of a run and returns the number of tests that failed
+/// unexpectedly.
+///
+/// The badge and the summary line are only written to stdout if `stdout` is
+/// set.
+fn report(run: &Run<'_>, stdout: bool) -> Result<usize> {
+    let Run {
+        shared,
+        runner,
+        results,
+        timings,
+        wall_time: wall_time_elapsed,
+    } = run;
+    let args = &shared.args;
+    let wall_time_elapsed = *wall_time_elapsed;
+
+    let (
+        expected_pass_test_pass_times,
+        expected_pass_test_fail_times,
+        expected_fail_test_pass_times,
+        expected_fail_test_fail_times,
+    ) = timings.clone().into_parts();
+
+    //===================//
+    // Print summary     //
+    //===================//
+
+    eprintln!("\n{}", "=".repeat(60));
+    eprintln!("Test Summary");
+    eprintln!("{}", "=".repeat(60));
+    eprintln!();
+
+    let passed = results.iter().filter(|p| p.result.is_passed()).count();
+    let failed = results.iter().filter(|p| p.result.is_failed()).count();
+    let skipped = results.iter().filter(|p| p.result.is_skipped()).count();
+    let truncated = results
+        .iter()
+        .any(|p| matches!(p.result, TestResult::Skipped(SkipReason::RunTimeout { .. })));
+    let summary = Summary::new(passed, failed, skipped, wall_time_elapsed)
+        .with_run_id(&shared.run_id)
+        .with_truncated(truncated)
+        .with_engine(
+            args.engine.map(|engine| engine.to_string()),
+            args.engine_version.clone(),
+        )
+        .with_specification(&args.branch, shared.specification_commit.clone());
+
+    eprint!("{}", summary.render_figures());
+
+    let baseline_statuses = results
+        .iter()
+        .map(|p| (p, shared.baseline.status(&p.name, &p.result)))
+        .collect::<Vec<_>>();
+    let expected_failures = baseline_statuses
+        .iter()
+        .filter(|(_, status)| *status == baseline::Status::ExpectedFailure)
+        .count();
+    let unexpected_passes = baseline_statuses
+        .iter()
+        .filter(|(_, status)| *status == baseline::Status::UnexpectedPass)
+        .map(|(p, _)| p.name.as_str())
+        .collect::<Vec<_>>();
+
+    if args.baseline.is_some() {
+        eprintln!();
+        eprintln!("Expected failures (baseline): {}", expected_failures);
+        eprintln!("Unexpectedly passing:         {}", unexpected_passes.len());
+
+        for name in &unexpected_passes {
+            eprintln!("  - {name} (remove it from the baseline)");
+        }
+    }
+
+    if let Some(path) = args.baseline.as_ref().filter(|_| args.update_baseline) {
+        shared
+            .baseline
+            .updated(results.iter().map(|p| (p.name.as_str(), &p.result)))
+            .write(path)?;
+        info!("updated baseline `{}`", path.display());
+    }
+
+    let setup_times = results.iter().filter_map(|p| p.setup).collect::<Vec<_>>();
+    if !setup_times.is_empty() {
+        eprintln!();
+        eprintln!(
+            "Setup time: {:.2}s (reported by {} test(s), excluded from the stats below)",
+            setup_times.iter().sum::<Duration>().as_secs_f64(),
+            setup_times.len()
+        );
+    }
+
+    eprintln!();
+
+    // Calculate and display statistics for each category
+    for (times, expected, actual) in [
+        (&expected_pass_test_pass_times, "pass", "pass"),
+        (&expected_pass_test_fail_times, "pass", "fail"),
+        (&expected_fail_test_pass_times, "fail", "pass"),
+        (&expected_fail_test_fail_times, "fail", "fail"),
+    ] {
+        if let Some(stats) = DurationStats::new(times) {
+            eprintln!(
+                "Stats (expected {expected}, test {actual}, n={}): {stats}",
+                stats.n()
+            );
+        }
+    }
+
+    //=======================//
+    // Output JSON to stdout //
+    //=======================//
+
+    let badge_passed = results.iter().filter(|p| p.result.is_passed()).count();
+    let badge_failed = results.iter().filter(|p| p.result.is_failed()).count();
+    let badge_total = badge_passed + badge_failed;
+
+    let values = overall_values(args, badge_passed, badge_failed);
+    let badge = Badge::from_results(&args.label, badge_passed, badge_total).with_templates(
+        &args.label,
+        args.badge_message_template.as_deref(),
+        &values,
+    );
+
+    if stdout {
+        badge.output();
+    }
+
+    if let Some(dir) = &args.tier_badges {
+        write_tier_badges(dir, args, results)?;
+    }
+
+    //=======================//
+    // Write the result sinks //
+    //=======================//
+
+    let preprocessed = runner
+        .tests()
+        .filter(|test| test.preprocessed())
+        .map(Test::file_name)
+        .collect::<BTreeSet<_>>();
+    let section_urls = runner
+        .tests()
+        .filter_map(|test| {
+            let url = section_url(shared.specification_url.as_deref(), test)?;
+            Some((test.file_name(), url))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let paths = runner
+        .tests()
+        .filter(|_| args.layout != Layout::Flat)
+        .map(|test| {
+            let path = test.relative_path(args.layout);
+            (test.index(), path.to_string_lossy().replace('\\', "/"))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let report = Report::new(
+        results
+            .iter()
+            .map(|p| {
+                Entry::new(&p.name, &p.result, p.elapsed)
+                    .with_lines(p.lines)
+                    .with_path(paths.get(&p.index).cloned())
+                    .with_section_url(section_urls.get(p.name.as_str()).cloned())
+                    .with_setup(p.setup)
+                    .with_invocation(p.invocation.clone())
+                    .with_preprocessed(preprocessed.contains(p.name.as_str()))
+            })
+            .collect(),
+    )
+    .with_run_id(&shared.run_id)
+    .with_duration(wall_time_elapsed)
+    .with_truncated(truncated);
+
+    // The options for common sinks come first, so their output is in the
+    // same place regardless of the sinks in the config file.
+    let sinks = args
+        .summary_format
+        .map(|format| sink::Config::Console { format })
+        .into_iter()
+        .chain(
+            args.results_file
+                .clone()
+                .map(|path| sink::Config::File { path }),
+        )
+        .chain(
+            args.markdown_report
+                .clone()
+                .map(|path| sink::Config::Markdown { path }),
+        )
+        .chain(
+            args.history_file
+                .clone()
+                .map(|path| sink::Config::History { path }),
+        )
+        .chain(args.slack_webhook.clone().map(|url| sink::Config::Slack {
+            url,
+            on: args.notify_on,
+        }))
+        .chain(args.sinks.iter().cloned())
+        .filter(|sink| stdout || !sink.is_console())
+        .collect::<Vec<_>>();
+
+    // Notifications compare against the previous run in the history, which
+    // is read before this run is appended to it
+    let record = history::Record::new(badge.label(), &summary, &report);
+    let previous = sinks
+        .iter()
+        .find_map(|sink| match sink {
+            sink::Config::History { path } => Some(path),
+            _ => None,
+        })
+        .and_then(|path| match history::previous(path, &record) {
+            Ok(previous) => previous,
+            Err(e) => {
+                warn!("failed to read the previous run from the history: {e:#}");
+                None
+            }
+        });
+    if let Some(previous) = &previous {
+        eprint!("\n{}", record.render_changes_since(previous));
+    }
+
+    let sink_results = sink::Results::new(&report, &summary, &badge)
+        .with_previous(previous.as_ref().map(history::Record::summary));
+
+    // A sink that fails (e.g., a webhook that times out) does not stop the
+    // other sinks or the steps after them; the run fails at the end instead.
+    let mut failed_sinks = Vec::new();
+    let mut links = args
+        .tier_badges
+        .iter()
+        .map(|dir| index::Link::new("Tier badges", dir))
+        .collect::<Vec<_>>();
+    for sink in sinks {
+        if let Err(e) = sink.build().write(&sink_results) {
+            warn!("failed to write results to {sink}: {e:#}");
+            failed_sinks.push(sink);
+        } else {
+            links.extend(index::Link::to_sink(&sink));
+        }
+    }
 
     //=======================//
-    // Output JSON to stdout //
+    // Sign the results file //
     //=======================//
 
-    let badge_passed = results.iter().filter(|(_, r)| r.is_passed()).count();
-    let badge_failed = results.iter().filter(|(_, r)| r.is_failed()).count();
-    let badge_total = badge_passed + badge_failed;
+    if let Some(key) = &args.sign_results
+        && let Some(path) = &args.results_file
+        && !failed_sinks
+            .iter()
+            .any(|sink| matches!(sink, sink::Config::File { .. }))
+    {
+        let predicate = attestation::Predicate::builder()
+            .repository_url(&args.repository_url)
+            .branch(&args.branch)
+            .maybe_commit(shared.specification_commit.clone())
+            .maybe_engine(args.engine.map(|engine| engine.to_string()))
+            .maybe_engine_version(args.engine_version.clone())
+            .run_id(&shared.run_id)
+            .build();
+        let statement = attestation::statement_path(path);
+        let signature = Statement::new(path, predicate)?.sign(&statement, key)?;
+        eprintln!(
+            "Signed attestation of `{}` written to `{}` (signature `{}`)",
+            path.display(),
+            statement.display(),
+            signature.display()
+        );
+    }
+
+    //===================================//
+    // Record the state for the next run //
+    //===================================//
+
+    if let Some(path) = &args.state_file {
+        let state = match Report::from_path(path) {
+            Ok(previous) => previous.updated(report.clone()),
+            Err(e) => {
+                if path.exists() {
+                    warn!("replacing the state of the previous run: {e:#}");
+                }
+                report.clone()
+            }
+        };
+        state.write(path)?;
+    }
+
+    //=========================//
+    // Bundle the failed tests //
+    //=========================//
+
+    if let Some(path) = &args.bundle_failures {
+        let failures = results
+            .iter()
+            .filter_map(|p| {
+                let TestResult::Failed(reason) = &p.result else {
+                    return None;
+                };
+                let test = runner.tests().find(|t| t.file_name() == p.name)?;
+
+                Some(bundle::Failure {
+                    test,
+                    reason,
+                    invocation: p.invocation.as_ref(),
+                    workdir: p.workdir.as_deref(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let environment = format!(
+            "spectool {}\nrun: {}\nos: {}\narch: {}\ncommand: {}\n",
+            env!("CARGO_PKG_VERSION"),
+            shared.run_id,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            args.command_template()
+        );
+
+        bundle::write(path, &failures, &environment)?;
+        info!(
+            "wrote {} failed test(s) to `{}`",
+            failures.len(),
+            path.display()
+        );
+        links.push(index::Link::new("Failed tests bundle", path));
+    }
+
+    //=====================//
+    // Write the HTML index //
+    //=====================//
+
+    if let Some(path) = &args.html_index {
+        let artifacts_dir = args
+            .artifacts_dir
+            .as_ref()
+            .map(|dir| dir.join(&shared.run_id));
+        links.extend(
+            artifacts_dir
+                .iter()
+                .map(|dir| index::Link::new("Artifacts", dir)),
+        );
+
+        let run = index::Run {
+            label: badge.label(),
+            run_id: &shared.run_id,
+            summary: &summary,
+            report: &report,
+            artifacts_dir: artifacts_dir.as_deref(),
+        };
+        index::write(path, &run, &links)?;
+        info!("wrote the index of the run to `{}`", path.display());
+    }
+
+    //================================//
+    // Remove the working directories //
+    //================================//
+
+    let mut kept = 0;
+    for processed in results {
+        let Some(workdir) = &processed.workdir else {
+            continue;
+        };
+
+        if args.keep_workdirs.keeps(&processed.result) {
+            tracing::debug!(
+                "kept the working directory of `{}` at `{}`",
+                processed.name,
+                workdir.display()
+            );
+            kept += 1;
+        } else if let Err(e) = std::fs::remove_dir_all(workdir) {
+            warn!("failed to remove `{}`: {e}", workdir.display());
+        }
+    }
+
+    if kept > 0 {
+        let work_root = args.work_root();
+        info!(
+            "kept {kept} working director{} within `{}`",
+            if kept == 1 { "y" } else { "ies" },
+            work_root.display()
+        );
+    }
+
+    if !failed_sinks.is_empty() {
+        bail!(
+            "failed to write results to {}",
+            failed_sinks
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    Ok(failed - expected_failures)
+}
+
+/// Gets the values of the placeholders of the overall badge of a run.
+fn overall_values(args: &Args, passed: usize, failed: usize) -> TemplateValues {
+    TemplateValues {
+        passed,
+        failed,
+        branch: args.branch.clone(),
+        engine_version: args.engine_version.clone(),
+        tier: Some(String::from("overall")),
+    }
+}
 
-    Badge::from_results(&args.label, badge_passed, badge_total).output();
+/// Writes a badge for all tests and for each conformance tier to a directory.
+fn write_tier_badges(dir: &Path, args: &Args, results: &[Processed]) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("creating badge directory `{}`", dir.display()))?;
 
-    if args.strict && failed > 0 {
-        bail!("{failed} test(s) failed");
+    let label = if args.label.contains("{tier}") {
+        args.label.clone()
+    } else {
+        format!("{} ({{tier}})", args.label)
+    };
+
+    let tiers = std::iter::once(None).chain(Tier::iter().map(Some));
+
+    for tier in tiers {
+        let selected = results
+            .iter()
+            .filter(|p| tier.is_none_or(|tier| p.tier == tier))
+            .collect::<Vec<_>>();
+        let passed = selected.iter().filter(|p| p.result.is_passed()).count();
+        let failed = selected.iter().filter(|p| p.result.is_failed()).count();
+        let name = tier.map_or_else(|| String::from("overall"), |tier| tier.to_string());
+
+        let values = TemplateValues {
+            passed,
+            failed,
+            branch: args.branch.clone(),
+            engine_version: args.engine_version.clone(),
+            tier: Some(name.clone()),
+        };
+
+        Badge::from_results(&label, passed, passed + failed)
+            .with_templates(&label, args.badge_message_template.as_deref(), &values)
+            .write(&dir.join(format!("{name}.json")))?;
     }
 
+    info!("wrote tier badges to `{}`", dir.display());
     Ok(())
 }
 
-/// Processes a single test.
-fn process_test(
-    test: Test,
-    args: Arc<Args>,
-    root_dir: Arc<PathBuf>,
-    timings: TestTimings,
-    print_lock: Arc<Mutex<()>>,
-    tx: mpsc::Sender<(String, TestResult)>,
-) {
-    // Check if test should be filtered by include/exclude
-    let test_name = test.file_name().trim_end_matches(".wdl");
-    if !args.include.is_empty()
+/// Validates the command template and related options before any tests run.
+///
+/// Catching these problems up front avoids producing the same confusing
+/// failure for every test in the suite.
+fn validate_command(args: &Args) -> Result<(), Diagnostic> {
+    if args.command.is_none() {
+        return Err(
+            Diagnostic::new(Code::MissingOption, "no command template was provided").with_hint(
+                format!(
+                    "pass the command template, pass `--engine`, or set `command` in `{}`",
+                    config_file::DEFAULT_FILE_NAME
+                ),
+            ),
+        );
+    }
+
+    let templates = [
+        args.command_template(),
+        args.workflow_target_args.as_str(),
+        args.task_target_args.as_str(),
+    ];
+
+    for template in templates {
+        validate_template(template)?;
+
+        if args.no_shell {
+            shell::split(template)?;
+        }
+    }
+
+    if args.sign_results.is_some() && args.results_file.is_none() {
+        return Err(Diagnostic::new(
+            Code::MissingOption,
+            "`--sign-results` signs the results file, but no results file was provided",
+        )
+        .with_hint("pass `--results-file` with the path to write the results to"));
+    }
+
+    if args.rerun_failed && args.state_file.is_none() {
+        return Err(Diagnostic::new(
+            Code::MissingOption,
+            "`--rerun-failed` reads the state of the previous run, but no state file was provided",
+        )
+        .with_hint("pass `--state-file` to both runs with the path to record the state in"));
+    }
+
+    if args.failed_first
+        && args.state_file.is_none()
+        && args.history_file.is_none()
         && !args
-            .include
+            .sinks
             .iter()
-            .any(|pattern| test_name.contains(pattern.as_str()))
+            .any(|sink| matches!(sink, sink::Config::History { .. }))
     {
-        return;
+        return Err(Diagnostic::new(
+            Code::MissingOption,
+            "`--failed-first` reads the failures of earlier runs, but no state or history file \
+             was provided",
+        )
+        .with_hint("pass `--state-file` or `--history-file` to every run"));
+    }
+
+    if let Some(check_command) = &args.check_command {
+        validate_template(check_command)?;
+
+        if args.no_shell {
+            shell::split(check_command)?;
+        }
+
+        if !uses_placeholder(check_command, "path") {
+            return Err(Diagnostic::new(
+                Code::MissingPlaceholder,
+                format!(
+                    "check command template `{}` does not reference the test file",
+                    check_command
+                ),
+            )
+            .with_hint("add `~{path}` where the checker expects the WDL file"));
+        }
     }
-    if !args.exclude.is_empty()
-        && args
-            .exclude
+
+    let uses = |name: &str| {
+        templates
             .iter()
-            .any(|pattern| test_name.contains(pattern.as_str()))
-    {
-        return;
+            .any(|template| uses_placeholder(template, name))
+    };
+
+    if !uses("path") {
+        return Err(Diagnostic::new(
+            Code::MissingPlaceholder,
+            format!(
+                "command template `{}` does not reference the test file",
+                args.command_template()
+            ),
+        )
+        .with_hint("add `~{path}` where the engine expects the WDL file"));
     }
 
-    // Check if test should be ignored
-    if test.config().ignore() {
-        print_result(
-            test.file_name(),
-            "SKIP",
-            Some("test marked with `ignore: true`"),
-            None,
-            &print_lock,
+    match args.input_mode {
+        InputMode::File if !uses("input") => {
+            return Err(Diagnostic::new(
+                Code::MissingPlaceholder,
+                format!(
+                    "command template `{}` does not reference the inputs file",
+                    args.command_template()
+                ),
+            )
+            .with_hint("add `~{input}` where the engine expects the inputs JSON file"));
+        }
+        InputMode::Args if !uses("input_args") => {
+            return Err(Diagnostic::new(
+                Code::MissingPlaceholder,
+                format!(
+                    "command template `{}` does not reference the input arguments",
+                    args.command_template()
+                ),
+            )
+            .with_hint("add `~{input_args}` where the engine expects `key=value` inputs"));
+        }
+        InputMode::Args if uses("input") => {
+            return Err(Diagnostic::new(
+                Code::ConflictingOptions,
+                "`~{input}` is not available when `--input-mode args` is used",
+            )
+            .with_hint("use `~{input_args}` instead, or pass `--input-mode file`"));
+        }
+        _ => {}
+    }
+
+    let source = args.output_source();
+
+    if !uses("output") && source == OutputSource::File && args.output_file.is_none() {
+        return Err(Diagnostic::new(
+            Code::MissingPlaceholder,
+            "no way to collect the outputs of each test was provided",
+        )
+        .with_hint(
+            "add `~{output}` where the engine writes its outputs JSON, pass `--redirect-stdout` \
+             if the engine prints its outputs, or pass `--output-file` if the engine writes \
+             them to a known location",
+        ));
+    }
+
+    match (&args.engine_options_file, uses("options")) {
+        (None, true) => {
+            return Err(Diagnostic::new(
+                Code::MissingPlaceholder,
+                "`~{options}` is used, but no engine options file was provided",
+            )
+            .with_hint("pass `--engine-options-file` with a template for the options file"));
+        }
+        (Some(path), false) => {
+            return Err(Diagnostic::new(
+                Code::MissingPlaceholder,
+                format!(
+                    "engine options file `{}` is never passed to the command",
+                    path.display()
+                ),
+            )
+            .with_hint("add `~{options}` where the engine expects its options file"));
+        }
+        _ => {}
+    }
+
+    if source == OutputSource::Stdout && uses("output") {
+        warn!(
+            "reading outputs from stdout overwrites any file the command writes to \
+             `~{{output}}` with the standard output of the command"
         );
-        // SAFETY: we always expect the channel to send.
-        tx.send((
-            test.file_name().to_string(),
-            TestResult::Skipped(SkipReason::Ignored),
+    }
+
+    if source == OutputSource::Stdout && args.output_file.is_some() {
+        return Err(Diagnostic::new(
+            Code::ConflictingOptions,
+            "outputs cannot be read from both stdout and `--output-file`",
+        )
+        .with_hint(
+            "remove either `--redirect-stdout` (or `--outputs-from stdout`) or `--output-file`",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Gets the URL of the rendered specification within a repository hosted at
+/// an `https` URL, at a commit or branch.
+pub(crate) fn specification_url(repository_url: &str, reference: &str) -> Option<String> {
+    if !repository_url.starts_with("https://") {
+        return None;
+    }
+
+    let base = repository_url.trim_end_matches('/');
+    let base = base.strip_suffix(".git").unwrap_or(base);
+    Some(format!("{base}/blob/{reference}/{SPEC_FILE_NAME}"))
+}
+
+/// Gets the URL of the specification section containing a test.
+pub(crate) fn section_url(specification_url: Option<&str>, test: &Test) -> Option<String> {
+    Some(format!("{}#{}", specification_url?, test.anchor()?))
+}
+
+/// Checks out the specification repository and reads the specification.
+pub(crate) fn read_specification(
+    branch: &str,
+    repository_url: &str,
+    specification_dir: Option<PathBuf>,
+) -> Result<String> {
+    checkout_specification(branch, repository_url, specification_dir)
+        .map(|specification| specification.contents)
+}
+
+/// Checks out the specification repository and reads the specification,
+/// along with the commit that was checked out.
+pub(crate) fn checkout_specification(
+    branch: &str,
+    repository_url: &str,
+    specification_dir: Option<PathBuf>,
+) -> Result<Specification> {
+    let (repository, path) = Repository::builder()
+        .branch(branch.to_string())
+        .url(repository_url.to_string())
+        .maybe_local_dir(specification_dir)
+        .build()
+        .checkout()?;
+
+    let spec = path.join(SPEC_FILE_NAME);
+
+    if !spec.exists() {
+        return Err(Diagnostic::new(
+            Code::SpecNotFound,
+            format!(
+                "the specification does not exist at `{}` in the git repository",
+                SPEC_FILE_NAME
+            ),
+        )
+        .with_hint(format!(
+            "check that `{}` is a checkout of the WDL specification repository, or remove it \
+             to have `spectool` clone branch `{}` again",
+            path.display(),
+            branch
         ))
-        .unwrap();
+        .into());
+    }
+
+    let contents =
+        std::fs::read_to_string(&spec).with_context(|| format!("reading `{}`", spec.display()))?;
+    let commit = repository
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map(|commit| commit.id().to_string())
+        .ok();
+
+    Ok(Specification {
+        contents,
+        commit,
+        path: spec,
+    })
+}
+
+/// Pulls every container image referenced by the tests that will run.
+///
+/// Pull failures are reported but otherwise ignored; the affected tests are
+/// left to fail on their own.
+fn pull_images(args: &Args, tests: &[&Test], pool: &rayon::ThreadPool, environment: &Environment) {
+    let images = tests
+        .iter()
+        .filter(|test| !test.config().ignore())
+        .flat_map(|test| container_images(test.src()))
+        .collect::<BTreeSet<_>>();
+
+    if images.is_empty() {
         return;
     }
 
+    eprintln!("Pulling {} container image(s)...", images.len());
+    let pulled = AtomicUsize::new(0);
+    let total = images.len();
+
+    pool.install(|| {
+        images.par_iter().for_each(|image| {
+            let command = args.pull_command.replace("~{image}", &quote(image));
+            let error = match run_command(&bash(&command), Path::new("."), None, environment) {
+                Ok(output) if output.status.success() => None,
+                Ok(output) => Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+                Err(reason) => Some(reason.to_string()),
+            };
+
+            let status = match error {
+                Some(error) => {
+                    warn!("pulling `{image}` failed: {error}");
+                    "failed to pull"
+                }
+                None => "pulled",
+            };
+
+            let n = pulled.fetch_add(1, Ordering::SeqCst) + 1;
+            eprintln!("[{n}/{total}] {status} {image}");
+        });
+    });
+}
+
+/// Processes a single test.
+fn process_test(
+    test: Test,
+    shared: Arc<Shared>,
+    timings: TestTimings,
+    printer: Arc<Printer>,
+    tx: mpsc::Sender<Processed>,
+) {
+    let args = &shared.args;
+    let root_dir = &shared.root_dir;
+
+    let test_name = test.file_name().trim_end_matches(".wdl");
+    printer.started(test.file_name());
+
+    // Reports the test as skipped. Skips caused by the run stopping early are
+    // not printed, as the summary reports the tests that did not run.
+    let skip = |test: &Test, reason: SkipReason| {
+        if !matches!(
+            reason,
+            SkipReason::Aborted { .. }
+                | SkipReason::CrashLoop { .. }
+                | SkipReason::RunTimeout { .. }
+        ) {
+            print_result(
+                test.file_name(),
+                "SKIP",
+                Some(&reason.to_string()),
+                None,
+                &ResultContext::default(),
+                shared.columns,
+                &printer,
+            );
+        }
+
+        // SAFETY: we always expect the channel to send.
+        tx.send(Processed::skipped(test, reason)).unwrap();
+    };
+
+    // Check if test should be ignored
+    if test.config().ignore() {
+        return skip(&test, SkipReason::Ignored);
+    }
+
     // Check if test has required capabilities
     let missing_capabilities: Vec<Capability> = test
         .config()
@@ -475,30 +2556,96 @@ fn process_test(
 
     if !missing_capabilities.is_empty() {
         let reason = SkipReason::MissingCapabilities(missing_capabilities);
-        print_result(
-            test.file_name(),
-            "SKIP",
-            Some(&reason.to_string()),
-            None,
-            &print_lock,
+        return skip(&test, reason);
+    }
+
+    // Check if the engine is able to execute the target
+    if shared.profile.tasks_unsupported() && matches!(test.target(), Some(Target::Task(_))) {
+        return skip(&test, SkipReason::TasksUnsupported);
+    }
+
+    // Check if the engine supports the constructs the test uses
+    if !test.unsupported_constructs().is_empty() {
+        let reason = SkipReason::UnsupportedConstructs(test.unsupported_constructs().to_vec());
+        return skip(&test, reason);
+    }
+
+    // Check that the data the test refers to is defined by the specification
+    if !test.undeclared_data().is_empty() {
+        let reason = SkipReason::UndeclaredData(test.undeclared_data().to_vec());
+        return skip(&test, reason);
+    }
+
+    // Check if the test could be transformed to the target dialect
+    if let Some(dialect) = shared.args.target_dialect
+        && !test.untransformable().is_empty()
+    {
+        let reason = SkipReason::Untransformable {
+            dialect,
+            features: test.untransformable().to_vec(),
+        };
+        return skip(&test, reason);
+    }
+
+    // Check if the run was aborted after too many failures
+    if let Some(max_failures) = args.max_failures()
+        && shared.failures.load(Ordering::SeqCst) >= max_failures
+    {
+        return skip(&test, SkipReason::Aborted { max_failures });
+    }
+
+    // Check if the run was aborted because the engine keeps failing the same
+    // way
+    if shared.crash_loop.tripped() {
+        return skip(
+            &test,
+            SkipReason::CrashLoop {
+                tests: shared.crash_loop.threshold(),
+            },
         );
-        // SAFETY: we always expect the channel to send.
-        tx.send((test.file_name().to_string(), TestResult::Skipped(reason)))
-            .unwrap();
-        return;
+    }
+
+    // Check if the run has reached its timeout
+    if let Some(timeout) = args.run_timeout
+        && shared
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    {
+        return skip(&test, SkipReason::RunTimeout { timeout });
+    }
+
+    if let Some(events) = &shared.events {
+        events.emit(&Event::TestStarted {
+            test: test.file_name(),
+        });
     }
 
     // Create isolated working directory for this test
     let workdir = tempfile::Builder::new()
-        .prefix(&format!("spectool-{}-", test_name))
-        .tempdir()
+        .prefix(&format!("{}{}-", crate::cache::WORKDIR_PREFIX, test_name))
+        .tempdir_in(args.work_root())
         .expect("tempdir to create")
         .keep();
 
+    // Mark the working directory as in use so that other runs don't evict it
+    // while the test is running
+    let _in_use = crate::cache::InUse::acquire(&workdir)
+        .inspect_err(|e| warn!("failed to lock `{}`: {e}", workdir.display()))
+        .ok();
+
     // Copy data directory to the working directory
     let source_data_dir = root_dir.join("data");
     let dest_data_dir = &workdir;
-    if source_data_dir.exists() {
+    if args.stage_referenced_data {
+        for reference in test.data_references() {
+            if let Err(e) = stage(&source_data_dir, &workdir.join("data"), &reference) {
+                warn!(
+                    "failed to stage `data/{reference}` for `{}`: {e:#}",
+                    test.file_name()
+                );
+            }
+        }
+    } else if source_data_dir.exists() {
         let mut options = fs_extra::dir::CopyOptions::new();
         options.overwrite = true;
         options.copy_inside = true;
@@ -508,20 +2655,55 @@ fn process_test(
     }
 
     // Create the inputs file
-    let input_file = create_input_json(&test, &workdir).unwrap();
+    let input_file = match args.input_mode {
+        InputMode::File => create_input_json(&test, &workdir).unwrap(),
+        InputMode::Args => workdir.join("inputs.json"),
+    };
 
     // Substitute the command
     let target = test.target().expect("target should be inferred");
     let output_file = workdir.join("outputs.json");
-    let command = substitute()
-        .command(args.command.clone())
+    let options_file = args.engine_options_file.as_ref().map(|path| {
+        workdir.join(
+            path.file_name()
+                .expect("engine options template to have a file name"),
+        )
+    });
+    let substitutions = Substitutions::builder()
         .path(test.path().unwrap().to_path_buf())
         .input(input_file)
+        .input_args(input_args(test.input()))
         .output(output_file)
         .target(target.clone())
-        .workflow_target_args(args.workflow_target_args.clone())
-        .task_target_args(args.task_target_args.clone())
-        .call();
+        .workdir(workdir.clone())
+        .maybe_options(options_file.clone())
+        .build();
+
+    // Write the engine options file
+    if let (Some(template), Some(options_file)) = (&shared.engine_options, &options_file) {
+        // SAFETY: we expect to be able to write to the working directory
+        // created for this test.
+        std::fs::write(options_file, substitutions.apply(template)).unwrap();
+    }
+
+    // Templates were checked to split before any tests ran
+    let (argv, command) = if args.no_shell {
+        let argv = substitute_argv()
+            .command(args.command_template().to_string())
+            .substitutions(&substitutions)
+            .target_args(shared.profile.target_args())
+            .call()
+            .expect("command template to split");
+        let command = shell::join(&argv);
+        (argv, command)
+    } else {
+        let command = substitute()
+            .command(args.command_template().to_string())
+            .substitutions(&substitutions)
+            .target_args(shared.profile.target_args())
+            .call();
+        (bash(&command), command)
+    };
 
     tracing::debug!("executing command `{}`", command);
 
@@ -532,73 +2714,311 @@ fn process_test(
         .map(|path| PathBuf::from(path.replace("~{target}", target.name())));
 
     // Execute the test and evaluate the result
-    let start_time = std::time::Instant::now();
-    let result = execute_and_evaluate_test(
-        &test,
-        &command,
-        &root_dir,
-        &workdir,
-        args.redirect_stdout,
-        output_file.as_deref(),
-        args.output_selector.as_deref(),
+    let start_time = Instant::now();
+    let heartbeat = args.heartbeat.map(Duration::from_secs);
+    let mut outputs_from = None;
+    let (result, command) = with_heartbeat(
+        test.file_name(),
+        heartbeat,
+        &printer,
+        shared.events.as_ref(),
+        || match &args.check_command {
+            Some(check_command) if test.config().tags().contains(&Tag::Static) => {
+                let (argv, command) = if args.no_shell {
+                    let argv = substitutions
+                        .apply_argv(check_command)
+                        .expect("check command to split");
+                    let command = shell::join(&argv);
+                    (argv, command)
+                } else {
+                    let command = substitutions.apply(check_command);
+                    (bash(&command), command)
+                };
+                tracing::debug!("checking with command `{}`", command);
+                (check_test(&test, &argv, &shared, &workdir), command)
+            }
+            _ => {
+                let validation = validation_options(&test, &shared.profile, args.float_tolerance)
+                    .with_workdir(workdir.clone());
+                let result = execute_and_evaluate_test(
+                    &test,
+                    &argv,
+                    &shared,
+                    &workdir,
+                    output_file.as_deref(),
+                    &validation,
+                    &mut outputs_from,
+                );
+                (result, command)
+            }
+        },
     );
     let elapsed = start_time.elapsed();
+    let invocation = Invocation::builder()
+        .command(command)
+        .working_dir(root_dir.clone())
+        .env(shared.environment.recorded_vars())
+        .isolated(shared.environment.isolated())
+        .maybe_outputs_from(outputs_from)
+        .build();
+
+    if let Some(dir) = &args.artifacts_dir
+        && let Err(e) = artifacts::write(
+            &dir.join(&shared.run_id),
+            &test,
+            &invocation,
+            &workdir,
+            output_file.as_deref(),
+        )
+    {
+        warn!(
+            "failed to write the artifacts of `{}`: {e:#}",
+            test.file_name()
+        );
+    }
+
+    // Separate the setup time reported by the engine from the execution time
+    let setup = args
+        .setup_time_file
+        .as_ref()
+        .and_then(|template| read_setup_time(Path::new(&substitutions.apply(template))));
+    let execution = elapsed.saturating_sub(setup.unwrap_or_default());
+
+    // Check the execution time against the test's duration budget
+    let over_budget = test
+        .config()
+        .max_duration()
+        .filter(|limit| execution > *limit);
+    let result = match (result, over_budget) {
+        (TestResult::Passed, Some(limit)) if args.enforce_durations => {
+            TestResult::Failed(FailureReason::DurationExceeded {
+                limit,
+                actual: execution,
+            })
+        }
+        (result, _) => result,
+    };
+    let budget_warning = over_budget.map(|limit| {
+        format!(
+            "exceeded the maximum duration of {:.2}s",
+            limit.as_secs_f64()
+        )
+    });
 
     // Print result and categorize timing
     let expected_to_fail = test.config().fail();
+    let status = shared.baseline.status(test.file_name(), &result);
     match &result {
         TestResult::Passed => {
-            print_result(test.file_name(), "PASS", None, Some(elapsed), &print_lock);
+            print_result(
+                test.file_name(),
+                match status {
+                    baseline::Status::UnexpectedPass => "XPASS",
+                    _ => "PASS",
+                },
+                budget_warning.as_deref(),
+                Some(elapsed),
+                &ResultContext::default(),
+                shared.columns,
+                &printer,
+            );
             if expected_to_fail {
                 timings
                     .expected_fail_test_pass
                     .lock()
                     .unwrap()
-                    .push(elapsed);
+                    .push(execution);
             } else {
                 timings
                     .expected_pass_test_pass
                     .lock()
                     .unwrap()
-                    .push(elapsed);
+                    .push(execution);
             }
         }
         TestResult::Failed(reason) => {
             print_result(
                 test.file_name(),
-                "FAIL",
+                match status {
+                    baseline::Status::ExpectedFailure => "XFAIL",
+                    _ => "FAIL",
+                },
                 Some(&reason.to_string()),
                 Some(elapsed),
-                &print_lock,
+                &ResultContext {
+                    lines: test.lines(),
+                    section_url: section_url(shared.specification_url.as_deref(), &test).as_deref(),
+                    invocation: args.show_commands.then_some(&invocation),
+                    diff: reason.diff(),
+                },
+                shared.columns,
+                &printer,
             );
 
+            if status != baseline::Status::ExpectedFailure
+                && let Some(max_failures) = args.max_failures()
+                && shared.failures.fetch_add(1, Ordering::SeqCst) + 1 == max_failures
+            {
+                printer.print(|| {
+                    eprintln!(
+                        "Aborting after {max_failures} failure(s); tests that have not started \
+                         are skipped"
+                    );
+                });
+            }
+
             if expected_to_fail {
                 timings
                     .expected_fail_test_fail
                     .lock()
                     .unwrap()
-                    .push(elapsed);
+                    .push(execution);
             } else {
                 timings
                     .expected_pass_test_fail
                     .lock()
                     .unwrap()
-                    .push(elapsed);
+                    .push(execution);
             }
         }
-        TestResult::Skipped(reason) => {
-            print_result(
-                test.file_name(),
-                "SKIP",
-                Some(&reason.to_string()),
-                Some(elapsed),
-                &print_lock,
-            );
+        TestResult::Skipped(reason) => {
+            print_result(
+                test.file_name(),
+                "SKIP",
+                Some(&reason.to_string()),
+                Some(elapsed),
+                &ResultContext::default(),
+                shared.columns,
+                &printer,
+            );
+        }
+    }
+
+    let stderr = std::fs::read_to_string(workdir.join("stderr.log")).ok();
+    if let Some(error) = shared.crash_loop.record(&result, stderr.as_deref()) {
+        printer.print(|| {
+            eprintln!(
+                "Aborting after the first {} tests failed with the same error; the engine may \
+                 not be installed or configured correctly:\n    {error}\nTests that have not \
+                 started are skipped (pass `--crash-loop-threshold 0` to run them anyway)",
+                shared.crash_loop.threshold()
+            );
+        });
+    }
+
+    // SAFETY: we always expect the channel to send.
+    tx.send(Processed {
+        index: test.index(),
+        name: test.file_name().to_string(),
+        tier: test.tier(),
+        lines: test.lines(),
+        result,
+        elapsed: Some(elapsed),
+        setup,
+        workdir: Some(workdir),
+        invocation: Some(invocation),
+    })
+    .unwrap();
+}
+
+/// Copies a file or directory within the `data` directory to the same place
+/// within another directory.
+///
+/// Nothing is copied if the source does not exist, so that a test referring to
+/// a missing resource fails on its own.
+fn stage(source_dir: &Path, dest_dir: &Path, reference: &str) -> Result<()> {
+    let source = source_dir.join(reference);
+    let dest = dest_dir.join(reference);
+
+    if source.is_dir() {
+        std::fs::create_dir_all(&dest)?;
+        let mut options = fs_extra::dir::CopyOptions::new();
+        options.overwrite = true;
+        options.content_only = true;
+        fs_extra::dir::copy(&source, &dest, &options)?;
+    } else if source.is_file() {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&source, &dest)?;
+    }
+
+    Ok(())
+}
+
+/// The setup time reported by an engine in a `--setup-time-file`.
+#[derive(Deserialize)]
+struct SetupTime {
+    /// The time spent on setup (e.g., pulling images) in seconds.
+    setup_secs: f64,
+}
+
+/// Reads the setup time reported by an engine.
+///
+/// Returns `None` if the file does not exist or cannot be parsed.
+fn read_setup_time(path: &Path) -> Option<Duration> {
+    let contents = std::fs::read_to_string(path).ok()?;
+
+    match serde_json::from_str::<SetupTime>(&contents) {
+        Ok(time) => Duration::try_from_secs_f64(time.setup_secs).ok(),
+        Err(e) => {
+            warn!("ignoring setup time file `{}`: {e}", path.display());
+            None
         }
     }
+}
 
-    // SAFETY: we always expect the channel to send.
-    tx.send((test.file_name().to_string(), result)).unwrap();
+/// Calls a function, printing a heartbeat for the test every `interval` until
+/// the function returns.
+///
+/// Each heartbeat is also written to the stream of events, if there is one.
+fn with_heartbeat<T>(
+    test_name: &str,
+    interval: Option<Duration>,
+    printer: &Printer,
+    events: Option<&Events>,
+    f: impl FnOnce() -> T,
+) -> T {
+    let Some(interval) = interval else {
+        return f();
+    };
+
+    let (done, rx) = mpsc::channel::<()>();
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            let start = Instant::now();
+            while let Err(mpsc::RecvTimeoutError::Timeout) = rx.recv_timeout(interval) {
+                let elapsed = start.elapsed();
+                printer.print(|| {
+                    eprintln!("still running {test_name} ({})", format_elapsed(elapsed));
+                });
+                if let Some(events) = events {
+                    events.emit(&Event::Heartbeat {
+                        test: test_name,
+                        elapsed_secs: elapsed.as_secs_f64(),
+                    });
+                }
+            }
+        });
+
+        let result = f();
+        drop(done);
+        result
+    })
+}
+
+/// Formats an elapsed time compactly (e.g., `45s`, `3m12s`, or `1h02m03s`).
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if hours > 0 {
+        format!("{hours}h{minutes:02}m{seconds:02}s")
+    } else if minutes > 0 {
+        format!("{minutes}m{seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
 }
 
 /// Creates an `input.json` file.
@@ -614,45 +3034,188 @@ fn create_input_json(test: &Test, work_dir: &Path) -> Result<PathBuf> {
     Ok(input_file_path)
 }
 
+/// Gets the arguments that run a command line with `bash`.
+fn bash(command: &str) -> Vec<String> {
+    vec![
+        String::from("bash"),
+        String::from("-c"),
+        command.to_string(),
+    ]
+}
+
+/// Runs a program with arguments (see [`bash()`] to run a command line within
+/// a shell).
+///
+/// If a stream prefix is provided, each line the command writes to stdout or
+/// stderr is also printed to the console as it is produced, prefixed with
+/// `[<prefix>]`. The output is captured either way.
+fn run_command(
+    argv: &[String],
+    root_dir: &Path,
+    stream: Option<&str>,
+    environment: &Environment,
+) -> Result<Output, FailureReason> {
+    let Some((program, arguments)) = argv.split_first() else {
+        return Err(FailureReason::ExecutionError(String::from(
+            "the command is empty",
+        )));
+    };
+
+    let mut process = Command::new(program);
+    process
+        .args(arguments)
+        .current_dir(root_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    environment.apply(&mut process);
+
+    let output = match stream {
+        None => process.output(),
+        Some(prefix) => process.spawn().and_then(|mut child| {
+            // SAFETY: both streams were configured as piped above.
+            let stdout = child.stdout.take().unwrap();
+            let stderr = child.stderr.take().unwrap();
+
+            let (stdout, stderr) = std::thread::scope(|scope| {
+                let stdout = scope.spawn(|| tee(stdout, prefix));
+                let stderr = scope.spawn(|| tee(stderr, prefix));
+
+                // SAFETY: the reader threads do not panic.
+                (stdout.join().unwrap(), stderr.join().unwrap())
+            });
+
+            Ok(Output {
+                status: child.wait()?,
+                stdout: stdout?,
+                stderr: stderr?,
+            })
+        }),
+    }
+    .map_err(|e| FailureReason::ExecutionError(e.to_string()))?;
+
+    tracing::trace!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+    tracing::trace!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    Ok(output)
+}
+
+/// Reads a stream to the end, printing each line to the console prefixed
+/// with `[<prefix>]` as it is read.
+///
+/// Returns everything that was read.
+fn tee(reader: impl Read, prefix: &str) -> std::io::Result<Vec<u8>> {
+    let mut reader = BufReader::new(reader);
+    let mut captured = Vec::new();
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+
+        eprintln!(
+            "[{prefix}] {}",
+            String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n'])
+        );
+        captured.extend_from_slice(&line);
+    }
+
+    Ok(captured)
+}
+
+/// Saves the stdout and stderr of a command to the working directory of a test.
+fn save_logs(workdir: &Path, output: &Output) {
+    for (name, contents) in [
+        ("stdout.log", &output.stdout),
+        ("stderr.log", &output.stderr),
+    ] {
+        if let Err(e) = std::fs::write(workdir.join(name), contents) {
+            warn!("failed to save `{name}` to `{}`: {e}", workdir.display());
+        }
+    }
+}
+
+/// Checks a static test with a validation-only command.
+fn check_test(test: &Test, argv: &[String], shared: &Shared, workdir: &Path) -> TestResult {
+    let stream = shared.args.stream.then_some(test.file_name());
+    let output = match run_command(argv, &shared.root_dir, stream, &shared.environment) {
+        Ok(output) => output,
+        Err(reason) => return TestResult::Failed(reason),
+    };
+
+    save_logs(workdir, &output);
+
+    let exit_code = output.status.code().unwrap_or(-1);
+
+    match (test.config().fail(), exit_code == 0) {
+        (true, true) => TestResult::Failed(FailureReason::UnexpectedSuccess),
+        (false, false) => TestResult::Failed(FailureReason::ReturnCodeMismatch {
+            expected: ReturnCode::Single(0),
+            actual: exit_code,
+        }),
+        _ => TestResult::Passed,
+    }
+}
+
+/// Returns whether a file exists and is not empty.
+fn has_contents(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0)
+}
+
 /// Executes a test and evaluates the result.
+///
+/// Where the outputs were read from is stored in `used` once the command has
+/// run.
 fn execute_and_evaluate_test(
     test: &Test,
-    command: &str,
-    root_dir: &Path,
+    argv: &[String],
+    shared: &Shared,
     workdir: &Path,
-    redirect_stdout: bool,
     output_file: Option<&Path>,
-    output_selector: Option<&str>,
+    validation: &ValidationOptions,
+    used: &mut Option<OutputSource>,
 ) -> TestResult {
     // Execute the command
-    let output = match Command::new("bash")
-        .args(["-c", command])
-        .current_dir(root_dir)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-    {
+    let stream = shared.args.stream.then_some(test.file_name());
+    let output = match run_command(argv, &shared.root_dir, stream, &shared.environment) {
         Ok(output) => output,
-        Err(e) => {
-            return TestResult::Failed(FailureReason::ExecutionError(e.to_string()));
-        }
+        Err(reason) => return TestResult::Failed(reason),
     };
 
+    save_logs(workdir, &output);
+
     let exit_code = output.status.code().unwrap_or(-1);
 
-    tracing::trace!("stdout: {}", String::from_utf8_lossy(&output.stdout));
-    tracing::trace!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+    // Write stdout to `outputs.json` if outputs are read from stdout, or if
+    // they may be and the command did not write an outputs file
+    let declared = output_file
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| workdir.join("outputs.json"));
+    let source = match shared.args.output_source() {
+        OutputSource::Auto if has_contents(&declared) => OutputSource::File,
+        OutputSource::Auto => OutputSource::Stdout,
+        source => source,
+    };
+    *used = Some(source);
+    tracing::debug!(
+        "reading the outputs of `{}` from {source}",
+        test.file_name()
+    );
 
-    // Write stdout to `outputs.json` if `redirect_stdout` is enabled
-    if redirect_stdout {
+    let output_file = if source == OutputSource::Stdout {
         let outputs_path = workdir.join("outputs.json");
         if let Err(e) = std::fs::write(&outputs_path, &output.stdout) {
-            return TestResult::Failed(FailureReason::ExecutionError(format!(
+            return TestResult::Failed(FailureReason::HarnessError(format!(
                 "failed to write stdout to `outputs.json`: {}",
                 e
             )));
         }
-    }
+
+        None
+    } else {
+        output_file
+    };
 
     // Determine if test should have failed
     let expected_to_fail = test.config().fail();
@@ -687,73 +3250,323 @@ fn execute_and_evaluate_test(
             .map(|p| p.to_path_buf())
             .unwrap_or_else(|| workdir.join("outputs.json"));
 
-        let actual_output = match std::fs::read_to_string(&outputs_path) {
-            Ok(content) => content,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return TestResult::Failed(FailureReason::NoOutput);
-            }
-            Err(e) => {
-                return TestResult::Failed(FailureReason::OutputMismatch {
-                    details: format!("failed to read `outputs.json`: {}", e),
-                });
-            }
-        };
+        if let Err(reason) =
+            evaluate_outputs(expected_output, &outputs_path, &shared.profile, validation)
+        {
+            return TestResult::Failed(reason);
+        }
+    }
 
-        // Check if `outputs.json` is empty
-        if actual_output.trim().is_empty() {
-            return TestResult::Failed(FailureReason::NoOutput);
+    // Check that any expected files were produced
+    if let Err(e) = validation::validate_files(workdir, test.config().expected_files()) {
+        return TestResult::Failed(FailureReason::FileMismatch {
+            details: e.to_string(),
+        });
+    }
+
+    TestResult::Passed
+}
+
+/// Gets the options the outputs of a test are validated with.
+pub(crate) fn validation_options(
+    test: &Test,
+    profile: &Profile,
+    float_tolerance: Option<FloatTolerance>,
+) -> ValidationOptions {
+    ValidationOptions::builder()
+        .exclude(test.config().exclude_outputs().to_vec())
+        .forbidden(test.config().forbidden_outputs().to_vec())
+        .unordered(test.config().unordered_outputs().to_vec())
+        .patterns(test.config().pattern_outputs().to_vec())
+        .key_map(
+            profile.output_key_map().merged(
+                &KeyMap::builder()
+                    .renames(test.config().rename_outputs().clone())
+                    .build(),
+            ),
+        )
+        .compare_files(test.config().compare_files())
+        .maybe_float_tolerance(test.config().float_tolerance().or(float_tolerance))
+        .build()
+}
+
+/// Validates the outputs an engine wrote to `outputs_path` against the
+/// expected outputs, after applying the selectors of the profile.
+pub(crate) fn evaluate_outputs(
+    expected_output: &serde_json::Value,
+    outputs_path: &Path,
+    profile: &Profile,
+    validation: &ValidationOptions,
+) -> Result<(), FailureReason> {
+    let actual_output = match std::fs::read_to_string(outputs_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(FailureReason::NoOutput);
+        }
+        Err(e) => {
+            return Err(FailureReason::OutputMismatch {
+                details: format!("failed to read `outputs.json`: {}", e),
+                diff: None,
+                keys: Vec::new(),
+            });
         }
+    };
 
-        let actual_output: serde_json::Value = match serde_json::from_str(&actual_output) {
-            Ok(value) => value,
-            Err(e) => {
-                return TestResult::Failed(FailureReason::OutputMismatch {
-                    details: format!("failed to parse `outputs.json`: {}", e),
-                });
-            }
+    // Check if `outputs.json` is empty
+    if actual_output.trim().is_empty() {
+        return Err(FailureReason::NoOutput);
+    }
+
+    let actual_output: serde_json::Value =
+        serde_json::from_str(&actual_output).map_err(|e| FailureReason::OutputMismatch {
+            details: format!("failed to parse `outputs.json`: {}", e),
+            diff: None,
+            keys: Vec::new(),
+        })?;
+
+    // Apply the output selectors in sequence
+    let actual_output = selector::apply_all(profile.output_selectors(), &actual_output)?;
+
+    // Apply the expected selectors in sequence
+    let expected_output = selector::apply_all(profile.expected_selectors(), expected_output)?;
+
+    validation::validate(&expected_output, &actual_output, validation).map_err(|e| {
+        let mismatch = e.downcast_ref::<validation::Mismatch>();
+        FailureReason::OutputMismatch {
+            details: e.to_string(),
+            diff: mismatch
+                .and_then(|mismatch| mismatch.diff())
+                .map(str::to_string),
+            keys: mismatch
+                .map(|mismatch| mismatch.keys().to_vec())
+                .unwrap_or_default(),
+        }
+    })
+}
+
+/// The context printed beneath the result of a test.
+#[derive(Default)]
+pub(crate) struct ResultContext<'a> {
+    /// The lines the test occupies within the specification, printed so that
+    /// the test can be jumped to.
+    pub(crate) lines: Option<Lines>,
+
+    /// The URL of the specification section containing the test, printed so
+    /// that the normative text can be read.
+    pub(crate) section_url: Option<&'a str>,
+
+    /// How the command for the test was invoked, printed so that it can be
+    /// copied and pasted to reproduce the test.
+    pub(crate) invocation: Option<&'a Invocation>,
+
+    /// The diff of the expected and actual outputs.
+    pub(crate) diff: Option<&'a str>,
+}
+
+/// Prints the results of tests to stderr one at a time, keeping a progress
+/// bar below them if one is shown.
+#[derive(Default)]
+pub(crate) struct Printer {
+    /// The lock held while printing.
+    lock: Mutex<()>,
+
+    /// The progress bar.
+    progress: Option<ProgressBar>,
+
+    /// The number of tests that passed, failed, and were skipped so far.
+    counts: Mutex<[usize; 3]>,
+
+    /// Whether the status of each result is colored.
+    color: bool,
+
+    /// Whether only the results of failed and unexpectedly passing tests are
+    /// printed.
+    quiet: bool,
+}
+
+impl Printer {
+    /// Creates a printer.
+    pub(crate) fn new(color: bool, quiet: bool) -> Self {
+        Self {
+            color,
+            quiet,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a progress bar for a number of tests.
+    fn with_progress(self, total: usize) -> Self {
+        let style = ProgressStyle::with_template(
+            "{elapsed_precise} [{bar:30}] {pos}/{len} {msg} (ETA {eta})",
+        )
+        .expect("progress template to be valid")
+        .progress_chars("=> ");
+
+        Self {
+            progress: Some(ProgressBar::new(total as u64).with_style(style)),
+            ..self
+        }
+    }
+
+    /// Prints with the lock held, above the progress bar if there is one.
+    pub(crate) fn print(&self, f: impl FnOnce()) {
+        // SAFETY: we expect the lock to always eventually be acquired.
+        let _guard = self.lock.lock().unwrap();
+
+        match &self.progress {
+            Some(progress) => progress.suspend(f),
+            None => f(),
+        }
+    }
+
+    /// Shows that a test has started.
+    fn started(&self, test_name: &str) {
+        if let Some(progress) = &self.progress {
+            progress.set_message(self.message(test_name));
+        }
+    }
+
+    /// Counts a test that has finished.
+    fn finished(&self, test_name: &str, result: &TestResult) {
+        let Some(progress) = &self.progress else {
+            return;
         };
 
-        // Apply output selector if provided
-        let actual_output = if let Some(selector) = output_selector {
-            match apply_selector(selector, &actual_output) {
-                Ok(transformed) => transformed,
-                Err(failure_reason) => return TestResult::Failed(failure_reason),
+        {
+            // SAFETY: we expect the lock to always eventually be acquired.
+            let mut counts = self.counts.lock().unwrap();
+            match result {
+                TestResult::Passed => counts[0] += 1,
+                TestResult::Failed(_) => counts[1] += 1,
+                TestResult::Skipped(_) => counts[2] += 1,
             }
-        } else {
-            actual_output
-        };
+        }
 
-        if let Err(e) = validate_outputs(
-            expected_output,
-            &actual_output,
-            test.config().exclude_outputs(),
-        ) {
-            return TestResult::Failed(FailureReason::OutputMismatch {
-                details: e.to_string(),
-            });
+        progress.set_message(self.message(test_name));
+        progress.inc(1);
+    }
+
+    /// Removes the progress bar once every test has finished.
+    fn clear(&self) {
+        if let Some(progress) = &self.progress {
+            progress.finish_and_clear();
         }
     }
 
-    TestResult::Passed
+    /// Renders the counts and the most recent test for the progress bar.
+    fn message(&self, test_name: &str) -> String {
+        // SAFETY: we expect the lock to always eventually be acquired.
+        let [passed, failed, skipped] = *self.counts.lock().unwrap();
+        format!("{passed} passed, {failed} failed, {skipped} skipped · {test_name}")
+    }
+}
+
+/// The layout of result lines on the console.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Columns {
+    /// The column the status of each test is aligned to.
+    status: usize,
+
+    /// The width of the console.
+    width: usize,
+}
+
+impl Columns {
+    /// The width of the console when it cannot be determined.
+    const DEFAULT_WIDTH: usize = 100;
+
+    /// The room left after the status column for the status and time (e.g.,
+    /// `XPASS [123.45s]`).
+    const STATUS_WIDTH: usize = 16;
+
+    /// The fewest dots between a test name and its status.
+    const MIN_DOTS: usize = 3;
+
+    /// Lays out result lines for tests with the given names on a console of
+    /// the given width.
+    ///
+    /// The status column follows the longest name, unless that would leave
+    /// no room for the status within the width.
+    pub(crate) fn new<'a>(names: impl IntoIterator<Item = &'a str>, width: Option<usize>) -> Self {
+        let width = width
+            .filter(|width| *width > 0)
+            .unwrap_or(Self::DEFAULT_WIDTH);
+        let longest = names
+            .into_iter()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        Self {
+            status: (longest + Self::MIN_DOTS).min(width.saturating_sub(Self::STATUS_WIDTH)),
+            width,
+        }
+    }
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self::new([], None)
+    }
+}
+
+/// Gets the width of the terminal on stderr, if it is one.
+pub(crate) fn terminal_width() -> Option<usize> {
+    console::Term::stderr()
+        .size_checked()
+        .map(|(_, columns)| usize::from(columns))
+}
+
+/// Wraps text at whitespace so that no line is wider than `width`, keeping
+/// the line breaks already within it.
+///
+/// Words wider than `width` are left on lines of their own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+
+    lines
 }
 
-/// Prints a test result in the format: <test_name>...RESULT [time]
-fn print_result(
+/// Renders a test result in the format: <test_name>...RESULT [time]
+///
+/// The details follow in parentheses if they fit within the width of the
+/// console, and are otherwise wrapped onto indented lines below.
+fn render_result(
     test_name: &str,
     status: &str,
     details: Option<&str>,
     elapsed: Option<std::time::Duration>,
-    lock: &Mutex<()>,
-) {
-    const TOTAL_WIDTH: usize = 50;
-
-    let dots_len = TOTAL_WIDTH.saturating_sub(test_name.len());
-    let dots = ".".repeat(dots_len);
+    columns: Columns,
+    color: bool,
+) -> String {
+    let name_width = test_name.chars().count();
+    let dots = ".".repeat(
+        columns
+            .status
+            .saturating_sub(name_width)
+            .max(Columns::MIN_DOTS),
+    );
 
     let (color_code, reset_code) = match status {
-        "PASS" => ("\x1b[32m", "\x1b[0m"), // Green
-        "FAIL" => ("\x1b[31m", "\x1b[0m"), // Red
-        "SKIP" => ("\x1b[33m", "\x1b[0m"), // Yellow
+        _ if !color => ("", ""),
+        "PASS" => ("\x1b[32m", "\x1b[0m"),           // Green
+        "FAIL" => ("\x1b[31m", "\x1b[0m"),           // Red
+        "SKIP" | "XFAIL" => ("\x1b[33m", "\x1b[0m"), // Yellow
+        "XPASS" => ("\x1b[36m", "\x1b[0m"),          // Cyan
         _ => ("", ""),
     };
 
@@ -761,110 +3574,241 @@ fn print_result(
         .map(|d| format!(" [{:.2}s]", d.as_secs_f64()))
         .unwrap_or_default();
 
-    // SAFETY: we expect the lock to always eventually be acquired.
-    let _guard = lock.lock().unwrap();
+    let mut rendered = format!("{test_name}{dots}{color_code}{status}{reset_code}{time_str}");
 
-    if let Some(details_str) = details {
-        eprintln!(
-            "{}{}{}{}{}{} ({})",
-            test_name, dots, color_code, status, reset_code, time_str, details_str
-        );
-    } else {
-        eprintln!(
-            "{}{}{}{}{}{}",
-            test_name, dots, color_code, status, reset_code, time_str
-        );
+    if let Some(details) = details {
+        let line_width = name_width + dots.len() + status.len() + time_str.len();
+        if !details.contains('\n') && line_width + details.chars().count() + 3 <= columns.width {
+            rendered.push_str(&format!(" ({details})"));
+        } else {
+            for line in wrap(details, columns.width.saturating_sub(4)) {
+                rendered.push_str(&format!("\n    {line}"));
+            }
+        }
     }
+
+    rendered
 }
 
-/// Applies a `jq` selector to a JSON value.
-fn apply_selector(
-    selector: &str,
-    input: &serde_json::Value,
-) -> Result<serde_json::Value, FailureReason> {
-    use jaq_core::load::{Arena, File, Loader};
-    use jaq_core::{Compiler, Ctx, Vars, data, unwrap_valr};
-    use jaq_json::Val;
+/// Prints a test result (see [`render_result()`]).
+///
+/// Any context is printed on the following lines.
+pub(crate) fn print_result(
+    test_name: &str,
+    status: &str,
+    details: Option<&str>,
+    elapsed: Option<std::time::Duration>,
+    context: &ResultContext<'_>,
+    columns: Columns,
+    printer: &Printer,
+) {
+    if printer.quiet && !matches!(status, "FAIL" | "XPASS") {
+        return;
+    }
 
-    let program = File {
-        code: selector,
-        path: (),
-    };
-    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
-    let arena = Arena::default();
+    let rendered = render_result(test_name, status, details, elapsed, columns, printer.color);
 
-    // Parse the selector
-    let modules = loader.load(&arena, program).map_err(|errs| {
-        let error_msg = errs
-            .into_iter()
-            .map(|(file, err)| format!("{}: {:?}", file.code, err))
-            .collect::<Vec<_>>()
-            .join("; ");
-        FailureReason::SelectorError {
-            selector: selector.to_string(),
-            details: error_msg,
-        }
-    })?;
-
-    // Compile the selector
-    let filter = Compiler::default()
-        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
-        .compile(modules)
-        .map_err(|errs| {
-            let error_msg = errs
-                .into_iter()
-                .map(|(file, err)| {
-                    let err_str = err
-                        .into_iter()
-                        .map(|(name, _)| name)
-                        .collect::<Vec<_>>()
-                        .join(", ");
-                    format!("{}: undefined: {}", file.code, err_str)
-                })
-                .collect::<Vec<_>>()
-                .join("; ");
-            FailureReason::SelectorError {
-                selector: selector.to_string(),
-                details: error_msg,
-            }
-        })?;
+    printer.print(|| {
+        eprintln!("{rendered}");
 
-    // Convert `serde_json::Value` to `jaq` `Val` using JSON string roundtrip
-    let json_str = input.to_string();
-    let jaq_input = jaq_json::read::parse_single(json_str.as_bytes()).map_err(|e| {
-        FailureReason::SelectorError {
-            selector: selector.to_string(),
-            details: format!("failed to parse input as JSON: {}", e),
+        if let Some(lines) = context.lines {
+            eprintln!("    at {SPEC_FILE_NAME}:{} (lines {lines})", lines.start());
         }
-    })?;
 
-    // Execute the selector
-    let ctx = Ctx::<data::JustLut<Val>>::new(&filter.lut, Vars::new([]));
-    let mut outputs = filter.id.run((ctx, jaq_input)).map(unwrap_valr);
+        if let Some(url) = context.section_url {
+            eprintln!("    see {url}");
+        }
 
-    // Expect exactly one output
-    let first_output = outputs.next();
-    let second_output = outputs.next();
+        if let Some(invocation) = context.invocation {
+            eprintln!("    $ {}", invocation.to_shell());
+        }
 
-    match (first_output, second_output) {
-        (None, _) => Err(FailureReason::SelectorError {
-            selector: selector.to_string(),
-            details: "selector produced no output".to_string(),
-        }),
-        (Some(Err(e)), _) => Err(FailureReason::SelectorError {
-            selector: selector.to_string(),
-            details: format!("selector execution failed: {}", e),
-        }),
-        (Some(Ok(_)), Some(_)) => Err(FailureReason::SelectorError {
-            selector: selector.to_string(),
-            details: "selector produced multiple outputs (expected exactly one)".to_string(),
-        }),
-        (Some(Ok(val)), None) => {
-            let json_str = val.to_string();
-            serde_json::from_str(&json_str).map_err(|e| FailureReason::SelectorError {
-                selector: selector.to_string(),
-                details: format!("failed to convert result to JSON: {}", e),
-            })
+        if let Some(diff) = context.diff {
+            for line in validation::diff::render(diff, printer.color).lines() {
+                eprintln!("    {line}");
+            }
         }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specification_urls() {
+        assert_eq!(
+            specification_url("https://github.com/openwdl/wdl.git", "wdl-1.2").as_deref(),
+            Some("https://github.com/openwdl/wdl/blob/wdl-1.2/SPEC.md")
+        );
+        assert_eq!(
+            specification_url("https://github.com/openwdl/wdl/", "abc123").as_deref(),
+            Some("https://github.com/openwdl/wdl/blob/abc123/SPEC.md")
+        );
+        assert_eq!(specification_url("/srv/git/wdl.git", "wdl-1.2"), None);
+    }
+
+    #[test]
+    fn elapsed_formatting() {
+        assert_eq!(format_elapsed(Duration::from_secs(45)), "45s");
+        assert_eq!(format_elapsed(Duration::from_secs(192)), "3m12s");
+        assert_eq!(format_elapsed(Duration::from_secs(3723)), "1h02m03s");
+    }
+
+    #[test]
+    fn tee_captures_everything() {
+        let input = b"first\nsecond\r\nno newline";
+        let captured = tee(&input[..], "test.wdl").unwrap();
+        assert_eq!(captured, input);
+    }
+
+    #[test]
+    fn unreferenced_resources() {
+        let resource = |filename: &'static str| {
+            crate::conformance::Resource::builder()
+                .filename(filename)
+                .src("")
+                .build()
+        };
+        let resources = [
+            resource("greetings.txt"),
+            resource("nested/a.txt"),
+            resource("unused.txt"),
+        ]
+        .into_iter()
+        .collect::<Resources<'_>>();
+        let test = |input: serde_json::Value| {
+            Test::builder()
+                .file_name(String::from("t.wdl"))
+                .src(String::from("version 1.2\nworkflow t {}\n"))
+                .input(input)
+                .config(Default::default())
+                .build()
+        };
+
+        let tests = [
+            test(serde_json::json!({"t.f": "data/greetings.txt"})),
+            test(serde_json::json!({"t.d": "data/nested"})),
+        ];
+        assert_eq!(
+            super::unreferenced_resources(&resources, &tests),
+            ["unused.txt"]
+        );
+        assert_eq!(
+            super::unreferenced_resources(&resources, &tests[..1]),
+            ["nested/a.txt", "unused.txt"]
+        );
+    }
+
+    #[test]
+    fn result_lines() {
+        let columns = Columns::new(["a.wdl", "longer_name.wdl"], Some(40));
+        assert_eq!(
+            columns,
+            Columns {
+                status: 18,
+                width: 40
+            }
+        );
+        assert_eq!(
+            Columns::new(["a_very_long_test_name.wdl"], Some(30)).status,
+            14
+        );
+
+        assert_eq!(
+            render_result("a.wdl", "SKIP", Some("ignored"), None, columns, true),
+            "a.wdl.............\x1b[33mSKIP\x1b[0m (ignored)"
+        );
+        assert_eq!(
+            render_result(
+                "longer_name.wdl",
+                "FAIL",
+                Some("expected `a.x` to be 1, but it was 2 in the outputs"),
+                Some(Duration::from_secs(1)),
+                columns,
+                false,
+            ),
+            "longer_name.wdl...FAIL [1.00s]\n    expected `a.x` to be 1, but it was \
+             2\n    in the outputs"
+        );
+
+        assert_eq!(wrap("a b\n\nccc d", 3), ["a b", "", "ccc", "d"]);
+    }
+
+    #[test]
+    fn keep_workdirs() {
+        let failed = TestResult::Failed(FailureReason::NoOutput);
+        assert!(KeepWorkdirs::All.keeps(&TestResult::Passed));
+        assert!(KeepWorkdirs::Failed.keeps(&failed));
+        assert!(!KeepWorkdirs::Failed.keeps(&TestResult::Passed));
+        assert!(!KeepWorkdirs::None.keeps(&failed));
+
+        let keep = |argv: &[&str]| Args::parse_from(argv).keep_workdirs;
+        assert_eq!(keep(&["test", "cmd"]), KeepWorkdirs::Failed);
+        assert_eq!(keep(&["test", "--keep-workdirs", "cmd"]), KeepWorkdirs::All);
+        assert_eq!(
+            keep(&["test", "--keep-workdirs=none", "cmd"]),
+            KeepWorkdirs::None
+        );
+    }
+
+    #[test]
+    fn progress_counts() {
+        let printer = Printer::default().with_progress(3);
+        printer.finished("a.wdl", &TestResult::Passed);
+        printer.finished("b.wdl", &TestResult::Failed(FailureReason::NoOutput));
+
+        assert_eq!(
+            printer.message("c.wdl"),
+            "1 passed, 1 failed, 0 skipped · c.wdl"
+        );
+        assert_eq!(printer.progress.as_ref().unwrap().position(), 2);
+        assert_eq!(
+            Printer::default().message("a.wdl"),
+            "0 passed, 0 failed, 0 skipped · a.wdl"
+        );
+    }
+
+    #[test]
+    fn recent_failures_with_templated_label() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = dir.path().join("history.jsonl");
+        let report = Report::new(vec![Entry::new(
+            "a.wdl",
+            &TestResult::Failed(FailureReason::NoOutput),
+            None,
+        )]);
+        let summary = Summary::new(0, 1, 0, Duration::ZERO).with_specification("wdl-1.2", None);
+        history::append(
+            &history,
+            &history::Record::new("Engine 1.0 (wdl-1.2)", &summary, &report),
+        )
+        .unwrap();
+
+        let args = Args::parse_from([
+            "test",
+            "--label",
+            "Engine {engine_version} ({branch})",
+            "--engine-version",
+            "1.0",
+            "--history-file",
+            history.to_str().unwrap(),
+            "--failed-first",
+            "cmd",
+        ]);
+        let failures = recent_failures(&args).unwrap();
+        assert_eq!(failures.get("a.wdl"), Some(&0));
+
+        let args = Args::parse_from([
+            "test",
+            "--engine-version",
+            "2.0",
+            "--label",
+            "Engine {engine_version} ({branch})",
+            "--history-file",
+            history.to_str().unwrap(),
+            "cmd",
+        ]);
+        assert!(recent_failures(&args).unwrap().is_empty());
     }
 }
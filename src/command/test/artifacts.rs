@@ -0,0 +1,61 @@
+//! Per-test artifacts for debugging runs after the fact.
+
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+
+use crate::conformance::Test;
+use crate::report::Invocation;
+
+/// The logs copied from the working directory of a test.
+const LOG_FILES: &[&str] = &["stdout.log", "stderr.log"];
+
+/// Writes the artifacts of a test to a subdirectory of `dir` named after it.
+///
+/// The subdirectory contains the command that was run (`command.sh`), its
+/// logs, the inputs, and the actual outputs, if any were produced. Outputs are
+/// read from `output_file` if the engine writes them outside the working
+/// directory.
+pub fn write(
+    dir: &Path,
+    test: &Test,
+    invocation: &Invocation,
+    workdir: &Path,
+    output_file: Option<&Path>,
+) -> Result<()> {
+    let dir = dir.join(test.file_name().trim_end_matches(".wdl"));
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("creating artifacts directory `{}`", dir.display()))?;
+
+    std::fs::write(
+        dir.join("command.sh"),
+        format!("#!/usr/bin/env bash\n{}\n", invocation.to_shell()),
+    )
+    .context("writing `command.sh`")?;
+
+    if let Some(input) = test.input() {
+        std::fs::write(
+            dir.join("inputs.json"),
+            serde_json::to_string_pretty(input)?,
+        )
+        .context("writing `inputs.json`")?;
+    }
+
+    let outputs = output_file
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| workdir.join("outputs.json"));
+    let files = LOG_FILES
+        .iter()
+        .map(|name| (workdir.join(name), *name))
+        .chain(std::iter::once((outputs, "outputs.json")));
+
+    for (source, name) in files {
+        if source.is_file() {
+            std::fs::copy(&source, dir.join(name))
+                .with_context(|| format!("copying `{}`", source.display()))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Archives of failed tests for attaching to engine bug reports.
+
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use crate::conformance::FailureReason;
+use crate::conformance::Test;
+use crate::report::Invocation;
+
+/// The files collected from the working directory of a failed test.
+const WORKDIR_FILES: &[&str] = &["outputs.json", "stdout.log", "stderr.log"];
+
+/// A failed test to include in a bundle.
+pub struct Failure<'a> {
+    /// The test that failed.
+    pub test: &'a Test,
+
+    /// The reason the test failed.
+    pub reason: &'a FailureReason,
+
+    /// How the command for the test was invoked.
+    pub invocation: Option<&'a Invocation>,
+
+    /// The working directory of the test.
+    pub workdir: Option<&'a Path>,
+}
+
+/// Writes a gzipped tarball containing everything needed to reproduce each
+/// failed test.
+///
+/// Each test gets a directory named after it containing the WDL document, the
+/// inputs, the expected and actual outputs, the command's logs, the command
+/// itself, and the failure reason. An `environment.txt` describing the
+/// environment of the run is written at the root of the archive.
+pub fn write(path: &Path, failures: &[Failure<'_>], environment: &str) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("creating bundle `{}`", path.display()))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    append(&mut archive, "environment.txt", environment.as_bytes())?;
+
+    for failure in failures {
+        let dir = failure.test.file_name().trim_end_matches(".wdl");
+
+        append(
+            &mut archive,
+            &format!("{dir}/{}", failure.test.file_name()),
+            failure.test.src().as_bytes(),
+        )?;
+        append(
+            &mut archive,
+            &format!("{dir}/failure.txt"),
+            format!("{}: {}\n", failure.reason.code(), failure.reason).as_bytes(),
+        )?;
+
+        if let Some(input) = failure.test.input() {
+            append(
+                &mut archive,
+                &format!("{dir}/inputs.json"),
+                serde_json::to_string_pretty(input)?.as_bytes(),
+            )?;
+        }
+
+        if let Some(output) = failure.test.output() {
+            append(
+                &mut archive,
+                &format!("{dir}/expected_outputs.json"),
+                serde_json::to_string_pretty(output)?.as_bytes(),
+            )?;
+        }
+
+        if let Some(invocation) = failure.invocation {
+            append(
+                &mut archive,
+                &format!("{dir}/command.sh"),
+                format!("#!/usr/bin/env bash\n{}\n", invocation.to_shell()).as_bytes(),
+            )?;
+        }
+
+        if let Some(workdir) = failure.workdir {
+            for name in WORKDIR_FILES {
+                let source = workdir.join(name);
+                if source.is_file() {
+                    archive
+                        .append_path_with_name(&source, format!("{dir}/{name}"))
+                        .with_context(|| format!("adding `{}` to bundle", source.display()))?;
+                }
+            }
+        }
+    }
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("writing bundle `{}`", path.display()))?;
+
+    Ok(())
+}
+
+/// Appends an in-memory file to an archive.
+fn append<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    contents: &[u8],
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+
+    archive
+        .append_data(&mut header, name, contents)
+        .with_context(|| format!("adding `{name}` to bundle"))
+}
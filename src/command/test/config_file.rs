@@ -0,0 +1,447 @@
+//! Reading the settings of a test run from a `spectool.toml` file.
+
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::Args;
+use super::InputMode;
+use super::KeepWorkdirs;
+use super::environment;
+use super::matrix;
+use super::provenance;
+use crate::cache::Size;
+use crate::conformance::Capability;
+use crate::conformance::Dialect;
+use crate::conformance::FloatTolerance;
+use crate::conformance::Layout;
+use crate::conformance::Pattern;
+use crate::conformance::Tag;
+use crate::engine::Engine;
+use crate::engine::OutputSource;
+use crate::report::SummaryFormat;
+use crate::sink;
+use crate::wdl::Construct;
+
+/// The name of the config file discovered in the current directory.
+pub const DEFAULT_FILE_NAME: &str = "spectool.toml";
+
+/// The settings that may be provided in a config file.
+///
+/// Each key is the long name of the corresponding option of `spectool test`,
+/// and `command` is the command template.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    /// The command template.
+    command: Option<String>,
+    /// See `--engine`.
+    engine: Option<Engine>,
+    /// See `--engine-manifest`.
+    engine_manifest: Option<PathBuf>,
+    /// See `--matrix`, as a `[[matrix]]` table for each engine.
+    matrix: Option<Vec<matrix::Entry>>,
+    /// See `--env`, as a table of variables.
+    env: Option<BTreeMap<String, String>>,
+    /// See `--branch`.
+    branch: Option<String>,
+    /// See `--repository-url`.
+    repository_url: Option<String>,
+    /// See `--specification-dir`.
+    specification_dir: Option<PathBuf>,
+    /// See `--specification-url`.
+    specification_url: Option<String>,
+    /// See `--conformance-test-dir`.
+    conformance_test_dir: Option<PathBuf>,
+    /// See `--capabilities`.
+    capabilities: Option<Vec<Capability>>,
+    /// See `--all-capabilities`.
+    all_capabilities: Option<bool>,
+    /// See `--workflow-target-args`.
+    workflow_target_args: Option<String>,
+    /// See `--task-target-args`.
+    task_target_args: Option<String>,
+    /// See `--tasks-unsupported`.
+    tasks_unsupported: Option<bool>,
+    /// See `--unsupported-constructs`.
+    unsupported_constructs: Option<Vec<Construct>>,
+    /// See `--wrap-tasks`.
+    wrap_tasks: Option<bool>,
+    /// See `--strip-output-prefix`.
+    strip_output_prefix: Option<bool>,
+    /// See `--float-tolerance`.
+    float_tolerance: Option<FloatTolerance>,
+    /// See `--redirect-stdout`.
+    redirect_stdout: Option<bool>,
+    /// See `--outputs-from`.
+    outputs_from: Option<OutputSource>,
+    /// See `--input-mode`.
+    input_mode: Option<InputMode>,
+    /// See `--no-shell`.
+    no_shell: Option<bool>,
+    /// See `--engine-options-file`.
+    engine_options_file: Option<PathBuf>,
+    /// See `--check-command`.
+    check_command: Option<String>,
+    /// See `--output-file`.
+    output_file: Option<String>,
+    /// See `--include`.
+    include: Option<Vec<String>>,
+    /// See `--exclude`.
+    exclude: Option<Vec<String>>,
+    /// See `--include-regex`.
+    include_regex: Option<Vec<Pattern>>,
+    /// See `--exclude-regex`.
+    exclude_regex: Option<Vec<Pattern>>,
+    /// See `--tags`.
+    tags: Option<Vec<Tag>>,
+    /// See `--exclude-tags`.
+    exclude_tags: Option<Vec<Tag>>,
+    /// See `--sections`.
+    sections: Option<Vec<String>>,
+    /// See `--wdl-versions`.
+    wdl_versions: Option<Vec<String>>,
+    /// See `--shard-index`.
+    shard_index: Option<usize>,
+    /// See `--shard-count`.
+    shard_count: Option<usize>,
+    /// See `--output-selector`.
+    output_selector: Option<Vec<String>>,
+    /// See `--expected-selector`.
+    expected_selector: Option<Vec<String>>,
+    /// See `--selectors-file`.
+    selectors_file: Option<PathBuf>,
+    /// See `--inject-wdl-version`.
+    inject_wdl_version: Option<String>,
+    /// See `--preprocess-cmd`.
+    preprocess_cmd: Option<String>,
+    /// See `--target-dialect`.
+    target_dialect: Option<Dialect>,
+    /// See `--lenient-json`.
+    lenient_json: Option<bool>,
+    /// See `--label`.
+    label: Option<String>,
+    /// See `--badge-message-template`.
+    badge_message_template: Option<String>,
+    /// See `--engine-version`.
+    engine_version: Option<String>,
+    /// See `--tier-badges`.
+    tier_badges: Option<PathBuf>,
+    /// See `--summary-format`.
+    summary_format: Option<SummaryFormat>,
+    /// See `--results-file`.
+    results_file: Option<PathBuf>,
+    /// See `--sign-results`.
+    sign_results: Option<PathBuf>,
+    /// See `--markdown-report`.
+    markdown_report: Option<PathBuf>,
+    /// See `--layout`.
+    layout: Option<Layout>,
+    /// See `--history-file`.
+    history_file: Option<PathBuf>,
+    /// See `--slack-webhook`.
+    slack_webhook: Option<String>,
+    /// See `--notify-on`.
+    notify_on: Option<sink::Notify>,
+    /// See `--pull-images`.
+    pull_images: Option<bool>,
+    /// See `--pull-command`.
+    pull_command: Option<String>,
+    /// See `--stage-referenced-data`.
+    stage_referenced_data: Option<bool>,
+    /// See `--work-root`.
+    work_root: Option<PathBuf>,
+    /// See `--keep-workdirs`.
+    keep_workdirs: Option<KeepWorkdirs>,
+    /// See `--hermetic-env`.
+    hermetic_env: Option<bool>,
+    /// See `--env-allow`.
+    env_allow: Option<Vec<String>>,
+    /// See `--env-deny`.
+    env_deny: Option<Vec<String>>,
+    /// See `--locale`.
+    locale: Option<String>,
+    /// See `--setup-time-file`.
+    setup_time_file: Option<String>,
+    /// See `--artifacts-dir`.
+    artifacts_dir: Option<PathBuf>,
+    /// See `--html-index`.
+    html_index: Option<PathBuf>,
+    /// See `--enforce-durations`.
+    enforce_durations: Option<bool>,
+    /// See `--baseline`.
+    baseline: Option<PathBuf>,
+    /// See `--update-baseline`.
+    update_baseline: Option<bool>,
+    /// See `--fail-fast`.
+    fail_fast: Option<bool>,
+    /// See `--max-failures`.
+    max_failures: Option<NonZeroUsize>,
+    /// See `--crash-loop-threshold`.
+    crash_loop_threshold: Option<usize>,
+    /// See `--rerun-failed`.
+    rerun_failed: Option<bool>,
+    /// See `--failed-first`.
+    failed_first: Option<bool>,
+    /// See `--state-file`.
+    state_file: Option<PathBuf>,
+    /// See `--strict`.
+    strict: Option<bool>,
+    /// See `--cache-max-size`.
+    cache_max_size: Option<Size>,
+    /// See `--n-cpu`.
+    n_cpu: Option<usize>,
+    /// The destinations the results are written to, as a `[[sink]]` table
+    /// for each.
+    sink: Option<Vec<sink::Config>>,
+
+    /// The raw contents of the file, used to report each value's provenance.
+    #[serde(skip)]
+    raw: toml::Table,
+
+    /// The directory containing the file.
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl ConfigFile {
+    /// Reads a config file.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading config file `{}`", path.display()))?;
+
+        let mut file = toml::from_str::<Self>(&contents)
+            .with_context(|| format!("parsing config file `{}`", path.display()))?;
+
+        file.raw = toml::from_str(&contents).expect("config file to have already been parsed");
+        file.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(file)
+    }
+
+    /// Finds the config file for a run.
+    ///
+    /// An explicitly provided path must exist; otherwise, `spectool.toml` is
+    /// used if it exists within the current directory.
+    pub fn discover(path: Option<&Path>) -> Result<Option<Self>> {
+        match path {
+            Some(path) => Self::from_path(path).map(Some),
+            None if Path::new(DEFAULT_FILE_NAME).is_file() => {
+                Self::from_path(Path::new(DEFAULT_FILE_NAME)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fills in each setting of `args` that was not provided on the command
+    /// line or through an environment variable with the value from the file.
+    ///
+    /// Relative paths within the file are resolved against the directory
+    /// containing it.
+    pub fn apply(mut self, args: &mut Args) {
+        for entry in self.matrix.iter_mut().flatten() {
+            entry.resolve(&self.dir);
+        }
+
+        if let Some(sinks) = self.sink.take() {
+            args.sinks = sinks.into_iter().map(|s| s.resolve(&self.dir)).collect();
+        }
+
+        if let Some(env) = self.env.take()
+            && !provenance::is_explicit(&args.settings, "--env")
+        {
+            let vars = env
+                .keys()
+                .map(|key| environment::redact(key))
+                .collect::<Vec<_>>();
+            provenance::record(
+                &mut args.settings,
+                "--env",
+                &vars.join(","),
+                provenance::Source::ConfigFile,
+            );
+            args.env = env.into_iter().collect();
+        }
+
+        /// Applies each field whose value is used as-is, wrapped in `Some`,
+        /// or resolved as a path (optional or not).
+        macro_rules! apply {
+            ($($kind:ident $field:ident),* $(,)?) => {
+                $(
+                    if let Some(value) = self.$field {
+                        let key = stringify!($field).replace('_', "-");
+                        let name = match key.as_str() {
+                            "command" => String::from("<COMMAND>"),
+                            key => format!("--{key}"),
+                        };
+
+                        if !provenance::is_explicit(&args.settings, &name) {
+                            provenance::record(
+                                &mut args.settings,
+                                &name,
+                                &render(&self.raw[&key]),
+                                provenance::Source::ConfigFile,
+                            );
+                            args.$field = apply!(@$kind self.dir, value);
+                        }
+                    }
+                )*
+            };
+            (@value $dir:expr, $value:expr) => { $value };
+            (@some $dir:expr, $value:expr) => { Some($value) };
+            (@path $dir:expr, $value:expr) => { Some($dir.join($value)) };
+        }
+
+        apply!(
+            some command,
+            some engine,
+            path engine_manifest,
+            value matrix,
+            value branch,
+            value repository_url,
+            path specification_dir,
+            some specification_url,
+            path conformance_test_dir,
+            value capabilities,
+            value all_capabilities,
+            value workflow_target_args,
+            value task_target_args,
+            value tasks_unsupported,
+            value unsupported_constructs,
+            value wrap_tasks,
+            value strip_output_prefix,
+            some float_tolerance,
+            value redirect_stdout,
+            some outputs_from,
+            value input_mode,
+            value no_shell,
+            path engine_options_file,
+            some check_command,
+            some output_file,
+            value include,
+            value exclude,
+            value include_regex,
+            value exclude_regex,
+            value tags,
+            value exclude_tags,
+            value sections,
+            value wdl_versions,
+            some shard_index,
+            some shard_count,
+            value output_selector,
+            value expected_selector,
+            path selectors_file,
+            some inject_wdl_version,
+            some preprocess_cmd,
+            some target_dialect,
+            value lenient_json,
+            value label,
+            some badge_message_template,
+            some engine_version,
+            path tier_badges,
+            some summary_format,
+            path results_file,
+            path sign_results,
+            path markdown_report,
+            value layout,
+            path history_file,
+            some slack_webhook,
+            value notify_on,
+            value pull_images,
+            value pull_command,
+            value stage_referenced_data,
+            path work_root,
+            value keep_workdirs,
+            value hermetic_env,
+            value env_allow,
+            value env_deny,
+            some locale,
+            some setup_time_file,
+            path artifacts_dir,
+            path html_index,
+            value enforce_durations,
+            path baseline,
+            value update_baseline,
+            value fail_fast,
+            some max_failures,
+            value crash_loop_threshold,
+            value rerun_failed,
+            value failed_first,
+            path state_file,
+            value strict,
+            some cache_max_size,
+            value n_cpu,
+        );
+    }
+}
+
+/// Renders a TOML value the way it would be written on the command line.
+fn render(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Array(values) => values.iter().map(render).collect::<Vec<_>>().join(","),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory as _;
+    use clap::FromArgMatches as _;
+
+    use super::*;
+
+    #[test]
+    fn command_line_overrides_file() {
+        let contents = r#"
+            command = "engine run ~{path} ~{input} ~{output}"
+            include = ["array", "map"]
+            label = "Engine"
+            specification-dir = "wdl"
+            env = { TOKEN = "secret" }
+        "#;
+
+        let mut config = toml::from_str::<ConfigFile>(contents).unwrap();
+        config.raw = toml::from_str(contents).unwrap();
+        config.dir = PathBuf::from("/ci");
+
+        let matches = Args::command()
+            .try_get_matches_from(["test", "--label", "Override"])
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches)
+            .unwrap()
+            .with_settings(&matches);
+        config.apply(&mut args);
+
+        assert_eq!(
+            args.command.as_deref(),
+            Some("engine run ~{path} ~{input} ~{output}")
+        );
+        assert_eq!(args.include, ["array", "map"]);
+        assert_eq!(args.label, "Override");
+        assert_eq!(args.specification_dir, Some(PathBuf::from("/ci/wdl")));
+
+        let find = |name: &str| {
+            provenance::render(&args.settings)
+                .lines()
+                .find(|line| line.starts_with(name))
+                .unwrap()
+                .to_string()
+        };
+        assert!(find("--include").ends_with("\"array,map\" (config file)"));
+        assert!(find("--label").ends_with("(command line)"));
+        assert_eq!(args.env, [(String::from("TOKEN"), String::from("secret"))]);
+        assert!(find("--env").ends_with("\"TOKEN=<redacted>\" (config file)"));
+
+        assert!(
+            toml::from_str::<ConfigFile>("unknown = true")
+                .unwrap_err()
+                .to_string()
+                .contains("unknown field")
+        );
+    }
+}
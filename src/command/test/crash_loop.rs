@@ -0,0 +1,170 @@
+//! Detection of an engine that fails every test in the same way, such as one
+//! that is not installed or cannot start, so that the run can be aborted
+//! early instead of producing the same failure for every test.
+
+use std::sync::Mutex;
+
+use crate::conformance::FailureReason;
+use crate::conformance::TestResult;
+
+/// The state of the detection.
+#[derive(Debug)]
+enum State {
+    /// Every test that finished so far failed with the same error.
+    Watching,
+    /// A test finished without the error that the earlier tests failed with,
+    /// so the engine is not crash looping.
+    Cleared,
+    /// The first tests all failed with the same error.
+    Tripped,
+}
+
+/// Watches the first tests to finish for the same execution failure.
+#[derive(Debug)]
+pub struct CrashLoop {
+    /// The number of tests that must fail with the same error.
+    threshold: usize,
+
+    /// The state of the detection, the error the tests failed with, and the
+    /// number of tests that failed with it.
+    inner: Mutex<(State, Option<String>, usize)>,
+}
+
+impl CrashLoop {
+    /// Creates a detector that trips after `threshold` tests fail with the
+    /// same error, or never if `threshold` is zero.
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            inner: Mutex::new((
+                if threshold == 0 {
+                    State::Cleared
+                } else {
+                    State::Watching
+                },
+                None,
+                0,
+            )),
+        }
+    }
+
+    /// Gets the number of tests that must fail with the same error.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Gets whether the first tests all failed with the same error.
+    pub fn tripped(&self) -> bool {
+        // SAFETY: we expect the lock to always eventually be acquired.
+        matches!(self.inner.lock().unwrap().0, State::Tripped)
+    }
+
+    /// Records the result of a test that was executed, along with the last
+    /// line the command wrote to stderr.
+    ///
+    /// Returns the error the tests failed with if this result trips the
+    /// detection.
+    pub fn record(&self, result: &TestResult, stderr: Option<&str>) -> Option<String> {
+        // SAFETY: we expect the lock to always eventually be acquired.
+        let mut inner = self.inner.lock().unwrap();
+        let (state, error, count) = &mut *inner;
+
+        if !matches!(state, State::Watching) {
+            return None;
+        }
+
+        let Some(signature) = signature(result, stderr) else {
+            *state = State::Cleared;
+            return None;
+        };
+
+        match error {
+            Some(error) if *error != signature => {
+                *state = State::Cleared;
+                return None;
+            }
+            Some(_) => *count += 1,
+            None => {
+                *error = Some(signature);
+                *count = 1;
+            }
+        }
+
+        if *count < self.threshold {
+            return None;
+        }
+
+        *state = State::Tripped;
+        error.clone()
+    }
+}
+
+/// Gets what identifies the way a test failed to execute (by failing to
+/// start, exiting with an unexpected return code, or producing no outputs):
+/// the failure and the last line the command wrote to stderr.
+///
+/// Tests that passed or failed for any other reason (e.g., mismatched outputs)
+/// have none.
+fn signature(result: &TestResult, stderr: Option<&str>) -> Option<String> {
+    let TestResult::Failed(
+        reason @ (FailureReason::ExecutionError(_)
+        | FailureReason::ReturnCodeMismatch { .. }
+        | FailureReason::NoOutput),
+    ) = result
+    else {
+        return None;
+    };
+
+    Some(
+        match stderr.and_then(|s| s.lines().rev().find(|l| !l.trim().is_empty())) {
+            Some(line) => format!("{reason} ({})", line.trim()),
+            None => reason.to_string(),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::ReturnCode;
+
+    #[test]
+    fn trips_on_the_same_error() {
+        let missing = TestResult::Failed(FailureReason::ReturnCodeMismatch {
+            expected: ReturnCode::Single(0),
+            actual: 127,
+        });
+        let stderr = "bash: line 1: engine: command not found\n";
+
+        let detector = CrashLoop::new(2);
+        assert_eq!(detector.record(&missing, Some(stderr)), None);
+        assert_eq!(
+            detector.record(&missing, Some(stderr)).as_deref(),
+            Some(
+                "return code mismatch: expected Single(0), got 127 (bash: line 1: engine: command \
+                 not found)"
+            )
+        );
+        assert!(detector.tripped());
+        assert_eq!(detector.record(&missing, Some(stderr)), None);
+
+        let detector = CrashLoop::new(2);
+        detector.record(&missing, Some(stderr));
+        detector.record(&missing, Some("license expired"));
+        assert!(!detector.tripped());
+        assert_eq!(detector.record(&missing, Some(stderr)), None);
+
+        let detector = CrashLoop::new(1);
+        assert_eq!(detector.record(&TestResult::Passed, None), None);
+        assert_eq!(detector.record(&missing, None), None);
+
+        assert_eq!(CrashLoop::new(0).record(&missing, None), None);
+
+        let detector = CrashLoop::new(1);
+        assert!(
+            detector
+                .record(&TestResult::Failed(FailureReason::NoOutput), Some(stderr))
+                .is_some()
+        );
+    }
+}
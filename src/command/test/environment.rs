@@ -0,0 +1,229 @@
+//! The environment that commands are run with.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+/// The variables passed through to commands by default with
+/// `--hermetic-env`.
+pub const DEFAULT_ALLOWED: &[&str] = &["HOME", "PATH", "TMPDIR", "USER"];
+
+/// The locale set for commands with `--hermetic-env` when no `--locale` is
+/// provided.
+pub const DEFAULT_LOCALE: &str = "C.UTF-8";
+
+/// What the values of variables set with `--env` are recorded as.
+pub const REDACTED: &str = "<redacted>";
+
+/// The environment that commands are run with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Environment {
+    /// The variables set for commands.
+    vars: BTreeMap<String, String>,
+
+    /// Whether commands are run with only `vars` rather than in addition to
+    /// the environment `spectool` was run with.
+    isolated: bool,
+
+    /// The variables within `vars` that were set with `--env`.
+    injected: BTreeSet<String>,
+}
+
+impl Environment {
+    /// Creates an environment that adds the locale, if one is provided, to
+    /// the environment `spectool` was run with.
+    pub fn inherited(locale: Option<&str>) -> Self {
+        Self {
+            vars: locale.map(locale_vars).into_iter().flatten().collect(),
+            isolated: false,
+            injected: BTreeSet::new(),
+        }
+    }
+
+    /// Creates a scrubbed environment from the given variables.
+    ///
+    /// Only the default variables and those in `allow` are passed through,
+    /// less any in `deny`. `PATH` is cleaned of empty and relative entries,
+    /// and the locale and time zone are fixed so that results do not vary
+    /// with the configuration of the machine running the tests.
+    pub fn hermetic(
+        vars: impl IntoIterator<Item = (String, String)>,
+        allow: &[String],
+        deny: &[String],
+        locale: Option<&str>,
+    ) -> Self {
+        let mut vars = vars
+            .into_iter()
+            .filter(|(key, _)| {
+                (DEFAULT_ALLOWED.contains(&key.as_str()) || allow.contains(key))
+                    && !deny.contains(key)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        if let Some(path) = vars.get_mut("PATH") {
+            *path = clean_path(path);
+        }
+
+        vars.extend(locale_vars(locale.unwrap_or(DEFAULT_LOCALE)));
+        vars.insert(String::from("TZ"), String::from("UTC"));
+
+        Self {
+            vars,
+            isolated: true,
+            injected: BTreeSet::new(),
+        }
+    }
+
+    /// Sets variables for commands, replacing any with the same name.
+    ///
+    /// The variables are set even in a hermetic environment, and their values
+    /// are redacted from what is recorded about each invocation, as they may
+    /// be credentials.
+    pub fn with_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        for (key, value) in vars {
+            self.injected.insert(key.clone());
+            self.vars.insert(key, value);
+        }
+        self
+    }
+
+    /// Gets the variables set for commands as they are recorded with each
+    /// invocation: the values of those set with `--env` are redacted.
+    pub fn recorded_vars(&self) -> BTreeMap<String, String> {
+        self.vars
+            .iter()
+            .map(|(key, value)| {
+                let value = if self.injected.contains(key) {
+                    REDACTED.to_string()
+                } else {
+                    value.clone()
+                };
+                (key.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Parses a `KEY=VALUE` variable.
+    pub fn parse_var(s: &str) -> Result<(String, String), String> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => Err(format!("`{s}` is not of the form `KEY=VALUE`")),
+        }
+    }
+
+    /// Gets whether commands are run with only these variables.
+    pub fn isolated(&self) -> bool {
+        self.isolated
+    }
+
+    /// Applies the environment to a command.
+    pub fn apply(&self, command: &mut std::process::Command) {
+        if self.isolated {
+            command.env_clear();
+        }
+
+        command.envs(&self.vars);
+    }
+}
+
+/// Redacts the value of a `KEY=VALUE` variable set with `--env`, so that
+/// `--explain-config` does not print credentials.
+pub fn redact(var: &str) -> String {
+    let key = var.split_once('=').map_or(var, |(key, _)| key);
+    format!("{key}={REDACTED}")
+}
+
+/// Gets the variables that set the locale.
+fn locale_vars(locale: &str) -> [(String, String); 2] {
+    [
+        (String::from("LANG"), locale.to_string()),
+        (String::from("LC_ALL"), locale.to_string()),
+    ]
+}
+
+/// Removes empty, relative, and duplicate entries from a `PATH`.
+fn clean_path(path: &str) -> String {
+    let mut entries = Vec::new();
+
+    for entry in path.split(':') {
+        if entry.starts_with('/') && !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+
+    entries.join(":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hermetic() {
+        let vars = [
+            ("PATH", "/usr/bin::./bin:/bin:/usr/bin"),
+            ("HOME", "/home/ci"),
+            ("USER", "ci"),
+            ("LANG", "de_DE.UTF-8"),
+            ("JAVA_HOME", "/opt/java"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+
+        let environment = Environment::hermetic(
+            vars,
+            &[String::from("JAVA_HOME")],
+            &[String::from("USER")],
+            None,
+        );
+
+        assert!(environment.isolated());
+        assert_eq!(
+            environment
+                .vars
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>(),
+            [
+                "HOME=/home/ci",
+                "JAVA_HOME=/opt/java",
+                "LANG=C.UTF-8",
+                "LC_ALL=C.UTF-8",
+                "PATH=/usr/bin:/bin",
+                "TZ=UTC",
+            ]
+        );
+
+        let inherited = Environment::inherited(Some("en_US.UTF-8"));
+        assert!(!inherited.isolated());
+        assert_eq!(inherited.vars["LC_ALL"], "en_US.UTF-8");
+    }
+
+    #[test]
+    fn injected() {
+        let environment = Environment::hermetic(
+            [(
+                String::from("MINIWDL_CFG"),
+                String::from("/etc/miniwdl.cfg"),
+            )],
+            &[],
+            &[],
+            None,
+        )
+        .with_vars([
+            (String::from("MINIWDL_CFG"), String::from("ci.cfg")),
+            (String::from("TOKEN"), String::from("secret")),
+        ]);
+
+        assert_eq!(environment.vars["MINIWDL_CFG"], "ci.cfg");
+        assert_eq!(environment.vars["TOKEN"], "secret");
+        assert_eq!(environment.recorded_vars()["TOKEN"], REDACTED);
+        assert_eq!(environment.recorded_vars()["TZ"], "UTC");
+
+        assert_eq!(
+            Environment::parse_var("A=b=c"),
+            Ok((String::from("A"), String::from("b=c")))
+        );
+        assert!(Environment::parse_var("=b").is_err());
+        assert!(Environment::parse_var("A").is_err());
+    }
+}
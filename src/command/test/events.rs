@@ -0,0 +1,189 @@
+//! A stream of newline-delimited JSON events describing the progress of a
+//! run, for orchestrators that track long runs as they happen.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::report::Outcome;
+
+/// An event within a run.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// The run started.
+    RunStarted {
+        /// The number of tests that were selected.
+        tests: usize,
+    },
+    /// A test started to execute.
+    TestStarted {
+        /// The file name of the test.
+        test: &'a str,
+    },
+    /// A test is still running after another `--heartbeat` interval.
+    Heartbeat {
+        /// The file name of the test.
+        test: &'a str,
+        /// How long the test has been running in seconds.
+        elapsed_secs: f64,
+    },
+    /// A test finished, or was skipped.
+    TestFinished {
+        /// The file name of the test.
+        test: &'a str,
+        /// The outcome of the test.
+        #[serde(flatten)]
+        outcome: Outcome,
+        /// The command that was run for the test, if it was executed.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        command: Option<&'a str>,
+        /// The execution time of the test in seconds, if it was executed.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        duration_secs: Option<f64>,
+    },
+    /// The run finished.
+    RunFinished {
+        /// The number of tests that passed.
+        passed: usize,
+        /// The number of tests that failed.
+        failed: usize,
+        /// The number of tests that were skipped.
+        skipped: usize,
+        /// The duration of the run in seconds.
+        duration_secs: f64,
+    },
+}
+
+/// An event with the time it happened and the run it happened in.
+#[derive(Serialize)]
+struct Timestamped<'a> {
+    /// When the event happened, in seconds since the Unix epoch.
+    timestamp: f64,
+
+    /// The identifier of the run.
+    run_id: &'a str,
+
+    /// The event.
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Writes events as lines of JSON to a file or stdout.
+pub struct Events {
+    /// Where the events are written.
+    writer: Mutex<Box<dyn Write + Send>>,
+
+    /// The identifier of the run.
+    run_id: String,
+}
+
+impl Events {
+    /// Creates a stream of the events of a run written to a file, or to
+    /// stdout if the path is `-`.
+    pub fn create(path: &Path, run_id: &str) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(
+                File::create(path)
+                    .with_context(|| format!("creating events file `{}`", path.display()))?,
+            )
+        };
+
+        Ok(Self {
+            writer: Mutex::new(writer),
+            run_id: run_id.to_string(),
+        })
+    }
+
+    /// Writes an event.
+    ///
+    /// Events that cannot be written are logged rather than failing the run.
+    pub fn emit(&self, event: &Event<'_>) {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        let line = render(&Timestamped {
+            timestamp,
+            run_id: &self.run_id,
+            event,
+        });
+
+        // SAFETY: we expect the lock to always eventually be acquired.
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{line}").and_then(|_| writer.flush()) {
+            warn!("failed to write event: {e}");
+        }
+    }
+}
+
+/// Renders an event as a line of JSON.
+fn render(event: &Timestamped<'_>) -> String {
+    // SAFETY: events only contain strings, numbers, and outcomes, which
+    // always serialize.
+    serde_json::to_string(event).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::TestResult;
+
+    #[test]
+    fn lines() {
+        let event = Event::TestFinished {
+            test: "a.wdl",
+            outcome: Outcome::from(&TestResult::Failed(FailureReason::NoOutput)),
+            command: Some("engine run a.wdl"),
+            duration_secs: Some(1.5),
+        };
+        let line = render(&Timestamped {
+            timestamp: 1.0,
+            run_id: "run",
+            event: &event,
+        });
+
+        let value = serde_json::from_str::<serde_json::Value>(&line).unwrap();
+        assert_eq!(value["event"], "test_finished");
+        assert_eq!(value["timestamp"], 1.0);
+        assert_eq!(value["test"], "a.wdl");
+        assert_eq!(value["status"], "failed");
+        assert_eq!(value["code"], "no_output");
+        assert_eq!(value["command"], "engine run a.wdl");
+        assert_eq!(value["duration_secs"], 1.5);
+        assert!(!line.contains('\n'));
+
+        let line = render(&Timestamped {
+            timestamp: 2.0,
+            run_id: "run",
+            event: &Event::TestStarted { test: "b.wdl" },
+        });
+        assert_eq!(
+            line,
+            r#"{"timestamp":2.0,"run_id":"run","event":"test_started","test":"b.wdl"}"#
+        );
+
+        let line = render(&Timestamped {
+            timestamp: 3.0,
+            run_id: "run",
+            event: &Event::Heartbeat {
+                test: "b.wdl",
+                elapsed_secs: 60.0,
+            },
+        });
+        assert_eq!(
+            line,
+            r#"{"timestamp":3.0,"run_id":"run","event":"heartbeat","test":"b.wdl","elapsed_secs":60.0}"#
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! An HTML index of the files written by a run, so that the directory they
+//! are written to can be browsed as a single CI artifact.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use anyhow::Result;
+
+use crate::report::Outcome;
+use crate::report::Report;
+use crate::report::Summary;
+use crate::sink;
+
+/// A file or directory written by the run.
+pub struct Link {
+    /// What was written (e.g., `JUnit report`).
+    label: String,
+
+    /// The path that was written.
+    path: PathBuf,
+}
+
+impl Link {
+    /// Creates a link to a file or directory.
+    pub fn new(label: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            label: label.into(),
+            path: path.into(),
+        }
+    }
+
+    /// Creates a link to the file written by a sink, if it writes one.
+    pub fn to_sink(sink: &sink::Config) -> Option<Self> {
+        let label = match sink {
+            sink::Config::File { .. } => "Results file",
+            sink::Config::Junit { .. } => "JUnit report",
+            sink::Config::Badge { .. } => "Badge",
+            sink::Config::Markdown { .. } => "Markdown report",
+            sink::Config::History { .. } => "History",
+            sink::Config::Console { .. }
+            | sink::Config::Webhook { .. }
+            | sink::Config::Slack { .. } => return None,
+        };
+
+        Some(Self::new(label, sink.path()?))
+    }
+}
+
+/// The run an index is written for.
+pub struct Run<'a> {
+    /// The label of the badge for the run.
+    pub label: &'a str,
+
+    /// The identifier of the run.
+    pub run_id: &'a str,
+
+    /// The summary of the run.
+    pub summary: &'a Summary,
+
+    /// The report of every test.
+    pub report: &'a Report,
+
+    /// The directory containing the artifacts of each test, if they were
+    /// written.
+    pub artifacts_dir: Option<&'a Path>,
+}
+
+/// Writes an HTML page with the metadata and summary of a run that links to
+/// every file written by the run and to the artifacts of each failed test.
+pub fn write(path: &Path, run: &Run<'_>, links: &[Link]) -> Result<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    std::fs::write(path, render(dir, run, links))
+        .with_context(|| format!("writing index `{}`", path.display()))
+}
+
+/// Renders the index, with links relative to the directory it is written
+/// to.
+fn render(dir: &Path, run: &Run<'_>, links: &[Link]) -> String {
+    let summary = run.summary;
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{label}: {passed}/{total} passed</title>\n</head>\n<body>\n\
+         <h1>{label}</h1>\n<dl>\n",
+        label = escape(run.label),
+        passed = summary.passed(),
+        total = summary.total(),
+    );
+
+    let metadata = [
+        ("Run", Some(run.run_id)),
+        ("Engine", summary.engine()),
+        ("Engine version", summary.engine_version()),
+        ("Branch", summary.branch()),
+        ("Commit", summary.commit()),
+        ("spectool", Some(env!("CARGO_PKG_VERSION"))),
+    ];
+    for (term, value) in metadata {
+        if let Some(value) = value {
+            html.push_str(&format!("<dt>{term}</dt><dd>{}</dd>\n", escape(value)));
+        }
+    }
+    html.push_str("</dl>\n<table>\n");
+
+    for (label, value) in summary.figures() {
+        html.push_str(&format!("<tr><th>{label}</th><td>{value}</td></tr>\n"));
+    }
+    html.push_str("</table>\n");
+
+    if !links.is_empty() {
+        html.push_str("<h2>Files</h2>\n<ul>\n");
+        for link in links {
+            html.push_str(&format!(
+                "<li><a href=\"{href}\">{label}</a> <code>{path}</code></li>\n",
+                href = escape(&href(dir, &link.path)),
+                label = escape(&link.label),
+                path = escape(&link.path.display().to_string()),
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    let failed = run
+        .report
+        .tests()
+        .iter()
+        .filter_map(|entry| match entry.result() {
+            Outcome::Failed { message, .. } => Some((entry, message)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !failed.is_empty() {
+        html.push_str("<h2>Failed tests</h2>\n<ul>\n");
+        for (entry, message) in failed {
+            let name = escape(entry.name());
+            let name = match run.artifacts_dir {
+                Some(artifacts_dir) => {
+                    let artifacts = artifacts_dir.join(entry.name().trim_end_matches(".wdl"));
+                    format!("<a href=\"{}\">{name}</a>", escape(&href(dir, &artifacts)))
+                }
+                None => name,
+            };
+            let section = entry
+                .section_url()
+                .map(|url| format!(" (<a href=\"{}\">specification</a>)", escape(url)))
+                .unwrap_or_default();
+            html.push_str(&format!("<li>{name}{section}: {}</li>\n", escape(message)));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Gets the link to a path from a page within a directory: a relative link
+/// if the path is within the directory, and a `file://` URL otherwise.
+fn href(dir: &Path, path: &Path) -> String {
+    let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let path = absolute(path);
+
+    match path.strip_prefix(absolute(dir)) {
+        Ok(relative) => relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => format!("file://{}", path.display()),
+    }
+}
+
+/// Escapes text for HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::TestResult;
+    use crate::report::Entry;
+
+    #[test]
+    fn links() {
+        let report = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Passed, None),
+            Entry::new(
+                "b<c>.wdl",
+                &TestResult::Failed(FailureReason::NoOutput),
+                None,
+            ),
+        ]);
+        let summary = Summary::new(1, 1, 0, Duration::from_secs(2));
+        let run = Run {
+            label: "WDL",
+            run_id: "run",
+            summary: &summary,
+            report: &report,
+            artifacts_dir: Some(Path::new("/ci/out/artifacts/run")),
+        };
+        let links = [
+            Link::to_sink(&sink::Config::Junit {
+                path: PathBuf::from("/ci/out/junit.xml"),
+            })
+            .unwrap(),
+            Link::new("History", "/var/history.jsonl"),
+        ];
+
+        let html = render(Path::new("/ci/out"), &run, &links);
+        assert!(html.contains("<title>WDL: 1/2 passed</title>"));
+        assert!(html.contains("<dt>Run</dt><dd>run</dd>"));
+        assert!(html.contains("<a href=\"junit.xml\">JUnit report</a>"));
+        assert!(html.contains("<a href=\"file:///var/history.jsonl\">History</a>"));
+        assert!(html.contains("<a href=\"artifacts/run/b&lt;c&gt;\">b&lt;c&gt;.wdl</a>"));
+    }
+}
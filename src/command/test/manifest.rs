@@ -0,0 +1,191 @@
+//! Filling in the settings of a test run from an engine manifest.
+
+use std::path::Path;
+
+use anyhow::Result;
+use tracing::info;
+
+use super::Args;
+use super::provenance;
+use crate::manifest::Manifest;
+
+/// Reads the manifest at `path` and fills in each setting of `args` that was
+/// not otherwise provided with the value from the manifest.
+///
+/// The manifest takes precedence over the engine preset, which is applied
+/// afterwards.
+pub fn apply(path: &Path, args: &mut Args) -> Result<()> {
+    let manifest = Manifest::from_path(path)?;
+    info!(
+        "using engine manifest `{}`{}",
+        path.display(),
+        manifest
+            .name()
+            .map(|name| format!(" for `{name}`"))
+            .unwrap_or_default()
+    );
+
+    fill(&manifest, args);
+    Ok(())
+}
+
+/// Records that a setting is filled in from the manifest, returning `false`
+/// if the setting was already provided.
+fn claim(args: &mut Args, name: &str, value: &str) -> bool {
+    if provenance::is_set(&args.settings, name) {
+        return false;
+    }
+
+    provenance::record(
+        &mut args.settings,
+        name,
+        value,
+        provenance::Source::Manifest,
+    );
+    true
+}
+
+/// Fills in each setting of `args` that was not otherwise provided.
+fn fill(manifest: &Manifest, args: &mut Args) {
+    if let Some(command) = manifest.command()
+        && args.command.is_none()
+        && claim(args, "<COMMAND>", command)
+    {
+        args.command = Some(command.to_string());
+    }
+
+    if let Some(version) = manifest.version()
+        && claim(args, "--engine-version", version)
+    {
+        args.engine_version = Some(version.to_string());
+    }
+
+    if let Some(value) = manifest.workflow_target_args()
+        && claim(args, "--workflow-target-args", value)
+    {
+        args.workflow_target_args = value.to_string();
+    }
+
+    if let Some(value) = manifest.task_target_args()
+        && claim(args, "--task-target-args", value)
+    {
+        args.task_target_args = value.to_string();
+    }
+
+    if let Some(versions) = manifest.wdl_versions()
+        && claim(args, "--wdl-versions", &versions.join(","))
+    {
+        args.wdl_versions = versions.to_vec();
+    }
+
+    if let Some(capabilities) = manifest.capabilities()
+        && !args.all_capabilities
+        && claim(args, "--capabilities", &join(capabilities))
+    {
+        args.capabilities = capabilities.to_vec();
+    }
+
+    if let Some(constructs) = manifest.unsupported_constructs()
+        && claim(args, "--unsupported-constructs", &join(constructs))
+    {
+        args.unsupported_constructs = constructs.to_vec();
+    }
+
+    if let Some(value) = manifest.tasks_unsupported()
+        && claim(args, "--tasks-unsupported", &value.to_string())
+    {
+        args.tasks_unsupported = value;
+    }
+
+    let outputs = manifest.outputs();
+
+    // The outputs are always read from standard output with
+    // `--redirect-stdout`, so the manifest's conventions do not apply.
+    if !args.redirect_stdout {
+        if let Some(file) = outputs.file()
+            && claim(args, "--output-file", file)
+        {
+            args.output_file = Some(file.to_string());
+        }
+
+        if let Some(source) = outputs.from()
+            && claim(args, "--outputs-from", &source.to_string())
+        {
+            args.outputs_from = Some(source);
+        }
+    }
+
+    if let Some(selectors) = outputs.selectors()
+        && claim(args, "--output-selector", &selectors.join(","))
+    {
+        args.output_selector = selectors.to_vec();
+    }
+
+    if let Some(selectors) = outputs.expected_selectors()
+        && claim(args, "--expected-selector", &selectors.join(","))
+    {
+        args.expected_selector = selectors.to_vec();
+    }
+
+    if let Some(value) = outputs.strip_prefix()
+        && claim(args, "--strip-output-prefix", &value.to_string())
+    {
+        args.strip_output_prefix = value;
+    }
+}
+
+/// Joins values the way they would be written on the command line.
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory as _;
+    use clap::FromArgMatches as _;
+
+    use super::*;
+    use crate::conformance::Capability;
+    use crate::engine::OutputSource;
+
+    #[test]
+    fn fills_unset_settings() {
+        let manifest = toml::from_str::<Manifest>(
+            r#"
+            command = "engine run ~{path} ~{input} ~{output}"
+            version = "1.0.0"
+            capabilities = ["cpu"]
+
+            [outputs]
+            from = "file"
+            selectors = [".outputs"]
+            "#,
+        )
+        .unwrap();
+
+        let matches = Args::command()
+            .try_get_matches_from(["test", "--output-selector", ".results"])
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches)
+            .unwrap()
+            .with_settings(&matches);
+        fill(&manifest, &mut args);
+
+        assert_eq!(
+            args.command.as_deref(),
+            Some("engine run ~{path} ~{input} ~{output}")
+        );
+        assert_eq!(args.engine_version.as_deref(), Some("1.0.0"));
+        assert_eq!(args.capabilities, [Capability::Cpu]);
+        assert_eq!(args.outputs_from, Some(OutputSource::File));
+        assert_eq!(args.output_selector, [".results"]);
+
+        let rendered = provenance::render(&args.settings);
+        assert!(rendered.contains("\"1.0.0\" (engine manifest)"));
+        assert!(rendered.contains("\".results\" (command line)"));
+    }
+}
@@ -0,0 +1,459 @@
+//! Running the tests against several engines and reporting a conformance
+//! matrix.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Result;
+use anyhow::bail;
+use clap::ValueEnum as _;
+use serde::Deserialize;
+
+use super::Args;
+use super::environment;
+use super::execute;
+use super::manifest;
+use super::provenance;
+use super::report;
+use super::validate_command;
+use crate::conformance::Dialect;
+use crate::conformance::TestResult;
+use crate::diagnostic::Code;
+use crate::diagnostic::Diagnostic;
+use crate::engine::Engine;
+use crate::engine::OutputSource;
+use crate::sink;
+use crate::wdl::Construct;
+
+/// An engine the tests are run against within a matrix.
+///
+/// Each setting that is provided replaces the setting of the run; the rest
+/// are filled in from the engine preset, if any.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Entry {
+    /// The name of the engine within the matrix and the files of the run.
+    name: String,
+    /// The command template.
+    command: Option<String>,
+    /// See `--engine`.
+    engine: Option<Engine>,
+    /// See `--engine-manifest`.
+    engine_manifest: Option<PathBuf>,
+    /// See `--workflow-target-args`.
+    workflow_target_args: Option<String>,
+    /// See `--task-target-args`.
+    task_target_args: Option<String>,
+    /// See `--output-selector`.
+    output_selector: Option<Vec<String>>,
+    /// See `--expected-selector`.
+    expected_selector: Option<Vec<String>>,
+    /// See `--outputs-from`.
+    outputs_from: Option<OutputSource>,
+    /// See `--output-file`.
+    output_file: Option<String>,
+    /// See `--engine-version`.
+    engine_version: Option<String>,
+    /// See `--baseline`.
+    baseline: Option<PathBuf>,
+    /// See `--preprocess-cmd`.
+    preprocess_cmd: Option<String>,
+    /// See `--target-dialect`.
+    target_dialect: Option<Dialect>,
+    /// See `--unsupported-constructs`.
+    unsupported_constructs: Option<Vec<Construct>>,
+    /// Variables set for this engine in addition to those of `--env`,
+    /// replacing any with the same name.
+    env: Option<BTreeMap<String, String>>,
+}
+
+impl Entry {
+    /// Resolves the relative paths of the entry against the directory
+    /// containing the config file it was read from.
+    pub fn resolve(&mut self, dir: &Path) {
+        for path in [&mut self.baseline, &mut self.engine_manifest]
+            .into_iter()
+            .flatten()
+        {
+            *path = dir.join(&*path);
+        }
+    }
+
+    /// Replaces the settings of a run with those of the engine.
+    ///
+    /// The files and directories written by the run are given the name of
+    /// the engine so that the runs of each engine do not overwrite each other.
+    fn apply(&self, args: &mut Args) {
+        let record = |args: &mut Args, name: &str, value: &str| {
+            provenance::record(&mut args.settings, name, value, provenance::Source::Matrix)
+        };
+
+        args.matrix.clear();
+        args.command = self.command.clone();
+        args.engine = self.engine;
+
+        if let Some(command) = &self.command {
+            record(args, "<COMMAND>", command);
+        }
+
+        if let Some(engine) = self.engine {
+            record(args, "--engine", &engine.to_string());
+        }
+
+        if let Some(value) = &self.engine_manifest {
+            record(args, "--engine-manifest", &value.display().to_string());
+            args.engine_manifest = Some(value.clone());
+        }
+
+        if let Some(value) = &self.workflow_target_args {
+            record(args, "--workflow-target-args", value);
+            args.workflow_target_args = value.clone();
+        }
+
+        if let Some(value) = &self.task_target_args {
+            record(args, "--task-target-args", value);
+            args.task_target_args = value.clone();
+        }
+
+        if let Some(value) = &self.output_selector {
+            record(args, "--output-selector", &value.join(","));
+            args.output_selector = value.clone();
+        }
+
+        if let Some(value) = &self.expected_selector {
+            record(args, "--expected-selector", &value.join(","));
+            args.expected_selector = value.clone();
+        }
+
+        if let Some(value) = self.outputs_from {
+            record(args, "--outputs-from", &value.to_string());
+            args.outputs_from = Some(value);
+        }
+
+        if let Some(value) = &self.output_file {
+            record(args, "--output-file", value);
+            args.output_file = Some(value.clone());
+        }
+
+        if let Some(value) = &self.engine_version {
+            record(args, "--engine-version", value);
+            args.engine_version = Some(value.clone());
+        }
+
+        if let Some(value) = &self.baseline {
+            record(args, "--baseline", &value.display().to_string());
+            args.baseline = Some(value.clone());
+        }
+
+        if let Some(value) = &self.preprocess_cmd {
+            record(args, "--preprocess-cmd", value);
+            args.preprocess_cmd = Some(value.clone());
+        }
+
+        if let Some(value) = self.target_dialect {
+            record(args, "--target-dialect", &value.to_string());
+            args.target_dialect = Some(value);
+        }
+
+        if let Some(value) = &self.unsupported_constructs {
+            let constructs = value.iter().map(ToString::to_string).collect::<Vec<_>>();
+            record(args, "--unsupported-constructs", &constructs.join(","));
+            args.unsupported_constructs = value.clone();
+        }
+
+        if let Some(value) = &self.env {
+            args.env.retain(|(key, _)| !value.contains_key(key));
+            args.env.extend(value.clone());
+            let vars = args
+                .env
+                .iter()
+                .map(|(key, _)| environment::redact(key))
+                .collect::<Vec<_>>();
+            record(args, "--env", &vars.join(","));
+        }
+
+        for path in [
+            &mut args.results_file,
+            &mut args.markdown_report,
+            &mut args.bundle_failures,
+            &mut args.html_index,
+            &mut args.state_file,
+            &mut args.events,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(args.sinks.iter_mut().filter_map(sink::Config::path_mut))
+        // Events written to stdout are told apart by their run identifier
+        .filter(|path| path.as_os_str() != "-")
+        {
+            *path = with_name(path, &self.name);
+        }
+
+        for dir in [
+            &mut args.tier_badges,
+            &mut args.artifacts_dir,
+            &mut args.conformance_test_dir,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *dir = dir.join(&self.name);
+        }
+    }
+}
+
+impl FromStr for Entry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((name, command)) = s.split_once('=') {
+            if name.is_empty() || command.is_empty() {
+                return Err(format!("`{s}` must have both a name and a command"));
+            }
+
+            return Ok(Self {
+                name: name.to_string(),
+                command: Some(command.to_string()),
+                ..Default::default()
+            });
+        }
+
+        let engine = Engine::from_str(s, true)
+            .map_err(|_| format!("`{s}` is neither an engine preset nor `NAME=COMMAND`"))?;
+
+        Ok(Self {
+            name: engine.to_string(),
+            engine: Some(engine),
+            ..Default::default()
+        })
+    }
+}
+
+/// Adds the name of an engine to the name of a file, before its extensions
+/// (e.g., `results.json` becomes `results.sprocket.json`).
+fn with_name(path: &Path, name: &str) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let file_name = match file_name.char_indices().skip(1).find(|(_, c)| *c == '.') {
+        Some((i, _)) => format!("{}.{name}{}", &file_name[..i], &file_name[i..]),
+        None => format!("{file_name}.{name}"),
+    };
+
+    path.with_file_name(file_name)
+}
+
+/// The results of each test for one engine of a matrix, in specification
+/// order.
+type Column = (String, Vec<(String, TestResult)>);
+
+/// Runs the tests against every engine of the matrix and prints the
+/// conformance matrix.
+pub fn main(args: Args, run_id: String) -> Result<()> {
+    if args.update_baseline {
+        return Err(Diagnostic::new(
+            Code::ConflictingOptions,
+            "`--update-baseline` cannot be used with `--matrix`",
+        )
+        .with_hint("update the baseline of each engine with a separate run")
+        .into());
+    }
+
+    let mut names = BTreeSet::new();
+    if let Some(entry) = args.matrix.iter().find(|e| !names.insert(e.name.as_str())) {
+        return Err(Diagnostic::new(
+            Code::ConflictingOptions,
+            format!(
+                "engine `{}` appears in the matrix more than once",
+                entry.name
+            ),
+        )
+        .with_hint("give each engine a unique name with `NAME=COMMAND`")
+        .into());
+    }
+
+    let mut specification = None;
+    let mut columns = Vec::new();
+    let mut unexpected_failures = 0;
+
+    for entry in &args.matrix {
+        let span = tracing::error_span!("engine", name = %entry.name);
+        let _span = span.enter();
+
+        let mut engine_args = args.clone();
+        entry.apply(&mut engine_args);
+        if let Some(path) = engine_args.engine_manifest.clone() {
+            manifest::apply(&path, &mut engine_args)?;
+        }
+        engine_args.apply_preset();
+
+        if args.explain_config {
+            println!("[{}]", entry.name);
+            println!("{}\n", provenance::render(&engine_args.settings));
+            validate_command(&engine_args)?;
+            continue;
+        }
+
+        eprintln!("\n{}", "=".repeat(60));
+        eprintln!("Engine: {}", entry.name);
+        eprintln!("{}", "=".repeat(60));
+
+        let run = execute(engine_args, run_id.clone(), &mut specification)?;
+        unexpected_failures += report(&run, false)?;
+
+        columns.push((
+            entry.name.clone(),
+            run.results
+                .iter()
+                .map(|p| (p.name.clone(), p.result.clone()))
+                .collect(),
+        ));
+    }
+
+    if args.explain_config {
+        return Ok(());
+    }
+
+    print!("{}", render(&columns));
+
+    if args.strict && unexpected_failures > 0 {
+        bail!("{unexpected_failures} test(s) failed");
+    }
+
+    Ok(())
+}
+
+/// Renders the conformance matrix as a Markdown table with a row for each
+/// test and a column for each engine.
+fn render(columns: &[Column]) -> String {
+    let mut names = Vec::new();
+    let mut seen = BTreeSet::new();
+    for (name, _) in columns.iter().flat_map(|(_, results)| results) {
+        if seen.insert(name.as_str()) {
+            names.push(name.as_str());
+        }
+    }
+
+    let lookups = columns
+        .iter()
+        .map(|(_, results)| {
+            results
+                .iter()
+                .map(|(name, result)| (name.as_str(), result))
+                .collect::<BTreeMap<_, _>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut table = String::from("| Test |");
+    let mut divider = String::from("| --- |");
+    for (engine, _) in columns {
+        // SAFETY: writing to a `String` never fails.
+        write!(table, " {engine} |").unwrap();
+        divider.push_str(" :-: |");
+    }
+    table.push('\n');
+    table.push_str(&divider);
+    table.push('\n');
+
+    for name in names {
+        // SAFETY: writing to a `String` never fails.
+        write!(table, "| `{name}` |").unwrap();
+        for lookup in &lookups {
+            let cell = match lookup.get(name) {
+                Some(TestResult::Passed) => "passed",
+                Some(TestResult::Failed(_)) => "failed",
+                Some(TestResult::Skipped(_)) => "skipped",
+                None => "-",
+            };
+
+            // SAFETY: writing to a `String` never fails.
+            write!(table, " {cell} |").unwrap();
+        }
+        table.push('\n');
+    }
+
+    table.push_str("| **Passed** |");
+    for lookup in &lookups {
+        let passed = lookup.values().filter(|r| r.is_passed()).count();
+        let failed = lookup.values().filter(|r| r.is_failed()).count();
+
+        // SAFETY: writing to a `String` never fails.
+        write!(table, " {passed}/{} |", passed + failed).unwrap();
+    }
+    table.push('\n');
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::SkipReason;
+
+    #[test]
+    fn entries() {
+        let entry = "miniwdl".parse::<Entry>().unwrap();
+        assert_eq!(entry.name, "miniwdl");
+        assert_eq!(entry.engine, Some(Engine::Miniwdl));
+
+        let entry = "mine=engine run ~{path}".parse::<Entry>().unwrap();
+        assert_eq!(entry.name, "mine");
+        assert_eq!(entry.command.as_deref(), Some("engine run ~{path}"));
+
+        assert!("unknown".parse::<Entry>().is_err());
+        assert!("=engine run".parse::<Entry>().is_err());
+
+        assert_eq!(
+            with_name(Path::new("out/results.json"), "toil"),
+            Path::new("out/results.toil.json")
+        );
+        assert_eq!(
+            with_name(Path::new("failures.tar.gz"), "toil"),
+            Path::new("failures.toil.tar.gz")
+        );
+        assert_eq!(
+            with_name(Path::new("results"), "toil"),
+            Path::new("results.toil")
+        );
+    }
+
+    #[test]
+    fn render_matrix() {
+        let columns = vec![
+            (
+                String::from("a"),
+                vec![
+                    (String::from("one.wdl"), TestResult::Passed),
+                    (
+                        String::from("two.wdl"),
+                        TestResult::Failed(FailureReason::NoOutput),
+                    ),
+                ],
+            ),
+            (
+                String::from("b"),
+                vec![
+                    (String::from("one.wdl"), TestResult::Passed),
+                    (
+                        String::from("two.wdl"),
+                        TestResult::Skipped(SkipReason::Ignored),
+                    ),
+                    (String::from("three.wdl"), TestResult::Passed),
+                ],
+            ),
+        ];
+
+        assert_eq!(
+            render(&columns),
+            "| Test | a | b |\n| --- | :-: | :-: |\n| `one.wdl` | passed | passed |\n| `two.wdl` | \
+             failed | skipped |\n| `three.wdl` | - | passed |\n| **Passed** | 1/2 | 2/2 |\n"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Tracking where each effective setting of a test run came from.
+
+use std::fmt;
+
+use clap::ArgMatches;
+use clap::CommandFactory as _;
+use clap::parser::ValueSource;
+
+use super::Args;
+use super::environment;
+use crate::engine::Engine;
+
+/// Where the effective value of a setting came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// The value is the default.
+    Default,
+    /// The value was read from an environment variable.
+    Environment,
+    /// The value was provided on the command line.
+    CommandLine,
+    /// The value was read from the config file.
+    ConfigFile,
+    /// The value was read from an engine manifest.
+    Manifest,
+    /// The value was filled in from the preset of an engine.
+    Preset(Engine),
+    /// The value was set by an engine of a matrix.
+    Matrix,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::Environment => write!(f, "environment"),
+            Source::CommandLine => write!(f, "command line"),
+            Source::ConfigFile => write!(f, "config file"),
+            Source::Manifest => write!(f, "engine manifest"),
+            Source::Preset(engine) => write!(f, "`{engine}` preset"),
+            Source::Matrix => write!(f, "matrix"),
+        }
+    }
+}
+
+/// The effective value of a single setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    /// The name of the setting as it is written on the command line.
+    name: String,
+
+    /// The effective value of the setting.
+    value: String,
+
+    /// Where the value came from.
+    source: Source,
+}
+
+/// Collects the effective value and source of every setting that has a value.
+pub fn collect(matches: &ArgMatches) -> Vec<Setting> {
+    let command = Args::command();
+
+    command
+        .get_arguments()
+        .filter_map(|arg| {
+            let id = arg.get_id().as_str();
+            let source = match matches.value_source(id)? {
+                ValueSource::DefaultValue => Source::Default,
+                ValueSource::EnvVariable => Source::Environment,
+                _ => Source::CommandLine,
+            };
+
+            let value = matches
+                .get_raw(id)?
+                .map(|value| match id {
+                    "env" => environment::redact(&value.to_string_lossy()),
+                    _ => value.to_string_lossy().into_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let name = match arg.get_long() {
+                Some(long) => format!("--{long}"),
+                None => format!("<{}>", id.to_uppercase()),
+            };
+
+            Some(Setting {
+                name,
+                value,
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Records the effective value and source of a setting, replacing any
+/// previously collected value.
+pub fn record(settings: &mut Vec<Setting>, name: &str, value: &str, source: Source) {
+    let setting = Setting {
+        name: name.to_string(),
+        value: value.to_string(),
+        source,
+    };
+
+    match settings.iter_mut().find(|s| s.name == name) {
+        Some(existing) => *existing = setting,
+        None => settings.push(setting),
+    }
+}
+
+/// Returns whether a setting was provided explicitly, either on the command
+/// line or through an environment variable.
+pub fn is_explicit(settings: &[Setting], name: &str) -> bool {
+    settings
+        .iter()
+        .any(|s| s.name == name && matches!(s.source, Source::CommandLine | Source::Environment))
+}
+
+/// Returns whether a setting was provided from any source other than its
+/// default.
+pub fn is_set(settings: &[Setting], name: &str) -> bool {
+    settings
+        .iter()
+        .any(|s| s.name == name && s.source != Source::Default)
+}
+
+/// Renders the effective settings with where each value came from.
+pub fn render(settings: &[Setting]) -> String {
+    let width = settings
+        .iter()
+        .map(|setting| setting.name.len())
+        .max()
+        .unwrap_or_default();
+
+    settings
+        .iter()
+        .map(|setting| {
+            format!(
+                "{name:<width$}  {value:?} ({source})",
+                name = setting.name,
+                value = setting.value,
+                source = setting.source
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::FromArgMatches as _;
+
+    use super::*;
+    use crate::engine::OutputSource;
+
+    #[test]
+    fn sources() {
+        let matches = Args::command()
+            .try_get_matches_from([
+                "test",
+                "--branch",
+                "wdl-1.1",
+                "--exclude",
+                "a,b",
+                "engine run ~{path} ~{input} ~{output}",
+            ])
+            .unwrap();
+        let settings = collect(&matches);
+
+        let find = |name: &str| settings.iter().find(|s| s.name == name).unwrap();
+        assert_eq!(find("--branch").value, "wdl-1.1");
+        assert_eq!(find("--branch").source, Source::CommandLine);
+        assert_eq!(find("--exclude").value, "a,b");
+        assert_eq!(find("--label").source, Source::Default);
+        assert_eq!(find("<COMMAND>").source, Source::CommandLine);
+        assert!(settings.iter().all(|s| s.name != "--output-file"));
+
+        let matches = Args::command()
+            .try_get_matches_from(["test", "--engine", "miniwdl"])
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches)
+            .unwrap()
+            .with_settings(&matches);
+        args.apply_preset();
+
+        assert_eq!(args.command_template(), "miniwdl run ~{path} -i ~{input}");
+        assert_eq!(args.output_source(), OutputSource::Auto);
+        assert_eq!(args.task_target_args, "--task ~{target}");
+
+        let find = |name: &str| args.settings.iter().find(|s| s.name == name).unwrap();
+        assert_eq!(find("<COMMAND>").source, Source::Preset(Engine::Miniwdl));
+        assert_eq!(find("--output-selector").value, ".outputs");
+        assert_eq!(find("--branch").source, Source::Default);
+    }
+}
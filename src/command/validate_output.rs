@@ -0,0 +1,107 @@
+//! A subcommand to validate outputs without running a test.
+
+use std::io::IsTerminal as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use anyhow::bail;
+use clap::Parser;
+use serde::de::DeserializeOwned;
+
+use crate::conformance::FailureReason;
+use crate::conformance::FloatTolerance;
+use crate::conformance::test::Config;
+use crate::conformance::test::validation;
+use crate::conformance::test::validation::KeyMap;
+use crate::conformance::test::validation::Options as ValidationOptions;
+
+/// Validates actual outputs against expected outputs.
+///
+/// Only the validation performed by `spectool test` is run, which is useful
+/// for debugging comparison behavior without re-running a workflow.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// Path to the expected outputs.
+    #[arg(long, value_name = "PATH")]
+    expected: PathBuf,
+
+    /// Path to the actual outputs reported by the engine.
+    #[arg(long, value_name = "PATH")]
+    actual: PathBuf,
+
+    /// Path to a test config (e.g., with `exclude_outputs`).
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Strip the `<target>.` prefix from output keys before validation.
+    #[arg(long, default_value_t = false)]
+    strip_output_prefix: bool,
+
+    /// The tolerance within which floating point outputs are considered equal
+    /// (e.g., `1e-6` or `abs=1e-6,rel=1e-9`).
+    ///
+    /// A `float_tolerance` in the config takes precedence.
+    #[arg(long, value_name = "TOLERANCE")]
+    float_tolerance: Option<FloatTolerance>,
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let expected: serde_json::Value = read_json(&args.expected)?;
+    let actual: serde_json::Value = read_json(&args.actual)?;
+    let config: Config = match &args.config {
+        Some(path) => read_json(path)?,
+        None => Config::default(),
+    };
+
+    let options = ValidationOptions::builder()
+        .exclude(config.exclude_outputs().to_vec())
+        .forbidden(config.forbidden_outputs().to_vec())
+        .unordered(config.unordered_outputs().to_vec())
+        .patterns(config.pattern_outputs().to_vec())
+        .key_map(
+            KeyMap::builder()
+                .renames(config.rename_outputs().clone())
+                .strip_prefix(args.strip_output_prefix)
+                .build(),
+        )
+        .compare_files(config.compare_files())
+        .maybe_float_tolerance(config.float_tolerance().or(args.float_tolerance))
+        .build();
+
+    if let Err(e) = validation::validate(&expected, &actual, &options) {
+        if let Some(diff) = e
+            .downcast_ref::<validation::Mismatch>()
+            .and_then(|mismatch| mismatch.diff())
+        {
+            eprint!(
+                "{}",
+                validation::diff::render(
+                    diff,
+                    crate::diagnostic::use_color(std::io::stderr().is_terminal()),
+                )
+            );
+        }
+
+        bail!(
+            "{}",
+            FailureReason::OutputMismatch {
+                details: e.to_string(),
+                diff: None,
+                keys: Vec::new(),
+            }
+        );
+    }
+
+    println!("outputs match");
+    Ok(())
+}
+
+/// Reads and parses a JSON file.
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("reading `{}`", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("parsing `{}`", path.display()))
+}
@@ -0,0 +1,207 @@
+//! A subcommand to check the conformance tests within a specification for
+//! structural problems.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::bail;
+use clap::Parser;
+
+use crate::conformance::JsonSyntax;
+use crate::conformance::Resources;
+use crate::conformance::Tests;
+use crate::conformance::test::near_misses;
+
+/// The file name of the specification.
+const SPEC_FILE_NAME: &str = "SPEC.md";
+
+/// Checks the conformance tests within the specification for structural
+/// problems without running them.
+///
+/// Reports duplicated test file names, blocks that cannot be parsed, tests
+/// whose target cannot be inferred, input keys that do not name an input of
+/// the target, and `<details>` blocks that look like tests but do not match
+/// the expected structure.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The branch to check out.
+    #[arg(short, long, default_value = "wdl-1.2")]
+    branch: String,
+
+    /// The git repository URL to clone.
+    #[arg(long, default_value = "https://github.com/openwdl/wdl.git")]
+    repository_url: String,
+
+    /// A directory that contains the specification repository.
+    #[arg(short, long)]
+    specification_dir: Option<PathBuf>,
+
+    /// Accept comments, trailing commas, and other JSON5 syntax within the
+    /// input, output, and config blocks of each test.
+    #[arg(long, default_value_t = false)]
+    lenient_json: bool,
+}
+
+/// The main method.
+pub fn main(args: Args) -> Result<()> {
+    let contents = super::test::read_specification(
+        &args.branch,
+        &args.repository_url,
+        args.specification_dir.clone(),
+    )?;
+
+    let findings = check(
+        &contents,
+        if args.lenient_json {
+            JsonSyntax::Lenient
+        } else {
+            JsonSyntax::Strict
+        },
+    )?;
+
+    for warning in &findings.warnings {
+        eprintln!("warning: {warning}");
+    }
+
+    let problems = findings.problems;
+    for problem in &problems {
+        println!("{problem}\n");
+    }
+
+    if !problems.is_empty() {
+        bail!("found {} problem(s) in `{SPEC_FILE_NAME}`", problems.len());
+    }
+
+    eprintln!("no problems found in `{SPEC_FILE_NAME}`");
+    Ok(())
+}
+
+/// The findings of checking a specification.
+#[derive(Debug, Default)]
+struct Findings {
+    /// Descriptions of the problems that must be fixed.
+    problems: Vec<String>,
+
+    /// Descriptions of things that may be unintended.
+    warnings: Vec<String>,
+}
+
+/// Checks a specification.
+fn check(contents: &str, syntax: JsonSyntax) -> Result<Findings> {
+    let tests = Tests::compile_with_syntax(contents, syntax)?;
+    let resources = Resources::compile(contents)?;
+    let mut problems = Vec::new();
+
+    for line in near_misses(contents) {
+        problems.push(format!(
+            "{SPEC_FILE_NAME}:{line}: `<details>` block contains an example but is not a \
+             conformance test; check that it has a `<summary>` with `Example: <name>.wdl` and a \
+             `wdl` code block"
+        ));
+    }
+
+    for error in tests.errors() {
+        problems.push(error.to_string());
+    }
+
+    let mut names = BTreeMap::<&str, usize>::new();
+    for test in tests.tests() {
+        *names.entry(test.file_name()).or_default() += 1;
+    }
+
+    for (name, count) in names.into_iter().filter(|(_, count)| *count > 1) {
+        problems.push(format!("test file name `{name}` is used by {count} tests"));
+    }
+
+    for test in tests.tests() {
+        let mut test = test.clone();
+
+        if let Err(e) = test.infer_and_validate_target() {
+            problems.push(format!("{e:#}"));
+            continue;
+        }
+
+        if let Err(e) = test.validate_input_keys() {
+            problems.push(format!("{e:#}"));
+        }
+    }
+
+    let mut references = BTreeSet::new();
+    for test in tests.tests() {
+        for reference in test.data_references() {
+            if !resources.covers(&reference) {
+                problems.push(format!(
+                    "test `{}` refers to `data/{reference}`, which is not a resource in the \
+                     specification",
+                    test.file_name()
+                ));
+            }
+
+            references.insert(reference);
+        }
+    }
+
+    let warnings = resources
+        .unreferenced(&references)
+        .map(|resource| {
+            format!(
+                "resource `{}` is not referred to by any test",
+                resource.filename()
+            )
+        })
+        .collect();
+
+    Ok(Findings { problems, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems() {
+        let test = |name: &str, input: &str| {
+            format!(
+                "<details>\n<summary>\nExample: {name}.wdl\n\n```wdl\nversion 1.2\n\ntask a {{\n  \
+                 input {{\n    Int x\n  }}\n  command <<<>>>\n}}\n\ntask b {{\n  command \
+                 <<<>>>\n}}\n```\n</summary>\n<p>\nExample input:\n\n```json\n{input}\n```\n</p>\n\
+                 </details>\n\n"
+            )
+        };
+
+        let resource = |name: &str| {
+            format!(
+                "<details>\n<summary>\nResource: {name}\n\n```\nhello\n```\n</summary>\n\
+                 </details>\n\n"
+            )
+        };
+
+        let spec = [
+            test("dup", r#"{"a.x": 1}"#),
+            test("dup", r#"{"a.x": 1}"#),
+            test("unknown", r#"{"c.x": 1}"#),
+            test("wrong_key", r#"{"a.y": 1}"#),
+            test(
+                "data",
+                r#"{"a.x": 1, "a.f": ["data/hello.txt", "data/missing.txt"]}"#,
+            ),
+            resource("hello.txt"),
+            resource("unused.txt"),
+        ]
+        .concat();
+
+        let Findings { problems, warnings } = check(&spec, JsonSyntax::Strict).unwrap();
+        assert_eq!(problems.len(), 5, "{problems:#?}");
+        assert!(problems[0].contains("`dup.wdl` is used by 2 tests"));
+        assert!(problems[1].contains("input prefix `c`"));
+        assert!(problems[2].contains("input key `a.y`"));
+        assert!(problems[3].contains("input key `a.f`"));
+        assert!(problems[4].contains("`data/missing.txt`, which is not a resource"));
+        assert_eq!(
+            warnings,
+            ["resource `unused.txt` is not referred to by any test"]
+        );
+    }
+}
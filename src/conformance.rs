@@ -6,10 +6,27 @@ pub mod test;
 pub use resource::Resource;
 pub use resource::Resources;
 pub use test::Capability;
+pub use test::Dialect;
+pub use test::ExpectedFile;
+pub use test::Explanation;
+pub use test::FailureCategory;
+pub use test::FailureCode;
 pub use test::FailureReason;
+pub use test::FileComparison;
+pub use test::FloatTolerance;
+pub use test::InferenceOutcome;
+pub use test::JsonSyntax;
+pub use test::Layout;
+pub use test::Lines;
+pub use test::ParseError;
+pub use test::Pattern;
 pub use test::ReturnCode;
+pub use test::Shard;
 pub use test::SkipReason;
+pub use test::Tag;
 pub use test::Target;
 pub use test::Test;
+pub use test::TestFilter;
 pub use test::TestResult;
 pub use test::Tests;
+pub use test::Tier;
@@ -1,5 +1,6 @@
 //! Conformance test resource parsing from within `SPEC.md`.
 
+use std::collections::BTreeSet;
 use std::sync::LazyLock;
 
 use anyhow::Result;
@@ -22,38 +23,46 @@ static RESOURCE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(PATTERN).unwrap()
 });
 
-/// A resource file.
+/// A resource file, borrowed from the specification it was compiled from.
 #[derive(Builder, Debug)]
 #[builder(builder_type = Builder)]
-pub struct Resource {
+pub struct Resource<'a> {
     /// The file name.
-    filename: String,
+    filename: &'a str,
 
     /// The source of the resource file.
-    src: String,
+    src: &'a str,
 }
 
-impl Resource {
+impl<'a> Resource<'a> {
     /// Gets the file name.
-    pub fn filename(&self) -> &str {
-        &self.filename
+    pub fn filename(&self) -> &'a str {
+        self.filename
     }
 
     /// Gets the source of the resource file.
-    pub fn src(&self) -> &str {
-        &self.src
+    pub fn src(&self) -> &'a str {
+        self.src
+    }
+
+    /// Returns whether a path within the `data` directory refers to the
+    /// resource or to a directory containing it.
+    pub fn covered_by(&self, path: &str) -> bool {
+        self.filename
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
     }
 }
 
 /// A set of resource files.
 #[derive(Debug)]
-pub struct Resources(Vec<Resource>);
+pub struct Resources<'a>(Vec<Resource<'a>>);
 
-impl Resources {
+impl<'a> Resources<'a> {
     /// Turns a markdown specification into a set of resources.
-    pub fn compile<S: AsRef<str>>(contents: S) -> Result<Self> {
-        let contents = contents.as_ref();
-
+    ///
+    /// The resources borrow from the specification rather than copying it.
+    pub fn compile(contents: &'a str) -> Result<Self> {
         RESOURCE_REGEX
             .captures_iter(contents)
             .map(build_resource)
@@ -61,28 +70,45 @@ impl Resources {
     }
 }
 
-impl Resources {
+impl<'a> Resources<'a> {
     /// Generates an iterator for the resources.
-    pub fn iter(&self) -> impl Iterator<Item = &Resource> {
+    pub fn iter(&self) -> impl Iterator<Item = &Resource<'a>> {
         self.0.iter()
     }
+
+    /// Returns whether a path within the `data` directory is a resource or a
+    /// directory containing one.
+    pub fn covers(&self, path: &str) -> bool {
+        self.0.iter().any(|resource| resource.covered_by(path))
+    }
+
+    /// Gets the resources that none of the paths within the `data` directory
+    /// refer to.
+    pub fn unreferenced(
+        &self,
+        references: &BTreeSet<String>,
+    ) -> impl Iterator<Item = &Resource<'a>> {
+        self.0
+            .iter()
+            .filter(|resource| !references.iter().any(|r| resource.covered_by(r)))
+    }
 }
 
-impl FromIterator<Resource> for Resources {
-    fn from_iter<T: IntoIterator<Item = Resource>>(iter: T) -> Self {
+impl<'a> FromIterator<Resource<'a>> for Resources<'a> {
+    fn from_iter<T: IntoIterator<Item = Resource<'a>>>(iter: T) -> Self {
         Self(iter.into_iter().collect())
     }
 }
 
 /// Builds a resource from a set of captures.
-fn build_resource(captures: Captures<'_>) -> Result<Resource> {
+fn build_resource(captures: Captures<'_>) -> Result<Resource<'_>> {
     let filename = required_string(&captures, 1, "filename")?;
     let src = required_string(&captures, 2, "source")?;
     Ok(Resource::builder().filename(filename).src(src).build())
 }
 
 /// Parses a _required_ group within a test.
-fn required_string(captures: &Captures<'_>, index: usize, name: &str) -> Result<String> {
+fn required_string<'a>(captures: &Captures<'a>, index: usize, name: &str) -> Result<&'a str> {
     captures
         .get(index)
         .ok_or_else(|| {
@@ -92,7 +118,7 @@ fn required_string(captures: &Captures<'_>, index: usize, name: &str) -> Result<
                 captures.get(0).unwrap().as_str()
             )
         })
-        .map(|v| v.as_str().to_owned())
+        .map(|v| v.as_str())
 }
 
 #[cfg(test)]
@@ -177,6 +203,11 @@ Resource: empty.txt
 
         assert_eq!(items[3].filename(), "empty.txt");
         assert_eq!(items[3].src(), "\n");
+
+        assert!(resources.covers("cities.txt"));
+        assert!(resources.covers("data/nested"));
+        assert!(!resources.covers("data/nest"));
+        assert!(!resources.covers("missing.txt"));
     }
 
     #[test]
@@ -1,31 +1,56 @@
 //! Conformance test parsing from within `SPEC.md`.
 
+use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
-use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
 use anyhow::bail;
 use bon::Builder;
+use rayon::prelude::*;
 use regex::Captures;
+use regex::Match;
 use regex::Regex;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
+use sha2::Digest as _;
+use sha2::Sha256;
+use tracing::debug;
 
 use crate::wdl;
+use parse::BlockError;
 
 mod config;
+pub mod dialect;
+mod filter;
+pub mod inference;
+mod parse;
 pub mod result;
 pub mod runner;
 pub mod validation;
 
 pub use config::Capability;
 pub use config::Config;
+pub use config::ExpectedFile;
+pub use config::FileComparison;
+pub use config::FloatTolerance;
 pub use config::ReturnCode;
 pub use config::Tag;
+pub use config::Tier;
+pub use dialect::Dialect;
+pub use filter::Pattern;
+pub use filter::Shard;
+pub use filter::TestFilter;
+pub use inference::InferenceOutcome;
+pub use parse::Lines;
+pub use parse::ParseError;
+pub use result::Explanation;
+pub use result::FailureCategory;
+pub use result::FailureCode;
 pub use result::FailureReason;
 pub use result::SkipReason;
 pub use result::TestResult;
@@ -52,6 +77,55 @@ static CONFORMANCE_TEST_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(PATTERN).unwrap()
 });
 
+/// The syntax accepted within the JSON blocks of a conformance test.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsonSyntax {
+    /// Blocks must be strict JSON.
+    #[default]
+    Strict,
+    /// Blocks may use JSON5 syntax, which includes comments, trailing
+    /// commas, and unquoted keys.
+    Lenient,
+}
+
+/// How compiled tests are laid out within the conformance tests directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// Every test is written to `<file-name>`.
+    #[default]
+    Flat,
+    /// Every test is written to `<section-slug>/<file-name>`, so that
+    /// sections may reuse the file names of examples in other sections.
+    Sections,
+}
+
+impl JsonSyntax {
+    /// Parses a JSON block with this syntax.
+    fn parse<D: DeserializeOwned>(self, s: &str) -> Result<D, BlockError> {
+        Ok(match self {
+            JsonSyntax::Strict => serde_json::from_str(s)?,
+            JsonSyntax::Lenient => json5::from_str(s)?,
+        })
+    }
+}
+
+/// The regex for any `<details>` block within the specification.
+static DETAILS_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<details>.*?</details>").unwrap());
+
+/// The regex for a markdown heading.
+static HEADING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^#{1,6}\s+(.+?)\s*#*\s*$").unwrap());
+
+/// The regex for the version statement of a WDL document.
+static VERSION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*version\s+(\S+)").unwrap());
+
+/// The regex for a string that is a path within the `data` directory.
+static DATA_REFERENCE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^data/([^"\s]+?)/*$"#).unwrap());
+
 /// A conformance test.
 #[derive(Builder, Clone, Debug)]
 #[builder(builder_type = Builder)]
@@ -62,6 +136,19 @@ pub struct Test {
     /// The file name of the test.
     file_name: String,
 
+    /// The position of the test within the specification.
+    #[builder(default)]
+    index: usize,
+
+    /// The heading of the specification section containing the test.
+    section: Option<String>,
+
+    /// The anchor of the specification section containing the test.
+    anchor: Option<String>,
+
+    /// The lines the test occupies within the specification.
+    lines: Option<Lines>,
+
     /// The source.
     src: String,
 
@@ -76,6 +163,24 @@ pub struct Test {
 
     /// The inferred or validated target workflow/task.
     inferred_target: Option<wdl::Target>,
+
+    /// Whether the source was rewritten before the test was written.
+    #[builder(default)]
+    preprocessed: bool,
+
+    /// The features the source uses that could not be transformed to the
+    /// target dialect.
+    #[builder(default)]
+    untransformable: Vec<String>,
+
+    /// The constructs the source uses that the engine does not support.
+    #[builder(default)]
+    unsupported_constructs: Vec<wdl::Construct>,
+
+    /// The paths within the `data` directory the test refers to that are not
+    /// resources in the specification.
+    #[builder(default)]
+    undeclared_data: Vec<String>,
 }
 
 impl Test {
@@ -89,6 +194,70 @@ impl Test {
         &self.file_name
     }
 
+    /// The path of the test relative to the conformance tests directory in a
+    /// layout.
+    ///
+    /// With [`Layout::Sections`], the directory is the anchor of the section
+    /// containing the test without leading hyphens, or `unsectioned` if the
+    /// test is not within a section.
+    pub fn relative_path(&self, layout: Layout) -> PathBuf {
+        match layout {
+            Layout::Flat => PathBuf::from(&self.file_name),
+            Layout::Sections => {
+                let dir = self
+                    .anchor
+                    .as_deref()
+                    .map(|anchor| anchor.trim_start_matches(['#', '-']))
+                    .filter(|dir| !dir.is_empty())
+                    .unwrap_or("unsectioned");
+                Path::new(dir).join(&self.file_name)
+            }
+        }
+    }
+
+    /// The zero-based position of the test within the specification.
+    ///
+    /// Tests that could not be parsed still occupy a position, so the index
+    /// of a test only changes when tests before it are added or removed.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The heading of the specification section containing the test.
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    /// The anchor of the specification section containing the test, as
+    /// generated by GitHub when rendering the specification (e.g., `#-struct`
+    /// for the heading `` 🗑 `struct` ``).
+    pub fn anchor(&self) -> Option<&str> {
+        self.anchor.as_deref()
+    }
+
+    /// The lines the test occupies within the specification, if it was
+    /// compiled from one.
+    pub fn lines(&self) -> Option<Lines> {
+        self.lines
+    }
+
+    /// The conformance tier of the test.
+    pub fn tier(&self) -> Tier {
+        if self.config.capabilities().is_empty() {
+            Tier::Required
+        } else {
+            Tier::Optional
+        }
+    }
+
+    /// The WDL version declared by the test's source.
+    pub fn version(&self) -> Option<&str> {
+        VERSION_REGEX
+            .captures(&self.src)
+            .and_then(|cap| cap.get(1))
+            .map(|m| m.as_str())
+    }
+
     /// The source of the test.
     pub fn src(&self) -> &str {
         &self.src
@@ -109,11 +278,188 @@ impl Test {
         &self.config
     }
 
+    /// Computes a stable hash of the contents of the test.
+    ///
+    /// The hash is the lowercase hexadecimal SHA-256 digest of the following
+    /// fields, in order, each preceded by its length in bytes as a big-endian
+    /// `u64`:
+    ///
+    /// 1. the version of the scheme (`spectool-test-v1`),
+    /// 2. the WDL source,
+    /// 3. the input as compact JSON with sorted keys (`null` if absent),
+    /// 4. the expected output in the same form, and
+    /// 5. the config in the same form, omitting fields with default values.
+    ///
+    /// The file name and path are not included, so renaming a test does not
+    /// change its hash, and adding a config field with a default value does
+    /// not change the hash of existing tests.
+    pub fn content_hash(&self) -> String {
+        let config = match serde_json::to_value(&self.config) {
+            Ok(Value::Object(mut fields)) => {
+                if let Ok(Value::Object(defaults)) = serde_json::to_value(Config::default()) {
+                    fields.retain(|key, value| defaults.get(key) != Some(value));
+                }
+                Value::Object(fields)
+            }
+            Ok(other) => other,
+            Err(_) => Value::Null,
+        };
+
+        let json = |value: Option<&Value>| value.unwrap_or(&Value::Null).to_string();
+        let fields = [
+            String::from("spectool-test-v1"),
+            self.src.clone(),
+            json(self.input.as_ref()),
+            json(self.output.as_ref()),
+            config.to_string(),
+        ];
+
+        let mut hasher = Sha256::new();
+        for field in &fields {
+            hasher.update((field.len() as u64).to_be_bytes());
+            hasher.update(field.as_bytes());
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Gets the paths within the `data` directory that the test refers to,
+    /// relative to that directory.
+    ///
+    /// References are the string values of the input and the string literals
+    /// within the WDL source that are paths starting with `data/`. Comments
+    /// are not scanned, and paths that are computed at runtime (such as
+    /// literals with placeholders) are not found.
+    pub fn data_references(&self) -> BTreeSet<String> {
+        /// Collects the references within the string values of a JSON value.
+        fn collect(value: &Value, references: &mut BTreeSet<String>) {
+            match value {
+                Value::String(s) => references.extend(find(s)),
+                Value::Array(values) => values.iter().for_each(|v| collect(v, references)),
+                Value::Object(map) => map.values().for_each(|v| collect(v, references)),
+                _ => {}
+            }
+        }
+
+        /// Finds the reference a string is, if any.
+        fn find(s: &str) -> Option<String> {
+            if s.contains("~{") || s.contains("${") {
+                return None;
+            }
+
+            DATA_REFERENCE_REGEX
+                .captures(s)
+                .map(|captures| captures[1].to_string())
+        }
+
+        /// Gets the contents of the string literals within a WDL source,
+        /// skipping comments.
+        ///
+        /// Literals are not parsed further, so a literal with a placeholder
+        /// that itself contains a literal is split apart; such literals are
+        /// computed at runtime regardless.
+        fn literals(src: &str) -> Vec<&str> {
+            let mut literals = Vec::new();
+            let mut chars = src.char_indices();
+
+            while let Some((start, quote)) = chars.next() {
+                match quote {
+                    '#' => {
+                        chars.by_ref().find(|(_, c)| *c == '\n');
+                    }
+                    '"' | '\'' => {
+                        let mut escaped = false;
+                        for (end, c) in chars.by_ref() {
+                            match c {
+                                _ if escaped => escaped = false,
+                                '\\' => escaped = true,
+                                // Literals cannot span lines
+                                '\n' => break,
+                                c if c == quote => {
+                                    literals.push(&src[start + 1..end]);
+                                    break;
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            literals
+        }
+
+        let mut references = literals(&self.src)
+            .into_iter()
+            .filter_map(find)
+            .collect::<BTreeSet<_>>();
+        if let Some(input) = &self.input {
+            collect(input, &mut references);
+        }
+
+        references
+    }
+
     /// Sets the path for the test.
     pub fn set_path(&mut self, path: PathBuf) {
         self.path = Some(path);
     }
 
+    /// Whether the source was rewritten before the test was written.
+    ///
+    /// The source returned by [`Test::src`] is always the source from the
+    /// specification.
+    pub fn preprocessed(&self) -> bool {
+        self.preprocessed
+    }
+
+    /// Marks the source as rewritten before the test was written.
+    pub fn set_preprocessed(&mut self) {
+        self.preprocessed = true;
+    }
+
+    /// The features the source uses that could not be transformed to the
+    /// target dialect.
+    ///
+    /// This is empty unless the tests were compiled for a target dialect.
+    pub fn untransformable(&self) -> &[String] {
+        &self.untransformable
+    }
+
+    /// Sets the features the source uses that could not be transformed to
+    /// the target dialect.
+    pub fn set_untransformable(&mut self, features: Vec<String>) {
+        self.untransformable = features;
+    }
+
+    /// The constructs the source uses that the engine does not support.
+    ///
+    /// This is empty unless the tests were compiled with unsupported
+    /// constructs.
+    pub fn unsupported_constructs(&self) -> &[wdl::Construct] {
+        &self.unsupported_constructs
+    }
+
+    /// Sets the constructs the source uses that the engine does not support.
+    pub fn set_unsupported_constructs(&mut self, constructs: Vec<wdl::Construct>) {
+        self.unsupported_constructs = constructs;
+    }
+
+    /// The paths within the `data` directory the test refers to that are not
+    /// resources in the specification, relative to that directory.
+    ///
+    /// This is empty unless the tests were compiled by a runner.
+    pub fn undeclared_data(&self) -> &[String] {
+        &self.undeclared_data
+    }
+
+    /// Sets the paths within the `data` directory the test refers to that are
+    /// not resources in the specification.
+    pub fn set_undeclared_data(&mut self, references: Vec<String>) {
+        self.undeclared_data = references;
+    }
+
     /// Gets the target workflow or task.
     ///
     /// Returns the inferred target if it has been set, otherwise `None`.
@@ -121,199 +467,362 @@ impl Test {
         self.inferred_target.as_ref()
     }
 
+    /// Infers the target workflow/task according to `SPEC.md` rules without
+    /// modifying the test.
+    pub fn infer_target(&self) -> Result<InferenceOutcome> {
+        inference::infer_target(&self.src, self.input.as_ref(), self.config.target())
+    }
+
     /// Infers and validates the target workflow/task name according to
     /// `SPEC.md` rules.
     ///
     /// This method must be called after test construction to determine what to
     /// execute.
     pub fn infer_and_validate_target(&mut self) -> Result<()> {
-        let decls = wdl::parse_wdl_declarations(&self.src).context("parsing WDL declarations")?;
+        let outcome = self.infer_target()?;
 
-        // Check if there's a single unambiguous target.
-        let single_target = decls.single_target();
-
-        // Check if target can be inferred from the input JSON.
-        let input_inferred_target = self.infer_target_from_input(&decls)?;
+        if let Some(conflict) = outcome.conflicts().first() {
+            bail!("{conflict} (test: `{}`)", self.file_name);
+        }
 
-        // Get the explicit target from config.
-        let config_target = self.config.target();
+        self.inferred_target = outcome.target().cloned();
+        Ok(())
+    }
 
-        // Apply validation rules from SPEC.md
-        match (single_target, input_inferred_target.as_ref(), config_target) {
-            // If target can be inferred but `config.target` is provided, error.
-            (Some(_), _, Some(_)) => {
-                bail!(
-                    "target should not be specified in config, as it can be inferred from the WDL directly (test: `{}`)",
-                    self.file_name
-                );
-            }
-            (_, Some(_), Some(_)) => {
-                bail!(
-                    "target should not be specified in config, as it can be inferred from the input JSON directly (test: `{}`)",
-                    self.file_name
-                );
-            }
+    /// Checks that every input key names an input of the target.
+    ///
+    /// Keys must be of the form `<target>.<input>` or, for workflows, the
+    /// nested form `<target>.<call>.<input>`. This does nothing if the target
+    /// has not been inferred or its inputs cannot be determined.
+    pub fn validate_input_keys(&self) -> Result<()> {
+        let (Some(target), Some(input)) = (&self.inferred_target, &self.input) else {
+            return Ok(());
+        };
 
-            // If single target exists, use it.
-            (Some(target), None, None) => {
-                self.inferred_target = Some(target);
-                Ok(())
-            }
+        let Some(obj) = input.as_object() else {
+            return Ok(());
+        };
 
-            // If can infer from input JSON, use that.
-            (None, Some(target), None) => {
-                self.inferred_target = Some(target.clone());
-                Ok(())
-            }
+        let Some(interface) = wdl::parse_interface(&self.src, target) else {
+            return Ok(());
+        };
 
-            // If both single target and input infer the same target, use it.
-            (Some(single), Some(input), None) if single == *input => {
-                self.inferred_target = Some(single);
-                Ok(())
-            }
+        let calls = wdl::call_names(&self.src);
+        // Structs defined in the document or imported under an alias
+        let decls = wdl::parse_wdl_declarations(&self.src);
+        let structs = decls
+            .structs()
+            .iter()
+            .cloned()
+            .chain(
+                decls
+                    .imports()
+                    .iter()
+                    .flat_map(|import| import.aliases().iter().map(|(_, a)| a.clone())),
+            )
+            .collect::<Vec<_>>();
 
-            // If single target and input disagree, error.
-            (Some(single), Some(input), None) => {
+        for key in obj.keys() {
+            let Some(rest) = key.strip_prefix(&format!("{}.", target.name())) else {
                 bail!(
-                    "conflicting target inference: WDL structure suggests `{:?}` but input suggests `{:?}` (test: `{}`)",
-                    single,
-                    input,
+                    "input key `{key}` does not start with the target name `{}` (test: `{}`)",
+                    target.name(),
                     self.file_name
                 );
-            }
-
-            // Multiple tasks, no input, no config target, error.
-            (None, None, None) if !decls.tasks().is_empty() => {
-                bail!(
-                    "target required in config: cannot infer which task to run (test: `{}`)",
-                    self.file_name,
-                );
-            }
+            };
+
+            let valid = match (target, rest.split_once('.')) {
+                (_, None) => {
+                    let Some(decl) = interface.inputs().iter().find(|decl| decl.name() == rest)
+                    else {
+                        bail!(
+                            "input key `{key}` does not name an input of `{}` (test: `{}`)",
+                            target.name(),
+                            self.file_name
+                        );
+                    };
+
+                    let ty = decl.ty().trim_end_matches('?');
+                    if structs.iter().any(|s| s == ty) && !obj[key].is_object() {
+                        bail!(
+                            "input `{key}` has struct type `{ty}`, but its value is not an object \
+                             (test: `{}`)",
+                            self.file_name
+                        );
+                    }
 
-            // Multiple tasks, no input, config target provided, ok.
-            (None, None, Some(target)) if !decls.tasks().is_empty() => {
-                // Validate that the target actually exists in the tasks
-                if !decls.tasks().contains(&target.to_string()) {
-                    bail!(
-                        "target `{}` not found in tasks (test: `{}`)",
-                        target,
-                        self.file_name
-                    );
+                    true
                 }
-                // Since we validated it's in tasks list, it's a Task
-                self.inferred_target = Some(wdl::Target::Task(target.to_string()));
-                Ok(())
-            }
-
-            // No workflow, no tasks, error.
-            (None, None, _) if decls.tasks().is_empty() && decls.workflow().is_none() => {
-                bail!(
-                    "no workflow or task found in WDL source (test: `{}`)",
-                    self.file_name
-                );
-            }
+                (wdl::Target::Workflow(_), Some((call, _))) => calls.iter().any(|c| c == call),
+                (wdl::Target::Task(_), Some(_)) => false,
+            };
 
-            // Should not reach here.
-            _ => {
+            if !valid {
                 bail!(
-                    "unexpected target inference state (test: `{}`)",
+                    "input key `{key}` does not name an input of `{}` or of a call within it \
+                     (test: `{}`)",
+                    target.name(),
                     self.file_name
                 );
             }
         }
+
+        Ok(())
     }
 
-    /// Attempts to infer the target from input JSON parameter prefixes.
+    /// Wraps a task target in a generated workflow that forwards the task's
+    /// inputs and outputs.
     ///
-    /// Returns `Some(target)` if all input parameters share a common prefix.
-    /// Returns `None` if there are no inputs or no common prefix can be determined.
-    fn infer_target_from_input(&self, decls: &wdl::WdlDeclarations) -> Result<Option<wdl::Target>> {
-        let input = match &self.input {
-            Some(input) => input,
-            None => return Ok(None),
+    /// The wrapper is appended to the source, the target becomes the wrapper
+    /// workflow, and the input and expected output keys are renamed to match.
+    /// This does nothing if the target is not a task.
+    pub fn wrap_task(&mut self) -> Result<()> {
+        let Some(wdl::Target::Task(task)) = self.inferred_target.clone() else {
+            return Ok(());
         };
 
-        let obj = match input.as_object() {
-            Some(obj) => obj,
-            None => return Ok(None),
-        };
+        let interface = wdl::parse_interface(&self.src, &wdl::Target::Task(task.clone()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "unable to find the definition of task `{}` (test: `{}`)",
+                    task,
+                    self.file_name
+                )
+            })?;
 
-        if obj.is_empty() {
-            return Ok(None);
+        let decls = wdl::parse_wdl_declarations(&self.src);
+        let mut name = format!("spectool_{task}");
+        while decls.tasks().contains(&name) {
+            name.push('_');
         }
 
-        // Extract unique prefixes from input keys (e.g., "my_task.input1" → "my_task")
-        let prefixes = obj
-            .keys()
-            .filter_map(|key| key.split('.').next().map(|s| s.to_string()))
-            .collect::<HashSet<String>>();
-
-        // Must have exactly one unique prefix
-        if prefixes.len() == 1 {
-            let prefix = prefixes.into_iter().next().unwrap();
-
-            // Check if prefix matches workflow or task
-            if matches!(decls.workflow(), Some(wf) if wf == prefix) {
-                Ok(Some(wdl::Target::Workflow(prefix)))
-            } else if decls.tasks().contains(&prefix) {
-                Ok(Some(wdl::Target::Task(prefix)))
-            } else {
-                bail!(
-                    "input prefix `{}` does not match any workflow or task in WDL (test: `{}`)",
-                    prefix,
-                    self.file_name
-                );
-            }
-        } else if prefixes.len() > 1 {
-            bail!("ambiguous input prefixes (test: `{}`)", self.file_name);
-        } else {
-            Ok(None)
-        }
+        self.src
+            .push_str(&wdl::wrapper_workflow(&name, &task, &interface));
+        self.input = self.input.take().map(|v| rename_prefix(v, &task, &name));
+        self.output = self.output.take().map(|v| rename_prefix(v, &task, &name));
+        self.inferred_target = Some(wdl::Target::Workflow(name));
+
+        Ok(())
     }
 }
 
 /// A set of conformance tests.
-pub struct Tests(Vec<Test>);
+pub struct Tests {
+    /// The conformance tests.
+    tests: Vec<Test>,
+
+    /// The errors for conformance tests that could not be parsed.
+    errors: Vec<ParseError>,
+}
 
 impl Tests {
     /// Turns a markdown specification into a set of conformance tests.
     pub fn compile<S: AsRef<str>>(contents: S) -> Result<Self> {
+        Self::compile_with_syntax(contents, JsonSyntax::Strict)
+    }
+
+    /// Turns a markdown specification into a set of conformance tests,
+    /// parsing the JSON blocks of each test with the given syntax.
+    pub fn compile_with_syntax<S: AsRef<str>>(contents: S, syntax: JsonSyntax) -> Result<Self> {
         let contents = contents.as_ref();
 
-        let tests = CONFORMANCE_TEST_REGEX
+        let mut tests = Vec::new();
+        let mut errors = Vec::new();
+
+        let headings = headings(contents);
+
+        // The tests are built in parallel and gathered in the order they
+        // appear in the specification
+        let built = CONFORMANCE_TEST_REGEX
             .captures_iter(contents)
-            .map(build_conformance_test)
-            .collect::<Result<Vec<Test>, _>>()?;
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|captures| {
+                let start = captures.get(0).unwrap().start();
+                (start, build_conformance_test(contents, captures, syntax))
+            })
+            .collect::<Vec<_>>();
 
-        Ok(Self(tests))
+        for (index, (start, result)) in built.into_iter().enumerate() {
+            let heading = headings
+                .iter()
+                .take_while(|heading| heading.offset < start)
+                .last();
+
+            match result {
+                Ok(mut test) => {
+                    test.index = index;
+                    test.section = heading.map(|heading| heading.text.to_string());
+                    test.anchor = heading.map(|heading| heading.anchor.clone());
+                    tests.push(test);
+                }
+                Err(e) => errors.push(e.downcast::<ParseError>()?),
+            }
+        }
+
+        Ok(Self { tests, errors })
+    }
+
+    /// Returns the errors for conformance tests that could not be parsed.
+    ///
+    /// These tests are not included in the set of conformance tests.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
     }
 
     /// Returns a reference to each conformance test.
+    ///
+    /// Tests are always returned in the order they appear in the
+    /// specification.
     pub fn tests(&self) -> impl Iterator<Item = &Test> {
-        self.0.iter()
+        self.tests.iter()
+    }
+
+    /// Returns a reference to each conformance test selected by a filter, in
+    /// the order they appear in the specification.
+    pub fn filter<'a>(&'a self, filter: &'a TestFilter) -> impl Iterator<Item = &'a Test> {
+        self.tests.iter().filter(|test| filter.matches(test))
     }
 
     /// Returns a mutable reference to each conformance test.
     pub fn tests_mut(&mut self) -> impl Iterator<Item = &mut Test> {
-        self.0.iter_mut()
+        self.tests.iter_mut()
     }
 
     /// Consumes `self` and returns the conformance tests.
     pub fn into_tests(self) -> impl Iterator<Item = Test> {
-        self.0.into_iter()
+        self.tests.into_iter()
+    }
+}
+
+/// Finds the `<details>` blocks that look like conformance tests but do not
+/// match the expected structure, returning the one-based line of each.
+///
+/// A block looks like a conformance test if it contains `Example:`.
+pub fn near_misses(contents: &str) -> Vec<usize> {
+    let tests = CONFORMANCE_TEST_REGEX
+        .find_iter(contents)
+        .map(|m| m.start())
+        .collect::<HashSet<_>>();
+
+    DETAILS_REGEX
+        .find_iter(contents)
+        .filter(|m| !tests.contains(&m.start()))
+        .filter(|m| m.as_str().to_lowercase().contains("example:"))
+        .map(|m| contents[..m.start()].matches('\n').count() + 1)
+        .collect()
+}
+
+/// A heading within the specification.
+struct Heading<'a> {
+    /// The offset of the heading within the specification.
+    offset: usize,
+
+    /// The text of the heading.
+    text: &'a str,
+
+    /// The anchor GitHub generates for the heading.
+    anchor: String,
+}
+
+/// Finds the headings of a specification, skipping lines within fenced code
+/// blocks (such as WDL comments).
+fn headings(contents: &str) -> Vec<Heading<'_>> {
+    let mut headings = Vec::new();
+    let mut counts = HashMap::<String, usize>::new();
+    let mut fenced = false;
+    let mut offset = 0;
+
+    for line in contents.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fenced = !fenced;
+            continue;
+        }
+
+        if fenced {
+            continue;
+        }
+
+        if let Some(captures) = HEADING_REGEX.captures(line) {
+            let text = captures.get(1).unwrap().as_str();
+            let slug = slug(text);
+
+            // Repeated headings get a numeric suffix, in order of appearance
+            let count = counts.entry(slug.clone()).or_default();
+            let anchor = match *count {
+                0 => slug,
+                n => format!("{slug}-{n}"),
+            };
+            *count += 1;
+
+            headings.push(Heading {
+                offset: start,
+                text,
+                anchor,
+            });
+        }
+    }
+
+    headings
+}
+
+/// Converts the text of a heading to an anchor as GitHub does: lowercased,
+/// with punctuation removed and spaces replaced by hyphens.
+fn slug(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Renames the `from.` prefix of each key in a JSON object to `to.`.
+///
+/// Values other than objects are returned unchanged.
+fn rename_prefix(value: Value, from: &str, to: &str) -> Value {
+    match value {
+        Value::Object(obj) => Value::Object(
+            obj.into_iter()
+                .map(|(key, value)| match key.strip_prefix(&format!("{from}.")) {
+                    Some(rest) => (format!("{to}.{rest}"), value),
+                    None => (key, value),
+                })
+                .collect(),
+        ),
+        other => other,
     }
 }
 
 /// Builds a conformance test from a set of captures.
-fn build_conformance_test(captures: Captures<'_>) -> Result<Test> {
+fn build_conformance_test(
+    contents: &str,
+    captures: Captures<'_>,
+    syntax: JsonSyntax,
+) -> Result<Test> {
     let file_name = required_string(&captures, 1, "filename")?;
     let src = required_string(&captures, 2, "source")?;
-    let input = optional_json_group(&captures, 3);
-    let output = optional_json_group(&captures, 4);
-    let config = optional_group::<Config>(&captures, 5)?.unwrap_or_default();
+
+    let group = |index, block| {
+        optional_group::<Value>(contents, &captures, index, &file_name, block, syntax)
+    };
+    let input = group(3, "Example input")?;
+    let output = group(4, "Example output")?;
+    let config =
+        optional_group::<Config>(contents, &captures, 5, &file_name, "Test config", syntax)?
+            .unwrap_or_default();
+    let lines = Lines::from_offsets(contents, captures.get(0).unwrap().range());
 
     Ok(Test::builder()
         .file_name(file_name)
+        .lines(lines)
         .src(src)
         .maybe_input(input)
         .maybe_output(output)
@@ -335,33 +844,340 @@ fn required_string(captures: &Captures<'_>, index: usize, name: &str) -> Result<
         .map(|v| v.as_str().to_owned())
 }
 
-/// Parses an _optional_ group within a test.
-fn optional_json_group(captures: &Captures<'_>, index: usize) -> Option<Value> {
-    captures.get(index).and_then(|v| v.as_str().parse().ok())
+/// A block within a test.
+enum Block<'a> {
+    /// The block is not present.
+    Absent,
+    /// The block is present but contains only whitespace.
+    Empty,
+    /// The block has contents.
+    Present(Match<'a>),
 }
 
-/// Parses an _optional_ group within a test.
-fn optional_group<D>(captures: &Captures<'_>, index: usize) -> Result<Option<D>>
+impl<'a> Block<'a> {
+    /// Gets a block from a group within a test.
+    fn from_group(captures: &Captures<'a>, index: usize) -> Self {
+        match captures.get(index) {
+            None => Block::Absent,
+            Some(m) if m.as_str().trim().is_empty() => Block::Empty,
+            Some(m) => Block::Present(m),
+        }
+    }
+}
+
+/// Parses an _optional_ JSON group within a test.
+///
+/// Empty blocks are treated as though they were absent.
+fn optional_group<D>(
+    contents: &str,
+    captures: &Captures<'_>,
+    index: usize,
+    test: &str,
+    block: &'static str,
+    syntax: JsonSyntax,
+) -> Result<Option<D>, ParseError>
 where
     D: DeserializeOwned,
 {
-    captures
-        .get(index)
-        .map(|m| {
-            serde_json::from_str::<D>(m.as_str()).with_context(|| {
-                format!(
-                    "parsing configuration:\n\n{}",
-                    captures.get(0).unwrap().as_str()
-                )
-            })
-        })
-        .transpose()
+    match Block::from_group(captures, index) {
+        Block::Absent => Ok(None),
+        Block::Empty => {
+            debug!("ignoring empty {block} block of `{test}`");
+            Ok(None)
+        }
+        Block::Present(m) => syntax
+            .parse::<D>(m.as_str())
+            .map(Some)
+            .map_err(|e| ParseError::new(contents, test, block, m, e)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use serde_json::json;
+
     use super::*;
 
+    #[test]
+    fn wrap_task() {
+        let mut test = Test::builder()
+            .file_name(String::from("greet.wdl"))
+            .src(String::from(
+                "version 1.2\ntask greet {\n  input {\n    String name\n  }\n  command <<<\n    \
+                 echo ~{name}\n  >>>\n  output {\n    String out = read_string(stdout())\n  \
+                 }\n}\n",
+            ))
+            .input(json!({"greet.name": "world"}))
+            .output(json!({"greet.out": "world"}))
+            .config(Config::default())
+            .build();
+
+        test.infer_and_validate_target().unwrap();
+        test.wrap_task().unwrap();
+
+        assert_eq!(
+            test.target(),
+            Some(&wdl::Target::Workflow(String::from("spectool_greet")))
+        );
+        assert_eq!(test.input(), Some(&json!({"spectool_greet.name": "world"})));
+        assert_eq!(test.output(), Some(&json!({"spectool_greet.out": "world"})));
+        assert!(test.src().contains("call greet { input: name = name }"));
+    }
+
+    #[test]
+    fn lenient_json() {
+        let spec = r#"
+<details>
+<summary>
+Example: lenient.wdl
+
+```wdl
+version 1.2
+
+workflow lenient {
+  output {
+    Int x = 1
+  }
+}
+```
+</summary>
+<p>
+Example output:
+
+```json
+{
+  // The only output.
+  "lenient.x": 1,
+}
+```
+
+Test config:
+
+```json
+{ "tags": ["static"], }
+```
+</p>
+</details>
+"#;
+
+        let tests = Tests::compile(spec).unwrap();
+        assert_eq!(tests.tests().count(), 0);
+        assert_eq!(tests.errors().len(), 1);
+
+        let tests = Tests::compile_with_syntax(spec, JsonSyntax::Lenient).unwrap();
+        let test = tests.tests().next().unwrap();
+        assert_eq!(test.output(), Some(&json!({"lenient.x": 1})));
+        assert_eq!(test.config().tags(), &[Tag::Static]);
+    }
+
+    #[test]
+    fn anchors() {
+        let spec = "# Types\n\n## 🗑 `Object`\n\n```wdl\n# Example\n```\n\n## Types\n\n## \
+                    Integer (Int) ##\n";
+        let anchors = headings(spec)
+            .into_iter()
+            .map(|heading| heading.anchor)
+            .collect::<Vec<_>>();
+        assert_eq!(anchors, ["types", "-object", "types-1", "integer-int"]);
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        let spec = r#"# Spec
+
+<details>
+<summary>
+Example: broken.wdl
+
+```wdl
+version 1.2
+
+workflow broken {}
+```
+</summary>
+<p>
+Example input:
+
+```json
+{
+  "broken.x": 1
+  "broken.y": 2
+}
+```
+</p>
+</details>
+
+<details>
+<summary>
+Example: fine.wdl
+
+```wdl
+version 1.2
+
+workflow fine {}
+```
+</summary>
+</details>
+"#;
+
+        let tests = Tests::compile(spec).unwrap();
+        assert_eq!(
+            tests
+                .tests()
+                .map(|t| (t.index(), t.file_name()))
+                .collect::<Vec<_>>(),
+            [(1, "fine.wdl")]
+        );
+
+        assert_eq!(tests.tests().next().unwrap().section(), Some("Spec"));
+        assert_eq!(
+            tests
+                .tests()
+                .next()
+                .unwrap()
+                .lines()
+                .map(|l| (l.start(), l.end())),
+            Some((25, 35))
+        );
+
+        let error = &tests.errors()[0];
+        assert_eq!(error.test(), "broken.wdl");
+        assert_eq!((error.line(), error.column()), (19, 3));
+        assert_eq!(
+            error.to_string(),
+            "SPEC.md:19:3: unable to parse the Example input block of `broken.wdl`: expected \
+             `,` or `}`\n   |\n19 |   \"broken.y\": 2\n   |   ^"
+        );
+    }
+
+    #[test]
+    fn empty_blocks() {
+        let spec = r#"
+<details>
+<summary>
+Example: empty.wdl
+
+```wdl
+version 1.2
+
+workflow empty {}
+```
+</summary>
+<p>
+Example input:
+
+```json
+```
+
+Test config:
+
+```json
+
+```
+</p>
+</details>
+"#;
+
+        let tests = Tests::compile(spec).unwrap();
+        assert!(tests.errors().is_empty());
+
+        let test = tests.tests().next().unwrap();
+        assert_eq!(test.input(), None);
+        assert!(test.config().tags().is_empty());
+    }
+
+    #[test]
+    fn input_keys() {
+        let src = "version 1.2\ntask greet {\n  input {\n    String name\n  }\n  command <<<>>>\n}\n\
+                   workflow w {\n  input {\n    Int n\n  }\n  call greet\n}\n";
+        let test = |input| {
+            let mut test = Test::builder()
+                .file_name(String::from("w.wdl"))
+                .src(String::from(src))
+                .input(input)
+                .config(Config::default())
+                .build();
+            test.infer_and_validate_target().unwrap();
+            test.validate_input_keys()
+        };
+
+        assert!(test(json!({"w.n": 1, "w.greet.name": "hi"})).is_ok());
+        assert!(
+            test(json!({"w.m": 1}))
+                .unwrap_err()
+                .to_string()
+                .contains("`w.m`")
+        );
+        assert!(test(json!({"w.other.name": "hi"})).is_err());
+
+        let src =
+            "version 1.2\nstruct P {\n  Int x\n}\nworkflow s {\n  input {\n    P? p\n  }\n}\n";
+        let mut test = Test::builder()
+            .file_name(String::from("s.wdl"))
+            .src(String::from(src))
+            .input(json!({"s.p": 1}))
+            .config(Config::default())
+            .build();
+        test.infer_and_validate_target().unwrap();
+        assert!(
+            test.validate_input_keys()
+                .unwrap_err()
+                .to_string()
+                .contains("struct type `P`")
+        );
+    }
+
+    #[test]
+    fn content_hash() {
+        let test = |input, file_name: &str| {
+            Test::builder()
+                .file_name(String::from(file_name))
+                .src(String::from("version 1.2\nworkflow w {}\n"))
+                .input(input)
+                .config(Config::default())
+                .build()
+        };
+
+        let hash = test(json!({"w.a": 1, "w.b": 2}), "w.wdl").content_hash();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            hash,
+            test(json!({"w.b": 2, "w.a": 1}), "renamed.wdl").content_hash()
+        );
+        assert_ne!(hash, test(json!({"w.a": 1}), "w.wdl").content_hash());
+    }
+
+    #[test]
+    fn data_references() {
+        let test = Test::builder()
+            .file_name(String::from("data.wdl"))
+            .src(String::from(
+                "version 1.2\n# Reads \"data/commented.txt\"\nworkflow data {\n  File f = \
+                 \"data/hello.txt\"\n  File g = 'data/single.txt' # not \"data/trailing.txt\"\n  \
+                 String s = \"x data/no\"\n  File h = \"data/~{s}.txt\"\n}\n",
+            ))
+            .input(serde_json::json!({
+                "data.files": ["data/cities.txt", "other/ignored.txt"],
+                "data.dir": "data/nested/",
+            }))
+            .config(Config::default())
+            .build();
+
+        assert_eq!(
+            test.data_references().into_iter().collect::<Vec<_>>(),
+            ["cities.txt", "hello.txt", "nested", "single.txt"]
+        );
+    }
+
+    #[test]
+    fn near_misses() {
+        let spec = "# Spec\n\n<details>\n<summary>\nExample: a.wdl\n\n```wdl\nversion 1.2\n```\n\
+                    </summary>\n</details>\n\n<details>\n<summary>\nExample: b.wdl\n</summary>\n\
+                    </details>\n\n<details>\nUnrelated\n</details>\n";
+
+        assert_eq!(super::near_misses(spec), [13]);
+    }
+
     #[test]
     fn regex_matches() {
         let example = r#"
@@ -1,15 +1,63 @@
 //! Configuration for conformance tests.
 
+use std::collections::BTreeMap;
+use std::time::Duration;
+
 use serde::Deserialize;
 use serde::Serialize;
 use strum_macros::EnumIter;
 
 /// A tag associated with a conformance test.
+///
+/// Tags other than those `spectool` gives a meaning to are kept as-is so
+/// that specification authors can classify tests for curation.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(try_from = "String", into = "String")]
 pub enum Tag {
     /// Test is for deprecated functionality.
     Deprecated,
+    /// Test only concerns static validity (parsing and type checking) and
+    /// does not need to be executed.
+    Static,
+    /// Any other tag.
+    Other(String),
+}
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tag::Deprecated => write!(f, "deprecated"),
+            Tag::Static => write!(f, "static"),
+            Tag::Other(tag) => write!(f, "{tag}"),
+        }
+    }
+}
+
+impl std::str::FromStr for Tag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" => Err(String::from("tags must not be empty")),
+            "deprecated" => Ok(Tag::Deprecated),
+            "static" => Ok(Tag::Static),
+            tag => Ok(Tag::Other(tag.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for Tag {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Tag> for String {
+    fn from(tag: Tag) -> Self {
+        tag.to_string()
+    }
 }
 
 /// A capability required by a conformance test.
@@ -41,6 +89,27 @@ impl std::fmt::Display for Capability {
     }
 }
 
+/// The conformance tier of a test.
+///
+/// A test is optional if it requires a runtime capability, as engines are not
+/// required to support every capability, and required otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Tier {
+    /// The test covers behavior every engine must support.
+    Required,
+    /// The test covers behavior that depends on an optional capability.
+    Optional,
+}
+
+impl std::fmt::Display for Tier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tier::Required => write!(f, "required"),
+            Tier::Optional => write!(f, "optional"),
+        }
+    }
+}
+
 /// The expected return code(s) for a conformance test.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -69,6 +138,183 @@ where
     }
 }
 
+/// A file that a conformance test expects the engine to produce.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExpectedFile {
+    /// A file that must exist.
+    Path(String),
+    /// A file that must exist with the given contents.
+    Hashed {
+        /// The path to the file relative to the working directory.
+        path: String,
+        /// The hex-encoded SHA-256 hash of the file's contents.
+        sha256: String,
+    },
+}
+
+impl ExpectedFile {
+    /// Gets the path to the file relative to the working directory.
+    pub fn path(&self) -> &str {
+        match self {
+            ExpectedFile::Path(path) => path,
+            ExpectedFile::Hashed { path, .. } => path,
+        }
+    }
+
+    /// Gets the expected SHA-256 hash of the file's contents, if any.
+    pub fn sha256(&self) -> Option<&str> {
+        match self {
+            ExpectedFile::Path(_) => None,
+            ExpectedFile::Hashed { sha256, .. } => Some(sha256),
+        }
+    }
+}
+
+/// A duration written as a number followed by a unit, such as `"120s"`.
+///
+/// The supported units are `ms`, `s`, `m`, and `h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MaxDuration(Duration);
+
+impl MaxDuration {
+    /// Gets the duration.
+    pub fn get(&self) -> Duration {
+        self.0
+    }
+}
+
+impl TryFrom<String> for MaxDuration {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let error = || {
+            format!(
+                "invalid duration `{s}`: expected a number followed by `ms`, `s`, `m`, or `h` \
+                 (e.g., `\"120s\"`)"
+            )
+        };
+
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(error)?;
+        let (number, unit) = s.split_at(split);
+        let number = number.parse::<f64>().map_err(|_| error())?;
+
+        let secs = match unit {
+            "ms" => number / 1000.0,
+            "s" => number,
+            "m" => number * 60.0,
+            "h" => number * 3600.0,
+            _ => return Err(error()),
+        };
+
+        Duration::try_from_secs_f64(secs)
+            .map(Self)
+            .map_err(|_| error())
+    }
+}
+
+impl From<MaxDuration> for String {
+    fn from(duration: MaxDuration) -> Self {
+        match duration.0.subsec_millis() {
+            0 => format!("{}s", duration.0.as_secs()),
+            _ => format!("{}ms", duration.0.as_millis()),
+        }
+    }
+}
+
+/// How outputs that name files are compared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileComparison {
+    /// Files are compared by their names.
+    #[default]
+    Name,
+    /// Files are compared by the hex-encoded SHA-256 hash of their contents.
+    Sha256,
+    /// Files are compared by their contents.
+    Contents,
+}
+
+/// The tolerance within which floating point outputs are considered equal.
+///
+/// Engines legitimately differ in how they format and round the results of
+/// floating point computations. By default, numbers must be equal to within
+/// [`f64::EPSILON`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FloatTolerance {
+    /// The maximum absolute difference between two numbers.
+    #[serde(default)]
+    absolute: f64,
+
+    /// The maximum difference between two numbers relative to the larger of
+    /// their magnitudes.
+    #[serde(default)]
+    relative: f64,
+}
+
+impl FloatTolerance {
+    /// Creates a new tolerance.
+    pub fn new(absolute: f64, relative: f64) -> Self {
+        Self { absolute, relative }
+    }
+
+    /// Gets the maximum absolute difference between two numbers.
+    pub fn absolute(&self) -> f64 {
+        self.absolute
+    }
+
+    /// Gets the maximum relative difference between two numbers.
+    pub fn relative(&self) -> f64 {
+        self.relative
+    }
+
+    /// Returns whether two numbers are equal within the tolerance.
+    pub fn matches(&self, expected: f64, actual: f64) -> bool {
+        let difference = (expected - actual).abs();
+
+        difference < f64::EPSILON
+            || difference <= self.absolute
+            || difference <= self.relative * expected.abs().max(actual.abs())
+    }
+}
+
+impl std::str::FromStr for FloatTolerance {
+    type Err = String;
+
+    /// Parses a tolerance such as `1e-6` (absolute) or `abs=1e-6,rel=1e-9`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "invalid float tolerance `{s}`: expected a non-negative number, or `abs=<number>` \
+                 and/or `rel=<number>` separated by a comma"
+            )
+        };
+
+        let mut tolerance = Self::default();
+        for part in s.split(',') {
+            let (field, value) = match part.split_once('=') {
+                Some(("abs", value)) => (&mut tolerance.absolute, value),
+                Some(("rel", value)) => (&mut tolerance.relative, value),
+                Some(_) => return Err(error()),
+                None => (&mut tolerance.absolute, part),
+            };
+
+            *field = value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| *value >= 0.0)
+                .ok_or_else(error)?;
+        }
+
+        Ok(tolerance)
+    }
+}
+
 /// A configuration for a conformance test.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -95,6 +341,23 @@ pub struct Config {
     #[serde(default)]
     exclude_outputs: Vec<String>,
 
+    /// Output keys that must not appear in the actual outputs.
+    ///
+    /// Entries are written the same way as those of `exclude_outputs`.
+    #[serde(default)]
+    forbidden_outputs: Vec<String>,
+
+    /// Output keys to rename before validating.
+    ///
+    /// Each key (or output path starting with the key) is renamed on both the
+    /// expected and actual outputs.
+    #[serde(default)]
+    rename_outputs: BTreeMap<String, String>,
+
+    /// Files the engine must produce in the working directory.
+    #[serde(default)]
+    expected_files: Vec<ExpectedFile>,
+
     /// Runtime capabilities required by the test.
     #[serde(default)]
     capabilities: Vec<Capability>,
@@ -102,6 +365,27 @@ pub struct Config {
     /// Tags associated with the test (e.g., deprecated).
     #[serde(default)]
     tags: Vec<Tag>,
+
+    /// The maximum time the test should take to execute.
+    #[serde(default)]
+    max_duration: Option<MaxDuration>,
+
+    /// The tolerance within which floating point outputs are considered
+    /// equal, overriding the tolerance of the run.
+    #[serde(default)]
+    float_tolerance: Option<FloatTolerance>,
+
+    /// How outputs that name files are compared.
+    #[serde(default)]
+    compare_files: FileComparison,
+
+    /// Output keys of arrays whose elements may appear in any order.
+    #[serde(default)]
+    unordered_outputs: Vec<String>,
+
+    /// Output keys whose expected strings are regular expressions.
+    #[serde(default)]
+    pattern_outputs: Vec<String>,
 }
 
 impl Config {
@@ -130,6 +414,21 @@ impl Config {
         &self.exclude_outputs
     }
 
+    /// Gets the output keys that must not appear in the actual outputs.
+    pub fn forbidden_outputs(&self) -> &[String] {
+        &self.forbidden_outputs
+    }
+
+    /// Gets the output keys to rename before validating.
+    pub fn rename_outputs(&self) -> &BTreeMap<String, String> {
+        &self.rename_outputs
+    }
+
+    /// Gets the files the engine must produce in the working directory.
+    pub fn expected_files(&self) -> &[ExpectedFile] {
+        &self.expected_files
+    }
+
     /// Gets the required capabilities.
     pub fn capabilities(&self) -> &[Capability] {
         &self.capabilities
@@ -139,6 +438,32 @@ impl Config {
     pub fn tags(&self) -> &[Tag] {
         &self.tags
     }
+
+    /// Gets the maximum time the test should take to execute.
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.max_duration.map(|duration| duration.get())
+    }
+
+    /// Gets the tolerance within which floating point outputs are considered
+    /// equal, if the test overrides it.
+    pub fn float_tolerance(&self) -> Option<FloatTolerance> {
+        self.float_tolerance
+    }
+
+    /// Gets how outputs that name files are compared.
+    pub fn compare_files(&self) -> FileComparison {
+        self.compare_files
+    }
+
+    /// Gets the output keys of arrays whose elements may appear in any order.
+    pub fn unordered_outputs(&self) -> &[String] {
+        &self.unordered_outputs
+    }
+
+    /// Gets the output keys whose expected strings are regular expressions.
+    pub fn pattern_outputs(&self) -> &[String] {
+        &self.pattern_outputs
+    }
 }
 
 #[cfg(test)]
@@ -155,8 +480,57 @@ mod tests {
         assert!(!config.fail());
         assert_eq!(config.return_code(), &ReturnCode::Any);
         assert_eq!(config.exclude_outputs(), &[] as &[String]);
+        assert_eq!(config.forbidden_outputs(), &[] as &[String]);
+        assert!(config.rename_outputs().is_empty());
+        assert!(config.expected_files().is_empty());
         assert_eq!(config.capabilities(), &[] as &[Capability]);
         assert_eq!(config.tags(), &[] as &[Tag]);
+        assert_eq!(config.max_duration(), None);
+    }
+
+    #[test]
+    fn max_duration() {
+        let parse = |s: &str| {
+            serde_json::from_str::<Config>(&format!(r#"{{"max_duration": "{s}"}}"#))
+                .map(|config| config.max_duration().unwrap())
+        };
+
+        assert_eq!(parse("120s").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse("1.5h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse("250ms").unwrap(), Duration::from_millis(250));
+        assert!(parse("120").is_err());
+        assert!(parse("soon").is_err());
+
+        let config = serde_json::from_str::<Config>(r#"{"max_duration": "2m"}"#).unwrap();
+        assert_eq!(
+            serde_json::to_value(&config).unwrap()["max_duration"],
+            "120s"
+        );
+    }
+
+    #[test]
+    fn float_tolerance() {
+        let tolerance = "abs=1e-6,rel=0.01".parse::<FloatTolerance>().unwrap();
+        assert_eq!(tolerance, FloatTolerance::new(1e-6, 0.01));
+        assert_eq!(
+            "0.5".parse::<FloatTolerance>().unwrap(),
+            FloatTolerance::new(0.5, 0.0)
+        );
+        assert!("rel=-1".parse::<FloatTolerance>().is_err());
+        assert!("ulps=2".parse::<FloatTolerance>().is_err());
+
+        assert!(FloatTolerance::default().matches(0.1 + 0.2, 0.3));
+        assert!(!FloatTolerance::default().matches(1.23456, 1.2346));
+        assert!(tolerance.matches(1000.0, 1009.0));
+        assert!(!tolerance.matches(1.0, 1.5));
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"float_tolerance": {"relative": 0.001}}"#).unwrap();
+        assert_eq!(
+            config.float_tolerance(),
+            Some(FloatTolerance::new(0.0, 0.001))
+        );
     }
 
     #[test]
@@ -198,8 +572,12 @@ mod tests {
             "fail": true,
             "return_code": 1,
             "exclude_outputs": ["timestamp"],
+            "forbidden_outputs": ["secret"],
+            "rename_outputs": {"my_task.old": "my_task.new"},
             "capabilities": ["cpu", "gpu"],
-            "tags": ["deprecated"]
+            "tags": ["deprecated"],
+            "unordered_outputs": ["my_task.files"],
+            "pattern_outputs": ["my_task.id"]
         }"#;
         let config: Config = serde_json::from_str(json).unwrap();
 
@@ -208,8 +586,30 @@ mod tests {
         assert!(config.fail());
         assert_eq!(config.return_code(), &ReturnCode::Single(1));
         assert_eq!(config.exclude_outputs(), &["timestamp"]);
+        assert_eq!(config.forbidden_outputs(), &["secret"]);
+        assert_eq!(
+            config
+                .rename_outputs()
+                .get("my_task.old")
+                .map(String::as_str),
+            Some("my_task.new")
+        );
         assert_eq!(config.capabilities(), &[Capability::Cpu, Capability::Gpu]);
         assert_eq!(config.tags(), &[Tag::Deprecated]);
+        assert_eq!(config.unordered_outputs(), &["my_task.files"]);
+        assert_eq!(config.pattern_outputs(), &["my_task.id"]);
+    }
+
+    #[test]
+    fn expected_files() {
+        let json = r#"{"expected_files": ["out/a.txt", {"path": "out/b.txt", "sha256": "abc"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+
+        let files = config.expected_files();
+        assert_eq!(files[0].path(), "out/a.txt");
+        assert_eq!(files[0].sha256(), None);
+        assert_eq!(files[1].path(), "out/b.txt");
+        assert_eq!(files[1].sha256(), Some("abc"));
     }
 
     #[test]
@@ -219,6 +619,29 @@ mod tests {
         assert_eq!(config.tags(), &[Tag::Deprecated]);
     }
 
+    #[test]
+    fn static_tag() {
+        let json = r#"{"tags": ["static"]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.tags(), &[Tag::Static]);
+    }
+
+    #[test]
+    fn other_tags() {
+        let json = r#"{"tags": ["slow", "deprecated"]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            config.tags(),
+            &[Tag::Other(String::from("slow")), Tag::Deprecated]
+        );
+        assert_eq!(
+            serde_json::to_string(config.tags()).unwrap(),
+            r#"["slow","deprecated"]"#
+        );
+
+        assert!(serde_json::from_str::<Config>(r#"{"tags": [""]}"#).is_err());
+    }
+
     #[test]
     fn unknown_field_rejected() {
         let json = r#"{"unknown_field": "value"}"#;
@@ -0,0 +1,249 @@
+//! Best-effort transforms of test sources to older WDL versions.
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+
+use crate::wdl;
+use crate::wdl::Construct;
+
+/// A WDL version that tests written for WDL 1.2 can be transformed to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+pub enum Dialect {
+    /// WDL 1.1.
+    #[value(name = "1.1")]
+    #[serde(rename = "1.1")]
+    V1_1,
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dialect::V1_1 => write!(f, "1.1"),
+        }
+    }
+}
+
+/// Regex to match a WDL 1.2 version statement.
+static VERSION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^(\s*version\s+)1\.2\b").unwrap());
+
+/// Regex to match the start of a `requirements` section.
+static REQUIREMENTS_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^(\s*)requirements(\s*\{)").unwrap());
+
+/// Regex to match the start of a `hints` section.
+static HINTS_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^[ \t]*hints\s*\{").unwrap());
+
+/// Regex to match the attributes of a `requirements` section that were
+/// renamed from those of a WDL 1.1 `runtime` section.
+static RENAMED_KEY_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^(\s*)(max_retries|return_codes)(\s*:)").unwrap());
+
+/// Regex to match requirements with no WDL 1.1 counterpart.
+static FPGA_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^\s*fpga\s*:").unwrap());
+
+/// The functions added to the standard library in WDL 1.2.
+const NEW_FUNCTIONS: &[&str] = &[
+    "chunk",
+    "contains",
+    "find",
+    "join_paths",
+    "matches",
+    "split",
+    "values",
+];
+
+/// Transforms the source of a WDL 1.2 test to a dialect.
+///
+/// `requirements` sections become `runtime` sections (with their attributes
+/// renamed), and `hints` sections are removed, as engines are free to ignore
+/// hints. Sources for other versions are returned unchanged.
+///
+/// Returns the transformed source along with a description of each feature
+/// the source uses that has no counterpart in the dialect; a test that uses
+/// any is not meaningful to run.
+pub fn transform(source: &str, dialect: Dialect) -> (String, Vec<String>) {
+    if !VERSION_REGEX.is_match(source) {
+        return (source.to_string(), Vec::new());
+    }
+
+    let mut unsupported = Vec::new();
+
+    let source = match dialect {
+        Dialect::V1_1 => {
+            let source = VERSION_REGEX.replace(source, "${1}1.1");
+            let source = remove_sections(&source, &HINTS_REGEX);
+            rename_requirements(&source, &mut unsupported)
+        }
+    };
+
+    unsupported.extend(
+        wdl::constructs(&source, &Construct::iter().collect::<Vec<_>>())
+            .into_iter()
+            .map(|construct| construct.description().to_string()),
+    );
+
+    for function in NEW_FUNCTIONS {
+        let regex = Regex::new(&format!(r"\b{function}\s*\(")).expect("regex to compile");
+        if regex.is_match(&source) {
+            unsupported.push(format!("the `{function}` function"));
+        }
+    }
+
+    (source, unsupported)
+}
+
+/// Finds the end (exclusive) of the brace-delimited block that opens at
+/// `start`.
+fn block_end(source: &str, start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+
+    for (i, c) in source[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(start + i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Removes every section whose start is matched by a regex (from the start
+/// of a line), along with the line break that ends it.
+fn remove_sections(source: &str, regex: &Regex) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(m) = regex.find(rest) {
+        let Some(mut end) = block_end(rest, m.end() - 1) else {
+            break;
+        };
+
+        // Remove the line break ending the section, and a blank line after it
+        // if the section was preceded by one
+        let blank_before = rest[..m.start()]
+            .trim_end_matches([' ', '\t'])
+            .ends_with("\n\n");
+        for _ in 0..if blank_before { 2 } else { 1 } {
+            match rest[end..].find('\n') {
+                Some(i) if rest[end..end + i].trim().is_empty() => end += i + 1,
+                _ => break,
+            }
+        }
+
+        result.push_str(&rest[..m.start()]);
+        rest = &rest[end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Turns `requirements` sections into `runtime` sections.
+fn rename_requirements(source: &str, unsupported: &mut Vec<String>) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(captures) = REQUIREMENTS_REGEX.captures(rest) {
+        let m = captures.get(0).unwrap();
+        let Some(end) = block_end(rest, m.end() - 1) else {
+            break;
+        };
+
+        let body = &rest[m.end()..end];
+        if FPGA_REGEX.is_match(body) {
+            unsupported.push(String::from("the `fpga` requirement"));
+        }
+
+        result.push_str(&rest[..m.start()]);
+        result.push_str(&captures[1]);
+        result.push_str("runtime");
+        result.push_str(&captures[2]);
+        result.push_str(
+            &RENAMED_KEY_REGEX.replace_all(body, |captures: &regex::Captures<'_>| {
+                let key = match &captures[2] {
+                    "max_retries" => "maxRetries",
+                    _ => "returnCodes",
+                };
+                format!("{}{key}{}", &captures[1], &captures[3])
+            }),
+        );
+        rest = &rest[end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_to_1_1() {
+        let source = r#"version 1.2
+
+task hello {
+  command <<< echo hello >>>
+
+  requirements {
+    container: "ubuntu:latest"
+    max_retries: 2
+  }
+
+  hints {
+    short_task: true
+  }
+
+  output {
+    String greeting = read_string(stdout())
+  }
+}
+"#;
+
+        let (transformed, unsupported) = transform(source, Dialect::V1_1);
+        assert_eq!(
+            transformed,
+            r#"version 1.1
+
+task hello {
+  command <<< echo hello >>>
+
+  runtime {
+    container: "ubuntu:latest"
+    maxRetries: 2
+  }
+
+  output {
+    String greeting = read_string(stdout())
+  }
+}
+"#
+        );
+        assert!(unsupported.is_empty());
+
+        let (_, unsupported) = transform(
+            "version 1.2\nworkflow w {\n  Array[String] s = split(\"a b\", \" \")\n  Int c = \
+             task.cpu\n}\n",
+            Dialect::V1_1,
+        );
+        assert_eq!(unsupported, ["the `task` variable", "the `split` function"]);
+
+        let source = "version 1.1\nworkflow w { hints { } }\n";
+        assert_eq!(
+            transform(source, Dialect::V1_1),
+            (source.to_string(), vec![])
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Selecting a subset of conformance tests.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use bon::Builder;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::conformance::Capability;
+use crate::conformance::Tag;
+use crate::conformance::Test;
+
+/// Criteria for selecting conformance tests.
+///
+/// Every criterion that is set must match for a test to be selected; an
+/// empty filter selects every test.
+#[derive(Builder, Clone, Debug, Default)]
+pub struct TestFilter {
+    /// Patterns of which a test's name must match at least one.
+    ///
+    /// Test names are matched without the `.wdl` extension.
+    #[builder(default)]
+    include: Vec<Pattern>,
+
+    /// Patterns of which a test's name must match none.
+    #[builder(default)]
+    exclude: Vec<Pattern>,
+
+    /// Tags of which a test must have at least one.
+    #[builder(default)]
+    tags: Vec<Tag>,
+
+    /// Tags of which a test must have none.
+    #[builder(default)]
+    exclude_tags: Vec<Tag>,
+
+    /// The available capabilities, all of which a test's required
+    /// capabilities must be among.
+    capabilities: Option<Vec<Capability>>,
+
+    /// Patterns of which the heading of the specification section containing
+    /// a test must contain at least one (case-insensitively).
+    #[builder(default)]
+    sections: Vec<String>,
+
+    /// WDL versions of which a test's version must be one.
+    #[builder(default)]
+    versions: Vec<String>,
+
+    /// The shard whose tests are selected.
+    shard: Option<Shard>,
+
+    /// The names of which a test's name must be one.
+    names: Option<BTreeSet<String>>,
+}
+
+impl TestFilter {
+    /// Returns whether a test is selected by the filter.
+    pub fn matches(&self, test: &Test) -> bool {
+        let name = test.file_name().trim_end_matches(".wdl");
+
+        if !self.include.is_empty() && !self.include.iter().any(|p| p.matches(name)) {
+            return false;
+        }
+
+        if self.exclude.iter().any(|p| p.matches(name)) {
+            return false;
+        }
+
+        if !self.tags.is_empty() && !test.config().tags().iter().any(|t| self.tags.contains(t)) {
+            return false;
+        }
+
+        if test
+            .config()
+            .tags()
+            .iter()
+            .any(|t| self.exclude_tags.contains(t))
+        {
+            return false;
+        }
+
+        if let Some(available) = &self.capabilities
+            && !test
+                .config()
+                .capabilities()
+                .iter()
+                .all(|c| available.contains(c))
+        {
+            return false;
+        }
+
+        if !self.sections.is_empty() {
+            let section = test.section().unwrap_or_default().to_lowercase();
+            if !self
+                .sections
+                .iter()
+                .any(|pattern| section.contains(&pattern.to_lowercase()))
+            {
+                return false;
+            }
+        }
+
+        if !self.versions.is_empty()
+            && !test
+                .version()
+                .is_some_and(|version| self.versions.iter().any(|v| v == version))
+        {
+            return false;
+        }
+
+        if let Some(shard) = &self.shard
+            && !shard.contains(name)
+        {
+            return false;
+        }
+
+        if let Some(names) = &self.names
+            && !names.contains(test.file_name())
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// A pattern that test names are matched against.
+///
+/// A pattern is either a glob (see [`Pattern::glob()`]) or, when parsed from
+/// a string, a regular expression in the syntax of the `regex` crate.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pattern {
+    /// The pattern as it was written.
+    source: String,
+
+    /// The regular expression that names are matched with.
+    regex: Regex,
+}
+
+impl Pattern {
+    /// Creates a pattern from a glob, in which `*` matches any sequence of
+    /// characters and `?` matches a single character.
+    ///
+    /// A glob with a wildcard must match the whole name (e.g., `string_*`
+    /// matches `string_join` but not `substring_find`), while a glob without
+    /// one matches any name that contains it.
+    pub fn glob(glob: &str) -> Self {
+        let mut pattern = String::new();
+        let mut wildcard = false;
+        for c in glob.chars() {
+            match c {
+                '*' => {
+                    wildcard = true;
+                    pattern.push_str(".*");
+                }
+                '?' => {
+                    wildcard = true;
+                    pattern.push('.');
+                }
+                c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+        }
+
+        if wildcard {
+            pattern = format!("^{pattern}$");
+        }
+
+        Self {
+            source: glob.to_string(),
+            regex: Regex::new(&pattern).expect("escaped glob to be a valid regex"),
+        }
+    }
+
+    /// Gets the pattern as it was written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns whether a name matches the pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = regex::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            source: s.to_string(),
+            regex: Regex::new(s)?,
+        })
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// One of several disjoint shards that together contain every test.
+///
+/// Tests are assigned to shards by a stable hash of their name, so every
+/// shard of a run selects the same tests regardless of the other filters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    /// The zero-based index of the shard.
+    index: usize,
+
+    /// The total number of shards.
+    count: usize,
+}
+
+impl Shard {
+    /// Creates a new shard, returning `None` if the index is out of range.
+    pub fn new(index: usize, count: usize) -> Option<Self> {
+        (index < count).then_some(Self { index, count })
+    }
+
+    /// Gets the zero-based index of the shard.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Gets the total number of shards.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns whether the test with the given name belongs to the shard.
+    pub fn contains(&self, name: &str) -> bool {
+        // FNV-1a, which unlike the standard library's hasher is stable across
+        // releases and platforms.
+        let hash = name
+            .trim_end_matches(".wdl")
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+            });
+
+        hash % self.count as u64 == self.index as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::test::Config;
+
+    #[test]
+    fn matches() {
+        let test = Test::builder()
+            .file_name(String::from("hello_world.wdl"))
+            .src(String::from("version 1.2\nworkflow hello_world {}\n"))
+            .section(String::from("Struct Definition"))
+            .config(Config::default())
+            .build();
+
+        let filter = |filter: TestFilter| filter.matches(&test);
+
+        assert!(filter(TestFilter::default()));
+        assert!(filter(
+            TestFilter::builder()
+                .include(vec![Pattern::glob("hello")])
+                .build()
+        ));
+        assert!(!filter(
+            TestFilter::builder()
+                .exclude(vec![Pattern::glob("world")])
+                .build()
+        ));
+        assert!(!filter(
+            TestFilter::builder()
+                .include(vec![Pattern::glob("world_*")])
+                .build()
+        ));
+        assert!(filter(
+            TestFilter::builder()
+                .include(vec![Pattern::glob("hello_*"), "^$".parse().unwrap()])
+                .build()
+        ));
+        assert!(!filter(
+            TestFilter::builder().tags(vec![Tag::Static]).build()
+        ));
+        assert!(filter(
+            TestFilter::builder()
+                .exclude_tags(vec![Tag::Static])
+                .build()
+        ));
+        assert!(filter(
+            TestFilter::builder()
+                .sections(vec![String::from("struct")])
+                .build()
+        ));
+        assert!(filter(
+            TestFilter::builder()
+                .versions(vec![String::from("1.2")])
+                .build()
+        ));
+        assert!(!filter(
+            TestFilter::builder()
+                .versions(vec![String::from("1.1")])
+                .build()
+        ));
+        assert!(filter(
+            TestFilter::builder()
+                .names(BTreeSet::from([String::from("hello_world.wdl")]))
+                .build()
+        ));
+        assert!(!filter(
+            TestFilter::builder().names(BTreeSet::new()).build()
+        ));
+    }
+
+    #[test]
+    fn patterns() {
+        let glob = Pattern::glob("string_*");
+        assert!(glob.matches("string_join"));
+        assert!(!glob.matches("substring_find"));
+
+        let glob = Pattern::glob("map?");
+        assert!(glob.matches("maps"));
+        assert!(!glob.matches("map"));
+        assert!(!glob.matches("bitmaps"));
+
+        let glob = Pattern::glob("arr.y");
+        assert!(glob.matches("nested_arr.y_test"));
+        assert!(!glob.matches("array"));
+
+        let regex = "^(read|write)_(json|tsv)$".parse::<Pattern>().unwrap();
+        assert!(regex.matches("read_tsv"));
+        assert!(!regex.matches("read_tsv_header"));
+        assert_eq!(regex.to_string(), "^(read|write)_(json|tsv)$");
+
+        assert!("(".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn shards() {
+        assert!(Shard::new(3, 3).is_none());
+
+        let names = (0..100)
+            .map(|i| format!("test_{i}.wdl"))
+            .collect::<Vec<_>>();
+        let shards = (0..3)
+            .map(|index| Shard::new(index, 3).unwrap())
+            .collect::<Vec<_>>();
+
+        for name in &names {
+            assert_eq!(
+                shards.iter().filter(|shard| shard.contains(name)).count(),
+                1
+            );
+        }
+
+        assert!(
+            shards
+                .iter()
+                .all(|shard| names.iter().any(|name| shard.contains(name)))
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! Inference of the workflow or task a conformance test executes.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::wdl;
+use crate::wdl::Target;
+
+/// A piece of evidence considered when inferring the target of a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Evidence {
+    /// The WDL contains a workflow, or no workflow and a single task.
+    Structure(Target),
+    /// Every input key is prefixed with the name of this target.
+    InputPrefix(Target),
+    /// The target was named in the test config.
+    Config(String),
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Evidence::Structure(target) => {
+                write!(f, "the WDL structure suggests `{}`", target.name())
+            }
+            Evidence::InputPrefix(target) => {
+                write!(f, "the input keys are prefixed with `{}`", target.name())
+            }
+            Evidence::Config(name) => write!(f, "the test config names `{name}`"),
+        }
+    }
+}
+
+/// A reason the target of a test could not be inferred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// The config names a target that can be inferred from the WDL.
+    RedundantWithStructure,
+    /// The config names a target that can be inferred from the input keys.
+    RedundantWithInput,
+    /// The WDL structure and the input keys suggest different targets.
+    Disagreement {
+        /// The target suggested by the WDL structure.
+        structure: Target,
+        /// The target suggested by the input keys.
+        input: Target,
+    },
+    /// The input keys are prefixed with a name that is not a workflow or
+    /// task in the WDL.
+    UnknownInputPrefix(String),
+    /// The input keys are prefixed with more than one name.
+    AmbiguousInputPrefixes(BTreeSet<String>),
+    /// There are multiple tasks, no workflow, no inputs, and no target in the
+    /// config.
+    TargetRequired,
+    /// The config names a task that is not in the WDL.
+    UnknownConfigTarget(String),
+    /// The WDL contains no workflow or task.
+    NoTargets,
+    /// The WDL contains more than one workflow.
+    MultipleWorkflows(Vec<String>),
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::RedundantWithStructure => write!(
+                f,
+                "target should not be specified in config, as it can be inferred from the WDL \
+                 directly"
+            ),
+            Conflict::RedundantWithInput => write!(
+                f,
+                "target should not be specified in config, as it can be inferred from the input \
+                 JSON directly"
+            ),
+            Conflict::Disagreement { structure, input } => write!(
+                f,
+                "conflicting target inference: WDL structure suggests `{structure:?}` but input \
+                 suggests `{input:?}`"
+            ),
+            Conflict::UnknownInputPrefix(prefix) => write!(
+                f,
+                "input prefix `{prefix}` does not match any workflow or task in WDL"
+            ),
+            Conflict::AmbiguousInputPrefixes(prefixes) => {
+                let prefixes = prefixes
+                    .iter()
+                    .map(|p| format!("`{p}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "ambiguous input prefixes: {prefixes}")
+            }
+            Conflict::TargetRequired => {
+                write!(
+                    f,
+                    "target required in config: cannot infer which task to run"
+                )
+            }
+            Conflict::UnknownConfigTarget(name) => write!(f, "target `{name}` not found in tasks"),
+            Conflict::NoTargets => write!(f, "no workflow or task found in WDL source"),
+            Conflict::MultipleWorkflows(names) => {
+                let names = names
+                    .iter()
+                    .map(|name| format!("`{name}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "multiple workflows found in WDL source: {names}")
+            }
+        }
+    }
+}
+
+/// The outcome of inferring the target of a test.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InferenceOutcome {
+    /// The chosen target, if one could be inferred without conflicts.
+    target: Option<Target>,
+
+    /// The evidence that was considered.
+    evidence: Vec<Evidence>,
+
+    /// The other workflows and tasks within the WDL.
+    alternatives: Vec<Target>,
+
+    /// The reasons the target could not be inferred.
+    conflicts: Vec<Conflict>,
+}
+
+impl InferenceOutcome {
+    /// Gets the chosen target.
+    pub fn target(&self) -> Option<&Target> {
+        self.target.as_ref()
+    }
+
+    /// Gets the evidence that was considered.
+    pub fn evidence(&self) -> &[Evidence] {
+        &self.evidence
+    }
+
+    /// Gets the other workflows and tasks within the WDL.
+    pub fn alternatives(&self) -> &[Target] {
+        &self.alternatives
+    }
+
+    /// Gets the reasons the target could not be inferred.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+}
+
+/// Infers the target of a test according to `SPEC.md` rules.
+///
+/// The rules are:
+///
+/// 1. If the WDL contains a workflow, that workflow is executed.
+/// 2. If the WDL contains no workflow and a single task, that task is
+///    executed.
+/// 3. If the WDL contains no workflow and multiple tasks, the target is
+///    inferred from the input key prefixes.
+/// 4. Otherwise, the target must be named in the test config, and it must
+///    not be named there if it can be inferred.
+pub fn infer_target(
+    src: &str,
+    input: Option<&Value>,
+    config_target: Option<&str>,
+) -> Result<InferenceOutcome> {
+    let decls = wdl::parse_wdl_declarations(src);
+    let mut outcome = InferenceOutcome::default();
+
+    // A document may only contain a single workflow.
+    if decls.workflows().len() > 1 {
+        outcome
+            .conflicts
+            .push(Conflict::MultipleWorkflows(decls.workflows().to_vec()));
+    }
+
+    // Check if there's a single unambiguous target.
+    let single_target = decls.single_target();
+    if let Some(target) = &single_target {
+        outcome.evidence.push(Evidence::Structure(target.clone()));
+    }
+
+    // Check if target can be inferred from the input JSON.
+    let input_target = match infer_target_from_input(input, &decls) {
+        Ok(target) => target,
+        Err(conflict) => {
+            outcome.conflicts.push(conflict);
+            None
+        }
+    };
+    if let Some(target) = &input_target {
+        outcome.evidence.push(Evidence::InputPrefix(target.clone()));
+    }
+
+    if let Some(name) = config_target {
+        outcome.evidence.push(Evidence::Config(name.to_string()));
+    }
+
+    let chosen = match (single_target, input_target, config_target) {
+        _ if !outcome.conflicts.is_empty() => Err(None),
+
+        // If target can be inferred but `config.target` is provided, error.
+        (Some(_), _, Some(_)) => Err(Some(Conflict::RedundantWithStructure)),
+        (_, Some(_), Some(_)) => Err(Some(Conflict::RedundantWithInput)),
+
+        // If single target exists, use it.
+        (Some(target), None, None) => Ok(target),
+
+        // If can infer from input JSON, use that.
+        (None, Some(target), None) => Ok(target),
+
+        // If both single target and input infer the same target, use it.
+        (Some(single), Some(input), None) if single == input => Ok(single),
+
+        // If single target and input disagree, error.
+        (Some(structure), Some(input), None) => {
+            Err(Some(Conflict::Disagreement { structure, input }))
+        }
+
+        // No workflow, no tasks, error.
+        (None, None, _) if decls.tasks().is_empty() => Err(Some(Conflict::NoTargets)),
+
+        // Multiple tasks, no input, no config target, error.
+        (None, None, None) => Err(Some(Conflict::TargetRequired)),
+
+        // Multiple tasks, no input, config target provided, ok if it exists.
+        (None, None, Some(name)) => {
+            if decls.tasks().iter().any(|task| task == name) {
+                Ok(Target::Task(name.to_string()))
+            } else {
+                Err(Some(Conflict::UnknownConfigTarget(name.to_string())))
+            }
+        }
+    };
+
+    match chosen {
+        Ok(target) => outcome.target = Some(target),
+        Err(conflict) => outcome.conflicts.extend(conflict),
+    }
+
+    outcome.alternatives = decls
+        .workflows()
+        .iter()
+        .map(|name| Target::Workflow(name.clone()))
+        .chain(decls.tasks().iter().map(|name| Target::Task(name.clone())))
+        .filter(|target| Some(target) != outcome.target.as_ref())
+        .collect();
+
+    Ok(outcome)
+}
+
+/// Attempts to infer the target from input JSON parameter prefixes.
+///
+/// Returns `Some(target)` if all input parameters share a common prefix.
+/// Returns `None` if there are no inputs or no common prefix can be determined.
+fn infer_target_from_input(
+    input: Option<&Value>,
+    decls: &wdl::WdlDeclarations,
+) -> Result<Option<Target>, Conflict> {
+    let Some(obj) = input.and_then(Value::as_object) else {
+        return Ok(None);
+    };
+
+    // Extract unique prefixes from input keys (e.g., "my_task.input1" → "my_task")
+    let mut prefixes = obj
+        .keys()
+        .filter_map(|key| key.split('.').next().map(|s| s.to_string()))
+        .collect::<BTreeSet<String>>();
+
+    // Must have exactly one unique prefix
+    match prefixes.len() {
+        0 => Ok(None),
+        1 => {
+            let prefix = prefixes.pop_first().unwrap();
+
+            // Check if prefix matches workflow or task
+            if decls.workflows().contains(&prefix) {
+                Ok(Some(Target::Workflow(prefix)))
+            } else if decls.tasks().contains(&prefix) {
+                Ok(Some(Target::Task(prefix)))
+            } else {
+                Err(Conflict::UnknownInputPrefix(prefix))
+            }
+        }
+        _ => Err(Conflict::AmbiguousInputPrefixes(prefixes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn outcomes() {
+        let src = "version 1.2\ntask a {}\ntask b {}\n";
+
+        let outcome = infer_target(src, Some(&json!({"b.x": 1})), None).unwrap();
+        assert_eq!(outcome.target(), Some(&Target::Task(String::from("b"))));
+        assert_eq!(
+            outcome.evidence(),
+            [Evidence::InputPrefix(Target::Task(String::from("b")))]
+        );
+        assert_eq!(outcome.alternatives(), [Target::Task(String::from("a"))]);
+        assert!(outcome.conflicts().is_empty());
+
+        let outcome = infer_target(src, None, None).unwrap();
+        assert_eq!(outcome.target(), None);
+        assert_eq!(outcome.conflicts(), [Conflict::TargetRequired]);
+
+        let outcome = infer_target("workflow a {}\nworkflow b {}\n", None, None).unwrap();
+        assert_eq!(outcome.target(), None);
+        assert_eq!(
+            outcome.conflicts(),
+            [Conflict::MultipleWorkflows(vec![
+                String::from("a"),
+                String::from("b")
+            ])]
+        );
+
+        let outcome = infer_target(src, Some(&json!({"a.x": 1, "b.x": 1})), None).unwrap();
+        assert_eq!(
+            outcome.conflicts(),
+            [Conflict::AmbiguousInputPrefixes(BTreeSet::from([
+                String::from("a"),
+                String::from("b")
+            ]))]
+        );
+    }
+}
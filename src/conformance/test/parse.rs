@@ -0,0 +1,217 @@
+//! Positions of conformance tests within the specification, and errors for
+//! conformance tests that could not be parsed.
+
+use std::fmt;
+use std::ops::Range;
+
+use regex::Match;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The name of the file conformance tests are parsed from.
+const SOURCE_NAME: &str = "SPEC.md";
+
+/// The range of lines a conformance test occupies within the specification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lines {
+    /// The one-based first line.
+    start: usize,
+
+    /// The one-based last line (inclusive).
+    end: usize,
+}
+
+impl Lines {
+    /// Gets the lines spanned by a range of byte offsets within the
+    /// specification.
+    pub fn from_offsets(contents: &str, offsets: Range<usize>) -> Self {
+        let start = contents[..offsets.start].matches('\n').count() + 1;
+        let end = start
+            + contents[offsets.start..offsets.end]
+                .trim_end_matches('\n')
+                .matches('\n')
+                .count();
+        Self { start, end }
+    }
+
+    /// Gets the one-based first line.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Gets the one-based last line (inclusive).
+    pub fn end(&self) -> usize {
+        self.end
+    }
+}
+
+impl fmt::Display for Lines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// An error parsing a block within a conformance test, with a position
+/// relative to the start of the block.
+#[derive(Debug)]
+pub(super) struct BlockError {
+    /// The error message.
+    pub message: String,
+
+    /// The one-based line and column within the block, if known.
+    pub position: Option<(usize, usize)>,
+}
+
+impl From<serde_json::Error> for BlockError {
+    fn from(e: serde_json::Error) -> Self {
+        // Drop the " at line X column Y" suffix, as the position is reported
+        // separately relative to the whole specification.
+        let message = e.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(index) => message[..index].to_string(),
+            None => message,
+        };
+
+        Self {
+            message,
+            position: (e.line() > 0).then(|| (e.line(), e.column().max(1))),
+        }
+    }
+}
+
+impl From<json5::Error> for BlockError {
+    fn from(e: json5::Error) -> Self {
+        let json5::Error::Message { msg, location } = e;
+
+        // Syntax errors are rendered by the underlying parser with their own
+        // snippet, the last line of which is the actual message.
+        let message = msg
+            .lines()
+            .last()
+            .map(|line| line.trim().trim_start_matches("= ").to_string())
+            .unwrap_or(msg);
+
+        Self {
+            message,
+            position: location.map(|location| (location.line, location.column)),
+        }
+    }
+}
+
+/// A conformance test within the specification that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The file name of the test.
+    test: String,
+
+    /// The name of the block that could not be parsed.
+    block: &'static str,
+
+    /// The one-based line within the specification.
+    line: usize,
+
+    /// The one-based column within the specification.
+    column: usize,
+
+    /// The error message.
+    message: String,
+
+    /// The line of the specification the error occurred on.
+    source_line: String,
+}
+
+impl ParseError {
+    /// Creates a new parse error for a block within a specification.
+    pub(super) fn new(
+        contents: &str,
+        test: &str,
+        block: &'static str,
+        m: Match<'_>,
+        error: BlockError,
+    ) -> Self {
+        let offset = match error.position {
+            Some((line, column)) => {
+                let line_start = m
+                    .as_str()
+                    .split_inclusive('\n')
+                    .take(line - 1)
+                    .map(str::len)
+                    .sum::<usize>();
+                let line_text = m.as_str()[line_start..].lines().next().unwrap_or_default();
+                let column = line_text
+                    .char_indices()
+                    .nth(column - 1)
+                    .map(|(i, _)| i)
+                    .unwrap_or(line_text.len());
+                m.start() + line_start + column
+            }
+            // Point at the start of the block's contents.
+            None => m.start() + (m.as_str().len() - m.as_str().trim_start().len()),
+        };
+
+        let line_start = contents[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let source_line = contents[line_start..]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        Self {
+            test: test.to_string(),
+            block,
+            line: contents[..offset].matches('\n').count() + 1,
+            column: contents[line_start..offset].chars().count() + 1,
+            message: error.message,
+            source_line,
+        }
+    }
+
+    /// Gets the file name of the test.
+    pub fn test(&self) -> &str {
+        &self.test
+    }
+
+    /// Gets the one-based line within the specification.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Gets the one-based column within the specification.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Gets the error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let indent = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        writeln!(
+            f,
+            "{SOURCE_NAME}:{line}:{column}: unable to parse the {block} block of `{test}`: \
+             {message}",
+            line = self.line,
+            column = self.column,
+            block = self.block,
+            test = self.test,
+            message = self.message
+        )?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{number} | {}", self.source_line)?;
+        write!(f, "{gutter} | {indent}^")
+    }
+}
+
+impl std::error::Error for ParseError {}
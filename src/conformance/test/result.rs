@@ -1,9 +1,17 @@
 //! Results of a conformance test.
 
 use std::fmt;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::Serialize;
+use strum_macros::EnumIter;
 
 use crate::conformance::Capability;
+use crate::conformance::Dialect;
 use crate::conformance::test::ReturnCode;
+use crate::conformance::test::validation::MismatchedKey;
+use crate::wdl::Construct;
 
 /// The result of running a conformance test.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -30,13 +38,24 @@ pub enum FailureReason {
     OutputMismatch {
         /// Details about the mismatch.
         details: String,
+        /// A unified diff of the expected and actual outputs, if available.
+        diff: Option<String>,
+        /// The key for each mismatch, if the outputs could be compared.
+        keys: Vec<MismatchedKey>,
     },
     /// The command execution failed with an error.
     ExecutionError(String),
+    /// `spectool` itself failed to prepare or inspect the test.
+    HarnessError(String),
     /// The test was expected to fail but succeeded.
     UnexpectedSuccess,
     /// No output was produced by the command.
     NoOutput,
+    /// An expected file was not produced or had unexpected contents.
+    FileMismatch {
+        /// Details about the mismatch.
+        details: String,
+    },
     /// The output selector failed.
     SelectorError {
         /// The selector that failed.
@@ -44,6 +63,300 @@ pub enum FailureReason {
         /// Details about the error.
         details: String,
     },
+    /// The output selector produced `null`.
+    SelectorNull {
+        /// The selector that produced `null`.
+        selector: String,
+    },
+    /// The output selector produced a value other than an object.
+    SelectorNotObject {
+        /// The selector that produced the value.
+        selector: String,
+        /// The type of the value that was produced.
+        found: String,
+        /// A snippet of the value that was produced.
+        snippet: String,
+    },
+    /// The test took longer than its maximum duration.
+    DurationExceeded {
+        /// The maximum duration of the test.
+        limit: Duration,
+        /// The execution time of the test.
+        actual: Duration,
+    },
+}
+
+/// The broad category of a test failure.
+///
+/// Categories are stable and intended for filtering and charting failures
+/// without parsing display strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// The engine did not execute the test as expected.
+    Execution,
+    /// The engine produced outputs that did not match the expected outputs.
+    Validation,
+    /// `spectool` itself failed while preparing or inspecting the test.
+    Harness,
+    /// The test exceeded a time limit.
+    Timeout,
+    /// The output selector could not be applied.
+    Selector,
+}
+
+impl FailureCategory {
+    /// Gets the stable string representation of the category.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureCategory::Execution => "execution",
+            FailureCategory::Validation => "validation",
+            FailureCategory::Harness => "harness",
+            FailureCategory::Timeout => "timeout",
+            FailureCategory::Selector => "selector",
+        }
+    }
+}
+
+impl fmt::Display for FailureCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A short, stable machine-readable code for a test failure.
+///
+/// There is exactly one code per [`FailureReason`] variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCode {
+    /// See [`FailureReason::ReturnCodeMismatch`].
+    ReturnCodeMismatch,
+    /// See [`FailureReason::OutputMismatch`].
+    OutputMismatch,
+    /// See [`FailureReason::ExecutionError`].
+    ExecutionError,
+    /// See [`FailureReason::HarnessError`].
+    HarnessError,
+    /// See [`FailureReason::UnexpectedSuccess`].
+    UnexpectedSuccess,
+    /// See [`FailureReason::NoOutput`].
+    NoOutput,
+    /// See [`FailureReason::FileMismatch`].
+    FileMismatch,
+    /// See [`FailureReason::SelectorError`].
+    SelectorError,
+    /// See [`FailureReason::SelectorNull`].
+    SelectorNull,
+    /// See [`FailureReason::SelectorNotObject`].
+    SelectorNotObject,
+    /// See [`FailureReason::DurationExceeded`].
+    DurationExceeded,
+}
+
+impl FailureCode {
+    /// Gets the stable string representation of the code.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureCode::ReturnCodeMismatch => "return_code_mismatch",
+            FailureCode::OutputMismatch => "output_mismatch",
+            FailureCode::ExecutionError => "execution_error",
+            FailureCode::HarnessError => "harness_error",
+            FailureCode::UnexpectedSuccess => "unexpected_success",
+            FailureCode::NoOutput => "no_output",
+            FailureCode::FileMismatch => "file_mismatch",
+            FailureCode::SelectorError => "selector_error",
+            FailureCode::SelectorNull => "selector_null",
+            FailureCode::SelectorNotObject => "selector_not_object",
+            FailureCode::DurationExceeded => "duration_exceeded",
+        }
+    }
+
+    /// Gets the category that failures with this code belong to.
+    pub fn category(&self) -> FailureCategory {
+        match self {
+            FailureCode::ReturnCodeMismatch
+            | FailureCode::ExecutionError
+            | FailureCode::UnexpectedSuccess => FailureCategory::Execution,
+            FailureCode::OutputMismatch | FailureCode::NoOutput | FailureCode::FileMismatch => {
+                FailureCategory::Validation
+            }
+            FailureCode::HarnessError => FailureCategory::Harness,
+            FailureCode::SelectorError
+            | FailureCode::SelectorNull
+            | FailureCode::SelectorNotObject => FailureCategory::Selector,
+            FailureCode::DurationExceeded => FailureCategory::Timeout,
+        }
+    }
+}
+
+/// An extended explanation of a failure code for triaging failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Explanation {
+    /// What `spectool` checked when it reported the failure.
+    checked: &'static str,
+
+    /// Common engine-side causes of the failure.
+    causes: &'static [&'static str],
+
+    /// The sections of the specification relevant to the failure.
+    spec_sections: &'static [&'static str],
+}
+
+impl Explanation {
+    /// Gets what `spectool` checked when it reported the failure.
+    pub fn checked(&self) -> &'static str {
+        self.checked
+    }
+
+    /// Gets the common engine-side causes of the failure.
+    pub fn causes(&self) -> &'static [&'static str] {
+        self.causes
+    }
+
+    /// Gets the sections of the specification relevant to the failure.
+    pub fn spec_sections(&self) -> &'static [&'static str] {
+        self.spec_sections
+    }
+}
+
+impl FailureCode {
+    /// Gets the extended explanation of this code.
+    pub fn explain(&self) -> Explanation {
+        match self {
+            FailureCode::ReturnCodeMismatch => Explanation {
+                checked: "The exit code of the engine command was compared against the \
+                          `return_code` in the test's configuration, if one is set.",
+                causes: &[
+                    "The engine failed to parse, type check, or execute the document.",
+                    "The engine does not support a feature used by the test; consider \
+                     whether a capability should be declared.",
+                    "The command template is wrong for the engine (e.g., a missing flag), \
+                     so every test fails the same way.",
+                ],
+                spec_sections: &["`return_codes` requirement"],
+            },
+            FailureCode::OutputMismatch => Explanation {
+                checked: "The outputs reported by the engine were parsed as JSON and \
+                          compared key-by-key against the test's expected outputs, after \
+                          any output selector, exclusions, and key remapping were applied.",
+                causes: &[
+                    "Output keys are not prefixed with the workflow or task name (see \
+                     `--strip-output-prefix` and `rename_outputs`).",
+                    "The engine's output JSON is nested (see `--output-selector`).",
+                    "`File` outputs are reported as absolute paths that do not end with \
+                     the expected file name.",
+                    "A standard library function or coercion behaves differently than \
+                     the specification requires.",
+                ],
+                spec_sections: &["JSON Output Format", "Standard Library"],
+            },
+            FailureCode::ExecutionError => Explanation {
+                checked: "The engine command was started with `bash -c`.",
+                causes: &[
+                    "The engine executable is not on `PATH`.",
+                    "`bash` is not available or the working directory is not accessible.",
+                ],
+                spec_sections: &[],
+            },
+            FailureCode::HarnessError => Explanation {
+                checked: "`spectool` prepared the test's working directory and collected \
+                          the engine's outputs.",
+                causes: &[
+                    "The working directory ran out of space or has restrictive \
+                     permissions.",
+                    "This is usually not caused by the engine; please report it to \
+                     `spectool` if it persists.",
+                ],
+                spec_sections: &[],
+            },
+            FailureCode::UnexpectedSuccess => Explanation {
+                checked: "The test is marked with `fail: true`, so the engine command was \
+                          expected to exit with a non-zero code.",
+                causes: &[
+                    "The engine accepts a document that the specification says is \
+                     invalid (e.g., a type error that is not caught).",
+                    "The engine reports errors without exiting with a non-zero code.",
+                ],
+                spec_sections: &["Type Coercion"],
+            },
+            FailureCode::NoOutput => Explanation {
+                checked: "The test has expected outputs, so the outputs file was read after \
+                          the engine command exited successfully.",
+                causes: &[
+                    "The engine does not write outputs to `~{output}`; consider \
+                     `--outputs-from stdout`, `--outputs-from auto`, or `--output-file`.",
+                    "The engine writes outputs somewhere relative to its own working \
+                     directory rather than the test's.",
+                ],
+                spec_sections: &["JSON Output Format"],
+            },
+            FailureCode::FileMismatch => Explanation {
+                checked: "Each file in the test's `expected_files` was looked up relative \
+                          to the test's working directory and, if a `sha256` was given, its \
+                          contents were hashed and compared.",
+                causes: &[
+                    "The engine writes files relative to its own working directory rather \
+                     than the test's (see the `~{workdir}` substitution).",
+                    "The engine does not preserve the file's name or directory structure.",
+                    "The file's contents differ, e.g., because of a missing trailing \
+                     newline.",
+                ],
+                spec_sections: &[],
+            },
+            FailureCode::SelectorError => Explanation {
+                checked: "The `--output-selector` was applied to the engine's outputs \
+                          before validation.",
+                causes: &[
+                    "The selector is not valid `jq` syntax.",
+                    "The selector does not produce exactly one value for the engine's \
+                     output JSON (e.g., the outputs are not nested as expected).",
+                ],
+                spec_sections: &[],
+            },
+            FailureCode::SelectorNull => Explanation {
+                checked: "The output selectors were applied to the engine's outputs and \
+                          the result was expected to be an object of outputs.",
+                causes: &[
+                    "The selector refers to a field the engine's output JSON does not \
+                     have (e.g., `.outputs` when outputs are not nested).",
+                    "The engine failed without writing its usual output JSON.",
+                ],
+                spec_sections: &[],
+            },
+            FailureCode::SelectorNotObject => Explanation {
+                checked: "The output selectors were applied to the engine's outputs and \
+                          the result was expected to be an object of outputs.",
+                causes: &[
+                    "The selector selects a single output value rather than the object \
+                     containing the outputs.",
+                    "The engine reports outputs as an array of name/value pairs; convert \
+                     them with `from_entries` or a similar transform.",
+                ],
+                spec_sections: &[],
+            },
+            FailureCode::DurationExceeded => Explanation {
+                checked: "The execution time of the test, excluding any setup time reported \
+                          by the engine, was compared against the `max_duration` in the \
+                          test's configuration because `--enforce-durations` was passed.",
+                causes: &[
+                    "The engine has a high fixed overhead per task, such as starting a \
+                     container or submitting a job.",
+                    "Container images were pulled during the test; consider \
+                     `--pull-images`.",
+                    "Too many tests were run in parallel for the machine; consider \
+                     lowering `--n-cpu`.",
+                ],
+                spec_sections: &[],
+            },
+        }
+    }
+}
+
+impl fmt::Display for FailureCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
 /// The reason a test was skipped.
@@ -53,6 +366,37 @@ pub enum SkipReason {
     Ignored,
     /// The test requires capabilities that were not provided.
     MissingCapabilities(Vec<Capability>),
+    /// The test targets a task, but the engine cannot execute bare tasks.
+    TasksUnsupported,
+    /// The test uses constructs the engine does not support.
+    UnsupportedConstructs(Vec<Construct>),
+    /// The test uses features that could not be transformed to the target
+    /// dialect.
+    Untransformable {
+        /// The target dialect.
+        dialect: Dialect,
+        /// The features that could not be transformed.
+        features: Vec<String>,
+    },
+    /// The test refers to paths within the `data` directory that are not
+    /// resources in the specification.
+    UndeclaredData(Vec<String>),
+    /// The run was aborted after reaching the maximum number of failures.
+    Aborted {
+        /// The maximum number of failures.
+        max_failures: usize,
+    },
+    /// The run was aborted because the first tests all failed to execute
+    /// with the same error.
+    CrashLoop {
+        /// The number of tests that failed with the same error.
+        tests: usize,
+    },
+    /// The test was not run because the run reached its time budget.
+    RunTimeout {
+        /// The time budget of the run.
+        timeout: Duration,
+    },
 }
 
 impl TestResult {
@@ -72,6 +416,48 @@ impl TestResult {
     }
 }
 
+impl FailureReason {
+    /// Gets the machine-readable code for this failure.
+    pub fn code(&self) -> FailureCode {
+        match self {
+            FailureReason::ReturnCodeMismatch { .. } => FailureCode::ReturnCodeMismatch,
+            FailureReason::OutputMismatch { .. } => FailureCode::OutputMismatch,
+            FailureReason::ExecutionError(_) => FailureCode::ExecutionError,
+            FailureReason::HarnessError(_) => FailureCode::HarnessError,
+            FailureReason::UnexpectedSuccess => FailureCode::UnexpectedSuccess,
+            FailureReason::NoOutput => FailureCode::NoOutput,
+            FailureReason::FileMismatch { .. } => FailureCode::FileMismatch,
+            FailureReason::SelectorError { .. } => FailureCode::SelectorError,
+            FailureReason::SelectorNull { .. } => FailureCode::SelectorNull,
+            FailureReason::SelectorNotObject { .. } => FailureCode::SelectorNotObject,
+            FailureReason::DurationExceeded { .. } => FailureCode::DurationExceeded,
+        }
+    }
+
+    /// Gets the category of this failure.
+    pub fn category(&self) -> FailureCategory {
+        self.code().category()
+    }
+
+    /// Gets a unified diff of the expected and actual outputs, if the outputs
+    /// did not match.
+    pub fn diff(&self) -> Option<&str> {
+        match self {
+            FailureReason::OutputMismatch { diff, .. } => diff.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Gets the key for each mismatch between the expected and actual
+    /// outputs.
+    pub fn mismatched_keys(&self) -> &[MismatchedKey] {
+        match self {
+            FailureReason::OutputMismatch { keys, .. } => keys,
+            _ => &[],
+        }
+    }
+}
+
 impl fmt::Display for FailureReason {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -82,21 +468,53 @@ impl fmt::Display for FailureReason {
                     expected, actual
                 )
             }
-            FailureReason::OutputMismatch { details } => {
+            FailureReason::OutputMismatch { details, .. } => {
                 write!(f, "output mismatch: {}", details)
             }
             FailureReason::ExecutionError(e) => {
                 write!(f, "execution error: {}", e)
             }
+            FailureReason::HarnessError(e) => {
+                write!(f, "harness error: {}", e)
+            }
             FailureReason::UnexpectedSuccess => {
                 write!(f, "test marked with `fail: true` but succeeded")
             }
             FailureReason::NoOutput => {
                 write!(f, "no output produced—the command may have failed")
             }
+            FailureReason::FileMismatch { details } => {
+                write!(f, "file mismatch: {}", details)
+            }
             FailureReason::SelectorError { selector, details } => {
                 write!(f, "selector error for `{}`: {}", selector, details)
             }
+            FailureReason::SelectorNull { selector } => {
+                write!(
+                    f,
+                    "selector `{}` produced `null`—the selected field may not exist",
+                    selector
+                )
+            }
+            FailureReason::SelectorNotObject {
+                selector,
+                found,
+                snippet,
+            } => {
+                write!(
+                    f,
+                    "selector `{}` produced {} rather than an object: {}",
+                    selector, found, snippet
+                )
+            }
+            FailureReason::DurationExceeded { limit, actual } => {
+                write!(
+                    f,
+                    "took {:.2}s, exceeding the maximum duration of {:.2}s",
+                    actual.as_secs_f64(),
+                    limit.as_secs_f64()
+                )
+            }
         }
     }
 }
@@ -113,6 +531,113 @@ impl fmt::Display for SkipReason {
                     .join(", ");
                 write!(f, "missing required capabilities: {}", caps_str)
             }
+            SkipReason::TasksUnsupported => {
+                write!(f, "the engine cannot execute a task directly")
+            }
+            SkipReason::UnsupportedConstructs(constructs) => {
+                let constructs = constructs
+                    .iter()
+                    .map(|c| c.description())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "the engine does not support {constructs}")
+            }
+            SkipReason::Untransformable { dialect, features } => {
+                write!(
+                    f,
+                    "cannot be transformed to WDL {dialect}: uses {}",
+                    features.join(", ")
+                )
+            }
+            SkipReason::UndeclaredData(references) => {
+                let references = references
+                    .iter()
+                    .map(|r| format!("`data/{r}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(
+                    f,
+                    "refers to {references}, which the specification does not define"
+                )
+            }
+            SkipReason::Aborted { max_failures } => {
+                write!(f, "the run was aborted after {max_failures} failure(s)")
+            }
+            SkipReason::CrashLoop { tests } => {
+                write!(
+                    f,
+                    "the run was aborted after the first {tests} tests failed with the same error"
+                )
+            }
+            SkipReason::RunTimeout { timeout } => {
+                write!(
+                    f,
+                    "not run: the run reached its timeout of {}s",
+                    timeout.as_secs()
+                )
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn codes_are_unique() {
+        let codes = FailureCode::iter()
+            .map(|code| code.as_str())
+            .collect::<HashSet<_>>();
+        assert_eq!(codes.len(), FailureCode::iter().count());
+    }
+
+    #[test]
+    fn codes_serialize_as_strings() {
+        for code in FailureCode::iter() {
+            let json = serde_json::to_value(code).unwrap();
+            assert_eq!(json, serde_json::Value::String(code.as_str().to_string()));
+        }
+    }
+
+    #[test]
+    fn every_code_is_explained() {
+        for code in FailureCode::iter() {
+            let explanation = code.explain();
+            assert!(!explanation.checked().is_empty());
+            assert!(!explanation.causes().is_empty());
+        }
+    }
+
+    #[test]
+    fn reason_category() {
+        assert_eq!(
+            FailureReason::NoOutput.category(),
+            FailureCategory::Validation
+        );
+        assert_eq!(
+            FailureReason::HarnessError(String::from("oops")).category(),
+            FailureCategory::Harness
+        );
+        assert_eq!(
+            FailureReason::SelectorError {
+                selector: String::from(".outputs"),
+                details: String::from("oops"),
+            }
+            .code(),
+            FailureCode::SelectorError
+        );
+        assert_eq!(
+            FailureReason::DurationExceeded {
+                limit: Duration::from_secs(120),
+                actual: Duration::from_secs(150),
+            }
+            .category(),
+            FailureCategory::Timeout
+        );
+    }
+}
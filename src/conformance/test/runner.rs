@@ -1,18 +1,27 @@
 //! The conformance test runner.
 
+use std::collections::HashSet;
 use std::fs::DirEntry;
+use std::io::Write as _;
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
 use std::sync::LazyLock;
 
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::bail;
+use bon::Builder;
+use rayon::prelude::*;
 use regex::Regex;
 use tracing::info;
 use tracing::warn;
 
 use crate::conformance;
+use crate::diagnostic::Code;
+use crate::diagnostic::Diagnostic;
+use crate::wdl;
 
 /// Replaces the WDL version statement in source code.
 ///
@@ -26,25 +35,96 @@ fn inject_version(src: &str, inject_wdl_version: &str) -> String {
         .to_string()
 }
 
+/// Rewrites the source of a test with a preprocessing command.
+///
+/// The command is run with `bash`, receiving the source on stdin and the file
+/// name of the test in `SPECTOOL_TEST`, and the rewritten source is read from
+/// its stdout.
+fn preprocess(command: &str, file_name: &str, src: &str) -> Result<String> {
+    let mut child = Command::new("bash")
+        .arg("-c")
+        .arg(command)
+        .env("SPECTOOL_TEST", file_name)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("running preprocessing command `{command}`"))?;
+
+    // The source is written from another thread so that a command writing
+    // before it has read all of its input cannot deadlock. A command that
+    // does not read its input closes the pipe early, which is not an error.
+    let mut stdin = child.stdin.take().expect("stdin to be piped");
+    let output = std::thread::scope(|scope| {
+        scope.spawn(move || stdin.write_all(src.as_bytes()).ok());
+        child.wait_with_output()
+    })
+    .with_context(|| format!("running preprocessing command `{command}`"))?;
+
+    if !output.status.success() {
+        bail!(
+            "preprocessing command failed for test `{file_name}` ({status}): {stderr}",
+            status = output.status,
+            stderr = String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).with_context(|| {
+        format!("preprocessing command wrote a source that is not UTF-8 for test `{file_name}`")
+    })
+}
+
+/// Options for compiling conformance tests.
+#[derive(Builder, Clone, Debug, Default)]
+pub struct CompileOptions {
+    /// Whether to remove existing entries in the conformance tests directory.
+    #[builder(default)]
+    force: bool,
+
+    /// The WDL version to inject into each test.
+    inject_wdl_version: Option<String>,
+
+    /// A command that rewrites the source of each test.
+    preprocess_cmd: Option<String>,
+
+    /// The WDL version to transform each test to.
+    target_dialect: Option<conformance::Dialect>,
+
+    /// The constructs the engine does not support.
+    #[builder(default)]
+    unsupported_constructs: Vec<wdl::Construct>,
+
+    /// Whether to wrap tests that target a task in a generated workflow.
+    #[builder(default)]
+    wrap_tasks: bool,
+
+    /// The syntax accepted within the JSON blocks of each test.
+    #[builder(default)]
+    json_syntax: conformance::JsonSyntax,
+
+    /// How the tests are laid out within the conformance tests directory.
+    #[builder(default)]
+    layout: conformance::Layout,
+}
+
 /// A runner for conformance tests.
-pub struct Runner {
+///
+/// The runner borrows the resources from the specification it was compiled
+/// from; the tests own only their sources and blocks.
+pub struct Runner<'a> {
     /// The root directory of the conformance test suite.
     root_dir: PathBuf,
 
     /// The conformance tests to execute.
     tests: conformance::Tests,
+
+    /// The resources the tests may refer to within the `data` directory.
+    resources: conformance::Resources<'a>,
 }
 
-impl Runner {
+impl<'a> Runner<'a> {
     /// Compiles conformance tests.
-    pub fn compile<S: AsRef<str>>(
-        root_dir: PathBuf,
-        contents: S,
-        force: bool,
-        inject_wdl_version: Option<String>,
-    ) -> Result<Self> {
-        let contents = contents.as_ref();
-
+    pub fn compile(root_dir: PathBuf, contents: &'a str, options: &CompileOptions) -> Result<Self> {
         //=========================================//
         // Prepare the conformance tests directory //
         //=========================================//
@@ -54,7 +134,7 @@ impl Runner {
             root_dir.display()
         );
 
-        ensure_empty_dir(&root_dir, force)?;
+        ensure_empty_dir(&root_dir, options.force)?;
 
         //==================================//
         // Ensure the data directory exists //
@@ -69,58 +149,111 @@ impl Runner {
 
         let resources = conformance::Resources::compile(contents)?;
 
-        for resource in resources.iter() {
-            let file_path = data_dir.join(resource.filename());
-            if file_path.exists() {
-                bail!(
-                    "resource with name `{}` was attempted to be written multiple times",
-                    file_path.display()
-                );
-            }
+        let mut file_paths = HashSet::new();
+        let resources_to_write = resources
+            .iter()
+            .map(|resource| {
+                let file_path = data_dir.join(resource.filename());
+                if !file_paths.insert(file_path.clone()) {
+                    bail!(
+                        "resource with name `{}` was attempted to be written multiple times",
+                        file_path.display()
+                    );
+                }
+                Ok((resource, file_path))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Resources are written in parallel; errors are reported in the order
+        // the resources appear in the specification
+        resources_to_write
+            .into_par_iter()
+            .map(|(resource, file_path)| {
+                if let Some(parent) = file_path.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("creating parent directories for `{}`", resource.filename())
+                    })?;
+                }
 
-            if let Some(parent) = file_path.parent() {
-                std::fs::create_dir_all(parent).with_context(|| {
-                    format!("creating parent directories for `{}`", resource.filename())
-                })?;
-            }
-
-            std::fs::write(file_path, resource.src())
-                .with_context(|| format!("writing `{}` resource file", resource.filename()))?;
-        }
+                std::fs::write(file_path, resource.src())
+                    .with_context(|| format!("writing `{}` resource file", resource.filename()))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect::<Result<()>>()?;
 
         //===============================//
         // Compile the conformance tests //
         //===============================//
 
-        let mut tests = conformance::Tests::compile(contents)?;
+        let mut tests = conformance::Tests::compile_with_syntax(contents, options.json_syntax)?;
 
-        for test in tests.tests_mut() {
-            // Infer and validate the target before writing the test
-            test.infer_and_validate_target()
-                .with_context(|| format!("inferring target for test `{}`", test.file_name()))?;
+        for error in tests.errors() {
+            warn!("skipping test `{}`:\n{error}", error.test());
+        }
 
-            let file_path = root_dir.join(test.file_name());
-            if file_path.exists() {
-                bail!(
-                    "conformance test with name `{}` was attempted to be written multiple times",
-                    file_path.display()
-                );
+        // Every test is written to its own path, so duplicates are caught
+        // before any test is written
+        let mut file_paths = HashSet::new();
+        let file_paths = tests
+            .tests()
+            .map(|test| {
+                let file_path = root_dir.join(test.relative_path(options.layout));
+                if !file_paths.insert(file_path.clone()) {
+                    bail!(
+                        "conformance test with name `{}` was attempted to be written multiple \
+                         times",
+                        file_path.display()
+                    );
+                }
+                Ok(file_path)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Tests are compiled in parallel; the error of the earliest test in
+        // the specification is reported, regardless of which finished first
+        tests
+            .tests_mut()
+            .zip(file_paths)
+            .par_bridge()
+            .map(|(test, file_path)| {
+                (
+                    test.index(),
+                    compile_test(test, file_path, options, &resources),
+                )
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|(index, result)| Some((index, result.err()?)))
+            .min_by_key(|(index, _)| *index)
+            .map_or(Ok(()), |(_, e)| Err(e))?;
+
+        // Check that every local import resolves to a test or resource,
+        // relative to the directory the test was written to
+        for test in tests.tests() {
+            let decls = wdl::parse_wdl_declarations(test.src());
+            let dir = test
+                .path()
+                .and_then(Path::parent)
+                .unwrap_or(root_dir.as_path());
+
+            for import in decls.imports().iter().filter(|import| import.is_local()) {
+                if !dir.join(import.uri()).exists() {
+                    warn!(
+                        "test `{}` imports `{}`, which is not a test or resource in the \
+                         specification",
+                        test.file_name(),
+                        import.uri()
+                    );
+                }
             }
-
-            // Apply version injection if requested
-            let src = if let Some(ref version) = inject_wdl_version {
-                inject_version(test.src(), version)
-            } else {
-                test.src().to_string()
-            };
-
-            std::fs::write(&file_path, src)
-                .with_context(|| format!("writing `{}` conformance test", test.file_name()))?;
-
-            test.set_path(file_path);
         }
 
-        Ok(Self { root_dir, tests })
+        Ok(Self {
+            root_dir,
+            tests,
+            resources,
+        })
     }
 
     /// Gets the root directory.
@@ -128,10 +261,120 @@ impl Runner {
         self.root_dir.as_path()
     }
 
+    /// Gets the resources the tests may refer to within the `data` directory.
+    pub fn resources(&self) -> &conformance::Resources<'a> {
+        &self.resources
+    }
+
     /// Gets the tests within the runner.
     pub fn tests(&self) -> impl Iterator<Item = &conformance::Test> {
         self.tests.tests()
     }
+
+    /// Gets the tests within the runner that are selected by a filter.
+    pub fn filter<'b>(
+        &'b self,
+        filter: &'b conformance::TestFilter,
+    ) -> impl Iterator<Item = &'b conformance::Test> {
+        self.tests.filter(filter)
+    }
+}
+
+/// Infers the target of a test, checks its inputs and data references, and
+/// writes its (possibly rewritten) source to a path.
+fn compile_test(
+    test: &mut conformance::Test,
+    file_path: PathBuf,
+    options: &CompileOptions,
+    resources: &conformance::Resources<'_>,
+) -> Result<()> {
+    // Infer and validate the target before writing the test
+    test.infer_and_validate_target()
+        .with_context(|| format!("inferring target for test `{}`", test.file_name()))?;
+
+    if let Err(e) = test.validate_input_keys() {
+        warn!("{e:#}");
+    }
+
+    // Catch references to data the specification never defines, so the
+    // test is skipped rather than failing at runtime for a less obvious
+    // reason
+    let undeclared = test
+        .data_references()
+        .into_iter()
+        .filter(|reference| !resources.covers(reference))
+        .collect::<Vec<_>>();
+    if !undeclared.is_empty() {
+        warn!(
+            "test `{}` refers to `data/{}`, which is not a resource in the \
+             specification",
+            test.file_name(),
+            undeclared.join("`, `data/")
+        );
+        test.set_undeclared_data(undeclared);
+    }
+
+    // Wrap task targets for engines that can only execute workflows
+    if options.wrap_tasks
+        && let Err(e) = test.wrap_task()
+    {
+        warn!("unable to wrap task in test `{}`: {e:#}", test.file_name());
+    }
+
+    // Apply version injection if requested
+    let src = if let Some(ref version) = options.inject_wdl_version {
+        inject_version(test.src(), version)
+    } else {
+        test.src().to_string()
+    };
+
+    // Transform the test to the target dialect if requested
+    let src = match options.target_dialect {
+        Some(dialect) => {
+            let (transformed, unsupported) = conformance::test::dialect::transform(&src, dialect);
+            if transformed != src {
+                test.set_preprocessed();
+            }
+            if !unsupported.is_empty() {
+                info!(
+                    "test `{}` cannot be transformed to WDL {dialect}: uses {}",
+                    test.file_name(),
+                    unsupported.join(", ")
+                );
+                test.set_untransformable(unsupported);
+            }
+            transformed
+        }
+        None => src,
+    };
+
+    // Apply the preprocessing command if requested
+    let src = match &options.preprocess_cmd {
+        Some(command) => {
+            let preprocessed = preprocess(command, test.file_name(), &src)?;
+            if preprocessed != src {
+                test.set_preprocessed();
+            }
+            preprocessed
+        }
+        None => src,
+    };
+
+    // Detect the constructs the engine does not support in the source
+    // as it will be run
+    test.set_unsupported_constructs(wdl::constructs(&src, &options.unsupported_constructs));
+
+    if let Some(parent) = file_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating the directory for test `{}`", test.file_name()))?;
+    }
+
+    std::fs::write(&file_path, src)
+        .with_context(|| format!("writing `{}` conformance test", test.file_name()))?;
+
+    test.set_path(file_path);
+
+    Ok(())
 }
 
 /// Ensures that the directory exists and is empty.
@@ -170,7 +413,7 @@ fn ensure_empty_dir<P: AsRef<Path>>(path: P, force: bool) -> Result<()> {
                 }
             }
         } else {
-            bail!(
+            let message = format!(
                 "{count} existing {entries_exist} in {dir}, but `--force` was not provided to overwrite {them}",
                 count = entries.len(),
                 dir = path.display(),
@@ -183,8 +426,174 @@ fn ensure_empty_dir<P: AsRef<Path>>(path: P, force: bool) -> Result<()> {
                 },
                 them = { if entries.len() == 1 { "it" } else { "them" } },
             );
+
+            return Err(Diagnostic::new(Code::DirNotEmpty, message)
+                .with_hint(
+                    "pass `--force` to remove the existing entries, or choose an empty directory \
+                     with `--conformance-test-dir`",
+                )
+                .into());
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::SkipReason;
+
+    /// Builds a specification with a single test that reads a resource.
+    fn spec(resource: &str) -> String {
+        format!(
+            r#"
+<details>
+<summary>
+Resource: {resource}
+
+```txt
+hello
+```
+
+</summary>
+</details>
+
+<details>
+<summary>
+Example: read_greeting.wdl
+
+```wdl
+version 1.2
+
+task read_greeting {{
+  input {{
+    File f
+  }}
+
+  command <<< cat ~{{f}} >>>
+
+  output {{
+    String greeting = read_string(stdout())
+  }}
+}}
+```
+</summary>
+<p>
+Example input:
+
+```json
+{{"read_greeting.f": "data/greetings.txt"}}
+```
+
+Example output:
+
+```json
+{{"read_greeting.greeting": "hello"}}
+```
+</p>
+</details>
+"#
+        )
+    }
+
+    #[test]
+    fn undeclared_resources() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = CompileOptions::default();
+
+        let declared = spec("greetings.txt");
+        let runner = Runner::compile(dir.path().join("ok"), &declared, &options);
+        assert_eq!(runner.unwrap().tests().count(), 1);
+
+        let undeclared = spec("greeting.txt");
+        let runner = Runner::compile(dir.path().join("missing"), &undeclared, &options).unwrap();
+        let test = runner.tests().next().unwrap();
+        assert_eq!(test.undeclared_data(), ["greetings.txt"]);
+        assert_eq!(
+            SkipReason::UndeclaredData(test.undeclared_data().to_vec()).to_string(),
+            "refers to `data/greetings.txt`, which the specification does not define"
+        );
+    }
+
+    #[test]
+    fn preprocessing() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = CompileOptions::builder()
+            .preprocess_cmd(String::from(
+                r##"echo "# $SPECTOOL_TEST"; sed 's/1\.2/1.1/'"##,
+            ))
+            .build();
+
+        let spec = spec("greetings.txt");
+        let runner = Runner::compile(dir.path().join("ok"), &spec, &options).unwrap();
+        let test = runner.tests().next().unwrap();
+        assert!(test.preprocessed());
+
+        let src = std::fs::read_to_string(test.path().unwrap()).unwrap();
+        assert!(src.starts_with("# read_greeting.wdl\n\nversion 1.1\n"));
+        assert!(test.src().contains("version 1.2"));
+
+        let options = CompileOptions::builder()
+            .preprocess_cmd(String::from("echo oops >&2; exit 3"))
+            .build();
+        let error = Runner::compile(dir.path().join("failed"), &spec, &options)
+            .err()
+            .unwrap();
+        assert!(error.to_string().ends_with("(exit status: 3): oops"));
+    }
+
+    #[test]
+    fn section_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec = spec("greetings.txt");
+        let example = &spec[spec.find("<details>\n<summary>\nExample").unwrap()..];
+        let spec = format!("## Strings\n{spec}\n## 🗑 Files\n{example}");
+
+        let error = Runner::compile(dir.path().join("flat"), &spec, &CompileOptions::default())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("written multiple times"));
+
+        let options = CompileOptions::builder()
+            .layout(conformance::Layout::Sections)
+            .build();
+        let root_dir = dir.path().join("sections");
+        let runner = Runner::compile(root_dir.clone(), &spec, &options).unwrap();
+        let paths = runner
+            .tests()
+            .map(|test| test.path().unwrap().strip_prefix(&root_dir).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                Path::new("strings/read_greeting.wdl"),
+                Path::new("files/read_greeting.wdl")
+            ]
+        );
+    }
+
+    #[test]
+    fn deterministic_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec = spec("greetings.txt");
+        let example = &spec[spec.find("<details>\n<summary>\nExample").unwrap()..];
+        let spec = (0..8).fold(spec.clone(), |spec, i| {
+            spec + &example.replace("read_greeting", &format!("read_greeting_{i}"))
+        });
+        let options = CompileOptions::builder()
+            .preprocess_cmd(String::from("echo \"$SPECTOOL_TEST\" >&2; exit 1"))
+            .build();
+
+        for i in 0..4 {
+            let error = Runner::compile(dir.path().join(i.to_string()), &spec, &options)
+                .err()
+                .unwrap();
+            assert!(
+                error
+                    .to_string()
+                    .ends_with("(exit status: 1): read_greeting.wdl")
+            );
+        }
+    }
+}
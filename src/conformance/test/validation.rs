@@ -1,12 +1,485 @@
 //! Validation of conformance test results.
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::path::PathBuf;
 
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::bail;
+use bon::Builder;
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
 use serde_json::Value;
+use sha2::Digest as _;
+use sha2::Sha256;
+
+use crate::conformance::ExpectedFile;
+use crate::conformance::test::FileComparison;
+use crate::conformance::test::FloatTolerance;
+
+pub mod diff;
+
+/// Remaps top-level output keys on both sides of a comparison.
+///
+/// This keeps validation meaningful when a target has been wrapped or when an
+/// engine reports output keys in a different form than the specification
+/// (e.g., without the `<target>.` prefix).
+#[derive(Builder, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyMap {
+    /// Explicit renames from one key to another.
+    #[builder(default)]
+    renames: BTreeMap<String, String>,
+
+    /// Whether to strip everything up to and including the first `.` from
+    /// each key.
+    #[builder(default)]
+    strip_prefix: bool,
+}
+
+impl KeyMap {
+    /// Returns whether the key map does not change any keys.
+    pub fn is_identity(&self) -> bool {
+        self.renames.is_empty() && !self.strip_prefix
+    }
+
+    /// Returns a new key map with the renames from `other` added.
+    ///
+    /// Renames in `other` take precedence over renames in `self`.
+    pub fn merged(&self, other: &KeyMap) -> KeyMap {
+        let mut renames = self.renames.clone();
+        renames.extend(other.renames.clone());
+
+        KeyMap {
+            renames,
+            strip_prefix: self.strip_prefix || other.strip_prefix,
+        }
+    }
+
+    /// Remaps a key or an output path that starts with a key (such as an
+    /// entry in `exclude_outputs`).
+    ///
+    /// Explicit renames are applied before the prefix is stripped. When
+    /// several renames match, the longest (most specific) one is applied.
+    pub fn key(&self, key: &str) -> String {
+        // Remap the first reference token of a JSON pointer
+        if let Some(pointer) = key.strip_prefix('/') {
+            let (first, rest) = pointer
+                .split_once('/')
+                .map(|(first, rest)| (first, format!("/{rest}")))
+                .unwrap_or((pointer, String::new()));
+            let first = first.replace("~1", "/").replace("~0", "~");
+            let remapped = self.key(&first).replace('~', "~0").replace('/', "~1");
+            return format!("/{remapped}{rest}");
+        }
+
+        let renamed = self
+            .renames
+            .iter()
+            .filter(|(from, _)| {
+                key.strip_prefix(from.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+            })
+            .max_by_key(|(from, _)| from.len())
+            .map(|(from, to)| format!("{to}{}", &key[from.len()..]))
+            .unwrap_or_else(|| key.to_string());
+
+        match renamed.split_once('.') {
+            Some((_, rest)) if self.strip_prefix => rest.to_string(),
+            _ => renamed,
+        }
+    }
+
+    /// Remaps the top-level keys of a JSON object.
+    ///
+    /// Values other than objects are returned unchanged.
+    pub fn apply(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(obj) if !self.is_identity() => Value::Object(
+                obj.iter()
+                    .map(|(key, value)| (self.key(key), value.clone()))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+}
+
+/// Options that control how outputs are validated.
+#[derive(Builder, Clone, Debug, Default)]
+pub struct Options {
+    /// Output keys or paths to exclude from validation.
+    #[builder(default)]
+    exclude: Vec<String>,
+
+    /// Output keys or paths that must not appear in the actual output,
+    /// written the same way as exclusions.
+    #[builder(default)]
+    forbidden: Vec<String>,
+
+    /// The remapping applied to output keys before comparison.
+    #[builder(default)]
+    key_map: KeyMap,
+
+    /// The tolerance within which floating point outputs are considered
+    /// equal.
+    #[builder(default)]
+    float_tolerance: FloatTolerance,
+
+    /// How actual outputs that name existing files are compared.
+    #[builder(default)]
+    compare_files: FileComparison,
+
+    /// Output keys or paths of arrays whose elements may appear in any order.
+    #[builder(default)]
+    unordered: Vec<String>,
+
+    /// Whether the elements of every array may appear in any order.
+    #[builder(default)]
+    unordered_arrays: bool,
+
+    /// Output keys or paths whose expected strings are regular expressions
+    /// that the actual strings must match in full.
+    #[builder(default)]
+    patterns: Vec<String>,
+
+    /// The directory relative paths within the actual outputs are resolved
+    /// against (the working directory of the test).
+    ///
+    /// If not set, they are resolved against the current directory.
+    workdir: Option<PathBuf>,
+}
+
+impl Options {
+    /// Replaces the tolerance within which floating point outputs are
+    /// considered equal.
+    pub fn with_float_tolerance(mut self, tolerance: FloatTolerance) -> Self {
+        self.float_tolerance = tolerance;
+        self
+    }
+
+    /// Replaces the output keys or paths of arrays whose elements may appear
+    /// in any order.
+    pub fn with_unordered(mut self, unordered: Vec<String>) -> Self {
+        self.unordered = unordered;
+        self
+    }
+
+    /// Sets whether the elements of every array may appear in any order.
+    pub fn with_unordered_arrays(mut self, unordered_arrays: bool) -> Self {
+        self.unordered_arrays = unordered_arrays;
+        self
+    }
+
+    /// Sets the directory relative paths within the actual outputs are
+    /// resolved against.
+    pub fn with_workdir(mut self, workdir: PathBuf) -> Self {
+        self.workdir = Some(workdir);
+        self
+    }
+
+    /// Gets the tolerance within which floating point outputs are considered
+    /// equal.
+    pub fn float_tolerance(&self) -> FloatTolerance {
+        self.float_tolerance
+    }
+}
+
+/// Validates that the actual output matches the expected output using the
+/// provided options.
+///
+/// Output keys on both sides are remapped with the options' [`KeyMap`] before
+/// forbidden keys are checked, excluded keys are removed, and the values are
+/// compared.
+pub fn validate(expected: &Value, actual: &Value, options: &Options) -> Result<()> {
+    let exclude = options
+        .exclude
+        .iter()
+        .map(|path| options.key_map.key(path))
+        .collect::<Vec<_>>();
+    let forbidden = options
+        .forbidden
+        .iter()
+        .map(|path| Exclusion::parse(&options.key_map.key(path)))
+        .collect::<Vec<_>>();
+    let unordered = options
+        .unordered
+        .iter()
+        .map(|path| options.key_map.key(path))
+        .collect::<Vec<_>>();
+    let patterns = options
+        .patterns
+        .iter()
+        .map(|path| Exclusion::parse(&options.key_map.key(path)))
+        .collect::<Vec<_>>();
+
+    let expected = options.key_map.apply(expected);
+    let workdir = options.workdir.as_deref().unwrap_or(Path::new("."));
+    let actual = normalize_separators(&options.key_map.apply(actual), workdir);
+    let actual = resolve_files(&actual, options.compare_files, workdir)?;
+
+    if let Some(path) = find_forbidden(&actual, &forbidden, &Location::default()) {
+        bail!("forbidden key in actual output: `{path}`");
+    }
+
+    let actual = if unordered.is_empty() && !options.unordered_arrays {
+        actual
+    } else {
+        let paths = unordered
+            .iter()
+            .map(|entry| Exclusion::parse(entry))
+            .chain(options.unordered_arrays.then_some(Exclusion::Any))
+            .collect::<Vec<_>>();
+
+        align_unordered(
+            &filter_outputs(&expected, &exclude),
+            &filter_outputs(&actual, &exclude),
+            &paths,
+            &Location::default(),
+            options.float_tolerance,
+        )
+    };
+
+    let actual = if patterns.is_empty() {
+        actual
+    } else {
+        match_patterns(&expected, &actual, &patterns, &Location::default(), false)?
+    };
+
+    validate_outputs(&expected, &actual, &exclude, options.float_tolerance)
+}
+
+/// Replaces each actual string that matches the regular expression in the
+/// same place of the expected outputs with the expression itself, so that
+/// comparing the result only reports the strings that do not match.
+///
+/// Expected strings are treated as expressions at and below the locations
+/// given by `patterns` (`within` is set once one has been reached).
+fn match_patterns(
+    expected: &Value,
+    actual: &Value,
+    patterns: &[Exclusion],
+    location: &Location,
+    within: bool,
+) -> Result<Value> {
+    Ok(match (expected, actual) {
+        (Value::String(pattern), Value::String(s)) if within => {
+            let regex = Regex::new(&format!("^(?:{pattern})$")).with_context(|| {
+                format!("invalid pattern for `{}`: \"{pattern}\"", location.indexed)
+            })?;
+
+            if regex.is_match(s) {
+                expected.clone()
+            } else {
+                actual.clone()
+            }
+        }
+        (Value::Object(e_obj), Value::Object(a_obj)) => Value::Object(
+            a_obj
+                .iter()
+                .map(|(key, a_val)| {
+                    let location = location.key(key);
+                    let value = match e_obj.get(key) {
+                        Some(e_val) => match_patterns(
+                            e_val,
+                            a_val,
+                            patterns,
+                            &location,
+                            within || patterns.iter().any(|p| p.matches(key, &location)),
+                        )?,
+                        None => a_val.clone(),
+                    };
+
+                    Ok((key.clone(), value))
+                })
+                .collect::<Result<_>>()?,
+        ),
+        (Value::Array(e_arr), Value::Array(a_arr)) => Value::Array(
+            a_arr
+                .iter()
+                .enumerate()
+                .map(|(i, a_val)| match e_arr.get(i) {
+                    Some(e_val) => {
+                        let location = location.index(i);
+                        let within = within || patterns.iter().any(|p| p.matches("", &location));
+                        match_patterns(e_val, a_val, patterns, &location, within)
+                    }
+                    None => Ok(a_val.clone()),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        _ => actual.clone(),
+    })
+}
+
+/// Reorders the elements of each unordered array within the actual outputs to
+/// line up with the matching elements of the expected outputs.
+///
+/// Each expected element is paired with the first remaining actual element
+/// that it matches; actual elements without a match are kept at the end in
+/// their original order, so that comparing the result reports the mismatch.
+/// Arrays are compared as multisets this way, with the same path
+/// normalization and floating point tolerance as any other comparison.
+fn align_unordered(
+    expected: &Value,
+    actual: &Value,
+    unordered: &[Exclusion],
+    location: &Location,
+    tolerance: FloatTolerance,
+) -> Value {
+    match (expected, actual) {
+        (Value::Object(e_obj), Value::Object(a_obj)) => Value::Object(
+            a_obj
+                .iter()
+                .map(|(key, a_val)| {
+                    let location = location.key(key);
+                    let value = match (e_obj.get(key), a_val) {
+                        (Some(e_val @ Value::Array(_)), Value::Array(a_arr))
+                            if unordered.iter().any(|u| u.matches(key, &location)) =>
+                        {
+                            align_elements(e_val, a_arr, tolerance)
+                        }
+                        (Some(e_val), _) => {
+                            align_unordered(e_val, a_val, unordered, &location, tolerance)
+                        }
+                        (None, _) => a_val.clone(),
+                    };
+
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        (Value::Array(e_arr), Value::Array(a_arr)) => Value::Array(
+            a_arr
+                .iter()
+                .enumerate()
+                .map(|(i, a_val)| match e_arr.get(i) {
+                    Some(e_val) => {
+                        align_unordered(e_val, a_val, unordered, &location.index(i), tolerance)
+                    }
+                    None => a_val.clone(),
+                })
+                .collect(),
+        ),
+        _ => actual.clone(),
+    }
+}
+
+/// Reorders the elements of an actual array to line up with those of an
+/// expected array.
+fn align_elements(expected: &Value, actual: &[Value], tolerance: FloatTolerance) -> Value {
+    let Value::Array(expected) = expected else {
+        return Value::Array(actual.to_vec());
+    };
+
+    let mut remaining = actual.iter().collect::<Vec<_>>();
+    let mut aligned = Vec::with_capacity(actual.len());
+
+    for e_val in expected {
+        if let Some(position) = remaining
+            .iter()
+            .position(|a_val| matches(e_val, a_val, tolerance))
+        {
+            aligned.push(remaining.remove(position).clone());
+        }
+    }
+
+    aligned.extend(remaining.into_iter().cloned());
+    Value::Array(aligned)
+}
+
+/// Rewrites each string within the actual outputs that is a relative path
+/// separated by `\` (as written by engines running natively on Windows) to be
+/// separated by `/` instead, provided the path exists within `workdir`.
+///
+/// Other strings containing `\`, such as regular expressions or escaped text,
+/// are left as they are.
+fn normalize_separators(value: &Value, workdir: &Path) -> Value {
+    match value {
+        Value::String(s) if s.contains('\\') && !is_windows_absolute(s) => {
+            let normalized = s.replace('\\', "/");
+            if Path::new(&normalized).is_relative() && workdir.join(&normalized).exists() {
+                Value::String(normalized)
+            } else {
+                value.clone()
+            }
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| normalize_separators(value, workdir))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), normalize_separators(value, workdir)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Replaces each string within the actual outputs that names an existing file
+/// with the file's hash or contents, according to how files are compared.
+///
+/// The expected output for such a file is then the hash or contents rather
+/// than the file's name. Relative paths are resolved against `workdir`.
+fn resolve_files(value: &Value, comparison: FileComparison, workdir: &Path) -> Result<Value> {
+    Ok(match value {
+        Value::String(s) if comparison != FileComparison::Name && workdir.join(s).is_file() => {
+            let contents = std::fs::read(workdir.join(s))
+                .with_context(|| format!("reading output file `{s}`"))?;
+
+            Value::String(if comparison == FileComparison::Sha256 {
+                format!("{:x}", Sha256::digest(&contents))
+            } else {
+                String::from_utf8_lossy(&contents).into_owned()
+            })
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| resolve_files(value, comparison, workdir))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| Ok((key.clone(), resolve_files(value, comparison, workdir)?)))
+                .collect::<Result<_>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+/// Validates that every expected file exists within a directory and, if a
+/// hash was provided, that its contents match.
+pub fn validate_files(dir: &Path, expected: &[ExpectedFile]) -> Result<()> {
+    for file in expected {
+        let path = dir.join(file.path());
+
+        if !path.is_file() {
+            bail!("expected file `{}` was not produced", file.path());
+        }
+
+        if let Some(expected_hash) = file.sha256() {
+            let contents = std::fs::read(&path)
+                .with_context(|| format!("reading expected file `{}`", file.path()))?;
+            let actual_hash = format!("{:x}", Sha256::digest(&contents));
+
+            if !actual_hash.eq_ignore_ascii_case(expected_hash) {
+                bail!(
+                    "contents of `{}` do not match: expected SHA-256 {expected_hash}, got \
+                     {actual_hash}",
+                    file.path()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
 
 /// Validates that the actual output matches the expected output.
 ///
@@ -18,47 +491,307 @@ use serde_json::Value;
 /// * `expected` - The expected output value from the test specification
 /// * `actual` - The actual output value from the test execution
 /// * `exclude` - A list of output keys to exclude from validation
+/// * `tolerance` - The tolerance within which numbers are considered equal
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` if the outputs match, or an error with details about the mismatch.
-pub fn validate_outputs(expected: &Value, actual: &Value, exclude: &[String]) -> Result<()> {
+/// Returns `Ok(())` if the outputs match, or a [`Mismatch`] error with every
+/// mismatch that was found.
+pub fn validate_outputs(
+    expected: &Value,
+    actual: &Value,
+    exclude: &[String],
+    tolerance: FloatTolerance,
+) -> Result<()> {
     let expected_filtered = filter_outputs(expected, exclude);
     let actual_filtered = filter_outputs(actual, exclude);
 
-    compare_json(&expected_filtered, &actual_filtered, "")
+    let mut mismatches = Vec::new();
+    compare_json(
+        &expected_filtered,
+        &actual_filtered,
+        "",
+        tolerance,
+        &mut mismatches,
+    );
+
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    let pretty = |value: &Value| serde_json::to_string_pretty(value).unwrap_or_default();
+    let diff = diff::unified(
+        &pretty(&expected_filtered),
+        &pretty(&reconcile(&expected_filtered, &actual_filtered, tolerance)),
+    );
+
+    let (keys, mismatches) = mismatches.into_iter().unzip();
+    Err(Mismatch {
+        mismatches,
+        keys,
+        diff,
+    }
+    .into())
+}
+
+/// The kind of a mismatch between expected and actual outputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchKind {
+    /// Booleans differ.
+    Boolean,
+    /// Numbers differ by more than the tolerance.
+    Number,
+    /// Strings that look like paths differ (after normalization).
+    Path,
+    /// Other strings differ.
+    String,
+    /// Arrays have different lengths.
+    ArrayLength,
+    /// A key was expected but is missing.
+    MissingKey,
+    /// A key is present but was not expected.
+    UnexpectedKey,
+    /// Values have different types.
+    Type,
+}
+
+impl MismatchKind {
+    /// Gets the stable string representation of the kind.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MismatchKind::Boolean => "boolean",
+            MismatchKind::Number => "number",
+            MismatchKind::Path => "path",
+            MismatchKind::String => "string",
+            MismatchKind::ArrayLength => "array_length",
+            MismatchKind::MissingKey => "missing_key",
+            MismatchKind::UnexpectedKey => "unexpected_key",
+            MismatchKind::Type => "type",
+        }
+    }
+}
+
+impl std::fmt::Display for MismatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// An output key (or a path within an output) that did not match.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MismatchedKey {
+    /// The dot-separated path to the value, with array indices (e.g.,
+    /// `main.items[0].name`).
+    path: String,
+
+    /// The kind of the mismatch.
+    kind: MismatchKind,
+}
+
+impl MismatchedKey {
+    /// Creates a mismatched key.
+    pub fn new(path: impl Into<String>, kind: MismatchKind) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+        }
+    }
+
+    /// Gets the path to the value.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Gets the path to the value with every array index replaced by `[*]`,
+    /// so that mismatches within different elements are counted together.
+    pub fn generalized_path(&self) -> String {
+        // SAFETY: the pattern is a valid regex.
+        Regex::new(r"\[\d+\]")
+            .unwrap()
+            .replace_all(&self.path, "[*]")
+            .into_owned()
+    }
+
+    /// Gets the kind of the mismatch.
+    pub fn kind(&self) -> MismatchKind {
+        self.kind
+    }
+}
+
+/// The mismatches between expected and actual outputs.
+#[derive(Debug)]
+pub struct Mismatch {
+    /// The message for each mismatch.
+    mismatches: Vec<String>,
+
+    /// The key for each mismatch.
+    keys: Vec<MismatchedKey>,
+
+    /// A unified diff of the expected and actual outputs, if one could be
+    /// produced.
+    diff: Option<String>,
+}
+
+impl Mismatch {
+    /// Gets the message for each mismatch.
+    pub fn mismatches(&self) -> &[String] {
+        &self.mismatches
+    }
+
+    /// Gets the key for each mismatch.
+    pub fn keys(&self) -> &[MismatchedKey] {
+        &self.keys
+    }
+
+    /// Gets a unified diff of the expected and actual outputs.
+    pub fn diff(&self) -> Option<&str> {
+        self.diff.as_deref()
+    }
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.mismatches.as_slice() {
+            [mismatch] => write!(f, "{mismatch}"),
+            mismatches => write!(
+                f,
+                "{} mismatches: {}",
+                mismatches.len(),
+                mismatches.join("; ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// An entry in the list of outputs excluded from validation (or compared as
+/// unordered, matched as patterns, or forbidden).
+#[derive(Debug)]
+enum Exclusion {
+    /// A key at any depth or a dot-separated path to a key.
+    ///
+    /// Array elements are transparent to these paths, so `items.timestamp`
+    /// matches the `timestamp` key of every element of `items`.
+    Path(String),
+
+    /// A dot-separated path with array indices (e.g., `items[0].timestamp`),
+    /// where `[*]` matches any index.
+    Indexed(Regex),
+
+    /// An RFC 6901 JSON pointer (e.g., `/items/0/timestamp`).
+    Pointer(String),
+
+    /// Any location.
+    Any,
+}
+
+impl Exclusion {
+    /// Parses an exclusion.
+    fn parse(entry: &str) -> Self {
+        if entry.starts_with('/') {
+            return Exclusion::Pointer(entry.to_string());
+        }
+
+        if entry.contains('[') {
+            let pattern = regex::escape(entry).replace(r"\[\*\]", r"\[\d+\]");
+            // SAFETY: the pattern is built from escaped text, so it is always
+            // a valid regex.
+            return Exclusion::Indexed(Regex::new(&format!("^{pattern}$")).unwrap());
+        }
+
+        Exclusion::Path(entry.to_string())
+    }
+
+    /// Returns whether the exclusion matches a key at a location.
+    fn matches(&self, key: &str, location: &Location) -> bool {
+        match self {
+            Exclusion::Path(path) => path == key || *path == location.path,
+            Exclusion::Indexed(regex) => regex.is_match(&location.indexed),
+            Exclusion::Pointer(pointer) => *pointer == location.pointer,
+            Exclusion::Any => true,
+        }
+    }
+}
+
+/// The location of a value within a JSON document, in each of the forms
+/// supported by exclusions.
+#[derive(Debug, Default)]
+struct Location {
+    /// The dot-separated path to the value, ignoring array indices.
+    path: String,
+
+    /// The dot-separated path to the value, including array indices.
+    indexed: String,
+
+    /// The JSON pointer to the value.
+    pointer: String,
+}
+
+impl Location {
+    /// Gets the location of a key within the object at this location.
+    fn key(&self, key: &str) -> Self {
+        let join = |path: &str| {
+            if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{path}.{key}")
+            }
+        };
+
+        Location {
+            path: join(&self.path),
+            indexed: join(&self.indexed),
+            pointer: format!(
+                "{}/{}",
+                self.pointer,
+                key.replace('~', "~0").replace('/', "~1")
+            ),
+        }
+    }
+
+    /// Gets the location of an element within the array at this location.
+    fn index(&self, index: usize) -> Self {
+        Location {
+            path: self.path.clone(),
+            indexed: format!("{}[{index}]", self.indexed),
+            pointer: format!("{}/{index}", self.pointer),
+        }
+    }
 }
 
 /// Filters out excluded keys from a JSON value.
 ///
 /// This function recursively processes JSON objects and removes any keys
-/// that are in the exclude list. Supports both simple keys ("timestamp") and
-/// nested paths ("nested.timestamp").
+/// that are in the exclude list. Supports simple keys ("timestamp"), nested
+/// paths ("nested.timestamp"), paths with array indices or wildcards
+/// ("items[*].timestamp"), and JSON pointers ("/items/0/timestamp").
 fn filter_outputs(value: &Value, exclude: &[String]) -> Value {
-    filter_outputs_recursive(value, exclude, "")
+    let exclusions = exclude
+        .iter()
+        .map(|entry| Exclusion::parse(entry))
+        .collect::<Vec<_>>();
+
+    filter_outputs_recursive(value, &exclusions, &Location::default())
 }
 
-/// Recursively filters outputs with path tracking for dot notation support.
-fn filter_outputs_recursive(value: &Value, exclude: &[String], current_path: &str) -> Value {
+/// Recursively filters outputs with location tracking for path support.
+fn filter_outputs_recursive(value: &Value, exclude: &[Exclusion], location: &Location) -> Value {
     match value {
         Value::Object(obj) => {
             let filtered = obj
                 .iter()
                 .filter_map(|(key, val)| {
-                    // Build the full path for this key
-                    let full_path = if current_path.is_empty() {
-                        key.clone()
-                    } else {
-                        format!("{current_path}.{key}")
-                    };
+                    let location = location.key(key);
 
                     // Check if this key or path should be excluded
-                    if exclude.contains(key) || exclude.contains(&full_path) {
+                    if exclude.iter().any(|e| e.matches(key, &location)) {
                         None
                     } else {
                         Some((
                             key.clone(),
-                            filter_outputs_recursive(val, exclude, &full_path),
+                            filter_outputs_recursive(val, exclude, &location),
                         ))
                     }
                 })
@@ -68,7 +801,8 @@ fn filter_outputs_recursive(value: &Value, exclude: &[String], current_path: &st
         Value::Array(arr) => {
             let filtered = arr
                 .iter()
-                .map(|val| filter_outputs_recursive(val, exclude, current_path))
+                .enumerate()
+                .map(|(i, val)| filter_outputs_recursive(val, exclude, &location.index(i)))
                 .collect();
             Value::Array(filtered)
         }
@@ -76,54 +810,160 @@ fn filter_outputs_recursive(value: &Value, exclude: &[String], current_path: &st
     }
 }
 
+/// Finds the first key within a JSON value that matches a forbidden entry,
+/// returning its full path (including any array indices).
+///
+/// Entries are written the same way as exclusions.
+fn find_forbidden(value: &Value, forbidden: &[Exclusion], location: &Location) -> Option<String> {
+    match value {
+        Value::Object(obj) => obj.iter().find_map(|(key, val)| {
+            let location = location.key(key);
+
+            if forbidden.iter().any(|f| f.matches(key, &location)) {
+                Some(location.indexed)
+            } else {
+                find_forbidden(val, forbidden, &location)
+            }
+        }),
+        Value::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .find_map(|(i, val)| find_forbidden(val, forbidden, &location.index(i))),
+        _ => None,
+    }
+}
+
+/// Returns whether two JSON values match.
+fn matches(expected: &Value, actual: &Value, tolerance: FloatTolerance) -> bool {
+    let mut mismatches = Vec::new();
+    compare_json(expected, actual, "", tolerance, &mut mismatches);
+    mismatches.is_empty()
+}
+
+/// Replaces each value within the actual outputs that matches the value in the
+/// same place of the expected outputs with the expected value.
+///
+/// A diff of the result against the expected outputs then only shows the
+/// values that do not match, rather than also, e.g., paths that only differ by
+/// directory or numbers within the tolerance.
+fn reconcile(expected: &Value, actual: &Value, tolerance: FloatTolerance) -> Value {
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => Value::Object(
+            a.iter()
+                .map(|(key, a_val)| {
+                    let value = match e.get(key) {
+                        Some(e_val) => reconcile(e_val, a_val, tolerance),
+                        None => a_val.clone(),
+                    };
+
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        (Value::Array(e), Value::Array(a)) => Value::Array(
+            a.iter()
+                .enumerate()
+                .map(|(i, a_val)| match e.get(i) {
+                    Some(e_val) => reconcile(e_val, a_val, tolerance),
+                    None => a_val.clone(),
+                })
+                .collect(),
+        ),
+        _ if matches(expected, actual, tolerance) => expected.clone(),
+        _ => actual.clone(),
+    }
+}
+
 /// Performs a deep comparison of two JSON values.
 ///
-/// This function recursively compares JSON values and provides detailed
-/// error messages indicating where mismatches occur.
+/// This function recursively compares JSON values and records a detailed
+/// message for every mismatch, indicating where it occurs.
 ///
 /// # Arguments
 ///
 /// * `expected` - The expected JSON value
 /// * `actual` - The actual JSON value
 /// * `path` - The current path in the JSON structure (for error messages)
-fn compare_json(expected: &Value, actual: &Value, path: &str) -> Result<()> {
+/// * `tolerance` - The tolerance within which numbers are considered equal
+/// * `mismatches` - The key and message for each mismatch found
+fn compare_json(
+    expected: &Value,
+    actual: &Value,
+    path: &str,
+    tolerance: FloatTolerance,
+    mismatches: &mut Vec<(MismatchedKey, String)>,
+) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
     match (expected, actual) {
-        (Value::Null, Value::Null) => Ok(()),
+        (Value::Null, Value::Null) => {}
         (Value::Bool(e), Value::Bool(a)) => {
-            if e == a {
-                Ok(())
-            } else {
-                bail!("boolean mismatch at `{path}`: expected {e}, got {a}")
+            if e != a {
+                mismatches.push((
+                    MismatchedKey::new(path, MismatchKind::Boolean),
+                    format!("boolean mismatch at `{path}`: expected {e}, got {a}"),
+                ));
             }
         }
         (Value::Number(e), Value::Number(a)) => {
-            // Compare numbers with floating point tolerance
-            let e_f64 = e.as_f64().context("expected number as f64")?;
-            let a_f64 = a.as_f64().context("actual number as f64")?;
+            let integer = |n: &serde_json::Number| n.is_i64() || n.is_u64();
 
-            if (e_f64 - a_f64).abs() < f64::EPSILON {
-                Ok(())
+            // Integers are compared exactly; the floating point tolerance only
+            // applies when either side is not an integer
+            if integer(e) && integer(a) {
+                if e != a {
+                    mismatches.push((
+                        MismatchedKey::new(path, MismatchKind::Number),
+                        format!("number mismatch at `{path}`: expected {e}, got {a}"),
+                    ));
+                }
             } else {
-                bail!("number mismatch at `{path}`: expected {e_f64}, got {a_f64}")
+                let e_f64 = e.as_f64().unwrap_or(f64::NAN);
+                let a_f64 = a.as_f64().unwrap_or(f64::NAN);
+
+                if !tolerance.matches(e_f64, a_f64) {
+                    mismatches.push((
+                        MismatchedKey::new(path, MismatchKind::Number),
+                        format!("number mismatch at `{path}`: expected {e_f64}, got {a_f64}"),
+                    ));
+                }
             }
         }
         (Value::String(e), Value::String(a)) => {
             let e_normalized = normalize_path(e);
             let a_normalized = normalize_path(a);
 
-            if e_normalized == a_normalized {
-                Ok(())
-            } else {
-                bail!("string mismatch at `{path}`: expected \"{e}\", got \"{a}\"")
+            if e_normalized != a_normalized {
+                let is_path = |s: &str| s.contains(['/', '\\']);
+                let kind = if is_path(e) || is_path(a) {
+                    MismatchKind::Path
+                } else {
+                    MismatchKind::String
+                };
+                mismatches.push((
+                    MismatchedKey::new(path, kind),
+                    format!("string mismatch at `{path}`: expected \"{e}\", got \"{a}\""),
+                ));
             }
         }
         (Value::Array(e), Value::Array(a)) => {
             if e.len() != a.len() {
-                bail!(
-                    "array length mismatch at `{path}`: expected {} elements, got {} elements",
-                    e.len(),
-                    a.len()
-                );
+                mismatches.push((
+                    MismatchedKey::new(path, MismatchKind::ArrayLength),
+                    format!(
+                        "array length mismatch at `{path}`: expected {} elements, got {} \
+                         elements",
+                        e.len(),
+                        a.len()
+                    ),
+                ));
+                return;
             }
 
             for (i, (e_val, a_val)) in e.iter().zip(a.iter()).enumerate() {
@@ -132,53 +972,40 @@ fn compare_json(expected: &Value, actual: &Value, path: &str) -> Result<()> {
                 } else {
                     format!("{path}[{i}]")
                 };
-                compare_json(e_val, a_val, &item_path)?;
+                compare_json(e_val, a_val, &item_path, tolerance, mismatches);
             }
-
-            Ok(())
         }
         (Value::Object(e), Value::Object(a)) => {
             // Check for missing keys in actual
-            for key in e.keys() {
-                if !a.contains_key(key) {
-                    let key_path = if path.is_empty() {
-                        key.clone()
-                    } else {
-                        format!("{path}.{key}")
-                    };
-                    bail!("missing key in actual output: `{key_path}`");
-                }
+            for key in e.keys().filter(|key| !a.contains_key(*key)) {
+                mismatches.push((
+                    MismatchedKey::new(join(key), MismatchKind::MissingKey),
+                    format!("missing key in actual output: `{}`", join(key)),
+                ));
             }
 
             // Check for extra keys in actual
-            for key in a.keys() {
-                if !e.contains_key(key) {
-                    let key_path = if path.is_empty() {
-                        key.clone()
-                    } else {
-                        format!("{path}.{key}")
-                    };
-                    bail!("unexpected key in actual output: `{key_path}`");
-                }
+            for key in a.keys().filter(|key| !e.contains_key(*key)) {
+                mismatches.push((
+                    MismatchedKey::new(join(key), MismatchKind::UnexpectedKey),
+                    format!("unexpected key in actual output: `{}`", join(key)),
+                ));
             }
 
             // Compare values for matching keys
             for (key, e_val) in e.iter() {
-                let a_val = &a[key];
-                let key_path = if path.is_empty() {
-                    key.clone()
-                } else {
-                    format!("{path}.{key}")
-                };
-                compare_json(e_val, a_val, &key_path)?;
+                if let Some(a_val) = a.get(key) {
+                    compare_json(e_val, a_val, &join(key), tolerance, mismatches);
+                }
             }
-
-            Ok(())
         }
         _ => {
             let expected_type = type_name(expected);
             let actual_type = type_name(actual);
-            bail!("type mismatch at `{path}`: expected {expected_type}, got {actual_type}")
+            mismatches.push((
+                MismatchedKey::new(path, MismatchKind::Type),
+                format!("type mismatch at `{path}`: expected {expected_type}, got {actual_type}"),
+            ));
         }
     }
 }
@@ -200,7 +1027,12 @@ fn type_name(value: &Value) -> &'static str {
 /// This handles differences between WDL engines where some return full absolute
 /// paths for `File` and `Directory` types while others return just the basename.
 /// If the string represents an existing path on disk, returns just the filename.
-/// Otherwise returns the original string.
+///
+/// Engines running natively on Windows return paths such as
+/// `C:\work\out.txt`, which do not exist when validating elsewhere, so
+/// absolute Windows paths are reduced to their filename as well. Relative
+/// paths separated by `\` are rewritten beforehand by
+/// [`normalize_separators`].
 fn normalize_path(s: &str) -> Cow<'_, str> {
     let path = Path::new(s);
     if path.exists() {
@@ -208,11 +1040,28 @@ fn normalize_path(s: &str) -> Cow<'_, str> {
             .and_then(|name| name.to_str())
             .map(Cow::Borrowed)
             .unwrap_or(Cow::Borrowed(s))
+    } else if is_windows_absolute(s) {
+        s.trim_end_matches(['\\', '/'])
+            .rsplit(['\\', '/'])
+            .next()
+            .filter(|name| !name.is_empty() && !name.ends_with(':'))
+            .map(Cow::Borrowed)
+            .unwrap_or(Cow::Borrowed(s))
     } else {
         Cow::Borrowed(s)
     }
 }
 
+/// Returns whether a string is an absolute Windows path, either starting with
+/// a drive letter (e.g., `C:\` or `C:/`) or a UNC path (e.g., `\\server`).
+fn is_windows_absolute(s: &str) -> bool {
+    match s.as_bytes() {
+        [drive, b':', b'\\' | b'/', ..] => drive.is_ascii_alphabetic(),
+        [b'\\', b'\\', ..] => true,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,14 +1071,14 @@ mod tests {
     fn test_identical_objects() {
         let expected = json!({"a": 1, "b": "test"});
         let actual = json!({"a": 1, "b": "test"});
-        assert!(validate_outputs(&expected, &actual, &[]).is_ok());
+        assert!(validate_outputs(&expected, &actual, &[], FloatTolerance::default()).is_ok());
     }
 
     #[test]
     fn test_value_mismatch() {
         let expected = json!({"a": 1});
         let actual = json!({"a": 2});
-        let result = validate_outputs(&expected, &actual, &[]);
+        let result = validate_outputs(&expected, &actual, &[], FloatTolerance::default());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("number mismatch"));
     }
@@ -238,7 +1087,7 @@ mod tests {
     fn test_missing_key() {
         let expected = json!({"a": 1, "b": 2});
         let actual = json!({"a": 1});
-        let result = validate_outputs(&expected, &actual, &[]);
+        let result = validate_outputs(&expected, &actual, &[], FloatTolerance::default());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("missing key"));
     }
@@ -247,7 +1096,7 @@ mod tests {
     fn test_extra_key() {
         let expected = json!({"a": 1});
         let actual = json!({"a": 1, "b": 2});
-        let result = validate_outputs(&expected, &actual, &[]);
+        let result = validate_outputs(&expected, &actual, &[], FloatTolerance::default());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("unexpected key"));
     }
@@ -256,21 +1105,29 @@ mod tests {
     fn test_exclude_outputs() {
         let expected = json!({"a": 1, "timestamp": 100});
         let actual = json!({"a": 1, "timestamp": 200});
-        assert!(validate_outputs(&expected, &actual, &["timestamp".to_string()]).is_ok());
+        assert!(
+            validate_outputs(
+                &expected,
+                &actual,
+                &["timestamp".to_string()],
+                FloatTolerance::default()
+            )
+            .is_ok()
+        );
     }
 
     #[test]
     fn test_nested_objects() {
         let expected = json!({"outer": {"inner": {"value": 42}}});
         let actual = json!({"outer": {"inner": {"value": 42}}});
-        assert!(validate_outputs(&expected, &actual, &[]).is_ok());
+        assert!(validate_outputs(&expected, &actual, &[], FloatTolerance::default()).is_ok());
     }
 
     #[test]
     fn test_nested_mismatch() {
         let expected = json!({"outer": {"inner": {"value": 42}}});
         let actual = json!({"outer": {"inner": {"value": 43}}});
-        let result = validate_outputs(&expected, &actual, &[]);
+        let result = validate_outputs(&expected, &actual, &[], FloatTolerance::default());
         assert!(result.is_err());
         assert!(
             result
@@ -284,14 +1141,14 @@ mod tests {
     fn test_array_match() {
         let expected = json!({"items": [1, 2, 3]});
         let actual = json!({"items": [1, 2, 3]});
-        assert!(validate_outputs(&expected, &actual, &[]).is_ok());
+        assert!(validate_outputs(&expected, &actual, &[], FloatTolerance::default()).is_ok());
     }
 
     #[test]
     fn test_array_length_mismatch() {
         let expected = json!({"items": [1, 2, 3]});
         let actual = json!({"items": [1, 2]});
-        let result = validate_outputs(&expected, &actual, &[]);
+        let result = validate_outputs(&expected, &actual, &[], FloatTolerance::default());
         assert!(result.is_err());
         assert!(
             result
@@ -305,7 +1162,7 @@ mod tests {
     fn test_array_element_mismatch() {
         let expected = json!({"items": [1, 2, 3]});
         let actual = json!({"items": [1, 5, 3]});
-        let result = validate_outputs(&expected, &actual, &[]);
+        let result = validate_outputs(&expected, &actual, &[], FloatTolerance::default());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("items[1]"));
     }
@@ -314,7 +1171,7 @@ mod tests {
     fn test_type_mismatch() {
         let expected = json!({"value": 42});
         let actual = json!({"value": "42"});
-        let result = validate_outputs(&expected, &actual, &[]);
+        let result = validate_outputs(&expected, &actual, &[], FloatTolerance::default());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("type mismatch"));
     }
@@ -323,22 +1180,469 @@ mod tests {
     fn test_exclude_nested_key() {
         let expected = json!({"a": 1, "nested": {"timestamp": 100, "value": 42}});
         let actual = json!({"a": 1, "nested": {"timestamp": 200, "value": 42}});
-        assert!(validate_outputs(&expected, &actual, &["timestamp".to_string()]).is_ok());
+        assert!(
+            validate_outputs(
+                &expected,
+                &actual,
+                &["timestamp".to_string()],
+                FloatTolerance::default()
+            )
+            .is_ok()
+        );
     }
 
     #[test]
     fn test_exclude_nested_path() {
         let expected = json!({"a": 1, "nested": {"timestamp": 100, "value": 42}});
         let actual = json!({"a": 1, "nested": {"timestamp": 200, "value": 42}});
-        assert!(validate_outputs(&expected, &actual, &["nested.timestamp".to_string()]).is_ok());
+        assert!(
+            validate_outputs(
+                &expected,
+                &actual,
+                &["nested.timestamp".to_string()],
+                FloatTolerance::default()
+            )
+            .is_ok()
+        );
     }
 
     #[test]
     fn test_exclude_nested_path_preserves_other_fields() {
         let expected = json!({"a": 1, "nested": {"timestamp": 100, "value": 42}});
         let actual = json!({"a": 1, "nested": {"timestamp": 200, "value": 99}});
-        let result = validate_outputs(&expected, &actual, &["nested.timestamp".to_string()]);
+        let result = validate_outputs(
+            &expected,
+            &actual,
+            &["nested.timestamp".to_string()],
+            FloatTolerance::default(),
+        );
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("nested.value"));
     }
+
+    #[test]
+    fn test_expected_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("out")).unwrap();
+        std::fs::write(dir.path().join("out/hello.txt"), "hello\n").unwrap();
+
+        let present = ExpectedFile::Path(String::from("out/hello.txt"));
+        let hashed = ExpectedFile::Hashed {
+            path: String::from("out/hello.txt"),
+            sha256: String::from(
+                "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03",
+            ),
+        };
+        assert!(validate_files(dir.path(), &[present, hashed]).is_ok());
+
+        let missing = ExpectedFile::Path(String::from("out/missing.txt"));
+        let error = validate_files(dir.path(), &[missing]).unwrap_err();
+        assert!(error.to_string().contains("was not produced"));
+
+        let wrong = ExpectedFile::Hashed {
+            path: String::from("out/hello.txt"),
+            sha256: String::from("00"),
+        };
+        let error = validate_files(dir.path(), &[wrong]).unwrap_err();
+        assert!(error.to_string().contains("do not match"));
+    }
+
+    #[test]
+    fn test_exclude_array_wildcards() {
+        let expected =
+            json!({"wf.items": [{"id": 1, "timestamp": 100}, {"id": 2, "timestamp": 200}]});
+        let actual =
+            json!({"wf.items": [{"id": 1, "timestamp": 101}, {"id": 2, "timestamp": 201}]});
+
+        let exclude = vec![String::from("wf.items[*].timestamp")];
+        assert!(validate_outputs(&expected, &actual, &exclude, FloatTolerance::default()).is_ok());
+
+        let exclude = vec![String::from("wf.items[0].timestamp")];
+        let error =
+            validate_outputs(&expected, &actual, &exclude, FloatTolerance::default()).unwrap_err();
+        assert!(error.to_string().contains("wf.items[1].timestamp"));
+
+        // Array elements remain transparent to plain paths
+        let exclude = vec![String::from("wf.items.timestamp")];
+        assert!(validate_outputs(&expected, &actual, &exclude, FloatTolerance::default()).is_ok());
+    }
+
+    #[test]
+    fn test_exclude_json_pointers() {
+        let expected = json!({"wf.items": [{"a/b": 1}, {"a/b": 2}]});
+        let actual = json!({"wf.items": [{"a/b": 1}, {"a/b": 3}]});
+
+        let exclude = vec![String::from("/wf.items/1/a~1b")];
+        assert!(validate_outputs(&expected, &actual, &exclude, FloatTolerance::default()).is_ok());
+
+        let exclude = vec![String::from("/wf.items/0/a~1b")];
+        assert!(validate_outputs(&expected, &actual, &exclude, FloatTolerance::default()).is_err());
+    }
+
+    #[test]
+    fn test_key_map_exclusion_forms() {
+        let key_map = KeyMap::builder()
+            .renames(BTreeMap::from([(
+                String::from("wf.items"),
+                String::from("wf.elements"),
+            )]))
+            .build();
+        assert_eq!(key_map.key("wf.items[*].ts"), "wf.elements[*].ts");
+        assert_eq!(key_map.key("/wf.items/0/ts"), "/wf.elements/0/ts");
+
+        let key_map = KeyMap::builder().strip_prefix(true).build();
+        assert_eq!(key_map.key("/wf.items/0"), "/items/0");
+    }
+
+    #[test]
+    fn test_forbidden_outputs() {
+        let expected = json!({"wf.out": 1});
+        let options = Options::builder()
+            .forbidden(vec![String::from("secret"), String::from("wf.tmp_path")])
+            .build();
+
+        assert!(validate(&expected, &json!({"wf.out": 1}), &options).is_ok());
+
+        let error = validate(
+            &expected,
+            &json!({"wf.out": 1, "wf.tmp_path": "/tmp"}),
+            &options,
+        )
+        .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("forbidden key in actual output: `wf.tmp_path`")
+        );
+
+        let error = validate(
+            &json!({"wf.out": {"a": 1}}),
+            &json!({"wf.out": {"a": 1, "secret": 2}}),
+            &options,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("`wf.out.secret`"));
+    }
+
+    #[test]
+    fn test_float_tolerance() {
+        let expected = json!({"ratio": 1.23456, "values": [1.0, 2.0]});
+        let actual = json!({"ratio": 1.2346, "values": [1.0000001, 2.0]});
+
+        let result = validate_outputs(&expected, &actual, &[], FloatTolerance::default());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("number mismatch at `ratio`")
+        );
+
+        let tolerance = FloatTolerance::new(1e-4, 0.0);
+        assert!(validate_outputs(&expected, &actual, &[], tolerance).is_ok());
+    }
+
+    #[test]
+    fn test_compare_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("renamed-by-engine.txt");
+        std::fs::write(&path, "hello\n").unwrap();
+        let actual = json!({"out": path.to_str().unwrap()});
+
+        let options = |compare_files| Options::builder().compare_files(compare_files).build();
+
+        assert!(
+            validate(
+                &json!({"out": "hello.txt"}),
+                &actual,
+                &options(FileComparison::Name)
+            )
+            .is_err()
+        );
+        assert!(
+            validate(
+                &json!({"out": "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"}),
+                &actual,
+                &options(FileComparison::Sha256)
+            )
+            .is_ok()
+        );
+        assert!(
+            validate(
+                &json!({"out": "hello\n"}),
+                &actual,
+                &options(FileComparison::Contents)
+            )
+            .is_ok()
+        );
+        assert!(
+            validate(
+                &json!({"out": "bye\n"}),
+                &actual,
+                &options(FileComparison::Contents)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_unordered_outputs() {
+        let expected = json!({
+            "wf.matches": ["a.txt", "b.txt", "b.txt"],
+            "wf.ordered": [1, 2]
+        });
+        let options = Options::builder()
+            .unordered(vec![String::from("wf.matches")])
+            .build();
+
+        let actual = json!({
+            "wf.matches": ["b.txt", "a.txt", "b.txt"],
+            "wf.ordered": [1, 2]
+        });
+        assert!(validate(&expected, &actual, &options).is_ok());
+
+        // The elements must still match as a multiset
+        let actual = json!({
+            "wf.matches": ["b.txt", "a.txt", "a.txt"],
+            "wf.ordered": [1, 2]
+        });
+        assert!(validate(&expected, &actual, &options).is_err());
+
+        // Arrays not listed are still compared in order
+        let actual = json!({
+            "wf.matches": ["a.txt", "b.txt", "b.txt"],
+            "wf.ordered": [2, 1]
+        });
+        assert!(validate(&expected, &actual, &options).is_err());
+
+        // Unless every array is unordered
+        let options = options
+            .with_unordered(Vec::new())
+            .with_unordered_arrays(true);
+        assert!(validate(&expected, &actual, &options).is_ok());
+    }
+
+    #[test]
+    fn test_pattern_outputs() {
+        let expected = json!({
+            "wf.id": "[0-9a-f]{8}",
+            "wf.logs": ["run-\\d+\\.log", "run-\\d+\\.log"],
+            "wf.name": "[a-z]+"
+        });
+        let options = Options::builder()
+            .patterns(vec![String::from("wf.id"), String::from("wf.logs")])
+            .build();
+
+        let actual = json!({
+            "wf.id": "1a2b3c4d",
+            "wf.logs": ["run-1.log", "run-22.log"],
+            "wf.name": "[a-z]+"
+        });
+        assert!(validate(&expected, &actual, &options).is_ok());
+
+        // Patterns must match the whole string
+        let actual = json!({
+            "wf.id": "1a2b3c4d5",
+            "wf.logs": ["run-1.log", "run-22.log"],
+            "wf.name": "[a-z]+"
+        });
+        let err = validate(&expected, &actual, &options).unwrap_err();
+        assert!(err.to_string().contains("`wf.id`"));
+
+        // Strings not listed are still compared for equality
+        let actual = json!({
+            "wf.id": "1a2b3c4d",
+            "wf.logs": ["run-1.log", "run-22.log"],
+            "wf.name": "hello"
+        });
+        assert!(validate(&expected, &actual, &options).is_err());
+
+        let options = Options::builder()
+            .patterns(vec![String::from("wf.name")])
+            .build();
+        let expected = json!({"wf.name": "(unclosed"});
+        let err = validate(&expected, &expected, &options).unwrap_err();
+        assert!(err.to_string().starts_with("invalid pattern for `wf.name`"));
+    }
+
+    #[test]
+    fn test_windows_paths() {
+        assert_eq!(normalize_path(r"C:\work\out\hello.txt"), "hello.txt");
+        assert_eq!(normalize_path("d:/work/out/hello.txt"), "hello.txt");
+        assert_eq!(normalize_path(r"\\server\share\hello.txt"), "hello.txt");
+        assert_eq!(normalize_path(r"C:\"), r"C:\");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("out")).unwrap();
+        std::fs::write(dir.path().join("out").join("hello.txt"), "hello").unwrap();
+
+        let options = Options::default().with_workdir(dir.path().to_path_buf());
+        assert!(
+            validate(
+                &json!({"out": "out/hello.txt", "file": "hello.txt"}),
+                &json!({"out": r"out\hello.txt", "file": r"C:\work\hello.txt"}),
+                &options,
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_all_mismatches() {
+        let expected = json!({"a": 1, "b": 0.5, "c": [1, 2], "d": true});
+        let actual = json!({"a": 2, "b": 0.5001, "c": [1, 3], "e": true});
+        let tolerance = FloatTolerance::new(1e-3, 0.0);
+        let error = validate_outputs(&expected, &actual, &[], tolerance).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "4 mismatches: missing key in actual output: `d`; unexpected key in actual output: \
+             `e`; number mismatch at `a`: expected 1, got 2; number mismatch at `c[1]`: \
+             expected 2, got 3"
+        );
+
+        // Values that match are shown as expected in the diff
+        let mismatch = error.downcast_ref::<Mismatch>().unwrap();
+        assert_eq!(mismatch.mismatches().len(), 4);
+        assert_eq!(
+            mismatch.keys(),
+            [
+                MismatchedKey::new("d", MismatchKind::MissingKey),
+                MismatchedKey::new("e", MismatchKind::UnexpectedKey),
+                MismatchedKey::new("a", MismatchKind::Number),
+                MismatchedKey::new("c[1]", MismatchKind::Number),
+            ]
+        );
+        assert_eq!(mismatch.keys()[3].generalized_path(), "c[*]");
+        assert_eq!(
+            mismatch.diff().unwrap(),
+            "--- expected\n+++ actual\n@@ -1,9 +1,9 @@\n {\n-  \"a\": 1,\n+  \"a\": 2,\n   \
+             \"b\": 0.5,\n   \"c\": [\n     1,\n-    2\n+    3\n   ],\n-  \"d\": true\n+  \
+             \"e\": true\n }\n"
+        );
+    }
+
+    #[test]
+    fn test_key_map_strip_prefix() {
+        let expected = json!({"wf.out": 1, "wf.timestamp": 100});
+        let actual = json!({"out": 1, "timestamp": 200});
+        let options = Options::builder()
+            .exclude(vec!["wf.timestamp".to_string()])
+            .key_map(KeyMap::builder().strip_prefix(true).build())
+            .build();
+        assert!(validate(&expected, &actual, &options).is_ok());
+    }
+
+    #[test]
+    fn test_key_map_renames() {
+        let expected = json!({"task.out": {"value": 1}});
+        let actual = json!({"wrapper.out": {"value": 1}});
+        let key_map = KeyMap::builder()
+            .renames(BTreeMap::from([(
+                "wrapper.out".to_string(),
+                "task.out".to_string(),
+            )]))
+            .build();
+        let options = Options::builder().key_map(key_map.clone()).build();
+        assert!(validate(&expected, &actual, &options).is_ok());
+        assert_eq!(key_map.key("wrapper.out.value"), "task.out.value");
+    }
+
+    #[test]
+    fn test_float_tolerance_integers() {
+        let tolerance = FloatTolerance::new(10.0, 1e-3);
+
+        let expected = json!({"count": 1_000_000, "big": i64::MAX});
+        let actual = json!({"count": 1_000_001, "big": i64::MAX - 1});
+        let error = validate_outputs(&expected, &actual, &[], tolerance).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("number mismatch at `count`: expected 1000000, got 1000001")
+        );
+        assert!(error.to_string().contains("number mismatch at `big`"));
+
+        // The tolerance still applies when either side is not an integer
+        let actual = json!({"count": 1_000_001.0, "big": i64::MAX});
+        assert!(validate_outputs(&expected, &actual, &[], tolerance).is_ok());
+    }
+
+    #[test]
+    fn test_backslashes_in_strings() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = Options::default().with_workdir(dir.path().to_path_buf());
+
+        // Strings that are not paths within the working directory keep their
+        // backslashes
+        let error = validate(
+            &json!({"pattern": "a/d+", "missing": "out/hello.txt"}),
+            &json!({"pattern": r"a\d+", "missing": r"out\hello.txt"}),
+            &options,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains(r#"expected "a/d+", got "a\d+""#));
+        assert!(error.to_string().contains("string mismatch at `missing`"));
+    }
+
+    #[test]
+    fn test_compare_relative_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("out")).unwrap();
+        std::fs::write(dir.path().join("out").join("hello.txt"), "hello\n").unwrap();
+
+        // Relative paths are resolved against the working directory rather
+        // than the current directory
+        let options = Options::builder()
+            .compare_files(FileComparison::Contents)
+            .build()
+            .with_workdir(dir.path().to_path_buf());
+        assert!(
+            validate(
+                &json!({"out": "hello\n"}),
+                &json!({"out": "out/hello.txt"}),
+                &options
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_forbidden_output_forms() {
+        let expected = json!({"wf.items": [{"a": 1}, {"a": 2}]});
+        let actual = json!({"wf.items": [{"a": 1}, {"a": 2, "tmp": "/tmp"}]});
+
+        for entry in ["/wf.items/1/tmp", "wf.items[*].tmp", "wf.items[1].tmp"] {
+            let options = Options::builder()
+                .forbidden(vec![entry.to_string()])
+                .build();
+            let error = validate(&expected, &actual, &options).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "forbidden key in actual output: `wf.items[1].tmp`",
+                "{entry}"
+            );
+        }
+
+        for entry in ["/wf.items/0/tmp", "wf.items[0].tmp"] {
+            let options = Options::builder()
+                .forbidden(vec![entry.to_string()])
+                .build();
+            let error = validate(&expected, &actual, &options).unwrap_err();
+            assert!(!error.to_string().contains("forbidden"), "{entry}");
+        }
+    }
+
+    #[test]
+    fn test_key_map_nested_renames() {
+        let key_map = KeyMap::builder()
+            .renames(BTreeMap::from([
+                (String::from("wf"), String::from("x")),
+                (String::from("wf.out"), String::from("y")),
+            ]))
+            .build();
+        assert_eq!(key_map.key("wf.out"), "y");
+        assert_eq!(key_map.key("wf.out[0]"), "y[0]");
+        assert_eq!(key_map.key("/wf.out/0"), "/y/0");
+        assert_eq!(key_map.key("wf.other"), "x.other");
+        assert_eq!(key_map.key("wf.outer"), "x.outer");
+    }
 }
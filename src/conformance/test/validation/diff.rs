@@ -0,0 +1,217 @@
+//! Line-based unified diffs of expected and actual outputs.
+
+use std::fmt::Write as _;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// The largest number of cells in the table used to find the longest common
+/// subsequence of the changed lines, beyond which no diff is produced.
+const MAX_CELLS: usize = 4_000_000;
+
+/// An operation that turns the expected lines into the actual lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    /// The line is in both.
+    Equal,
+    /// The line is only in the expected lines.
+    Delete,
+    /// The line is only in the actual lines.
+    Insert,
+}
+
+/// Renders a unified diff from `expected` to `actual`.
+///
+/// Returns `None` if the two are the same, or if they differ in too many lines
+/// to compare.
+pub fn unified(expected: &str, actual: &str) -> Option<String> {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+    let ops = diff_lines(&expected, &actual)?;
+
+    // Group the changes, along with their context, into hunks
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != Op::Equal)
+    {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(ops.len());
+
+        match hunks.last_mut() {
+            Some((_, last)) if start <= *last => *last = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    if hunks.is_empty() {
+        return None;
+    }
+
+    // The line numbers in each side before each operation
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut e_line, mut a_line) = (0, 0);
+    for (op, _) in &ops {
+        positions.push((e_line, a_line));
+        match op {
+            Op::Equal => {
+                e_line += 1;
+                a_line += 1;
+            }
+            Op::Delete => e_line += 1,
+            Op::Insert => a_line += 1,
+        }
+    }
+
+    let range = |start: usize, count: usize| {
+        if count == 0 {
+            format!("{start},0")
+        } else {
+            format!("{},{count}", start + 1)
+        }
+    };
+
+    let mut diff = String::from("--- expected\n+++ actual\n");
+    for (start, end) in hunks {
+        let hunk = &ops[start..end];
+        let (e_start, a_start) = positions[start];
+        let e_count = hunk.iter().filter(|(op, _)| *op != Op::Insert).count();
+        let a_count = hunk.iter().filter(|(op, _)| *op != Op::Delete).count();
+
+        // SAFETY: writing to a `String` never fails.
+        writeln!(
+            diff,
+            "@@ -{} +{} @@",
+            range(e_start, e_count),
+            range(a_start, a_count)
+        )
+        .unwrap();
+
+        for (op, line) in hunk {
+            let prefix = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+
+            // SAFETY: writing to a `String` never fails.
+            writeln!(diff, "{prefix}{line}").unwrap();
+        }
+    }
+
+    Some(diff)
+}
+
+/// Colors the lines of a unified diff for a terminal.
+pub fn render(diff: &str, color: bool) -> String {
+    if !color {
+        return diff.to_string();
+    }
+
+    diff.lines()
+        .map(|line| {
+            let code = if line.starts_with("---") || line.starts_with("+++") {
+                "\x1b[1m"
+            } else if line.starts_with("@@") {
+                "\x1b[36m"
+            } else if line.starts_with('-') {
+                "\x1b[31m"
+            } else if line.starts_with('+') {
+                "\x1b[32m"
+            } else {
+                return format!("{line}\n");
+            };
+
+            format!("{code}{line}\x1b[0m\n")
+        })
+        .collect()
+}
+
+/// Finds the operations that turn the expected lines into the actual lines.
+fn diff_lines<'a>(expected: &[&'a str], actual: &[&'a str]) -> Option<Vec<(Op, &'a str)>> {
+    // Only the lines between the common prefix and suffix need comparing
+    let prefix = expected
+        .iter()
+        .zip(actual)
+        .take_while(|(e, a)| e == a)
+        .count();
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(actual[prefix..].iter().rev())
+        .take_while(|(e, a)| e == a)
+        .count();
+    let e = &expected[prefix..expected.len() - suffix];
+    let a = &actual[prefix..actual.len() - suffix];
+
+    if (e.len() + 1).saturating_mul(a.len() + 1) > MAX_CELLS {
+        return None;
+    }
+
+    // The length of the longest common subsequence of `e[i..]` and `a[j..]`
+    let width = a.len() + 1;
+    let mut lengths = vec![0u32; (e.len() + 1) * width];
+    for i in (0..e.len()).rev() {
+        for j in (0..a.len()).rev() {
+            lengths[i * width + j] = if e[i] == a[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = expected[..prefix]
+        .iter()
+        .map(|line| (Op::Equal, *line))
+        .collect::<Vec<_>>();
+
+    let (mut i, mut j) = (0, 0);
+    while i < e.len() && j < a.len() {
+        if e[i] == a[j] {
+            ops.push((Op::Equal, e[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            ops.push((Op::Delete, e[i]));
+            i += 1;
+        } else {
+            ops.push((Op::Insert, a[j]));
+            j += 1;
+        }
+    }
+
+    ops.extend(e[i..].iter().map(|line| (Op::Delete, *line)));
+    ops.extend(a[j..].iter().map(|line| (Op::Insert, *line)));
+    ops.extend(
+        expected[expected.len() - suffix..]
+            .iter()
+            .map(|line| (Op::Equal, *line)),
+    );
+
+    Some(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_hunks() {
+        let expected = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let actual = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+
+        assert_eq!(
+            unified(expected, actual).unwrap(),
+            "--- expected\n+++ actual\n@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n@@ -10,3 +10,4 \
+             @@\n j\n k\n l\n+m\n"
+        );
+
+        assert!(unified(expected, expected).is_none());
+        assert_eq!(
+            render("-b\n+B\n c\n", true),
+            "\x1b[31m-b\x1b[0m\n\x1b[32m+B\x1b[0m\n c\n"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! User-facing diagnostics for common setup failures.
+
+use std::fmt;
+
+/// A stable code identifying a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    /// The specification file could not be found.
+    SpecNotFound,
+    /// The conformance tests directory is not empty.
+    DirNotEmpty,
+    /// A command or badge template contains an unknown placeholder.
+    UnknownPlaceholder,
+    /// The command template is missing a required placeholder.
+    MissingPlaceholder,
+    /// Two or more options cannot be used together.
+    ConflictingOptions,
+    /// An option requires a value that was not provided.
+    MissingOption,
+    /// The failure code is not recognized.
+    UnknownFailureCode,
+    /// A named selector is not defined.
+    UnknownSelector,
+    /// No test in the specification has the given name.
+    UnknownTest,
+    /// A command template has a quote that is never closed.
+    UnbalancedQuote,
+}
+
+impl Code {
+    /// Gets the stable string representation of the code.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Code::SpecNotFound => "spec_not_found",
+            Code::DirNotEmpty => "dir_not_empty",
+            Code::UnknownPlaceholder => "unknown_placeholder",
+            Code::MissingPlaceholder => "missing_placeholder",
+            Code::ConflictingOptions => "conflicting_options",
+            Code::MissingOption => "missing_option",
+            Code::UnknownFailureCode => "unknown_failure_code",
+            Code::UnknownSelector => "unknown_selector",
+            Code::UnknownTest => "unknown_test",
+            Code::UnbalancedQuote => "unbalanced_quote",
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A diagnostic with a code and an optional remediation hint.
+///
+/// Diagnostics are regular errors that can be carried within an
+/// [`anyhow::Error`]; [`render()`] finds them within an error chain and
+/// prints them with their code and hint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The code.
+    code: Code,
+
+    /// The message.
+    message: String,
+
+    /// The remediation hint.
+    hint: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic.
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    /// Adds a remediation hint to the diagnostic.
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Gets the code.
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Gets the message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Gets the remediation hint.
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Returns whether to color what is written to a stream: only if it is a
+/// terminal and `NO_COLOR` is not set to a non-empty value.
+pub fn use_color(is_terminal: bool) -> bool {
+    is_terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// Renders an error for display on the command line.
+///
+/// If a [`Diagnostic`] is found anywhere within the error chain, it is
+/// rendered with its code and hint. Otherwise, the full error chain is
+/// rendered.
+pub fn render(error: &anyhow::Error, color: bool) -> String {
+    let (red, bold, reset) = if color {
+        ("\x1b[31;1m", "\x1b[1m", "\x1b[0m")
+    } else {
+        ("", "", "")
+    };
+
+    match error.chain().find_map(|e| e.downcast_ref::<Diagnostic>()) {
+        Some(diagnostic) => {
+            let mut rendered = format!(
+                "{red}error[{code}]{reset}{bold}: {message}{reset}",
+                code = diagnostic.code(),
+                message = diagnostic.message()
+            );
+
+            if let Some(hint) = diagnostic.hint() {
+                rendered.push_str(&format!("\n  = hint: {hint}"));
+            }
+
+            rendered
+        }
+        None => {
+            let mut rendered = format!("{red}error{reset}{bold}: {error}{reset}");
+
+            for cause in error.chain().skip(1) {
+                rendered.push_str(&format!("\n  caused by: {cause}"));
+            }
+
+            rendered
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn renders_code_and_hint() {
+        let error = anyhow::Error::new(
+            Diagnostic::new(Code::SpecNotFound, "no spec").with_hint("clone it"),
+        );
+
+        assert_eq!(
+            render(&error, false),
+            "error[spec_not_found]: no spec\n  = hint: clone it"
+        );
+    }
+
+    #[test]
+    fn finds_diagnostic_behind_context() {
+        let error = Err::<(), _>(Diagnostic::new(Code::DirNotEmpty, "not empty"))
+            .context("compiling tests")
+            .unwrap_err();
+
+        assert_eq!(render(&error, false), "error[dir_not_empty]: not empty");
+    }
+
+    #[test]
+    fn renders_plain_errors() {
+        let error = Err::<(), _>(anyhow::anyhow!("something broke"))
+            .context("running tests")
+            .unwrap_err();
+
+        assert_eq!(
+            render(&error, false),
+            "error: running tests\n  caused by: something broke"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Profiles describing how WDL execution engines are invoked.
+
+use std::fmt;
+
+use bon::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::conformance::Target;
+use crate::conformance::test::validation::KeyMap;
+use crate::wdl::Construct;
+
+/// Argument templates appended to the command depending on the kind of
+/// target being executed.
+///
+/// Templates support the same `~{...}` substitutions as the command.
+#[derive(Builder, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TargetArgs {
+    /// The arguments appended when running a workflow.
+    #[builder(into, default)]
+    workflow: String,
+
+    /// The arguments appended when running a task.
+    #[builder(into, default)]
+    task: String,
+}
+
+impl TargetArgs {
+    /// Gets the arguments appended when running a workflow.
+    pub fn workflow(&self) -> &str {
+        &self.workflow
+    }
+
+    /// Gets the arguments appended when running a task.
+    pub fn task(&self) -> &str {
+        &self.task
+    }
+
+    /// Gets the arguments appended when running the given target.
+    pub fn for_target(&self, target: &Target) -> &str {
+        match target {
+            Target::Workflow(_) => &self.workflow,
+            Target::Task(_) => &self.task,
+        }
+    }
+}
+
+/// A profile describing how a WDL execution engine is invoked.
+#[derive(Builder, Clone, Debug, Default, PartialEq, Eq)]
+#[builder(builder_type = Builder)]
+pub struct Profile {
+    /// The argument templates appended for each kind of target.
+    #[builder(default)]
+    target_args: TargetArgs,
+
+    /// Whether the engine is unable to execute a bare task.
+    #[builder(default)]
+    tasks_unsupported: bool,
+
+    /// The remapping applied to output keys before validation.
+    #[builder(default)]
+    output_key_map: KeyMap,
+
+    /// The `jq` selectors applied in sequence to the outputs before
+    /// validation.
+    #[builder(default)]
+    output_selectors: Vec<String>,
+
+    /// The `jq` selectors applied in sequence to the expected outputs before
+    /// validation.
+    #[builder(default)]
+    expected_selectors: Vec<String>,
+
+    /// The WDL constructs the engine does not support.
+    #[builder(default)]
+    unsupported_constructs: Vec<Construct>,
+}
+
+impl Profile {
+    /// Gets the argument templates appended for each kind of target.
+    pub fn target_args(&self) -> &TargetArgs {
+        &self.target_args
+    }
+
+    /// Returns whether the engine is unable to execute a bare task.
+    pub fn tasks_unsupported(&self) -> bool {
+        self.tasks_unsupported
+    }
+
+    /// Gets the remapping applied to output keys before validation.
+    pub fn output_key_map(&self) -> &KeyMap {
+        &self.output_key_map
+    }
+
+    /// Gets the `jq` selectors applied in sequence to the outputs before
+    /// validation.
+    pub fn output_selectors(&self) -> &[String] {
+        &self.output_selectors
+    }
+
+    /// Gets the `jq` selectors applied in sequence to the expected outputs
+    /// before validation.
+    pub fn expected_selectors(&self) -> &[String] {
+        &self.expected_selectors
+    }
+
+    /// Gets the WDL constructs the engine does not support.
+    pub fn unsupported_constructs(&self) -> &[Construct] {
+        &self.unsupported_constructs
+    }
+}
+
+/// Where the outputs of a command are read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputSource {
+    /// The outputs are read from the file the command writes to `~{output}`
+    /// (or from `--output-file`).
+    File,
+    /// The outputs are parsed from the standard output of the command.
+    Stdout,
+    /// The outputs are read from the file if the command wrote one and
+    /// otherwise parsed from the standard output of the command.
+    Auto,
+}
+
+impl fmt::Display for OutputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputSource::File => write!(f, "file"),
+            OutputSource::Stdout => write!(f, "stdout"),
+            OutputSource::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// A WDL execution engine with a known-good preset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// [Cromwell](https://github.com/broadinstitute/cromwell).
+    Cromwell,
+    /// [miniwdl](https://github.com/chanzuckerberg/miniwdl).
+    Miniwdl,
+    /// [Sprocket](https://github.com/stjude-rust-labs/sprocket).
+    Sprocket,
+    /// [Toil](https://github.com/DataBiosphere/toil).
+    Toil,
+}
+
+impl Engine {
+    /// Gets the preset describing how the engine is invoked.
+    pub fn preset(&self) -> Preset {
+        match self {
+            Engine::Cromwell => Preset {
+                command: "cromwell run ~{path} -i ~{input} --metadata-output ~{output}",
+                outputs: OutputSource::File,
+                profile: Profile::builder()
+                    .tasks_unsupported(true)
+                    .output_selectors(vec![String::from(".outputs")])
+                    .build(),
+            },
+            Engine::Miniwdl => Preset {
+                command: "miniwdl run ~{path} -i ~{input}",
+                outputs: OutputSource::Auto,
+                profile: Profile::builder()
+                    .target_args(TargetArgs::builder().task("--task ~{target}").build())
+                    .output_selectors(vec![String::from(".outputs")])
+                    .build(),
+            },
+            Engine::Sprocket => Preset {
+                command: "sprocket run ~{path} ~{input} -t ~{target}",
+                outputs: OutputSource::Auto,
+                profile: Profile::default(),
+            },
+            Engine::Toil => Preset {
+                command: "toil-wdl-runner ~{path} --inputs ~{input}",
+                outputs: OutputSource::Auto,
+                profile: Profile::default(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Engine::Cromwell => write!(f, "cromwell"),
+            Engine::Miniwdl => write!(f, "miniwdl"),
+            Engine::Sprocket => write!(f, "sprocket"),
+            Engine::Toil => write!(f, "toil"),
+        }
+    }
+}
+
+/// Known-good settings for invoking an engine.
+///
+/// Settings provided on the command line take precedence over the preset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preset {
+    /// The command template.
+    command: &'static str,
+
+    /// Where the engine's outputs are read from.
+    outputs: OutputSource,
+
+    /// The profile describing target arguments and output handling.
+    profile: Profile,
+}
+
+impl Preset {
+    /// Gets the command template.
+    pub fn command(&self) -> &str {
+        self.command
+    }
+
+    /// Gets where the engine's outputs are read from.
+    pub fn outputs(&self) -> OutputSource {
+        self.outputs
+    }
+
+    /// Gets the profile describing target arguments and output handling.
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_args_by_kind() {
+        let args = TargetArgs::builder()
+            .workflow("--workflow ~{target}")
+            .task("--task ~{target}")
+            .build();
+
+        assert_eq!(
+            args.for_target(&Target::Workflow(String::from("wf"))),
+            "--workflow ~{target}"
+        );
+        assert_eq!(
+            args.for_target(&Target::Task(String::from("t"))),
+            "--task ~{target}"
+        );
+    }
+}
@@ -0,0 +1,381 @@
+//! A history of test runs, kept as a file with a line of JSON for each run.
+
+use std::collections::BTreeMap;
+use std::io::Write as _;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::report::Outcome;
+use crate::report::Report;
+use crate::report::Summary;
+
+/// The status of a test within a run of the history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// The test passed.
+    Passed,
+    /// The test failed.
+    Failed,
+    /// The test was skipped.
+    Skipped,
+}
+
+impl From<&Outcome> for Status {
+    fn from(outcome: &Outcome) -> Self {
+        match outcome {
+            Outcome::Passed => Status::Passed,
+            Outcome::Failed { .. } => Status::Failed,
+            Outcome::Skipped { .. } => Status::Skipped,
+        }
+    }
+}
+
+/// A run within the history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// When the run finished, in seconds since the Unix epoch.
+    finished_at: u64,
+
+    /// The label of the badge for the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+
+    /// The summary of the run.
+    #[serde(flatten)]
+    summary: Summary,
+
+    /// The status of each test.
+    ///
+    /// Runs recorded before statuses were kept have none.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    tests: BTreeMap<String, Status>,
+}
+
+impl Record {
+    /// Creates a record of a run that has just finished.
+    pub fn new(label: &str, summary: &Summary, report: &Report) -> Self {
+        let finished_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            finished_at,
+            label: Some(label.to_string()),
+            summary: summary.clone(),
+            tests: report
+                .tests()
+                .iter()
+                .map(|entry| (entry.name().to_string(), Status::from(entry.result())))
+                .collect(),
+        }
+    }
+
+    /// Sets when the run finished, in seconds since the Unix epoch.
+    pub fn with_finished_at(mut self, finished_at: u64) -> Self {
+        self.finished_at = finished_at;
+        self
+    }
+
+    /// Gets when the run finished, in seconds since the Unix epoch.
+    pub fn finished_at(&self) -> u64 {
+        self.finished_at
+    }
+
+    /// Gets the summary of the run.
+    pub fn summary(&self) -> &Summary {
+        &self.summary
+    }
+
+    /// Gets the status of each test.
+    pub fn tests(&self) -> &BTreeMap<String, Status> {
+        &self.tests
+    }
+
+    /// Gets the name of the engine the run tested: the engine preset if one
+    /// was used, and otherwise the label of the badge.
+    pub fn engine(&self) -> &str {
+        series(
+            self.summary.engine(),
+            self.label.as_deref(),
+            self.summary.branch(),
+        )
+        .0
+    }
+
+    /// Gets the series of runs the run belongs to: runs of the same engine
+    /// against the same branch of the specification.
+    pub fn series(&self) -> (&str, &str) {
+        series(
+            self.summary.engine(),
+            self.label.as_deref(),
+            self.summary.branch(),
+        )
+    }
+
+    /// Gets the tests that failed in this run but passed in an earlier one,
+    /// and the tests that passed in this run but failed in the earlier one.
+    ///
+    /// Tests that were skipped or missing in either run are not compared.
+    pub fn changes_since<'a>(&'a self, earlier: &Record) -> (Vec<&'a str>, Vec<&'a str>) {
+        let mut broken = Vec::new();
+        let mut fixed = Vec::new();
+
+        for (name, status) in &self.tests {
+            match (earlier.tests.get(name), status) {
+                (Some(Status::Passed), Status::Failed) => broken.push(name.as_str()),
+                (Some(Status::Failed), Status::Passed) => fixed.push(name.as_str()),
+                _ => {}
+            }
+        }
+
+        (broken, fixed)
+    }
+
+    /// Renders the changes since an earlier run: a line with the number of
+    /// tests that were fixed and broken (e.g., `+3 −1 vs last run`), followed
+    /// by a line for each of those tests.
+    pub fn render_changes_since(&self, earlier: &Record) -> String {
+        let (broken, fixed) = self.changes_since(earlier);
+        let mut rendered = format!(
+            "+{} −{} vs last run ({})\n",
+            fixed.len(),
+            broken.len(),
+            format_timestamp(earlier.finished_at)
+        );
+
+        for name in fixed {
+            rendered.push_str(&format!("  + {name} (now passes)\n"));
+        }
+
+        for name in broken {
+            rendered.push_str(&format!("  − {name} (now fails)\n"));
+        }
+
+        rendered
+    }
+}
+
+/// Gets the series of a run from its engine preset, the rendered label of its
+/// badge, and the branch of the specification it tested (see
+/// [`Record::series()`]).
+pub fn series<'a>(
+    engine: Option<&'a str>,
+    label: Option<&'a str>,
+    branch: Option<&'a str>,
+) -> (&'a str, &'a str) {
+    (engine.or(label).unwrap_or("-"), branch.unwrap_or("-"))
+}
+
+/// Appends a run to a history file.
+pub fn append(path: &Path, record: &Record) -> Result<()> {
+    let line = serde_json::to_string(record).context("serializing history record")?;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening history `{}`", path.display()))?;
+    writeln!(file, "{line}").with_context(|| format!("appending to history `{}`", path.display()))
+}
+
+/// Reads every run from a history file, in the order they were appended.
+pub fn read(path: &Path) -> Result<Vec<Record>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("reading history `{}`", path.display()))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("parsing line {} of history `{}`", i + 1, path.display()))
+        })
+        .collect()
+}
+
+/// Finds the latest run in a history file within the same series as a run
+/// (see [`Record::series()`]).
+///
+/// Returns `None` if the history file does not exist yet.
+pub fn previous(path: &Path, record: &Record) -> Result<Option<Record>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    Ok(read(path)?
+        .into_iter()
+        .rev()
+        .find(|earlier| earlier.series() == record.series()))
+}
+
+/// Finds the tests that failed in the latest `runs` runs of a series within a
+/// history file, with the number of runs since each last failed (`0` for a
+/// test that failed in the latest run).
+///
+/// Returns no tests if the history file does not exist yet.
+pub fn recent_failures(
+    path: &Path,
+    series: (&str, &str),
+    runs: usize,
+) -> Result<BTreeMap<String, usize>> {
+    let mut failures = BTreeMap::new();
+    if !path.exists() {
+        return Ok(failures);
+    }
+
+    let records = read(path)?;
+    let recent = records
+        .iter()
+        .rev()
+        .filter(|record| record.series() == series)
+        .take(runs);
+    for (age, record) in recent.enumerate() {
+        for (name, status) in &record.tests {
+            if *status == Status::Failed {
+                failures.entry(name.clone()).or_insert(age);
+            }
+        }
+    }
+
+    Ok(failures)
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time (e.g.,
+/// `2026-03-16 09:05`).
+pub fn format_timestamp(secs: u64) -> String {
+    // The conversion from days to a civil date is from Howard Hinnant's
+    // `civil_from_days`.
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let time = secs % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        time / 3600,
+        time % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::TestResult;
+    use crate::report::Entry;
+
+    #[test]
+    fn changes() {
+        let failed = TestResult::Failed(FailureReason::NoOutput);
+        let summary = Summary::new(1, 1, 0, Duration::from_secs(1));
+        let earlier = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Passed, None),
+            Entry::new("b.wdl", &failed, None),
+        ]);
+        let later = Report::new(vec![
+            Entry::new("a.wdl", &failed, None),
+            Entry::new("b.wdl", &TestResult::Passed, None),
+            Entry::new("c.wdl", &failed, None),
+        ]);
+
+        let earlier = Record::new("WDL", &summary, &earlier);
+        let later = Record::new("WDL", &summary, &later);
+        assert_eq!(
+            later.changes_since(&earlier),
+            (vec!["a.wdl"], vec!["b.wdl"])
+        );
+        assert_eq!(later.series(), ("WDL", "-"));
+        assert_eq!(
+            later.render_changes_since(&earlier.with_finished_at(0)),
+            "+1 −1 vs last run (1970-01-01 00:00)\n  + b.wdl (now passes)\n  − a.wdl (now fails)\n"
+        );
+    }
+
+    #[test]
+    fn reads_older_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        std::fs::write(
+            &path,
+            "{\"finished_at\":1,\"passed\":3,\"failed\":1,\"skipped\":0,\"total\":4,\"percent\":\
+             75.0,\"duration_secs\":5.0}\n",
+        )
+        .unwrap();
+
+        let records = read(&path).unwrap();
+        assert_eq!(records[0].summary().passed(), 3);
+        assert!(records[0].tests().is_empty());
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(1_773_651_900), "2026-03-16 09:05");
+    }
+
+    #[test]
+    fn previous_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let report = Report::new(Vec::new());
+        let record = |label: &str, passed: usize| {
+            Record::new(label, &Summary::new(passed, 0, 0, Duration::ZERO), &report)
+        };
+
+        assert_eq!(previous(&path, &record("WDL", 1)).unwrap(), None);
+
+        append(&path, &record("WDL", 2)).unwrap();
+        append(&path, &record("WDL", 3)).unwrap();
+        append(&path, &record("other", 4)).unwrap();
+
+        let found = previous(&path, &record("WDL", 1)).unwrap().unwrap();
+        assert_eq!(found.summary().passed(), 3);
+    }
+
+    #[test]
+    fn recent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let failed = TestResult::Failed(FailureReason::NoOutput);
+        let summary = Summary::new(0, 0, 0, Duration::ZERO);
+        let record = |label: &str, failures: &[&str]| {
+            let report = Report::new(
+                failures
+                    .iter()
+                    .map(|name| Entry::new(*name, &failed, None))
+                    .chain([Entry::new("z.wdl", &TestResult::Passed, None)])
+                    .collect(),
+            );
+            Record::new(label, &summary, &report)
+        };
+
+        assert!(recent_failures(&path, ("WDL", "-"), 2).unwrap().is_empty());
+
+        append(&path, &record("WDL", &["a.wdl"])).unwrap();
+        append(&path, &record("WDL", &["b.wdl", "c.wdl"])).unwrap();
+        append(&path, &record("other", &["d.wdl"])).unwrap();
+        append(&path, &record("WDL", &["c.wdl"])).unwrap();
+
+        let failures = recent_failures(&path, ("WDL", "-"), 2).unwrap();
+        assert_eq!(
+            failures.into_iter().collect::<Vec<_>>(),
+            [(String::from("b.wdl"), 1), (String::from("c.wdl"), 0)]
+        );
+    }
+}
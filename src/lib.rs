@@ -1,11 +1,22 @@
 //! A conformance testing tool for WDL (Workflow Description Language) execution
 //! engines.
 
+pub mod attestation;
 pub mod badge;
+pub mod baseline;
+pub mod cache;
 pub mod command;
 pub mod conformance;
+pub mod diagnostic;
+pub mod engine;
+pub mod history;
+pub mod manifest;
+pub mod report;
 pub mod repository;
+pub mod selector;
 mod shell;
+pub mod sink;
 mod wdl;
 
 pub use repository::Repository;
+pub use wdl::Construct;
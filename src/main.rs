@@ -1,16 +1,80 @@
 //! The CLI interface for `spectool`.
 
+use std::io::IsTerminal as _;
+use std::process::ExitCode;
+
 use anyhow::Result;
+use clap::ArgMatches;
+use clap::CommandFactory as _;
+use clap::FromArgMatches as _;
 use clap::Parser;
 use clap::Subcommand;
 use clap_verbosity_flag::Verbosity;
+use spectool::command::cache::Args as CacheArgs;
+use spectool::command::diff::Args as DiffArgs;
+use spectool::command::doctor::Args as DoctorArgs;
+use spectool::command::explain::Args as ExplainArgs;
+use spectool::command::history::Args as HistoryArgs;
+use spectool::command::list::Args as ListArgs;
+use spectool::command::merge_results::Args as MergeResultsArgs;
+use spectool::command::open::Args as OpenArgs;
+use spectool::command::replay::Args as ReplayArgs;
+use spectool::command::selector_test::Args as SelectorTestArgs;
+use spectool::command::stats::Args as StatsArgs;
 use spectool::command::test::Args as TestArgs;
+use spectool::command::validate_output::Args as ValidateOutputArgs;
+use spectool::command::validate_spec::Args as ValidateSpecArgs;
 
 /// A supported subcommand.
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Performs conformance tests on the WDL specification.
-    Test(TestArgs),
+    Test(Box<TestArgs>),
+
+    /// Lists the conformance tests within the specification without running
+    /// them.
+    List(ListArgs),
+
+    /// Explains a failure code reported by the conformance tests.
+    Explain(ExplainArgs),
+
+    /// Validates actual outputs against expected outputs without running a
+    /// test.
+    ValidateOutput(ValidateOutputArgs),
+
+    /// Checks the conformance tests within the specification for structural
+    /// problems.
+    ValidateSpec(ValidateSpecArgs),
+
+    /// Evaluates an output selector against a sample file.
+    SelectorTest(SelectorTestArgs),
+
+    /// Manages the directories that `spectool` keeps between runs.
+    Cache(CacheArgs),
+
+    /// Checks the environment for common causes of failed test runs.
+    Doctor(DoctorArgs),
+
+    /// Compares the results of two test runs, such as from two engines.
+    Diff(DiffArgs),
+
+    /// Merges the results of several shards of a test run into one.
+    MergeResults(MergeResultsArgs),
+
+    /// Validates the outputs recorded by a previous test run again without
+    /// executing the engine.
+    Replay(ReplayArgs),
+
+    /// Aggregates which output keys most frequently mismatch across the
+    /// results of one or more test runs.
+    Stats(StatsArgs),
+
+    /// Shows the pass-rate trend of the runs recorded in a history file and
+    /// the tests each run newly broke or fixed.
+    History(HistoryArgs),
+
+    /// Opens the section of the specification that contains a test.
+    Open(OpenArgs),
 }
 
 /// A command-line tool for working with the WDL specification.
@@ -26,17 +90,51 @@ pub struct Args {
     verbosity: Verbosity,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+/// Runs the selected subcommand.
+fn run(args: Args, matches: &ArgMatches) -> Result<()> {
+    let quiet = args.verbosity.is_silent();
+    match args.command {
+        Command::Test(args) => {
+            let args = match matches.subcommand_matches("test") {
+                Some(matches) => args.with_settings(matches),
+                None => *args,
+            }
+            .with_quiet(quiet);
+            spectool::command::test::main(args)?
+        }
+        Command::List(args) => spectool::command::list::main(args)?,
+        Command::Explain(args) => spectool::command::explain::main(args)?,
+        Command::ValidateOutput(args) => spectool::command::validate_output::main(args)?,
+        Command::ValidateSpec(args) => spectool::command::validate_spec::main(args)?,
+        Command::SelectorTest(args) => spectool::command::selector_test::main(args)?,
+        Command::Cache(args) => spectool::command::cache::main(args)?,
+        Command::Doctor(args) => spectool::command::doctor::main(args)?,
+        Command::Diff(args) => spectool::command::diff::main(args)?,
+        Command::MergeResults(args) => spectool::command::merge_results::main(args)?,
+        Command::Replay(args) => spectool::command::replay::main(args)?,
+        Command::Stats(args) => spectool::command::stats::main(args)?,
+        Command::History(args) => spectool::command::history::main(args)?,
+        Command::Open(args) => spectool::command::open::main(args)?,
+    };
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
     tracing_subscriber::fmt()
         .with_max_level(args.verbosity)
         .with_writer(std::io::stderr)
         .init();
 
-    match args.command {
-        Command::Test(args) => spectool::command::test::main(args)?,
-    };
-
-    Ok(())
+    match run(args, &matches) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let color = spectool::diagnostic::use_color(std::io::stderr().is_terminal());
+            eprintln!("{}", spectool::diagnostic::render(&e, color));
+            ExitCode::FAILURE
+        }
+    }
 }
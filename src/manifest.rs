@@ -0,0 +1,222 @@
+//! Manifests published by engines to describe what they support.
+//!
+//! A manifest is a TOML file, conventionally named `capabilities.toml`, that
+//! an engine ships alongside its releases so that the settings of a
+//! conformance run are driven by data the engine maintains itself:
+//!
+//! ```toml
+//! name = "sprocket"
+//! version = "0.9.0"
+//! command = "sprocket run ~{path} ~{input} -t ~{target}"
+//! wdl-versions = ["1.2"]
+//! capabilities = ["cpu", "memory"]
+//! unsupported-constructs = ["directory"]
+//!
+//! [outputs]
+//! from = "auto"
+//! selectors = [".outputs"]
+//! ```
+//!
+//! Every key is optional.
+
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::conformance::Capability;
+use crate::engine::OutputSource;
+use crate::wdl::Construct;
+
+/// The conventional file name of a manifest.
+pub const FILE_NAME: &str = "capabilities.toml";
+
+/// A manifest describing what an engine supports and how it is invoked.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Manifest {
+    /// The name of the engine.
+    name: Option<String>,
+
+    /// The version of the engine.
+    version: Option<String>,
+
+    /// The command template that runs a test.
+    command: Option<String>,
+
+    /// The arguments appended when running a workflow.
+    workflow_target_args: Option<String>,
+
+    /// The arguments appended when running a task.
+    task_target_args: Option<String>,
+
+    /// The WDL versions the engine supports.
+    wdl_versions: Option<Vec<String>>,
+
+    /// The runtime capabilities the engine supports.
+    capabilities: Option<Vec<Capability>>,
+
+    /// The WDL constructs the engine does not support.
+    unsupported_constructs: Option<Vec<Construct>>,
+
+    /// Whether the engine is unable to execute a bare task.
+    tasks_unsupported: Option<bool>,
+
+    /// How the engine reports the outputs of a run.
+    #[serde(default)]
+    outputs: Outputs,
+}
+
+/// How an engine reports the outputs of a run.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Outputs {
+    /// Where the outputs are read from.
+    from: Option<OutputSource>,
+
+    /// The path the engine writes its outputs to.
+    file: Option<String>,
+
+    /// The `jq` selectors applied in sequence to the outputs.
+    selectors: Option<Vec<String>>,
+
+    /// The `jq` selectors applied in sequence to the expected outputs.
+    expected_selectors: Option<Vec<String>>,
+
+    /// Whether the engine reports outputs without the `<target>.` prefix.
+    strip_prefix: Option<bool>,
+}
+
+impl Manifest {
+    /// Reads a manifest.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading engine manifest `{}`", path.display()))?;
+
+        toml::from_str(&contents)
+            .with_context(|| format!("parsing engine manifest `{}`", path.display()))
+    }
+
+    /// Gets the name of the engine.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the version of the engine.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Gets the command template that runs a test.
+    pub fn command(&self) -> Option<&str> {
+        self.command.as_deref()
+    }
+
+    /// Gets the arguments appended when running a workflow.
+    pub fn workflow_target_args(&self) -> Option<&str> {
+        self.workflow_target_args.as_deref()
+    }
+
+    /// Gets the arguments appended when running a task.
+    pub fn task_target_args(&self) -> Option<&str> {
+        self.task_target_args.as_deref()
+    }
+
+    /// Gets the WDL versions the engine supports.
+    pub fn wdl_versions(&self) -> Option<&[String]> {
+        self.wdl_versions.as_deref()
+    }
+
+    /// Gets the runtime capabilities the engine supports.
+    pub fn capabilities(&self) -> Option<&[Capability]> {
+        self.capabilities.as_deref()
+    }
+
+    /// Gets the WDL constructs the engine does not support.
+    pub fn unsupported_constructs(&self) -> Option<&[Construct]> {
+        self.unsupported_constructs.as_deref()
+    }
+
+    /// Gets whether the engine is unable to execute a bare task.
+    pub fn tasks_unsupported(&self) -> Option<bool> {
+        self.tasks_unsupported
+    }
+
+    /// Gets how the engine reports the outputs of a run.
+    pub fn outputs(&self) -> &Outputs {
+        &self.outputs
+    }
+}
+
+impl Outputs {
+    /// Gets where the outputs are read from.
+    pub fn from(&self) -> Option<OutputSource> {
+        self.from
+    }
+
+    /// Gets the path the engine writes its outputs to.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Gets the `jq` selectors applied in sequence to the outputs.
+    pub fn selectors(&self) -> Option<&[String]> {
+        self.selectors.as_deref()
+    }
+
+    /// Gets the `jq` selectors applied in sequence to the expected outputs.
+    pub fn expected_selectors(&self) -> Option<&[String]> {
+        self.expected_selectors.as_deref()
+    }
+
+    /// Gets whether the engine reports outputs without the `<target>.`
+    /// prefix.
+    pub fn strip_prefix(&self) -> Option<bool> {
+        self.strip_prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let manifest = toml::from_str::<Manifest>(
+            r#"
+            name = "engine"
+            version = "1.0.0"
+            command = "engine run ~{path} ~{input}"
+            wdl-versions = ["1.1", "1.2"]
+            capabilities = ["cpu", "allow_nested_inputs"]
+            unsupported-constructs = ["directory", "else_if"]
+            tasks-unsupported = true
+
+            [outputs]
+            from = "stdout"
+            selectors = [".outputs"]
+            strip-prefix = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.name(), Some("engine"));
+        assert_eq!(manifest.version(), Some("1.0.0"));
+        assert_eq!(
+            manifest.capabilities(),
+            Some(&[Capability::Cpu, Capability::AllowNestedInputs][..])
+        );
+        assert_eq!(
+            manifest.unsupported_constructs(),
+            Some(&[Construct::Directory, Construct::ElseIf][..])
+        );
+        assert_eq!(manifest.tasks_unsupported(), Some(true));
+        assert_eq!(manifest.outputs().from(), Some(OutputSource::Stdout));
+        assert_eq!(manifest.outputs().strip_prefix(), Some(true));
+        assert_eq!(manifest.outputs().file(), None);
+
+        assert_eq!(toml::from_str::<Manifest>("").unwrap(), Manifest::default());
+        assert!(toml::from_str::<Manifest>("unknown = 1").is_err());
+    }
+}
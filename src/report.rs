@@ -0,0 +1,1010 @@
+//! Machine-readable reports of conformance test results.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use bon::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+use statrs::statistics::Data;
+use statrs::statistics::OrderStatistics;
+use statrs::statistics::Statistics;
+
+use crate::conformance::FailureCategory;
+use crate::conformance::FailureCode;
+use crate::conformance::Lines;
+use crate::conformance::TestResult;
+use crate::conformance::test::validation::MismatchedKey;
+use crate::engine::OutputSource;
+use crate::shell::quote;
+
+/// The outcome of a single test within a report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    /// The test passed.
+    Passed,
+    /// The test failed.
+    Failed {
+        /// The category of the failure.
+        category: FailureCategory,
+        /// The machine-readable code of the failure.
+        code: FailureCode,
+        /// The human-readable failure message.
+        message: String,
+        /// The key for each mismatch between the expected and actual
+        /// outputs.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        keys: Vec<MismatchedKey>,
+    },
+    /// The test was skipped.
+    Skipped {
+        /// The human-readable reason the test was skipped.
+        reason: String,
+    },
+}
+
+impl From<&TestResult> for Outcome {
+    fn from(result: &TestResult) -> Self {
+        match result {
+            TestResult::Passed => Outcome::Passed,
+            TestResult::Failed(reason) => Outcome::Failed {
+                category: reason.category(),
+                code: reason.code(),
+                message: reason.to_string(),
+                keys: reason.mismatched_keys().to_vec(),
+            },
+            TestResult::Skipped(reason) => Outcome::Skipped {
+                reason: reason.to_string(),
+            },
+        }
+    }
+}
+
+/// How the command for a test was invoked.
+#[derive(Builder, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invocation {
+    /// The command after all substitutions were applied.
+    #[builder(into)]
+    command: String,
+
+    /// The directory the command was executed in.
+    #[builder(into)]
+    working_dir: PathBuf,
+
+    /// Environment variables set for the command in addition to the
+    /// environment `spectool` was run with.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[builder(default)]
+    env: BTreeMap<String, String>,
+
+    /// Whether the command was run with only `env` rather than in addition to
+    /// the environment `spectool` was run with.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[builder(default)]
+    isolated: bool,
+
+    /// Where the outputs of the command were read from, if they were read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    outputs_from: Option<OutputSource>,
+}
+
+impl Invocation {
+    /// Gets the command after all substitutions were applied.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Gets the directory the command was executed in.
+    pub fn working_dir(&self) -> &Path {
+        &self.working_dir
+    }
+
+    /// Gets the environment variables set for the command.
+    pub fn env(&self) -> &BTreeMap<String, String> {
+        &self.env
+    }
+
+    /// Gets whether the command was run with only its environment variables.
+    pub fn isolated(&self) -> bool {
+        self.isolated
+    }
+
+    /// Gets where the outputs of the command were read from.
+    pub fn outputs_from(&self) -> Option<OutputSource> {
+        self.outputs_from
+    }
+
+    /// Renders the invocation as a shell command line that can be copied and
+    /// pasted to reproduce the test.
+    pub fn to_shell(&self) -> String {
+        let cd = format!("cd {}", quote(&self.working_dir.display().to_string()));
+
+        if self.env.is_empty() && !self.isolated {
+            return format!("{cd} && {}", self.command);
+        }
+
+        let env = self
+            .isolated
+            .then(|| String::from("-i"))
+            .into_iter()
+            .chain(
+                self.env
+                    .iter()
+                    .map(|(key, value)| quote(&format!("{key}={value}"))),
+            )
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        format!("{cd} && env {env} bash -c {}", quote(&self.command))
+    }
+}
+
+/// An entry for a single test within a report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// The file name of the test.
+    name: String,
+
+    /// The outcome of the test.
+    result: Outcome,
+
+    /// The lines the test occupies within the specification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lines: Option<Lines>,
+
+    /// The path of the test within the conformance tests directory, if the
+    /// tests were not laid out by file name alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+
+    /// The URL of the specification section containing the test.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    section_url: Option<String>,
+
+    /// The execution time of the test in seconds, if it was executed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<f64>,
+
+    /// The portion of the execution time that the engine reported as setup,
+    /// in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    setup_secs: Option<f64>,
+
+    /// How the command for the test was invoked, if it was executed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    invocation: Option<Invocation>,
+
+    /// Whether the source of the test was rewritten by `--preprocess-cmd` or
+    /// `--target-dialect`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    preprocessed: bool,
+}
+
+impl Entry {
+    /// Creates a new entry from a test result.
+    pub fn new(name: impl Into<String>, result: &TestResult, elapsed: Option<Duration>) -> Self {
+        Self {
+            name: name.into(),
+            result: Outcome::from(result),
+            lines: None,
+            path: None,
+            section_url: None,
+            duration_secs: elapsed.map(|d| d.as_secs_f64()),
+            setup_secs: None,
+            invocation: None,
+            preprocessed: false,
+        }
+    }
+
+    /// Sets the lines the test occupies within the specification.
+    pub fn with_lines(mut self, lines: Option<Lines>) -> Self {
+        self.lines = lines;
+        self
+    }
+
+    /// Sets the path of the test within the conformance tests directory.
+    pub fn with_path(mut self, path: Option<String>) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Sets the URL of the specification section containing the test.
+    pub fn with_section_url(mut self, section_url: Option<String>) -> Self {
+        self.section_url = section_url;
+        self
+    }
+
+    /// Sets the portion of the execution time reported as setup.
+    pub fn with_setup(mut self, setup: Option<Duration>) -> Self {
+        self.setup_secs = setup.map(|d| d.as_secs_f64());
+        self
+    }
+
+    /// Sets how the command for the test was invoked.
+    pub fn with_invocation(mut self, invocation: Option<Invocation>) -> Self {
+        self.invocation = invocation;
+        self
+    }
+
+    /// Sets whether the source of the test was rewritten before it was run.
+    pub fn with_preprocessed(mut self, preprocessed: bool) -> Self {
+        self.preprocessed = preprocessed;
+        self
+    }
+
+    /// Gets the file name of the test.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the outcome of the test.
+    pub fn result(&self) -> &Outcome {
+        &self.result
+    }
+
+    /// Gets the execution time of the test in seconds.
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.duration_secs
+    }
+
+    /// Gets the portion of the execution time reported as setup in seconds.
+    pub fn setup_secs(&self) -> Option<f64> {
+        self.setup_secs
+    }
+
+    /// Gets how the command for the test was invoked.
+    pub fn invocation(&self) -> Option<&Invocation> {
+        self.invocation.as_ref()
+    }
+
+    /// Gets the lines the test occupies within the specification.
+    pub fn lines(&self) -> Option<Lines> {
+        self.lines
+    }
+
+    /// Gets the path of the test within the conformance tests directory, if
+    /// the tests were not laid out by file name alone.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Gets the URL of the specification section containing the test.
+    pub fn section_url(&self) -> Option<&str> {
+        self.section_url.as_deref()
+    }
+
+    /// Gets whether the source of the test was rewritten before it was run.
+    pub fn preprocessed(&self) -> bool {
+        self.preprocessed
+    }
+}
+
+/// Generates an identifier for a run, in the form of a random (version 4)
+/// UUID.
+pub fn generate_run_id() -> String {
+    let id = rand::random::<u128>();
+    // Set the version (4) and variant (RFC 4122) bits
+    let id = (id & !(0xf << 76)) | (0x4 << 76);
+    let id = (id & !(0x3 << 62)) | (0x2 << 62);
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        id >> 96,
+        (id >> 80) & 0xffff,
+        (id >> 64) & 0xffff,
+        (id >> 48) & 0xffff,
+        id & 0xffff_ffff_ffff
+    )
+}
+
+/// A machine-readable report of a conformance test run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    /// The identifier of the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    run_id: Option<String>,
+
+    /// The wall time of the run in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<f64>,
+
+    /// Whether the run reached its timeout before every test was run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
+
+    /// The entries for each test.
+    tests: Vec<Entry>,
+}
+
+impl Report {
+    /// Creates a new report from a set of entries.
+    pub fn new(tests: Vec<Entry>) -> Self {
+        Self {
+            run_id: None,
+            duration_secs: None,
+            truncated: false,
+            tests,
+        }
+    }
+
+    /// Merges the reports of several runs that each ran a disjoint subset of
+    /// the tests, such as the shards of a run.
+    ///
+    /// Tests are ordered by their position within the specification where it
+    /// was recorded. The wall time is that of the longest run, as the runs are
+    /// expected to have run in parallel, the identifier is kept if every run
+    /// shares it, and the merged report is truncated if any run was.
+    ///
+    /// Returns an error if a test appears in more than one report.
+    pub fn merge(reports: Vec<Report>) -> Result<Self> {
+        let mut names = BTreeMap::new();
+        let mut run_ids = reports.iter().map(|r| r.run_id.as_deref());
+        let run_id = run_ids
+            .next()
+            .flatten()
+            .filter(|id| run_ids.all(|other| other == Some(id)))
+            .map(str::to_string);
+        let duration_secs = reports
+            .iter()
+            .filter_map(|r| r.duration_secs)
+            .max_by(f64::total_cmp);
+        let truncated = reports.iter().any(|r| r.truncated);
+
+        let mut tests = Vec::new();
+        for (i, report) in reports.into_iter().enumerate() {
+            for entry in report.tests {
+                if let Some(previous) = names.insert(entry.name.clone(), i) {
+                    bail!(
+                        "test `{}` appears in both report {} and report {}",
+                        entry.name,
+                        previous + 1,
+                        i + 1
+                    );
+                }
+                tests.push(entry);
+            }
+        }
+
+        tests.sort_by_key(|entry| entry.lines.map_or(usize::MAX, |lines| lines.start()));
+
+        Ok(Self {
+            run_id,
+            duration_secs,
+            truncated,
+            tests,
+        })
+    }
+
+    /// Sets the identifier of the run.
+    pub fn with_run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    /// Updates the report with the entries of a newer run.
+    ///
+    /// The entries of tests the newer run did not run, or skipped, are kept,
+    /// so that running a subset of the tests does not forget the outcomes of
+    /// the rest.
+    pub fn updated(self, newer: Report) -> Self {
+        let skipped = |entry: &Entry| matches!(entry.result, Outcome::Skipped { .. });
+        let newer_skipped = newer
+            .tests
+            .iter()
+            .map(|entry| (entry.name.clone(), skipped(entry)))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut tests = self
+            .tests
+            .into_iter()
+            .filter(|entry| match newer_skipped.get(&entry.name) {
+                None => true,
+                Some(newer_skipped) => *newer_skipped && !skipped(entry),
+            })
+            .collect::<Vec<_>>();
+        let kept = tests
+            .iter()
+            .map(|e| e.name.clone())
+            .collect::<BTreeSet<_>>();
+        tests.extend(
+            newer
+                .tests
+                .into_iter()
+                .filter(|entry| !kept.contains(&entry.name)),
+        );
+        tests.sort_by_key(|entry| entry.lines.map_or(usize::MAX, |lines| lines.start()));
+
+        Self {
+            run_id: newer.run_id,
+            duration_secs: newer.duration_secs,
+            truncated: newer.truncated,
+            tests,
+        }
+    }
+
+    /// Gets the names of the tests that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &str> {
+        self.tests
+            .iter()
+            .filter(|entry| matches!(entry.result, Outcome::Failed { .. }))
+            .map(|entry| entry.name.as_str())
+    }
+
+    /// Sets the wall time of the run.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration_secs = Some(duration.as_secs_f64());
+        self
+    }
+
+    /// Sets whether the run reached its timeout before every test was run.
+    pub fn with_truncated(mut self, truncated: bool) -> Self {
+        self.truncated = truncated;
+        self
+    }
+
+    /// Gets the identifier of the run, if one was recorded.
+    pub fn run_id(&self) -> Option<&str> {
+        self.run_id.as_deref()
+    }
+
+    /// Gets the wall time of the run, if it was recorded.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration_secs.map(Duration::from_secs_f64)
+    }
+
+    /// Summarizes the outcomes of the tests within the report.
+    pub fn summary(&self) -> Summary {
+        let count = |f: fn(&Outcome) -> bool| self.tests.iter().filter(|e| f(&e.result)).count();
+        let summary = Summary::new(
+            count(|o| matches!(o, Outcome::Passed)),
+            count(|o| matches!(o, Outcome::Failed { .. })),
+            count(|o| matches!(o, Outcome::Skipped { .. })),
+            self.duration().unwrap_or_default(),
+        )
+        .with_truncated(self.truncated);
+
+        match &self.run_id {
+            Some(id) => summary.with_run_id(id),
+            None => summary,
+        }
+    }
+
+    /// Gets the entries within the report.
+    pub fn tests(&self) -> &[Entry] {
+        &self.tests
+    }
+
+    /// Reads a report from a JSON file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading report `{}`", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("parsing report `{}`", path.display()))
+    }
+
+    /// Writes the report as JSON to a file.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self).context("serializing report")?;
+        std::fs::write(path, json).with_context(|| format!("writing report `{}`", path.display()))
+    }
+}
+
+/// The format of the summary printed at the end of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryFormat {
+    /// Space-separated `key=value` pairs.
+    Oneline,
+    /// A single-line JSON object.
+    Json,
+}
+
+/// The counts, pass rate, and duration of a conformance test run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    /// The number of tests that passed.
+    passed: usize,
+
+    /// The number of tests that failed.
+    failed: usize,
+
+    /// The number of tests that were skipped.
+    skipped: usize,
+
+    /// The number of tests that were executed.
+    total: usize,
+
+    /// The percentage of executed tests that passed, to one decimal place.
+    percent: f64,
+
+    /// The wall time of the run in seconds.
+    duration_secs: f64,
+
+    /// Whether the run reached its timeout before every test was run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
+
+    /// The identifier of the run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<String>,
+
+    /// The engine that was tested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    engine: Option<String>,
+
+    /// The version of the engine that was tested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    engine_version: Option<String>,
+
+    /// The branch of the specification that was tested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+
+    /// The commit of the specification that was tested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+}
+
+impl Summary {
+    /// Creates a new summary.
+    pub fn new(passed: usize, failed: usize, skipped: usize, duration: Duration) -> Self {
+        let total = passed + failed;
+        let percent = if total == 0 {
+            0.0
+        } else {
+            ((passed as f64) / (total as f64) * 1000.0).round() / 10.0
+        };
+
+        Self {
+            passed,
+            failed,
+            skipped,
+            total,
+            percent,
+            duration_secs: duration.as_secs_f64(),
+            truncated: false,
+            run_id: None,
+            engine: None,
+            engine_version: None,
+            branch: None,
+            commit: None,
+        }
+    }
+
+    /// Sets the engine that was tested and its version.
+    pub fn with_engine(mut self, engine: Option<String>, version: Option<String>) -> Self {
+        self.engine = engine;
+        self.engine_version = version;
+        self
+    }
+
+    /// Sets the branch and commit of the specification that was tested.
+    pub fn with_specification(mut self, branch: impl Into<String>, commit: Option<String>) -> Self {
+        self.branch = Some(branch.into());
+        self.commit = commit;
+        self
+    }
+
+    /// Sets the identifier of the run.
+    pub fn with_run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    /// Sets whether the run reached its timeout before every test was run.
+    pub fn with_truncated(mut self, truncated: bool) -> Self {
+        self.truncated = truncated;
+        self
+    }
+
+    /// Gets the number of tests that passed.
+    pub fn passed(&self) -> usize {
+        self.passed
+    }
+
+    /// Gets the number of tests that failed.
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Gets the number of tests that were skipped.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Gets the number of tests that were executed.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Gets the percentage of executed tests that passed.
+    pub fn percent(&self) -> f64 {
+        self.percent
+    }
+
+    /// Gets the wall time of the run in seconds.
+    pub fn duration_secs(&self) -> f64 {
+        self.duration_secs
+    }
+
+    /// Gets whether the run reached its timeout before every test was run.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Gets the engine that was tested.
+    pub fn engine(&self) -> Option<&str> {
+        self.engine.as_deref()
+    }
+
+    /// Gets the version of the engine that was tested.
+    pub fn engine_version(&self) -> Option<&str> {
+        self.engine_version.as_deref()
+    }
+
+    /// Gets the branch of the specification that was tested.
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    /// Gets the commit of the specification that was tested.
+    pub fn commit(&self) -> Option<&str> {
+        self.commit.as_deref()
+    }
+
+    /// Gets the figures of the summary with their labels, in the order they
+    /// are displayed by the console summary, the Markdown report, and
+    /// `spectool stats`.
+    pub fn figures(&self) -> [(&'static str, String); 6] {
+        [
+            ("Passed", self.passed.to_string()),
+            ("Failed", self.failed.to_string()),
+            ("Skipped", self.skipped.to_string()),
+            ("Total", self.total.to_string()),
+            ("Pass rate", format!("{:.1}%", self.percent)),
+            ("Duration", format!("{:.2}s", self.duration_secs)),
+        ]
+    }
+
+    /// Renders the figures of the summary as aligned `Label: value` lines,
+    /// followed by a line flagging a truncated run.
+    pub fn render_figures(&self) -> String {
+        let mut figures = self
+            .figures()
+            .into_iter()
+            .map(|(label, value)| format!("{:<10} {value}\n", format!("{label}:")))
+            .collect::<String>();
+        if self.truncated {
+            figures.push_str("Truncated: the run timed out, so the remaining tests were not run\n");
+        }
+        figures
+    }
+
+    /// Renders the summary as a single line.
+    pub fn render(&self, format: SummaryFormat) -> String {
+        match format {
+            SummaryFormat::Oneline => format!(
+                "passed={} failed={} skipped={} total={} percent={:.1} duration_secs={:.2}{}{}",
+                self.passed,
+                self.failed,
+                self.skipped,
+                self.total,
+                self.percent,
+                self.duration_secs,
+                if self.truncated {
+                    " truncated=true"
+                } else {
+                    ""
+                },
+                self.run_id
+                    .as_ref()
+                    .map(|id| format!(" run_id={id}"))
+                    .unwrap_or_default()
+            ),
+            SummaryFormat::Json => {
+                serde_json::to_string(self).expect("summary serialization to succeed")
+            }
+        }
+    }
+}
+
+/// The distribution of the durations of a set of tests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DurationStats {
+    /// The number of durations.
+    n: usize,
+
+    /// The mean duration in seconds.
+    mean: f64,
+
+    /// The median duration in seconds.
+    median: f64,
+
+    /// The standard deviation of the durations in seconds.
+    stddev: f64,
+}
+
+impl DurationStats {
+    /// Computes the distribution of a set of durations, or `None` if there are
+    /// none.
+    pub fn new(durations: &[Duration]) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+
+        let secs = durations
+            .iter()
+            .map(Duration::as_secs_f64)
+            .collect::<Vec<_>>();
+        Some(Self {
+            n: secs.len(),
+            mean: secs.as_slice().mean(),
+            stddev: secs.as_slice().std_dev(),
+            median: Data::new(secs).median(),
+        })
+    }
+
+    /// Gets the number of durations.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl fmt::Display for DurationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mean={:.2}s, median={:.2}s, stddev={:.2}s",
+            self.mean, self.median, self.stddev
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::SkipReason;
+
+    #[test]
+    fn failure_serializes_category_and_code() {
+        let entry = Entry::new(
+            "foo.wdl",
+            &TestResult::Failed(FailureReason::NoOutput),
+            Some(Duration::from_secs(2)),
+        );
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["result"]["status"], "failed");
+        assert_eq!(json["result"]["category"], "validation");
+        assert_eq!(json["result"]["code"], "no_output");
+        assert_eq!(json["duration_secs"], 2.0);
+    }
+
+    #[test]
+    fn invocation_to_shell() {
+        let invocation = Invocation::builder()
+            .command("sprocket run foo.wdl")
+            .working_dir("/tmp/my tests")
+            .build();
+        assert_eq!(
+            invocation.to_shell(),
+            "cd '/tmp/my tests' && sprocket run foo.wdl"
+        );
+
+        let invocation = Invocation::builder()
+            .command("sprocket run foo.wdl")
+            .working_dir("/tmp")
+            .env(BTreeMap::from([(String::from("TZ"), String::from("UTC"))]))
+            .build();
+        assert_eq!(
+            invocation.to_shell(),
+            "cd /tmp && env TZ=UTC bash -c 'sprocket run foo.wdl'"
+        );
+
+        let invocation = Invocation::builder()
+            .command("sprocket run foo.wdl")
+            .working_dir("/tmp")
+            .env(BTreeMap::from([(String::from("TZ"), String::from("UTC"))]))
+            .isolated(true)
+            .build();
+        assert_eq!(
+            invocation.to_shell(),
+            "cd /tmp && env -i TZ=UTC bash -c 'sprocket run foo.wdl'"
+        );
+    }
+
+    #[test]
+    fn summary_formats() {
+        let summary = Summary::new(157, 12, 3, Duration::from_millis(61_250));
+
+        assert_eq!(
+            summary.render(SummaryFormat::Oneline),
+            "passed=157 failed=12 skipped=3 total=169 percent=92.9 duration_secs=61.25"
+        );
+        assert_eq!(
+            summary.render(SummaryFormat::Json),
+            r#"{"passed":157,"failed":12,"skipped":3,"total":169,"percent":92.9,"duration_secs":61.25}"#
+        );
+
+        let summary = summary.with_run_id("abc");
+        assert!(
+            summary
+                .render(SummaryFormat::Oneline)
+                .ends_with(" duration_secs=61.25 run_id=abc")
+        );
+        assert!(
+            summary
+                .render(SummaryFormat::Json)
+                .ends_with(r#","run_id":"abc"}"#)
+        );
+    }
+
+    #[test]
+    fn run_ids() {
+        let regex = regex::Regex::new(
+            r"^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$",
+        )
+        .unwrap();
+        let (a, b) = (generate_run_id(), generate_run_id());
+
+        assert!(regex.is_match(&a), "`{a}` is not a UUID");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn merge() {
+        let lines = |start| Some(Lines::from_offsets(&"\n".repeat(start), start - 1..start));
+        let first = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Passed, None).with_lines(lines(1)),
+            Entry::new("c.wdl", &TestResult::Failed(FailureReason::NoOutput), None)
+                .with_lines(lines(30)),
+        ])
+        .with_run_id("abc")
+        .with_duration(Duration::from_secs(5));
+        let second = Report::new(vec![
+            Entry::new("b.wdl", &TestResult::Passed, None).with_lines(lines(10)),
+        ])
+        .with_run_id("abc")
+        .with_duration(Duration::from_secs(8));
+
+        let merged = Report::merge(vec![first.clone(), second.clone()]).unwrap();
+        assert_eq!(
+            merged.tests().iter().map(Entry::name).collect::<Vec<_>>(),
+            ["a.wdl", "b.wdl", "c.wdl"]
+        );
+        assert_eq!(merged.run_id(), Some("abc"));
+        assert_eq!(
+            merged.summary().render(SummaryFormat::Oneline),
+            "passed=2 failed=1 skipped=0 total=3 percent=66.7 duration_secs=8.00 run_id=abc"
+        );
+
+        let merged = Report::merge(vec![first.clone(), second.with_run_id("def")]).unwrap();
+        assert_eq!(merged.run_id(), None);
+
+        let error = Report::merge(vec![first.clone(), first]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "test `a.wdl` appears in both report 1 and report 2"
+        );
+    }
+
+    #[test]
+    fn updated() {
+        let previous = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Passed, None),
+            Entry::new("b.wdl", &TestResult::Failed(FailureReason::NoOutput), None),
+            Entry::new("c.wdl", &TestResult::Failed(FailureReason::NoOutput), None),
+        ]);
+        assert_eq!(previous.failed().collect::<Vec<_>>(), ["b.wdl", "c.wdl"]);
+
+        let newer = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Skipped(SkipReason::Ignored), None),
+            Entry::new("b.wdl", &TestResult::Passed, None),
+        ])
+        .with_run_id("abc");
+        let state = previous.updated(newer);
+        assert_eq!(state.failed().collect::<Vec<_>>(), ["c.wdl"]);
+        assert_eq!(state.summary().passed(), 2);
+        assert_eq!(state.run_id(), Some("abc"));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let report = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Passed, None),
+            Entry::new(
+                "b.wdl",
+                &TestResult::Failed(FailureReason::UnexpectedSuccess),
+                Some(Duration::from_millis(1500)),
+            )
+            .with_lines(Some(Lines::from_offsets("a\nb\nc\n", 2..6)))
+            .with_invocation(Some(
+                Invocation::builder()
+                    .command("engine run b.wdl")
+                    .working_dir("/tmp")
+                    .build(),
+            )),
+        ])
+        .with_run_id("abc");
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(r#""lines":{"start":2,"end":3}"#));
+        let parsed: Report = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+        assert_eq!(parsed.run_id(), Some("abc"));
+    }
+
+    #[test]
+    fn summary_figures() {
+        let summary = Summary::new(3, 1, 2, Duration::from_millis(5250));
+        assert_eq!(
+            summary.render_figures(),
+            "Passed:    3\nFailed:    1\nSkipped:   2\nTotal:     4\nPass rate: 75.0%\nDuration:  \
+             5.25s\n"
+        );
+
+        assert_eq!(DurationStats::new(&[]), None);
+        let stats = DurationStats::new(&[
+            Duration::from_secs(1),
+            Duration::from_secs(2),
+            Duration::from_secs(6),
+        ])
+        .unwrap();
+        assert_eq!(stats.n(), 3);
+        assert_eq!(stats.to_string(), "mean=3.00s, median=2.00s, stddev=2.65s");
+    }
+
+    #[test]
+    fn truncated() {
+        let timeout = SkipReason::RunTimeout {
+            timeout: Duration::from_secs(2700),
+        };
+        let report = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Passed, None),
+            Entry::new("b.wdl", &TestResult::Skipped(timeout), None),
+        ])
+        .with_truncated(true);
+
+        let summary = report.summary();
+        assert!(summary.truncated());
+        assert_eq!(
+            summary.render(SummaryFormat::Oneline),
+            "passed=1 failed=0 skipped=1 total=1 percent=100.0 duration_secs=0.00 truncated=true"
+        );
+        assert!(
+            summary
+                .render(SummaryFormat::Json)
+                .ends_with(r#""truncated":true}"#)
+        );
+        assert!(
+            summary
+                .render_figures()
+                .ends_with("Truncated: the run timed out, so the remaining tests were not run\n")
+        );
+        assert_eq!(
+            report.tests()[1].result(),
+            &Outcome::Skipped {
+                reason: String::from("not run: the run reached its timeout of 2700s"),
+            }
+        );
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(r#""truncated":true"#));
+        assert_eq!(serde_json::from_str::<Report>(&json).unwrap(), report);
+
+        let merged = Report::merge(vec![report, Report::new(Vec::new())]).unwrap();
+        assert!(merged.summary().truncated());
+        assert!(!Report::new(Vec::new()).summary().truncated());
+    }
+}
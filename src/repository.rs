@@ -35,9 +35,8 @@ impl Repository {
         let path = self.local_dir.unwrap_or_else(|| {
             // SAFETY: on all the platforms we support, we expect a temporary
             // directory to be able to be created.
-            let path = tempfile::tempdir()
+            let path = crate::cache::keep_dir(crate::cache::REPOSITORY_PREFIX)
                 .expect("tempdir to create")
-                .keep()
                 .join("wdl");
 
             info!(
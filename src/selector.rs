@@ -0,0 +1,446 @@
+//! `jq` selectors for transforming outputs.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::conformance::FailureReason;
+use crate::diagnostic::Code;
+use crate::diagnostic::Diagnostic;
+
+/// The prefix that marks a reference to a named selector.
+pub const NAMED_PREFIX: char = '@';
+
+/// One or more selectors applied in sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+enum Steps {
+    /// A single selector.
+    One(String),
+    /// Multiple selectors applied in sequence.
+    Many(Vec<String>),
+}
+
+/// A library of named selectors.
+///
+/// Each name maps to a selector or to a list of selectors applied in
+/// sequence. Named selectors are referenced as `@<name>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Library(BTreeMap<String, Steps>);
+
+impl Library {
+    /// Reads a library from a JSON file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading selector library `{}`", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("parsing selector library `{}`", path.display()))
+    }
+
+    /// Expands references to named selectors within a chain of selectors.
+    ///
+    /// Named selectors may not reference other named selectors.
+    pub fn resolve(&self, selectors: &[String]) -> Result<Vec<String>, Diagnostic> {
+        let mut resolved = Vec::new();
+
+        for selector in selectors {
+            let Some(name) = selector.strip_prefix(NAMED_PREFIX) else {
+                resolved.push(selector.clone());
+                continue;
+            };
+
+            match self.0.get(name) {
+                Some(Steps::One(selector)) => resolved.push(selector.clone()),
+                Some(Steps::Many(steps)) => resolved.extend(steps.iter().cloned()),
+                None => {
+                    let hint = if self.0.is_empty() {
+                        String::from("named selectors are loaded with `--selectors-file`")
+                    } else {
+                        let names = self
+                            .0
+                            .keys()
+                            .map(|name| format!("`{NAMED_PREFIX}{name}`"))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        format!("available named selectors are {names}")
+                    };
+
+                    return Err(Diagnostic::new(
+                        Code::UnknownSelector,
+                        format!("unknown named selector `{selector}`"),
+                    )
+                    .with_hint(hint));
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+/// The maximum length of a snippet of a selector result in a diagnostic.
+const SNIPPET_LEN: usize = 80;
+
+/// Applies a chain of `jq` selectors in sequence to a JSON value.
+///
+/// If any selectors are applied, the final result must be an object of
+/// outputs.
+pub fn apply_all(selectors: &[String], input: &Value) -> Result<Value, FailureReason> {
+    let output = selectors
+        .iter()
+        .try_fold(input.clone(), |value, selector| apply(selector, &value))?;
+
+    if selectors.is_empty() {
+        return Ok(output);
+    }
+
+    ensure_object(&selectors.join(" | "), output)
+}
+
+/// Ensures that the result of a selector is an object of outputs.
+fn ensure_object(selector: &str, output: Value) -> Result<Value, FailureReason> {
+    let selector = selector.to_string();
+    match output {
+        Value::Object(_) => Ok(output),
+        Value::Null => Err(FailureReason::SelectorNull { selector }),
+        other => Err(FailureReason::SelectorNotObject {
+            selector,
+            found: String::from(match other {
+                Value::Bool(_) => "a boolean",
+                Value::Number(_) => "a number",
+                Value::String(_) => "a string",
+                _ => "an array",
+            }),
+            snippet: snippet(&other),
+        }),
+    }
+}
+
+/// Renders a compact snippet of a JSON value, truncated if it is long.
+fn snippet(value: &Value) -> String {
+    let rendered = value.to_string();
+
+    match rendered.char_indices().nth(SNIPPET_LEN) {
+        Some((i, _)) => format!("{}…", &rendered[..i]),
+        None => rendered,
+    }
+}
+
+/// Applies a `jq` selector to a JSON value.
+///
+/// The selector must produce exactly one value.
+///
+/// Simple field and index paths (e.g., `.outputs` or `.a.b[0]`) are
+/// evaluated natively; all other selectors are evaluated with `jaq`.
+pub fn apply(selector: &str, input: &Value) -> Result<Value, FailureReason> {
+    if let Some(path) = parse_path(selector) {
+        return select_path(&path, input).map_err(|details| FailureReason::SelectorError {
+            selector: selector.to_string(),
+            details: format!("selector execution failed: {details}"),
+        });
+    }
+
+    apply_jq(selector, input)
+}
+
+/// A step within a simple selector path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    /// Selects a field of an object.
+    Field(String),
+    /// Selects an element of an array, counting from the end if negative.
+    Index(i64),
+}
+
+/// Parses a simple selector path made up of field accesses (`.a` or
+/// `."a b"`) and array indices (`[0]`), such as `.outputs` or `.a.b[0]`.
+///
+/// Returns `None` if the selector is anything other than a simple path,
+/// including one that does not start with `.` (in jq, `[0]` constructs an
+/// array rather than indexing one).
+fn parse_path(selector: &str) -> Option<Vec<Step>> {
+    let mut rest = selector.trim();
+    let mut steps = Vec::new();
+
+    if !rest.starts_with('.') {
+        return None;
+    }
+
+    if rest == "." {
+        return Some(steps);
+    }
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after.split_once(']')?;
+            steps.push(Step::Index(index.trim().parse().ok()?));
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(".\"") {
+            let (field, after) = after.split_once('"')?;
+            if field.contains('\\') {
+                return None;
+            }
+            steps.push(Step::Field(field.to_string()));
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            let field = &after[..end];
+            if field.is_empty() || field.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            steps.push(Step::Field(field.to_string()));
+            rest = &after[end..];
+        } else {
+            return None;
+        }
+    }
+
+    Some(steps)
+}
+
+/// Selects the value at a simple path with the same semantics as `jq`.
+///
+/// Missing fields and out-of-range indices select `null`.
+fn select_path(path: &[Step], input: &Value) -> Result<Value, String> {
+    let mut current = input;
+
+    for step in path {
+        current = match (step, current) {
+            (_, Value::Null) => return Ok(Value::Null),
+            (Step::Field(field), Value::Object(obj)) => match obj.get(field) {
+                Some(value) => value,
+                None => return Ok(Value::Null),
+            },
+            (Step::Index(index), Value::Array(arr)) => {
+                let index = if *index < 0 {
+                    arr.len().checked_sub(index.unsigned_abs() as usize)
+                } else {
+                    Some(*index as usize)
+                };
+                match index.and_then(|i| arr.get(i)) {
+                    Some(value) => value,
+                    None => return Ok(Value::Null),
+                }
+            }
+            (Step::Field(field), other) => {
+                return Err(format!(
+                    "cannot index {} with \"{field}\"",
+                    type_name(other)
+                ));
+            }
+            (Step::Index(_), other) => {
+                return Err(format!("cannot index {} with a number", type_name(other)));
+            }
+        };
+    }
+
+    Ok(current.clone())
+}
+
+/// Gets the `jq` name of the type of a JSON value.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Applies a `jq` selector to a JSON value with `jaq`.
+fn apply_jq(selector: &str, input: &Value) -> Result<Value, FailureReason> {
+    use jaq_core::load::{Arena, File, Loader};
+    use jaq_core::{Compiler, Ctx, Vars, data, unwrap_valr};
+    use jaq_json::Val;
+
+    let program = File {
+        code: selector,
+        path: (),
+    };
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+
+    // Parse the selector
+    let modules = loader.load(&arena, program).map_err(|errs| {
+        let error_msg = errs
+            .into_iter()
+            .map(|(file, err)| format!("{}: {:?}", file.code, err))
+            .collect::<Vec<_>>()
+            .join("; ");
+        FailureReason::SelectorError {
+            selector: selector.to_string(),
+            details: error_msg,
+        }
+    })?;
+
+    // Compile the selector
+    let filter = Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errs| {
+            let error_msg = errs
+                .into_iter()
+                .map(|(file, err)| {
+                    let err_str = err
+                        .into_iter()
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!("{}: undefined: {}", file.code, err_str)
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            FailureReason::SelectorError {
+                selector: selector.to_string(),
+                details: error_msg,
+            }
+        })?;
+
+    // Convert `Value` to `jaq` `Val` using JSON string roundtrip
+    let json_str = input.to_string();
+    let jaq_input = jaq_json::read::parse_single(json_str.as_bytes()).map_err(|e| {
+        FailureReason::SelectorError {
+            selector: selector.to_string(),
+            details: format!("failed to parse input as JSON: {}", e),
+        }
+    })?;
+
+    // Execute the selector
+    let ctx = Ctx::<data::JustLut<Val>>::new(&filter.lut, Vars::new([]));
+    let mut outputs = filter.id.run((ctx, jaq_input)).map(unwrap_valr);
+
+    // Expect exactly one output
+    let first_output = outputs.next();
+    let second_output = outputs.next();
+
+    match (first_output, second_output) {
+        (None, _) => Err(FailureReason::SelectorError {
+            selector: selector.to_string(),
+            details: "selector produced no output".to_string(),
+        }),
+        (Some(Err(e)), _) => Err(FailureReason::SelectorError {
+            selector: selector.to_string(),
+            details: format!("selector execution failed: {}", e),
+        }),
+        (Some(Ok(_)), Some(_)) => Err(FailureReason::SelectorError {
+            selector: selector.to_string(),
+            details: "selector produced multiple outputs (expected exactly one)".to_string(),
+        }),
+        (Some(Ok(val)), None) => {
+            let json_str = val.to_string();
+            serde_json::from_str(&json_str).map_err(|e| FailureReason::SelectorError {
+                selector: selector.to_string(),
+                details: format!("failed to convert result to JSON: {}", e),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureCode;
+
+    #[test]
+    fn simple_paths() {
+        assert_eq!(parse_path("."), Some(vec![]));
+        assert_eq!(
+            parse_path(".a.b[0]"),
+            Some(vec![
+                Step::Field(String::from("a")),
+                Step::Field(String::from("b")),
+                Step::Index(0)
+            ])
+        );
+        assert_eq!(
+            parse_path(r#"."wf.out"[-1]"#),
+            Some(vec![Step::Field(String::from("wf.out")), Step::Index(-1)])
+        );
+        assert_eq!(parse_path(".outputs | keys"), None);
+        assert_eq!(parse_path(".a[]"), None);
+        assert_eq!(parse_path("map(.a)"), None);
+        assert_eq!(parse_path("[0]"), None);
+        assert_eq!(parse_path(" [.a]"), None);
+    }
+
+    #[test]
+    fn native_selection() {
+        let input = serde_json::json!({"outputs": {"wf.out": [1, 2, 3]}, "id": 7});
+
+        assert_eq!(
+            apply(".outputs", &input).unwrap(),
+            serde_json::json!({"wf.out": [1, 2, 3]})
+        );
+        assert_eq!(apply(r#".outputs."wf.out"[-1]"#, &input).unwrap(), 3);
+        assert_eq!(apply(".missing.field", &input).unwrap(), Value::Null);
+        assert_eq!(
+            apply(".outputs.\"wf.out\"[5]", &input).unwrap(),
+            Value::Null
+        );
+        assert!(
+            apply(".id.value", &input)
+                .unwrap_err()
+                .to_string()
+                .contains("cannot index number")
+        );
+    }
+
+    #[test]
+    fn results_must_be_objects() {
+        let object = serde_json::json!({"wf.out": 1});
+        assert_eq!(ensure_object(".outputs", object.clone()).unwrap(), object);
+
+        assert_eq!(
+            ensure_object(".outputs", Value::Null).unwrap_err().code(),
+            FailureCode::SelectorNull
+        );
+
+        let reason = ensure_object(".outputs[]", serde_json::json!([1, 2])).unwrap_err();
+        assert_eq!(reason.code(), FailureCode::SelectorNotObject);
+        assert!(
+            reason
+                .to_string()
+                .contains("an array rather than an object: [1,2]")
+        );
+    }
+
+    #[test]
+    fn snippets_are_truncated() {
+        assert_eq!(snippet(&Value::from("short")), r#""short""#);
+
+        let long = snippet(&Value::from("x".repeat(200)));
+        assert_eq!(long.chars().count(), SNIPPET_LEN + 1);
+        assert!(long.ends_with('…'));
+    }
+
+    #[test]
+    fn resolve_named_selectors() {
+        let library: Library = serde_json::from_str(
+            r#"{"unwrap": ".outputs", "tidy": [".outputs", "with_entries(.)"]}"#,
+        )
+        .unwrap();
+
+        let selectors = [
+            String::from("@tidy"),
+            String::from(".a"),
+            String::from("@unwrap"),
+        ];
+        assert_eq!(
+            library.resolve(&selectors).unwrap(),
+            [".outputs", "with_entries(.)", ".a", ".outputs"]
+        );
+
+        let diagnostic = library.resolve(&[String::from("@missing")]).unwrap_err();
+        assert_eq!(diagnostic.code(), Code::UnknownSelector);
+        assert!(diagnostic.hint().unwrap().contains("`@unwrap`"));
+    }
+}
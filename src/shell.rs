@@ -1,45 +1,462 @@
 //! Shell faculties for substitutions.
 
 use std::path::PathBuf;
+use std::sync::LazyLock;
 
+use bon::Builder;
 use bon::builder;
+use regex::Regex;
+use serde_json::Value;
 
 use crate::conformance::Target;
+use crate::diagnostic::Code;
+use crate::diagnostic::Diagnostic;
+use crate::engine::TargetArgs;
 
-/// Builds the command with substitutions and target-specific arguments.
+/// The placeholders supported within command templates.
+pub const PLACEHOLDERS: &[&str] = &[
+    "path",
+    "input",
+    "input_args",
+    "output",
+    "target",
+    "workdir",
+    "options",
+];
+
+/// Regex to match placeholders within command templates.
+static PLACEHOLDER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"~\{([^}]*)\}").unwrap());
+
+/// The values substituted for placeholders within templates.
 ///
 /// Substitutions:
 ///
 /// - `~{path}` → path to the WDL file
 /// - `~{input}` → path to the inputs.json file
+/// - `~{input_args}` → the inputs as quoted `key=value` arguments
 /// - `~{output}` → path to the outputs.json file
 /// - `~{target}` → workflow or task name
+/// - `~{workdir}` → path to the working directory of the test
+/// - `~{options}` → path to the engine options file, if one was written
+#[derive(Builder, Debug, Clone)]
+#[builder(builder_type = Builder)]
+pub struct Substitutions {
+    /// The path to the WDL file.
+    path: PathBuf,
+
+    /// The path to the inputs file.
+    input: PathBuf,
+
+    /// The inputs as quoted `key=value` arguments.
+    #[builder(default)]
+    input_args: String,
+
+    /// The path to the outputs file.
+    output: PathBuf,
+
+    /// The target workflow or task.
+    target: Target,
+
+    /// The working directory of the test.
+    workdir: PathBuf,
+
+    /// The path to the engine options file.
+    options: Option<PathBuf>,
+}
+
+impl Substitutions {
+    /// Gets the target workflow or task.
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// Replaces every supported placeholder within a template.
+    pub fn apply(&self, template: &str) -> String {
+        let mut result = template.to_string();
+        result = result.replace("~{path}", &self.path.display().to_string());
+        result = result.replace("~{input}", &self.input.display().to_string());
+        result = result.replace("~{input_args}", &self.input_args);
+        result = result.replace("~{output}", &self.output.display().to_string());
+        result = result.replace("~{target}", self.target.name());
+        result = result.replace("~{workdir}", &self.workdir.display().to_string());
+
+        if let Some(options) = &self.options {
+            result = result.replace("~{options}", &options.display().to_string());
+        }
+
+        result
+    }
+
+    /// Splits a template into arguments as a POSIX shell would, then
+    /// replaces the placeholders within each argument.
+    ///
+    /// A substituted value is never split or otherwise interpreted, so paths
+    /// with spaces and inputs with quotes reach the program unchanged. An
+    /// argument that is exactly `~{input_args}` becomes one argument for each
+    /// input.
+    pub fn apply_argv(&self, template: &str) -> Result<Vec<String>, Diagnostic> {
+        let mut argv = Vec::new();
+
+        for word in split(template)? {
+            if word == "~{input_args}" {
+                argv.extend(split(&self.input_args)?);
+            } else {
+                argv.push(self.apply(&word));
+            }
+        }
+
+        Ok(argv)
+    }
+}
+
+/// Builds the arguments of a command that is run without a shell, with
+/// substitutions and target-specific arguments.
+///
+/// The target args are appended as in [`substitute()`], and the result is
+/// split into arguments with [`Substitutions::apply_argv()`].
+#[builder]
+pub fn substitute_argv(
+    mut command: String,
+    substitutions: &Substitutions,
+    target_args: &TargetArgs,
+) -> Result<Vec<String>, Diagnostic> {
+    command.push(' ');
+    command.push_str(target_args.for_target(substitutions.target()));
+
+    substitutions.apply_argv(&command)
+}
+
+/// Splits a command line into words as a POSIX shell would.
+///
+/// Single quotes preserve everything within them, double quotes preserve
+/// everything but backslash escapes of `"`, `\`, `$`, and `` ` ``, and a
+/// backslash outside of quotes preserves the next character. Nothing else
+/// (variables, globs, redirections, or pipes) is interpreted.
+pub fn split(command: &str) -> Result<Vec<String>, Diagnostic> {
+    let unbalanced = |quote: char| {
+        Diagnostic::new(
+            Code::UnbalancedQuote,
+            format!("command `{command}` has a `{quote}` that is never closed"),
+        )
+        .with_hint("close the quote, or escape it with a backslash")
+    };
+
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next().ok_or_else(|| unbalanced('\''))? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next().ok_or_else(|| unbalanced('"'))? {
+                        '"' => break,
+                        '\\' => match chars.next().ok_or_else(|| unbalanced('"'))? {
+                            c @ ('"' | '\\' | '$' | '`') => word.push(c),
+                            '\n' => {}
+                            c => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') | None => {}
+                Some(c) => word.get_or_insert_default().push(c),
+            },
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+
+    words.extend(word);
+    Ok(words)
+}
+
+/// Renders arguments as a command line for a POSIX shell, quoting each
+/// argument as needed.
+pub fn join(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds the command with substitutions and target-specific arguments.
 ///
 /// The appropriate target args template is selected based on the target type
-/// and appended to the command after substitutions.
+/// and appended to the command before substitutions are applied.
 #[builder]
 pub fn substitute(
     mut command: String,
-    path: PathBuf,
-    input: PathBuf,
-    output: PathBuf,
-    target: Target,
-    workflow_target_args: String,
-    task_target_args: String,
+    substitutions: &Substitutions,
+    target_args: &TargetArgs,
 ) -> String {
-    // Select the appropriate target args template and substitute target name
-    let target_args = match &target {
-        Target::Workflow(_) => workflow_target_args,
-        Target::Task(_) => task_target_args,
+    // Append the target args for the kind of target to the command
+    command.push(' ');
+    command.push_str(target_args.for_target(substitutions.target()));
+
+    substitutions.apply(&command).trim().to_string()
+}
+
+/// Expands a test input object into quoted `key=value` arguments.
+///
+/// String values are passed verbatim, while all other values are passed as
+/// compact JSON. Each argument is quoted for a POSIX shell.
+pub fn input_args(input: Option<&Value>) -> String {
+    let Some(obj) = input.and_then(Value::as_object) else {
+        return String::new();
     };
 
-    // Append target args to command
-    command.push(' ');
-    command.push_str(&target_args);
+    obj.iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            quote(&format!("{key}={value}"))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quotes a string for a POSIX shell.
+///
+/// Strings made up entirely of characters that have no special meaning to the
+/// shell are returned as is.
+pub fn quote(s: &str) -> String {
+    let safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./=:,+@%".contains(c));
+
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+/// Ensures that a command template only uses supported placeholders.
+pub fn validate_template(template: &str) -> Result<(), Diagnostic> {
+    for captures in PLACEHOLDER_REGEX.captures_iter(template) {
+        let name = &captures[1];
+
+        if PLACEHOLDERS.contains(&name) {
+            continue;
+        }
+
+        let supported = PLACEHOLDERS
+            .iter()
+            .map(|p| format!("`~{{{p}}}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let hint = match closest_placeholder(name) {
+            Some(suggestion) => {
+                format!("did you mean `~{{{suggestion}}}`? supported placeholders are {supported}")
+            }
+            None => format!("supported placeholders are {supported}"),
+        };
+
+        return Err(Diagnostic::new(
+            Code::UnknownPlaceholder,
+            format!("unknown placeholder `~{{{name}}}` in command template `{template}`"),
+        )
+        .with_hint(hint));
+    }
+
+    Ok(())
+}
+
+/// Returns whether a command template uses the given placeholder.
+pub fn uses_placeholder(template: &str, name: &str) -> bool {
+    PLACEHOLDER_REGEX
+        .captures_iter(template)
+        .any(|captures| &captures[1] == name)
+}
+
+/// Finds the supported placeholder closest to `name`, if any is close enough
+/// to be a likely typo.
+fn closest_placeholder(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_lowercase();
+
+    PLACEHOLDERS
+        .iter()
+        .map(|p| (*p, edit_distance(&name, p)))
+        .filter(|(_, distance)| *distance <= 2)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(p, _)| p)
+}
+
+/// Computes the Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_template() {
+        assert!(validate_template("sprocket run ~{path} ~{input} -t ~{target}").is_ok());
+    }
+
+    #[test]
+    fn unknown_placeholder_with_suggestion() {
+        let diagnostic = validate_template("miniwdl run ~{path} -i ~{inputs}").unwrap_err();
+        assert_eq!(diagnostic.code(), Code::UnknownPlaceholder);
+        assert!(diagnostic.message().contains("`~{inputs}`"));
+        assert!(
+            diagnostic
+                .hint()
+                .unwrap()
+                .starts_with("did you mean `~{input}`?")
+        );
+    }
+
+    #[test]
+    fn substitution() {
+        let substitutions = Substitutions::builder()
+            .path(PathBuf::from("/tests/hello.wdl"))
+            .input(PathBuf::from("/work/inputs.json"))
+            .output(PathBuf::from("/work/outputs.json"))
+            .target(Target::Task(String::from("hello_task")))
+            .workdir(PathBuf::from("/work"))
+            .options(PathBuf::from("/work/options.json"))
+            .build();
+
+        let target_args = TargetArgs::builder()
+            .workflow("--workflow ~{target}")
+            .task("--task ~{target}")
+            .build();
+
+        let command = substitute()
+            .command(String::from("engine run ~{path} -i ~{input} -o ~{options}"))
+            .substitutions(&substitutions)
+            .target_args(&target_args)
+            .call();
+
+        assert_eq!(
+            command,
+            "engine run /tests/hello.wdl -i /work/inputs.json -o /work/options.json --task \
+             hello_task"
+        );
+        assert_eq!(
+            substitutions.apply(r#"{"out": "~{workdir}/out"}"#),
+            r#"{"out": "/work/out"}"#
+        );
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("hello.txt"), "hello.txt");
+        assert_eq!(quote("hello world"), "'hello world'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote(""), "''");
+    }
+
+    #[test]
+    fn inputs_as_args() {
+        let input = serde_json::json!({
+            "hello.infile": "greetings.txt",
+            "hello.pattern": "hello .*",
+            "hello.numbers": [1, 2],
+        });
+
+        assert_eq!(
+            input_args(Some(&input)),
+            "hello.infile=greetings.txt 'hello.numbers=[1,2]' 'hello.pattern=hello .*'"
+        );
+        assert_eq!(input_args(None), "");
+    }
+
+    #[test]
+    fn placeholder_usage() {
+        assert!(uses_placeholder("run ~{path} > ~{output}", "output"));
+        assert!(!uses_placeholder("run ~{path}", "output"));
+        assert!(!uses_placeholder("run ~{outputs}", "output"));
+    }
+
+    #[test]
+    fn unknown_placeholder_without_suggestion() {
+        let diagnostic = validate_template("run ~{path} ~{engine}").unwrap_err();
+        assert!(
+            diagnostic
+                .hint()
+                .unwrap()
+                .starts_with("supported placeholders")
+        );
+    }
+
+    #[test]
+    fn argv() {
+        assert_eq!(
+            split(r#"run 'a b' "c \"d\" \e" f\ g '' h"#).unwrap(),
+            ["run", "a b", r#"c "d" \e"#, "f g", "", "h"]
+        );
+        assert_eq!(
+            split("run 'oops").unwrap_err().code(),
+            Code::UnbalancedQuote
+        );
+
+        let input = serde_json::json!({ "hello.greeting": "it's \"hi\"; rm -rf /" });
+        let substitutions = Substitutions::builder()
+            .path(PathBuf::from("/my tests/hello.wdl"))
+            .input(PathBuf::from("/work/inputs.json"))
+            .input_args(input_args(Some(&input)))
+            .output(PathBuf::from("/work/outputs.json"))
+            .target(Target::Workflow(String::from("hello")))
+            .workdir(PathBuf::from("/work"))
+            .build();
 
-    command = command.replace("~{path}", &path.display().to_string());
-    command = command.replace("~{input}", &input.display().to_string());
-    command = command.replace("~{output}", &output.display().to_string());
-    command = command.replace("~{target}", target.name());
-    command.trim().to_string()
+        let argv = substitute_argv()
+            .command(String::from(
+                "engine run ~{path} --out=~{output} ~{input_args}",
+            ))
+            .substitutions(&substitutions)
+            .target_args(&TargetArgs::builder().workflow("-w '~{target}'").build())
+            .call()
+            .unwrap();
+        assert_eq!(
+            argv,
+            [
+                "engine",
+                "run",
+                "/my tests/hello.wdl",
+                "--out=/work/outputs.json",
+                r#"hello.greeting=it's "hi"; rm -rf /"#,
+                "-w",
+                "hello"
+            ]
+        );
+        assert_eq!(split(&join(&argv)).unwrap(), argv);
+    }
 }
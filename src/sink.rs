@@ -0,0 +1,778 @@
+//! Destinations that the results of a test run are written to.
+
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::badge::Badge;
+use crate::history;
+use crate::report::Outcome;
+use crate::report::Report;
+use crate::report::Summary;
+use crate::report::SummaryFormat;
+
+/// How long a webhook is given to accept the results.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The results of a run, in each of the forms sinks write them in.
+#[derive(Debug)]
+pub struct Results<'a> {
+    /// The report of every test.
+    report: &'a Report,
+
+    /// The summary of the run.
+    summary: &'a Summary,
+
+    /// The badge for the run.
+    badge: &'a Badge,
+
+    /// The summary of the previous run of the same engine against the same
+    /// branch of the specification, if known.
+    previous: Option<&'a Summary>,
+}
+
+impl<'a> Results<'a> {
+    /// Creates the results of a run.
+    pub fn new(report: &'a Report, summary: &'a Summary, badge: &'a Badge) -> Self {
+        Self {
+            report,
+            summary,
+            badge,
+            previous: None,
+        }
+    }
+
+    /// Sets the summary of the previous run, which notifications are compared
+    /// against.
+    pub fn with_previous(mut self, previous: Option<&'a Summary>) -> Self {
+        self.previous = previous;
+        self
+    }
+
+    /// Gets the report of every test.
+    pub fn report(&self) -> &Report {
+        self.report
+    }
+
+    /// Gets the summary of the run.
+    pub fn summary(&self) -> &Summary {
+        self.summary
+    }
+
+    /// Gets the badge for the run.
+    pub fn badge(&self) -> &Badge {
+        self.badge
+    }
+
+    /// Gets the summary of the previous run, if known.
+    pub fn previous(&self) -> Option<&Summary> {
+        self.previous
+    }
+
+    /// Returns whether fewer tests passed than in the previous run.
+    pub fn regressed(&self) -> bool {
+        self.previous
+            .is_some_and(|previous| self.summary.passed() < previous.passed())
+    }
+}
+
+/// When a notification sink posts the results of a run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Notify {
+    /// After every run.
+    #[default]
+    Always,
+    /// Only when fewer tests passed than in the previous run of the same
+    /// engine recorded in the history file.
+    Regression,
+}
+
+impl Notify {
+    /// Returns whether results are posted.
+    fn fires(self, results: &Results<'_>) -> bool {
+        match self {
+            Notify::Always => true,
+            Notify::Regression => results.regressed(),
+        }
+    }
+}
+
+/// A destination that the results of a run are written to.
+pub trait ResultSink {
+    /// Writes the results of a run.
+    fn write(&self, results: &Results<'_>) -> Result<()>;
+}
+
+/// Prints the summary line to stdout.
+#[derive(Debug)]
+pub struct Console {
+    /// The format of the summary line.
+    format: SummaryFormat,
+}
+
+impl ResultSink for Console {
+    fn write(&self, results: &Results<'_>) -> Result<()> {
+        println!("{}", results.summary().render(self.format));
+        Ok(())
+    }
+}
+
+/// Writes the report of every test as JSON to a file.
+#[derive(Debug)]
+pub struct File {
+    /// The path of the file.
+    path: PathBuf,
+}
+
+impl ResultSink for File {
+    fn write(&self, results: &Results<'_>) -> Result<()> {
+        results.report().write(&self.path)
+    }
+}
+
+/// Writes a JUnit XML report to a file, for CI systems that display test
+/// results.
+#[derive(Debug)]
+pub struct JUnit {
+    /// The path of the file.
+    path: PathBuf,
+}
+
+impl ResultSink for JUnit {
+    fn write(&self, results: &Results<'_>) -> Result<()> {
+        std::fs::write(&self.path, junit(results.report()))
+            .with_context(|| format!("writing JUnit report to `{}`", self.path.display()))
+    }
+}
+
+/// Writes the badge to a file.
+#[derive(Debug)]
+pub struct BadgeFile {
+    /// The path of the file.
+    path: PathBuf,
+}
+
+impl ResultSink for BadgeFile {
+    fn write(&self, results: &Results<'_>) -> Result<()> {
+        results.badge().write(&self.path)
+    }
+}
+
+/// Writes a Markdown report to a file, for GitHub step summaries and pull
+/// request comments.
+#[derive(Debug)]
+pub struct Markdown {
+    /// The path of the file.
+    path: PathBuf,
+}
+
+impl ResultSink for Markdown {
+    fn write(&self, results: &Results<'_>) -> Result<()> {
+        std::fs::write(&self.path, markdown(results))
+            .with_context(|| format!("writing Markdown report to `{}`", self.path.display()))
+    }
+}
+
+/// Posts the summary and the report of every test as JSON to a URL.
+#[derive(Debug)]
+pub struct Webhook {
+    /// The URL to post to.
+    url: String,
+
+    /// When the results are posted.
+    on: Notify,
+}
+
+impl ResultSink for Webhook {
+    fn write(&self, results: &Results<'_>) -> Result<()> {
+        /// The body of the request.
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            /// The summary of the run.
+            summary: &'a Summary,
+            /// The summary of the previous run, if known.
+            #[serde(skip_serializing_if = "Option::is_none")]
+            previous: Option<&'a Summary>,
+            /// Whether fewer tests passed than in the previous run.
+            regressed: bool,
+            /// The report of every test.
+            report: &'a Report,
+        }
+
+        if !self.on.fires(results) {
+            return Ok(());
+        }
+
+        let body = serde_json::to_string(&Payload {
+            summary: results.summary(),
+            previous: results.previous(),
+            regressed: results.regressed(),
+            report: results.report(),
+        })
+        .context("serializing results")?;
+
+        post(&self.url, &body)
+    }
+}
+
+/// Posts a message with the summary of the run to a Slack incoming webhook.
+#[derive(Debug)]
+pub struct Slack {
+    /// The URL of the incoming webhook.
+    url: String,
+
+    /// When the message is posted.
+    on: Notify,
+}
+
+impl ResultSink for Slack {
+    fn write(&self, results: &Results<'_>) -> Result<()> {
+        if !self.on.fires(results) {
+            return Ok(());
+        }
+
+        let body = serde_json::json!({ "text": slack_message(results) }).to_string();
+        post(&self.url, &body)
+    }
+}
+
+/// Posts a JSON body to a URL.
+fn post(url: &str, body: &str) -> Result<()> {
+    ureq::AgentBuilder::new()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(body)
+        .with_context(|| format!("posting results to `{url}`"))?;
+
+    Ok(())
+}
+
+/// Appends the summary of the run and the status of each test as a line of
+/// JSON to a file, building up a history of runs to track conformance over
+/// time (see [`history`]).
+#[derive(Debug)]
+pub struct History {
+    /// The path of the file.
+    path: PathBuf,
+}
+
+impl ResultSink for History {
+    fn write(&self, results: &Results<'_>) -> Result<()> {
+        let record =
+            history::Record::new(results.badge().label(), results.summary(), results.report());
+        history::append(&self.path, &record)
+    }
+}
+
+/// The configuration of a sink, as a `[[sink]]` table in the config file.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Config {
+    /// See [`Console`].
+    Console {
+        /// The format of the summary line.
+        format: SummaryFormat,
+    },
+    /// See [`File`].
+    File {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// See [`JUnit`].
+    Junit {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// See [`BadgeFile`].
+    Badge {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// See [`Markdown`].
+    Markdown {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// See [`Webhook`].
+    Webhook {
+        /// The URL to post to.
+        url: String,
+        /// When the results are posted.
+        #[serde(default)]
+        on: Notify,
+    },
+    /// See [`Slack`].
+    Slack {
+        /// The URL of the incoming webhook.
+        url: String,
+        /// When the message is posted.
+        #[serde(default)]
+        on: Notify,
+    },
+    /// See [`History`].
+    History {
+        /// The path of the file.
+        path: PathBuf,
+    },
+}
+
+impl Config {
+    /// Gets the path of the file the sink writes to, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Config::File { path }
+            | Config::Junit { path }
+            | Config::Badge { path }
+            | Config::Markdown { path }
+            | Config::History { path } => Some(path),
+            Config::Console { .. } | Config::Webhook { .. } | Config::Slack { .. } => None,
+        }
+    }
+
+    /// Gets the path of the file the sink writes to, if any, for changing.
+    pub fn path_mut(&mut self) -> Option<&mut PathBuf> {
+        match self {
+            Config::File { path }
+            | Config::Junit { path }
+            | Config::Badge { path }
+            | Config::Markdown { path }
+            | Config::History { path } => Some(path),
+            Config::Console { .. } | Config::Webhook { .. } | Config::Slack { .. } => None,
+        }
+    }
+
+    /// Resolves the path of the sink against the directory containing the
+    /// config file it was read from.
+    pub fn resolve(mut self, dir: &Path) -> Self {
+        if let Some(path) = self.path_mut() {
+            *path = dir.join(&*path);
+        }
+        self
+    }
+
+    /// Returns whether the sink writes to stdout.
+    pub fn is_console(&self) -> bool {
+        matches!(self, Config::Console { .. })
+    }
+
+    /// Creates the sink.
+    pub fn build(&self) -> Box<dyn ResultSink> {
+        match self.clone() {
+            Config::Console { format } => Box::new(Console { format }),
+            Config::File { path } => Box::new(File { path }),
+            Config::Junit { path } => Box::new(JUnit { path }),
+            Config::Badge { path } => Box::new(BadgeFile { path }),
+            Config::Markdown { path } => Box::new(Markdown { path }),
+            Config::Webhook { url, on } => Box::new(Webhook { url, on }),
+            Config::Slack { url, on } => Box::new(Slack { url, on }),
+            Config::History { path } => Box::new(History { path }),
+        }
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Config::Console { .. } => write!(f, "console"),
+            Config::File { path } => write!(f, "file `{}`", path.display()),
+            Config::Junit { path } => write!(f, "JUnit report `{}`", path.display()),
+            Config::Badge { path } => write!(f, "badge `{}`", path.display()),
+            Config::Markdown { path } => write!(f, "Markdown report `{}`", path.display()),
+            Config::Webhook { url, .. } => write!(f, "webhook `{url}`"),
+            Config::Slack { .. } => write!(f, "Slack webhook"),
+            Config::History { path } => write!(f, "history `{}`", path.display()),
+        }
+    }
+}
+
+/// Renders a report as JUnit XML.
+fn junit(report: &Report) -> String {
+    let count = |f: fn(&Outcome) -> bool| report.tests().iter().filter(|e| f(e.result())).count();
+    let failures = count(|o| matches!(o, Outcome::Failed { .. }));
+    let skipped = count(|o| matches!(o, Outcome::Skipped { .. }));
+    let time = report
+        .tests()
+        .iter()
+        .filter_map(|e| e.duration_secs())
+        .sum::<f64>();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    // SAFETY: writing to a `String` never fails.
+    writeln!(
+        xml,
+        "<testsuites name=\"spectool\" tests=\"{tests}\" failures=\"{failures}\" \
+         skipped=\"{skipped}\" time=\"{time:.3}\">\n  <testsuite name=\"spectool\" \
+         tests=\"{tests}\" failures=\"{failures}\" skipped=\"{skipped}\" time=\"{time:.3}\">",
+        tests = report.tests().len()
+    )
+    .unwrap();
+
+    for entry in report.tests() {
+        let name = escape(entry.path().unwrap_or(entry.name()));
+        let time = entry.duration_secs().unwrap_or_default();
+
+        // SAFETY: writing to a `String` never fails.
+        match entry.result() {
+            Outcome::Passed => writeln!(
+                xml,
+                "    <testcase name=\"{name}\" classname=\"spectool\" time=\"{time:.3}\"/>"
+            ),
+            Outcome::Failed { code, message, .. } => writeln!(
+                xml,
+                "    <testcase name=\"{name}\" classname=\"spectool\" time=\"{time:.3}\">\n      \
+                 <failure type=\"{code}\" message=\"{message}\"/>\n    </testcase>",
+                message = escape(message)
+            ),
+            Outcome::Skipped { reason } => writeln!(
+                xml,
+                "    <testcase name=\"{name}\" classname=\"spectool\" time=\"{time:.3}\">\n      \
+                 <skipped message=\"{reason}\"/>\n    </testcase>",
+                reason = escape(reason)
+            ),
+        }
+        .unwrap();
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Renders the message posted to Slack: the counts and pass rate of the run,
+/// compared with the previous run if known.
+fn slack_message(results: &Results<'_>) -> String {
+    let summary = results.summary();
+    let mut message = format!(
+        "*{label}*: {passed}/{total} passed ({percent:.1}%), {skipped} skipped",
+        label = results.badge().label(),
+        passed = summary.passed(),
+        total = summary.total(),
+        percent = summary.percent(),
+        skipped = summary.skipped(),
+    );
+
+    if let Some(engine) = summary.engine() {
+        let version = summary
+            .engine_version()
+            .map(|version| format!(" {version}"))
+            .unwrap_or_default();
+        // SAFETY: writing to a `String` never fails.
+        write!(message, "\nEngine: {engine}{version}").unwrap();
+    }
+
+    if let Some(previous) = results.previous() {
+        let change = summary.passed() as i64 - previous.passed() as i64;
+        // SAFETY: writing to a `String` never fails.
+        write!(
+            message,
+            "\n{change:+} passed vs the previous run ({}/{})",
+            previous.passed(),
+            previous.total()
+        )
+        .unwrap();
+    }
+
+    if results.regressed() {
+        message.insert_str(0, ":warning: Conformance regressed\n");
+    }
+
+    message
+}
+
+/// Renders the results of a run as Markdown: a badge, a table of counts, and
+/// the details of each failure within a collapsible section.
+fn markdown(results: &Results<'_>) -> String {
+    let summary = results.summary();
+    let badge = results.badge();
+    let mut md = String::new();
+
+    // SAFETY: writing to a `String` never fails.
+    writeln!(
+        md,
+        "## Conformance results\n\n![{label}]({url})\n",
+        label = escape(badge.label()),
+        url = badge.image_url(),
+    )
+    .unwrap();
+
+    // The summary is a table with a right-aligned column for each figure
+    let (labels, values): (Vec<_>, Vec<_>) = summary.figures().into_iter().unzip();
+    let rules = labels
+        .iter()
+        .map(|label| format!("{}:", "-".repeat(label.len() - 1)))
+        .collect::<Vec<_>>();
+    for row in [labels.join(" | "), rules.join(" | "), values.join(" | ")] {
+        writeln!(md, "| {row} |").unwrap();
+    }
+
+    if summary.truncated() {
+        writeln!(
+            md,
+            "\n**Truncated:** the run timed out, so the remaining tests were not run."
+        )
+        .unwrap();
+    }
+
+    let failures = results
+        .report()
+        .tests()
+        .iter()
+        .filter(|e| matches!(e.result(), Outcome::Failed { .. }))
+        .collect::<Vec<_>>();
+    if !failures.is_empty() {
+        writeln!(md, "\n### Failures ({})\n", failures.len()).unwrap();
+    }
+
+    for entry in failures {
+        let Outcome::Failed {
+            category,
+            code,
+            message,
+            ..
+        } = entry.result()
+        else {
+            continue;
+        };
+
+        // SAFETY: writing to a `String` never fails.
+        writeln!(
+            md,
+            "<details>\n<summary><code>{name}</code> — {headline}</summary>\n\n\
+             - Failure: `{code}` ({category})",
+            name = escape(entry.name()),
+            headline = escape(message.lines().next().unwrap_or_default()),
+        )
+        .unwrap();
+        if let Some(lines) = entry.lines() {
+            writeln!(md, "- Lines: `SPEC.md:{lines}`").unwrap();
+        }
+        if let Some(url) = entry.section_url() {
+            writeln!(md, "- Section: <{url}>").unwrap();
+        }
+        writeln!(md, "\n```text\n{}\n```", message.trim_end()).unwrap();
+        if let Some(invocation) = entry.invocation() {
+            writeln!(md, "\n```sh\n{}\n```", invocation.to_shell()).unwrap();
+        }
+        md.push_str("\n</details>\n");
+    }
+
+    let skipped = results
+        .report()
+        .tests()
+        .iter()
+        .filter_map(|e| match e.result() {
+            Outcome::Skipped { reason } => Some((e.name(), reason)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !skipped.is_empty() {
+        writeln!(
+            md,
+            "\n<details>\n<summary>Skipped ({})</summary>\n",
+            skipped.len()
+        )
+        .unwrap();
+        for (name, reason) in skipped {
+            writeln!(md, "- `{name}`: {}", escape(reason)).unwrap();
+        }
+        md.push_str("\n</details>\n");
+    }
+
+    md
+}
+
+/// Escapes text for an XML attribute.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::FailureReason;
+    use crate::conformance::SkipReason;
+    use crate::conformance::TestResult;
+    use crate::report::Entry;
+
+    #[test]
+    fn junit_report() {
+        let report = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Passed, Some(Duration::from_secs(1))),
+            Entry::new(
+                "b<c>.wdl",
+                &TestResult::Failed(FailureReason::NoOutput),
+                Some(Duration::from_secs(2)),
+            ),
+            Entry::new("d.wdl", &TestResult::Skipped(SkipReason::Ignored), None),
+        ]);
+
+        let xml = junit(&report);
+        assert!(xml.contains(
+            "<testsuite name=\"spectool\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"3.000\">"
+        ));
+        assert!(xml.contains("<testcase name=\"a.wdl\" classname=\"spectool\" time=\"1.000\"/>"));
+        assert!(xml.contains("<testcase name=\"b&lt;c&gt;.wdl\""));
+        assert!(xml.contains("<failure type=\"no_output\""));
+        assert!(xml.contains("<skipped message="));
+    }
+
+    #[test]
+    fn markdown_report() {
+        let report = Report::new(vec![
+            Entry::new("a.wdl", &TestResult::Passed, None),
+            Entry::new("b.wdl", &TestResult::Failed(FailureReason::NoOutput), None)
+                .with_section_url(Some(String::from("https://example.com/SPEC.md#types"))),
+            Entry::new("d.wdl", &TestResult::Skipped(SkipReason::Ignored), None),
+        ]);
+        let summary = Summary::new(1, 1, 1, Duration::from_secs(2));
+        let badge = Badge::from_results("WDL 1.2", 1, 2);
+
+        let md = markdown(&Results::new(&report, &summary, &badge));
+        assert!(md.contains("![WDL 1.2](https://img.shields.io/badge/WDL%201.2-1%2F2%20passed-"));
+        assert!(md.contains(
+            "| Passed | Failed | Skipped | Total | Pass rate | Duration |\n\
+             | -----: | -----: | ------: | ----: | --------: | -------: |\n\
+             | 1 | 1 | 1 | 2 | 50.0% | 2.00s |\n"
+        ));
+        assert!(md.contains("### Failures (1)"));
+        assert!(md.contains("<summary><code>b.wdl</code> — "));
+        assert!(md.contains("- Failure: `no_output`"));
+        assert!(md.contains("- Section: <https://example.com/SPEC.md#types>"));
+        assert!(md.contains("<summary>Skipped (1)</summary>"));
+        assert!(!md.contains("a.wdl"));
+        assert!(!md.contains("Truncated"));
+
+        let summary = summary.with_truncated(true);
+        let md = markdown(&Results::new(&report, &summary, &badge));
+        assert!(md.contains("**Truncated:** the run timed out"));
+    }
+
+    #[test]
+    fn history_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let report = Report::new(Vec::new());
+        let summary = Summary::new(3, 1, 0, Duration::from_secs(5)).with_run_id("abc");
+        let badge = Badge::from_results("WDL", 3, 4);
+        let results = Results::new(&report, &summary, &badge);
+
+        let sink = Config::History { path: path.clone() }.build();
+        sink.write(&results).unwrap();
+        sink.write(&results).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["passed"], 3);
+        assert_eq!(record["run_id"], "abc");
+        assert!(record["finished_at"].as_u64().unwrap() > 0);
+        assert_eq!(record["label"], "WDL");
+
+        let records = history::read(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].summary(), &summary);
+    }
+
+    #[test]
+    fn configs() {
+        #[derive(Deserialize)]
+        struct File {
+            sink: Vec<Config>,
+        }
+
+        let file: File = toml::from_str(
+            r#"
+            [[sink]]
+            type = "junit"
+            path = "junit.xml"
+
+            [[sink]]
+            type = "console"
+            format = "json"
+            "#,
+        )
+        .unwrap();
+
+        let sinks = file
+            .sink
+            .into_iter()
+            .map(|sink| sink.resolve(Path::new("/ci")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sinks,
+            [
+                Config::Junit {
+                    path: PathBuf::from("/ci/junit.xml")
+                },
+                Config::Console {
+                    format: SummaryFormat::Json
+                },
+            ]
+        );
+        assert_eq!(sinks[0].to_string(), "JUnit report `/ci/junit.xml`");
+        assert!(sinks[1].is_console());
+    }
+
+    #[test]
+    fn notifications() {
+        let report = Report::new(Vec::new());
+        let summary = Summary::new(3, 1, 0, Duration::from_secs(5))
+            .with_engine(Some(String::from("sprocket")), Some(String::from("0.9.0")));
+        let previous = Summary::new(4, 0, 0, Duration::from_secs(5));
+        let badge = Badge::from_results("WDL 1.2", 3, 4);
+
+        let results = Results::new(&report, &summary, &badge);
+        assert!(!results.regressed());
+        assert!(Notify::Always.fires(&results));
+        assert!(!Notify::Regression.fires(&results));
+        assert_eq!(
+            slack_message(&results),
+            "*WDL 1.2*: 3/4 passed (75.0%), 0 skipped\nEngine: sprocket 0.9.0"
+        );
+
+        let results = results.with_previous(Some(&previous));
+        assert!(Notify::Regression.fires(&results));
+        assert_eq!(
+            slack_message(&results),
+            ":warning: Conformance regressed\n*WDL 1.2*: 3/4 passed (75.0%), 0 skipped\nEngine: \
+             sprocket 0.9.0\n-1 passed vs the previous run (4/4)"
+        );
+
+        let config: Config = toml::from_str(
+            "type = \"slack\"\nurl = \"https://hooks.slack.test\"\non = \"regression\"",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config::Slack {
+                url: String::from("https://hooks.slack.test"),
+                on: Notify::Regression
+            }
+        );
+        assert_eq!(config.to_string(), "Slack webhook");
+    }
+}
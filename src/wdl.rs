@@ -1,9 +1,12 @@
 //! Simple WDL inference faculties.
 
+use std::fmt;
+use std::ops::Range;
 use std::sync::LazyLock;
 
-use anyhow::Result;
 use regex::Regex;
+use serde::Deserialize;
+use strum_macros::EnumIter;
 
 /// A target to execute in a WDL file.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -24,27 +27,170 @@ impl Target {
     }
 }
 
-/// Regex to match workflow declarations in WDL.
-static WORKFLOW_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"(?m)^\s*workflow\s+(\w+)\s*\{").unwrap());
+/// Regex to match an import statement at the start of a string.
+static IMPORT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"\Aimport\s+(?:"([^"]*)"|'([^']*)')(?:\s+as\s+\w+)?((?:\s+alias\s+\w+\s+as\s+\w+)*)"#,
+    )
+    .unwrap()
+});
 
-/// Regex to match task declarations in WDL.
-static TASK_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"(?m)^\s*task\s+(\w+)\s*\{").unwrap());
+/// Regex to match the struct aliases of an import statement.
+static IMPORT_ALIAS_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"alias\s+(\w+)\s+as\s+(\w+)").unwrap());
+
+/// Regex to match calls in WDL.
+static CALL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*call\s+([\w.]+)(?:\s+as\s+(\w+))?").unwrap());
+
+/// Regex to match `container` and `docker` runtime attributes in WDL.
+static CONTAINER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?m)^\s*(?:container|docker)\s*:\s*(\[[^\]]*\]|"[^"]*"|'[^']*')"#).unwrap()
+});
+
+/// Regex to match string literals in WDL.
+static STRING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""([^"]*)"|'([^']*)'"#).unwrap());
+
+/// Regex to match the start of a multi-line string or a heredoc command.
+static HEREDOC_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<<<").unwrap());
+
+/// A WDL language construct that an engine may not support.
+///
+/// Constructs are detected statically so that tests using a construct an
+/// engine is known not to support can be skipped rather than failing with an
+/// opaque parse error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, clap::ValueEnum, EnumIter)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum Construct {
+    /// The `Directory` type.
+    Directory,
+    /// Multi-line strings (`<<< ... >>>` outside of a command section).
+    MultilineStrings,
+    /// `env` declarations.
+    EnvDeclarations,
+    /// The `task` variable (e.g., `task.cpu`).
+    TaskVariable,
+    /// `else if` clauses of conditionals.
+    ElseIf,
+    /// `requirements` sections.
+    Requirements,
+    /// `hints` sections.
+    Hints,
+}
+
+impl Construct {
+    /// Gets a description of the construct for messages.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Construct::Directory => "the `Directory` type",
+            Construct::MultilineStrings => "multi-line strings",
+            Construct::EnvDeclarations => "`env` declarations",
+            Construct::TaskVariable => "the `task` variable",
+            Construct::ElseIf => "`else if`",
+            Construct::Requirements => "`requirements` sections",
+            Construct::Hints => "`hints` sections",
+        }
+    }
+
+    /// Returns whether a WDL source uses the construct.
+    pub fn is_used(&self, source: &str) -> bool {
+        /// Regexes to match the constructs that are detected by a pattern.
+        static REGEXES: LazyLock<Vec<(Construct, Regex)>> = LazyLock::new(|| {
+            [
+                (Construct::Directory, r"\bDirectory\b"),
+                (Construct::EnvDeclarations, r"(?m)^\s*env\s+\w"),
+                (Construct::TaskVariable, r"\btask\.\w"),
+                (Construct::ElseIf, r"\belse\s+if\b"),
+                (Construct::Requirements, r"(?m)^\s*requirements\s*\{"),
+                (Construct::Hints, r"(?m)^\s*hints\s*\{"),
+            ]
+            .into_iter()
+            .map(|(construct, pattern)| (construct, Regex::new(pattern).unwrap()))
+            .collect()
+        });
+
+        match self {
+            // A `<<<` that does not open a command section opens a string
+            Construct::MultilineStrings => HEREDOC_REGEX
+                .find_iter(source)
+                .any(|m| !source[..m.start()].trim_end().ends_with("command")),
+            construct => REGEXES
+                .iter()
+                .any(|(c, regex)| c == construct && regex.is_match(source)),
+        }
+    }
+}
+
+impl fmt::Display for Construct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Construct::Directory => write!(f, "directory"),
+            Construct::MultilineStrings => write!(f, "multiline_strings"),
+            Construct::EnvDeclarations => write!(f, "env_declarations"),
+            Construct::TaskVariable => write!(f, "task_variable"),
+            Construct::ElseIf => write!(f, "else_if"),
+            Construct::Requirements => write!(f, "requirements"),
+            Construct::Hints => write!(f, "hints"),
+        }
+    }
+}
+
+/// Finds the constructs among `constructs` that a WDL source uses.
+pub fn constructs(source: &str, constructs: &[Construct]) -> Vec<Construct> {
+    constructs
+        .iter()
+        .copied()
+        .filter(|construct| construct.is_used(source))
+        .collect()
+}
+
+/// An import statement in a WDL file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    /// The URI of the imported document.
+    uri: String,
+    /// The struct aliases as `(name, alias)` pairs.
+    aliases: Vec<(String, String)>,
+}
+
+impl Import {
+    /// Gets the URI of the imported document.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Gets the struct aliases as `(name, alias)` pairs.
+    pub fn aliases(&self) -> &[(String, String)] {
+        &self.aliases
+    }
+
+    /// Returns whether the import refers to a local file rather than a URL.
+    pub fn is_local(&self) -> bool {
+        !self.uri.contains("://")
+    }
+}
 
 /// The declarations found in a WDL file.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WdlDeclarations {
-    /// The workflow name, if one exists.
-    workflow: Option<String>,
+    /// The workflow names.
+    ///
+    /// A valid WDL document contains at most one workflow.
+    workflows: Vec<String>,
     /// The task names.
     tasks: Vec<String>,
+    /// The struct names.
+    structs: Vec<String>,
+    /// The import statements.
+    imports: Vec<Import>,
 }
 
 impl WdlDeclarations {
-    /// Gets the workflow name.
-    pub fn workflow(&self) -> Option<&str> {
-        self.workflow.as_deref()
+    /// Gets the names of every workflow.
+    pub fn workflows(&self) -> &[String] {
+        &self.workflows
     }
 
     /// Gets the task names.
@@ -52,46 +198,650 @@ impl WdlDeclarations {
         &self.tasks
     }
 
+    /// Gets the struct names.
+    pub fn structs(&self) -> &[String] {
+        &self.structs
+    }
+
+    /// Gets the import statements.
+    pub fn imports(&self) -> &[Import] {
+        &self.imports
+    }
+
     /// Returns the single workflow or task target, if there is exactly one target to run.
     ///
     /// Returns `Some` if:
-    /// - There is a workflow (regardless of tasks)
+    /// - There is a single workflow (regardless of tasks)
     /// - There is no workflow and exactly one task
     ///
     /// Returns `None` if:
+    /// - There are multiple workflows
     /// - There is no workflow and zero or multiple tasks
     pub fn single_target(&self) -> Option<Target> {
-        match (&self.workflow, self.tasks.as_slice()) {
-            (Some(wf), _) => Some(Target::Workflow(wf.clone())), // Workflow always takes precedence
-            (None, [task]) => Some(Target::Task(task.clone())),
+        match (self.workflows.as_slice(), self.tasks.as_slice()) {
+            ([wf], _) => Some(Target::Workflow(wf.clone())), // Workflow always takes precedence
+            ([], [task]) => Some(Target::Task(task.clone())),
             _ => None,
         }
     }
 }
 
-/// Parses WDL source code to extract workflow and task declarations.
+/// A declaration within an `input` or `output` section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// The type of the declaration.
+    ty: String,
+    /// The name of the declaration.
+    name: String,
+    /// The expression assigned to the declaration, if any.
+    expr: Option<String>,
+}
+
+impl Declaration {
+    /// Gets the type of the declaration.
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+
+    /// Gets the name of the declaration.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl std::fmt::Display for Declaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.expr {
+            Some(expr) => write!(f, "{} {} = {}", self.ty, self.name, expr),
+            None => write!(f, "{} {}", self.ty, self.name),
+        }
+    }
+}
+
+/// The inputs and outputs of a workflow or task.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interface {
+    /// The input declarations.
+    inputs: Vec<Declaration>,
+    /// The output declarations.
+    outputs: Vec<Declaration>,
+}
+
+impl Interface {
+    /// Gets the input declarations.
+    pub fn inputs(&self) -> &[Declaration] {
+        &self.inputs
+    }
+
+    /// Gets the output declarations.
+    pub fn outputs(&self) -> &[Declaration] {
+        &self.outputs
+    }
+}
+
+/// Extracts the interface of the named target from WDL source code.
 ///
-/// This is a minimal regex-based parser that only extracts declaration names,
-/// not a full WDL parser.
-pub fn parse_wdl_declarations(source: &str) -> Result<WdlDeclarations> {
-    // Extract workflow name (should be at most one)
-    let workflow = WORKFLOW_REGEX
-        .captures(source)
-        .map(|cap| cap[1].to_string());
-
-    // Extract all task names
-    let tasks = TASK_REGEX
-        .captures_iter(source)
-        .map(|cap| cap[1].to_string())
-        .collect();
+/// Returns `None` if the target could not be found.
+pub fn parse_interface(source: &str, target: &Target) -> Option<Interface> {
+    let keyword = match target {
+        Target::Task(_) => "task",
+        Target::Workflow(_) => "workflow",
+    };
+
+    let skeleton = skeleton(source);
+    let body = items(&skeleton)
+        .into_iter()
+        .find(|item| item.keyword == keyword && item.name == Some(target.name()))
+        .and_then(|item| braced_block(&skeleton, item.open?))?;
+
+    let section = |name: &str| {
+        items(&skeleton[body.clone()])
+            .into_iter()
+            .find(|item| item.keyword == name && item.name.is_none())
+            .and_then(|item| braced_block(&skeleton[body.clone()], item.open?))
+            .map(|section| {
+                let section = body.start + section.start..body.start + section.end;
+                parse_declarations(&source[section.clone()], &skeleton[section])
+            })
+            .unwrap_or_default()
+    };
+
+    Some(Interface {
+        inputs: section("input"),
+        outputs: section("output"),
+    })
+}
+
+/// Returns the range of the contents of the brace-delimited block that opens
+/// at `start` within the skeleton of a document (see [`skeleton()`]).
+fn braced_block(skeleton: &str, start: usize) -> Option<Range<usize>> {
+    let mut depth = 0usize;
+
+    for (i, c) in skeleton[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(start + 1..start + i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Splits the contents of an `input` or `output` section into declarations,
+/// given the section and its skeleton (see [`skeleton()`]).
+///
+/// A declaration ends at a newline that is not nested within brackets,
+/// braces, or parentheses, and comments after a declaration are dropped.
+fn parse_declarations(section: &str, skeleton: &str) -> Vec<Declaration> {
+    let mut declarations = Vec::new();
+    let mut start = 0;
+    let mut depth = 0i32;
+
+    // The skeleton has the same offsets as the section, so brackets within
+    // strings and comments are not counted
+    for (i, c) in skeleton.char_indices().chain([(skeleton.len(), '\n')]) {
+        match c {
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' => depth -= 1,
+            '\n' if depth <= 0 => {
+                let end = start + skeleton[start..i].trim_end().len();
+                declarations.extend(parse_declaration(&section[start..end]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    declarations
+}
+
+/// Parses a single declaration, ignoring blank lines and comments.
+fn parse_declaration(text: &str) -> Option<Declaration> {
+    let text = text.trim();
+
+    if text.is_empty() || text.starts_with('#') {
+        return None;
+    }
+
+    let (lhs, expr) = match text.split_once('=') {
+        Some((lhs, expr)) => (lhs.trim(), Some(expr.trim().to_string())),
+        None => (text, None),
+    };
+
+    let (ty, name) = lhs.rsplit_once(char::is_whitespace)?;
+
+    Some(Declaration {
+        ty: ty.trim().to_string(),
+        name: name.to_string(),
+        expr,
+    })
+}
+
+/// Generates a workflow that wraps a task, forwarding its inputs and outputs.
+pub fn wrapper_workflow(name: &str, task: &str, interface: &Interface) -> String {
+    let mut wdl = format!("\nworkflow {name} {{\n");
+
+    if !interface.inputs().is_empty() {
+        wdl.push_str("    input {\n");
+        for input in interface.inputs() {
+            wdl.push_str(&format!("        {input}\n"));
+        }
+        wdl.push_str("    }\n\n");
+    }
+
+    if interface.inputs().is_empty() {
+        wdl.push_str(&format!("    call {task}\n"));
+    } else {
+        let args = interface
+            .inputs()
+            .iter()
+            .map(|input| format!("{name} = {name}", name = input.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        wdl.push_str(&format!("    call {task} {{ input: {args} }}\n"));
+    }
+
+    if !interface.outputs().is_empty() {
+        wdl.push_str("\n    output {\n");
+        for output in interface.outputs() {
+            wdl.push_str(&format!(
+                "        {ty} {name} = {task}.{name}\n",
+                ty = output.ty(),
+                name = output.name()
+            ));
+        }
+        wdl.push_str("    }\n");
+    }
+
+    wdl.push_str("}\n");
+    wdl
+}
+
+/// Blanks out the comments, the contents of strings, and the bodies of
+/// command sections within WDL source code, leaving the structure of the
+/// document.
+///
+/// Blanked characters are replaced with spaces and newlines are kept, so
+/// offsets within the skeleton are offsets within the source.
+///
+/// This is a scanner for the lexical structure of WDL rather than a parser of
+/// its grammar, as the `wdl` crates are not a dependency. It does not handle:
+///
+/// - A `}` within a brace-delimited command section (e.g., a shell function
+///   body), which ends the section as it does in WDL, although such commands
+///   are better written with `<<<`.
+/// - A `>>>` within a placeholder of a multi-line string or command, which
+///   ends it early.
+/// - Documents that are not valid WDL, such as with an unterminated string
+///   (which ends at the end of its line) or unbalanced braces.
+fn skeleton(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut skeleton = bytes.to_vec();
+    let mut blank = |range: Range<usize>| {
+        for b in &mut skeleton[range] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    };
+
+    let mut i = 0;
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'#' => {
+                let end = bytes[i..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map_or(bytes.len(), |n| i + n);
+                blank(i..end);
+                end
+            }
+            b'"' | b'\'' => {
+                let end = string_end(bytes, i);
+                blank(i + 1..contents_end(bytes, i + 1, end, &[bytes[i]]));
+                end
+            }
+            b'<' if bytes[i..].starts_with(b"<<<") => {
+                let end = heredoc_end(bytes, i + 3);
+                blank(i + 3..contents_end(bytes, i + 3, end, b">>>"));
+                end
+            }
+            b'c' if is_keyword(bytes, i, b"command") => {
+                let open = i
+                    + 7
+                    + bytes[i + 7..]
+                        .iter()
+                        .take_while(|b| b.is_ascii_whitespace())
+                        .count();
+                if bytes.get(open) == Some(&b'{') {
+                    let end = command_end(bytes, open + 1);
+                    blank(open + 1..contents_end(bytes, open + 1, end, b"}"));
+                    end
+                } else {
+                    open
+                }
+            }
+            _ => i + 1,
+        };
+    }
+
+    // Only whole characters are blanked, as every blanked range starts and
+    // ends next to an ASCII delimiter
+    String::from_utf8(skeleton).expect("skeleton to be valid UTF-8")
+}
+
+/// Returns the offset where the contents of a string, multi-line string, or
+/// command section that starts at `start` and ends at `end` end: before its
+/// closing delimiter, unless it is unterminated.
+fn contents_end(bytes: &[u8], start: usize, end: usize, delimiter: &[u8]) -> usize {
+    if end >= start + delimiter.len() && bytes[..end].ends_with(delimiter) {
+        end - delimiter.len()
+    } else {
+        end
+    }
+}
+
+/// Returns whether a keyword appears at `i` as a whole word.
+fn is_keyword(bytes: &[u8], i: usize, keyword: &[u8]) -> bool {
+    let is_word = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    bytes[i..].starts_with(keyword)
+        && !i
+            .checked_sub(1)
+            .and_then(|i| bytes.get(i))
+            .is_some_and(is_word)
+        && !bytes.get(i + keyword.len()).is_some_and(is_word)
+}
+
+/// Returns the offset after the string that opens with the quote at `start`.
+///
+/// An unterminated string ends at the end of its line.
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'\\' => i + 2,
+            b'\n' => return i,
+            b if b == quote => return i + 1,
+            b'~' | b'$' if bytes.get(i + 1) == Some(&b'{') => placeholder_end(bytes, i + 2),
+            _ => i + 1,
+        };
+    }
+
+    bytes.len()
+}
+
+/// Returns the offset after the `>>>` that closes a multi-line string or
+/// command whose contents start at `start`.
+fn heredoc_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .windows(3)
+        .position(|w| w == b">>>")
+        .map_or(bytes.len(), |n| start + n + 3)
+}
 
-    Ok(WdlDeclarations { workflow, tasks })
+/// Returns the offset after the `}` that closes a command section whose
+/// contents start at `start`.
+///
+/// As with WDL, the section ends at the first `}` that does not close a
+/// placeholder.
+fn command_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'}' => return i + 1,
+            b'~' | b'$' if bytes.get(i + 1) == Some(&b'{') => placeholder_end(bytes, i + 2),
+            _ => i + 1,
+        };
+    }
+
+    bytes.len()
+}
+
+/// Returns the offset after the `}` that closes a placeholder whose
+/// expression starts at `start`.
+fn placeholder_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut i = start;
+
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'{' => {
+                depth += 1;
+                i + 1
+            }
+            b'}' if depth == 0 => return i + 1,
+            b'}' => {
+                depth -= 1;
+                i + 1
+            }
+            b'"' | b'\'' => string_end(bytes, i),
+            _ => i + 1,
+        };
+    }
+
+    bytes.len()
+}
+
+/// A word that is not nested within braces in the skeleton of a document
+/// (see [`skeleton()`]), with the name and the brace that follow it, such as
+/// the keyword of a declaration (`task t {`) or a section (`input {`).
+#[derive(Debug)]
+struct Item<'a> {
+    /// The word, such as a keyword.
+    keyword: &'a str,
+    /// The word that follows the keyword, if any.
+    name: Option<&'a str>,
+    /// The offset of the keyword.
+    start: usize,
+    /// The offset of the brace that opens the body of the item, if it has
+    /// one.
+    open: Option<usize>,
+}
+
+/// Finds the words that are not nested within braces in the skeleton of a
+/// document or block (see [`skeleton()`]).
+fn items(skeleton: &str) -> Vec<Item<'_>> {
+    /// A token within the skeleton.
+    #[derive(Clone, Copy)]
+    enum Token<'a> {
+        /// A word, with its offset.
+        Word(usize, &'a str),
+        /// An opening brace, with its offset.
+        Open(usize),
+        /// A closing brace.
+        Close,
+        /// Any other character.
+        Other,
+    }
+
+    let mut tokens = Vec::new();
+    let mut chars = skeleton.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        tokens.push(match c {
+            '{' => Token::Open(i),
+            '}' => Token::Close,
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut end = i + c.len_utf8();
+                while let Some((j, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    end = j + c.len_utf8();
+                }
+                Token::Word(i, &skeleton[i..end])
+            }
+            c if c.is_whitespace() => continue,
+            _ => Token::Other,
+        });
+    }
+
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate() {
+        match *token {
+            Token::Open(_) => depth += 1,
+            Token::Close => depth = depth.saturating_sub(1),
+            Token::Word(start, keyword) if depth == 0 => {
+                let (name, open) = match tokens[i + 1..] {
+                    [Token::Word(_, name), Token::Open(open), ..] => (Some(name), Some(open)),
+                    [Token::Open(open), ..] => (None, Some(open)),
+                    [Token::Word(_, name), ..] => (Some(name), None),
+                    _ => (None, None),
+                };
+                items.push(Item {
+                    keyword,
+                    name,
+                    start,
+                    open,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    items
+}
+
+/// Parses WDL source code to extract workflow, task, and struct declarations
+/// and import statements.
+///
+/// Only the structure of the document is parsed: declarations within
+/// comments, strings, and command sections, or nested within another
+/// declaration, are ignored.
+pub fn parse_wdl_declarations(source: &str) -> WdlDeclarations {
+    let mut declarations = WdlDeclarations {
+        workflows: Vec::new(),
+        tasks: Vec::new(),
+        structs: Vec::new(),
+        imports: Vec::new(),
+    };
+
+    let skeleton = skeleton(source);
+    for item in items(&skeleton) {
+        let names = match item.keyword {
+            "workflow" => &mut declarations.workflows,
+            "task" => &mut declarations.tasks,
+            "struct" => &mut declarations.structs,
+            "import" => {
+                // The URI of the import is within a string, so it is read
+                // from the source rather than the skeleton
+                if let Some(cap) = IMPORT_REGEX.captures(&source[item.start..]) {
+                    declarations.imports.push(Import {
+                        uri: cap.get(1).or(cap.get(2)).unwrap().as_str().to_string(),
+                        aliases: IMPORT_ALIAS_REGEX
+                            .captures_iter(cap.get(3).map_or("", |m| m.as_str()))
+                            .map(|alias| (alias[1].to_string(), alias[2].to_string()))
+                            .collect(),
+                    });
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        if let (Some(name), Some(_)) = (item.name, item.open) {
+            names.push(name.to_string());
+        }
+    }
+
+    declarations
+}
+
+/// Finds the names by which calls are referenced (the alias of the call if
+/// it has one, otherwise the name of the called task or workflow).
+///
+/// Calls within comments, strings, and command sections are ignored.
+pub fn call_names(source: &str) -> Vec<String> {
+    CALL_REGEX
+        .captures_iter(&skeleton(source))
+        .map(|cap| match cap.get(2) {
+            Some(alias) => alias.as_str().to_string(),
+            None => cap[1].rsplit('.').next().unwrap().to_string(),
+        })
+        .collect()
+}
+
+/// Finds the container images referenced by `container` and `docker`
+/// runtime attributes.
+///
+/// Only literal images are returned; images that are interpolated or that
+/// allow any container (`*`) are ignored.
+pub fn container_images(source: &str) -> Vec<String> {
+    CONTAINER_REGEX
+        .captures_iter(source)
+        .flat_map(|cap| {
+            STRING_REGEX
+                .captures_iter(cap.get(1).unwrap().as_str())
+                .map(|lit| lit.get(1).or(lit.get(2)).unwrap().as_str().to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|image| {
+            !image.is_empty() && image != "*" && !image.contains("~{") && !image.contains("${")
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
+    use strum::IntoEnumIterator;
+
     use super::*;
 
+    #[test]
+    fn detects_constructs() {
+        let all = Construct::iter().collect::<Vec<_>>();
+        let wdl = r#"
+            version 1.2
+            task a {
+                input {
+                    Directory dir
+                    env String name = "a"
+                }
+                command <<< echo ~{name} >>>
+                requirements {
+                    cpu: 1
+                }
+                output {
+                    Int cpu = task.cpu
+                }
+            }
+        "#;
+
+        assert_eq!(
+            constructs(wdl, &all),
+            [
+                Construct::Directory,
+                Construct::EnvDeclarations,
+                Construct::TaskVariable,
+                Construct::Requirements,
+            ]
+        );
+        assert!(constructs(wdl, &[Construct::Hints, Construct::ElseIf]).is_empty());
+
+        let wdl = "workflow w {\n  String s = <<<\n    text\n  >>>\n}\n";
+        assert_eq!(constructs(wdl, &all), [Construct::MultilineStrings]);
+    }
+
+    #[test]
+    fn images() {
+        let wdl = r#"
+            version 1.2
+            task a {
+                command <<< echo hi >>>
+                runtime {
+                    container: "ubuntu:22.04"
+                }
+            }
+            task b {
+                command <<< echo hi >>>
+                requirements {
+                    container: ["quay.io/biocontainers/samtools:1.19", 'debian:bookworm', "*"]
+                }
+            }
+            task c {
+                input {
+                    String image
+                }
+                command <<< echo hi >>>
+                runtime {
+                    docker: "~{image}"
+                }
+            }
+        "#;
+
+        assert_eq!(
+            container_images(wdl),
+            [
+                "ubuntu:22.04",
+                "quay.io/biocontainers/samtools:1.19",
+                "debian:bookworm"
+            ]
+        );
+    }
+
+    #[test]
+    fn calls() {
+        let wdl = r#"
+            version 1.2
+            import "lib.wdl" as lib
+            workflow w {
+                call greet
+                call lib.greet as greet_again { name = "hi" }
+                scatter (i in [1, 2]) {
+                    call count
+                }
+            }
+        "#;
+
+        assert_eq!(call_names(wdl), ["greet", "greet_again", "count"]);
+    }
+
     #[test]
     fn single_workflow() {
         let wdl = r#"
@@ -103,8 +853,8 @@ mod tests {
             }
         "#;
 
-        let decls = parse_wdl_declarations(wdl).unwrap();
-        assert_eq!(decls.workflow(), Some("hello"));
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.workflows(), ["hello"]);
         assert_eq!(decls.tasks(), &[] as &[String]);
         assert_eq!(
             decls.single_target(),
@@ -123,8 +873,8 @@ mod tests {
             }
         "#;
 
-        let decls = parse_wdl_declarations(wdl).unwrap();
-        assert_eq!(decls.workflow(), None);
+        let decls = parse_wdl_declarations(wdl);
+        assert!(decls.workflows().is_empty());
         assert_eq!(decls.tasks(), &["my_task"]);
         assert_eq!(
             decls.single_target(),
@@ -151,8 +901,8 @@ mod tests {
             }
         "#;
 
-        let decls = parse_wdl_declarations(wdl).unwrap();
-        assert_eq!(decls.workflow(), Some("my_workflow"));
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.workflows(), ["my_workflow"]);
         assert_eq!(decls.tasks(), &["task1", "task2"]);
         assert_eq!(
             decls.single_target(),
@@ -172,12 +922,132 @@ mod tests {
             }
         "#;
 
-        let decls = parse_wdl_declarations(wdl).unwrap();
-        assert_eq!(decls.workflow(), None);
+        let decls = parse_wdl_declarations(wdl);
+        assert!(decls.workflows().is_empty());
         assert_eq!(decls.tasks(), &["task1", "task2"]);
         assert_eq!(decls.single_target(), None);
     }
 
+    #[test]
+    fn task_interface() {
+        let wdl = r#"
+            version 1.2
+            task hello_task {
+                input {
+                    File infile
+                    # the pattern to search for
+                    String pattern = "hello.*"
+                    Map[String, Int] counts = {
+                        "a": 1
+                    }
+                }
+
+                command <<<
+                    grep -E '~{pattern}' '~{infile}'
+                >>>
+
+                output {
+                    Array[String] matches = read_lines(stdout())
+                }
+            }
+        "#;
+
+        let interface = parse_interface(wdl, &Target::Task(String::from("hello_task"))).unwrap();
+
+        let inputs = interface
+            .inputs()
+            .iter()
+            .map(|d| (d.ty(), d.name(), d.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(inputs[0], ("File", "infile", String::from("File infile")));
+        assert_eq!(
+            inputs[1],
+            (
+                "String",
+                "pattern",
+                String::from(r#"String pattern = "hello.*""#)
+            )
+        );
+        assert_eq!(inputs[2].0, "Map[String, Int]");
+        assert_eq!(inputs[2].1, "counts");
+
+        assert_eq!(interface.outputs().len(), 1);
+        assert_eq!(interface.outputs()[0].ty(), "Array[String]");
+        assert_eq!(interface.outputs()[0].name(), "matches");
+
+        assert!(parse_interface(wdl, &Target::Task(String::from("missing"))).is_none());
+    }
+
+    #[test]
+    fn wrapper() {
+        let interface = Interface {
+            inputs: vec![Declaration {
+                ty: String::from("Int"),
+                name: String::from("x"),
+                expr: Some(String::from("1")),
+            }],
+            outputs: vec![Declaration {
+                ty: String::from("Int"),
+                name: String::from("y"),
+                expr: Some(String::from("x + 1")),
+            }],
+        };
+
+        let wdl = wrapper_workflow("wrap", "t", &interface);
+        assert_eq!(
+            wdl,
+            "\nworkflow wrap {\n    input {\n        Int x = 1\n    }\n\n    call t { input: x \
+             = x }\n\n    output {\n        Int y = t.y\n    }\n}\n"
+        );
+
+        let decls = parse_wdl_declarations(&wdl);
+        assert_eq!(decls.workflows(), ["wrap"]);
+    }
+
+    #[test]
+    fn structs_and_imports() {
+        let wdl = r#"
+            version 1.2
+            import "data/lib.wdl"
+            import 'https://example.com/other.wdl' as other alias Point as OtherPoint alias Line as L
+            struct Point {
+                Int x
+                Int y
+            }
+            workflow w {}
+        "#;
+
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.structs(), ["Point"]);
+
+        let imports = decls.imports();
+        assert_eq!(imports.len(), 2);
+        assert_eq!(imports[0].uri(), "data/lib.wdl");
+        assert!(imports[0].is_local());
+        assert!(!imports[1].is_local());
+        assert_eq!(
+            imports[1].aliases(),
+            [
+                (String::from("Point"), String::from("OtherPoint")),
+                (String::from("Line"), String::from("L"))
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_workflows() {
+        let wdl = r#"
+            version 1.2
+            workflow first {}
+            workflow second {}
+        "#;
+
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.workflows(), ["first", "second"]);
+        assert_eq!(decls.single_target(), None);
+    }
+
     #[test]
     fn no_workflow_or_task() {
         let wdl = r#"
@@ -185,9 +1055,168 @@ mod tests {
             # just a version, no declarations
         "#;
 
-        let decls = parse_wdl_declarations(wdl).unwrap();
-        assert_eq!(decls.workflow(), None);
+        let decls = parse_wdl_declarations(wdl);
+        assert!(decls.workflows().is_empty());
         assert_eq!(decls.tasks(), &[] as &[String]);
         assert_eq!(decls.single_target(), None);
     }
+
+    #[test]
+    fn ignores_comments() {
+        let wdl = r#"
+            version 1.2
+            # task commented_out {
+            #   command <<< >>>
+            # }
+            task real { # task trailing {
+                command <<< >>>
+            }
+        "#;
+
+        assert_eq!(parse_wdl_declarations(wdl).tasks(), ["real"]);
+    }
+
+    #[test]
+    fn ignores_strings() {
+        let wdl = r#"
+            version 1.2
+            task real {
+                meta {
+                    description: "a task { that } has task braces"
+                    note: 'task quoted {'
+                    escaped: "a \" task escaped {"
+                }
+                command <<< >>>
+            }
+            struct Sample { String name }
+        "#;
+
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.tasks(), ["real"]);
+        assert_eq!(decls.structs(), ["Sample"]);
+    }
+
+    #[test]
+    fn ignores_placeholders() {
+        let wdl = r#"
+            version 1.2
+            workflow w {
+                String s = "~{if true then "}" else "{"} ${"task t {"}"
+                call real
+            }
+            task real {
+                command <<< >>>
+            }
+        "#;
+
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.workflows(), ["w"]);
+        assert_eq!(decls.tasks(), ["real"]);
+        assert_eq!(call_names(wdl), ["real"]);
+    }
+
+    #[test]
+    fn ignores_heredoc_commands() {
+        let wdl = r#"
+            version 1.2
+            task real {
+                command <<<
+                    cat <<EOF
+                    task not_a_task {
+                    workflow not_a_workflow {
+                    call not_a_call
+                    EOF
+                    echo "~{true}" }
+                >>>
+            }
+        "#;
+
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.tasks(), ["real"]);
+        assert!(decls.workflows().is_empty());
+        assert!(call_names(wdl).is_empty());
+    }
+
+    #[test]
+    fn ignores_brace_commands() {
+        let wdl = r#"
+            version 1.2
+            task real {
+                command {
+                    echo "workflow fake {"
+                    call fake ${sep(" ", ["{", "}"])}
+                }
+            }
+        "#;
+
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.tasks(), ["real"]);
+        assert!(decls.workflows().is_empty());
+        assert!(call_names(wdl).is_empty());
+    }
+
+    #[test]
+    fn ignores_nested_declarations() {
+        let wdl = r#"
+            version 1.2
+            task outer {
+                meta {
+                    task: { nested: "task" }
+                }
+                command <<< >>>
+            }
+        "#;
+
+        let decls = parse_wdl_declarations(wdl);
+        assert_eq!(decls.tasks(), ["outer"]);
+        assert_eq!(
+            decls.single_target(),
+            Some(Target::Task(String::from("outer")))
+        );
+    }
+
+    #[test]
+    fn interface_with_braces_in_strings() {
+        let wdl = r#"
+            version 1.2
+            task braces {
+                input {
+                    String open = "{" # an input with a brace
+                    Array[String] names = [
+                        "}",
+                    ]
+                }
+                command <<< >>>
+                output {
+                    String out = open
+                }
+            }
+        "#;
+
+        let interface = parse_interface(wdl, &Target::Task(String::from("braces"))).unwrap();
+        assert_eq!(
+            interface
+                .inputs()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                r#"String open = "{""#,
+                "Array[String] names = [\n                        \"}\",\n                    ]"
+            ]
+        );
+        assert_eq!(interface.outputs()[0].to_string(), "String out = open");
+    }
+
+    #[test]
+    fn skeleton_keeps_offsets() {
+        let wdl = "task t {\n  # ünïcödé {\n  String s = \"ü}\"\n}\n";
+
+        // Every byte of a blanked character is replaced with a space
+        let blanked = |text: &str| " ".repeat(text.len());
+        let expected = wdl
+            .replace("# ünïcödé {", &blanked("# ünïcödé {"))
+            .replace("ü}", &blanked("ü}"));
+        assert_eq!(skeleton(wdl), expected);
+    }
 }